@@ -0,0 +1,231 @@
+use crate::nfa::{Transition, NFA};
+use crate::pikevm;
+use crate::prelude::*;
+
+use core::hash::Hash;
+
+/// The number of bits in the visited set of a [`BoundedBacktracker`], which bounds the product of
+/// the number of states and the length of the input that it can search.
+pub const DEFAULT_VISITED_CAPACITY: usize = 256 * 1024 * 8;
+
+/// A backtracking search of an NFA that finds the same submatches as a
+/// [`PikeVM`](crate::pikevm::PikeVM). Each pair of a state and an input position is explored at
+/// most once, as recorded in a bitmap, so searches still take time linear in the length of the
+/// input, but only inputs short enough for the bitmap to fit within its capacity can be searched.
+/// For those, it is usually faster than the Pike VM, since it follows a single path at a time
+/// instead of copying the capture slots of every thread.
+///
+/// ```
+/// use automata::backtrack::BoundedBacktracker;
+/// use automata::NFA;
+///
+/// // (a*)b, with the group recorded in slots 2 and 3.
+/// let mut a = NFA::new();
+/// let s = a.add_state(true);
+/// a.add_labeled_transition(a.start_state, s, 'a');
+/// let mut b = NFA::new();
+/// let s = b.add_state(true);
+/// b.add_labeled_transition(b.start_state, s, 'b');
+/// let nfa = NFA::concatenation(&NFA::tagged(NFA::kleene_star(&a), 2, 3), &b);
+///
+/// let bt = BoundedBacktracker::new(&nfa);
+/// let input: Vec<_> = "aab".chars().collect();
+/// assert!(input.len() <= bt.max_input_len());
+/// let slots = bt.captures(&input).unwrap();
+/// assert_eq!(vec![Some(0), Some(3), Some(0), Some(2)], slots);
+/// ```
+#[derive(Clone, Debug)]
+pub struct BoundedBacktracker<'a, T: Clone + Eq + Hash> {
+    nfa: &'a NFA<T>,
+    slots: usize,
+    capacity: usize,
+}
+
+/// A step of the search, kept on an explicit stack so that the search doesn't recurse.
+enum Frame {
+    /// Explore a state at an input position.
+    Explore(usize, usize),
+    /// Report a match that ends at an input position.
+    Accept(usize),
+    /// Restore a capture slot once the paths through the state that set it are exhausted.
+    Restore(usize, Option<usize>),
+}
+
+impl<'a, T> BoundedBacktracker<'a, T>
+where
+    T: Clone + Eq + Hash,
+{
+    /// Create a backtracker for the NFA, with a visited set of [`DEFAULT_VISITED_CAPACITY`] bits.
+    #[inline]
+    pub fn new(nfa: &'a NFA<T>) -> Self {
+        Self::with_capacity(nfa, DEFAULT_VISITED_CAPACITY)
+    }
+
+    /// Create a backtracker for the NFA, with a visited set of `capacity` bits.
+    #[inline]
+    pub fn with_capacity(nfa: &'a NFA<T>, capacity: usize) -> Self {
+        Self {
+            nfa,
+            slots: pikevm::slots(nfa),
+            capacity,
+        }
+    }
+
+    /// The NFA searched by the backtracker.
+    #[inline]
+    pub fn nfa(&self) -> &'a NFA<T> {
+        self.nfa
+    }
+
+    /// The number of capture slots of each match, including slots 0 and 1 for the match itself.
+    #[inline]
+    pub fn slots(&self) -> usize {
+        self.slots
+    }
+
+    /// The length of the longest input that the backtracker can search.
+    #[inline]
+    pub fn max_input_len(&self) -> usize {
+        (self.capacity / self.nfa.total_states.max(1)).saturating_sub(1)
+    }
+
+    /// Find the leftmost-first match that starts at the beginning of the input, and return its
+    /// capture slots. Slots whose tags weren't reached by the match are `None`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the input is longer than [`max_input_len`](Self::max_input_len).
+    #[inline]
+    pub fn captures<S>(&self, input: &[S]) -> Option<Vec<Option<usize>>>
+    where
+        T: PartialEq<S>,
+    {
+        self.run(input, true, false)
+    }
+
+    /// Find the leftmost-first match anywhere in the input, and return its capture slots.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the input is longer than [`max_input_len`](Self::max_input_len).
+    #[inline]
+    pub fn captures_unanchored<S>(&self, input: &[S]) -> Option<Vec<Option<usize>>>
+    where
+        T: PartialEq<S>,
+    {
+        self.run(input, false, false)
+    }
+
+    /// Determine if the whole input is accepted, and return the capture slots of the highest
+    /// priority path that accepts it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the input is longer than [`max_input_len`](Self::max_input_len).
+    #[inline]
+    pub fn captures_whole<S>(&self, input: &[S]) -> Option<Vec<Option<usize>>>
+    where
+        T: PartialEq<S>,
+    {
+        self.run(input, true, true)
+    }
+
+    #[inline]
+    fn run<S>(&self, input: &[S], anchored: bool, whole: bool) -> Option<Vec<Option<usize>>>
+    where
+        T: PartialEq<S>,
+    {
+        assert!(
+            input.len() <= self.max_input_len(),
+            "input of length {} exceeds the maximum of {}",
+            input.len(),
+            self.max_input_len()
+        );
+
+        let width = input.len() + 1;
+        let mut visited = vec![0u64; self.nfa.total_states * width / 64 + 1];
+
+        // The visited set is kept between starting positions, since a state that failed to lead to
+        // a match at a position fails again regardless of where the search started.
+        let last_start = if anchored { 0 } else { input.len() };
+        for start in 0..=last_start {
+            let mut slots = vec![None; self.slots];
+            slots[0] = Some(start);
+            if let Some(slots) = self.search(input, whole, &mut visited, slots, start) {
+                return Some(slots);
+            }
+        }
+
+        None
+    }
+
+    /// Search for a match that starts at the position, exploring each pair of a state and a
+    /// position not yet in the visited set.
+    #[inline]
+    fn search<S>(
+        &self,
+        input: &[S],
+        whole: bool,
+        visited: &mut [u64],
+        mut slots: Vec<Option<usize>>,
+        start: usize,
+    ) -> Option<Vec<Option<usize>>>
+    where
+        T: PartialEq<S>,
+    {
+        let width = input.len() + 1;
+        let mut stack = vec![Frame::Explore(self.nfa.start_state, start)];
+        while let Some(frame) = stack.pop() {
+            let (state, pos) = match frame {
+                Frame::Explore(state, pos) => (state, pos),
+                Frame::Accept(pos) => {
+                    slots[1] = Some(pos);
+                    return Some(slots);
+                }
+                Frame::Restore(slot, old) => {
+                    slots[slot] = old;
+                    continue;
+                }
+            };
+
+            let bit = state * width + pos;
+            if visited[bit / 64] & (1 << (bit % 64)) != 0 {
+                continue;
+            }
+            visited[bit / 64] |= 1 << (bit % 64);
+
+            if let Some(tags) = self.nfa.tags.get(&state) {
+                for &slot in tags {
+                    stack.push(Frame::Restore(slot, slots[slot]));
+                    slots[slot] = Some(pos);
+                }
+            }
+
+            // The frames are pushed in reverse order of priority, which is the order of the
+            // threads of the Pike VM: the paths that consume the next symbol from this state come
+            // first, then a match that ends here, then the paths through epsilon transitions.
+            if let Some(dests) = self.nfa.transition.get(&state, &Transition::Epsilon) {
+                for &dest in dests.iter().rev() {
+                    stack.push(Frame::Explore(dest, pos));
+                }
+            }
+            if self.nfa.is_accepting_state(&state) && (!whole || pos == input.len()) {
+                stack.push(Frame::Accept(pos));
+            }
+            if let Some(next) = input.get(pos) {
+                let mut nexts = Vec::new();
+                for (t, dests) in self.nfa.transitions_from(state) {
+                    match t {
+                        Transition::Some(symbol) if *symbol == *next => nexts.extend(dests),
+                        _ => {}
+                    }
+                }
+                for &dest in nexts.iter().rev() {
+                    stack.push(Frame::Explore(dest, pos + 1));
+                }
+            }
+        }
+
+        None
+    }
+}
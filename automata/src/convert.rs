@@ -1,8 +1,11 @@
+use crate::collections::{HashMap, HashSet, VecDeque};
 use crate::dfa::{Transition, DFA};
+use crate::matching::MatchKind;
 use crate::nfa::{self, NFA};
+use crate::prelude::*;
 
-use std::collections::{HashMap, HashSet, VecDeque};
-use std::hash::Hash;
+use core::fmt;
+use core::hash::Hash;
 
 /// Must be implemented by NFA transition symbol types to ensure each DFA state has only one
 /// possible transition on any symbol.
@@ -13,6 +16,22 @@ pub trait Disjoin: Sized {
     fn contains(&self, other: &Self) -> bool;
 }
 
+/// Single bytes are already disjoint, so automata over bytes can be determinized directly.
+impl Disjoin for u8 {
+    #[inline]
+    fn disjoin(vec: Vec<&Self>) -> Vec<Self> {
+        let mut bytes: Vec<_> = vec.into_iter().copied().collect();
+        bytes.sort_unstable();
+        bytes.dedup();
+        bytes
+    }
+
+    #[inline]
+    fn contains(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
 #[derive(Debug)]
 pub struct DFAFromNFA<T>
 where
@@ -20,17 +39,22 @@ where
 {
     pub dfa: DFA<T>,
     pub nfa_mapping: HashMap<usize, HashSet<usize>>,
+    /// The disjoint symbol classes that label the transitions of the DFA. Every transition
+    /// symbol of the NFA is the union of some of these classes.
+    pub alphabet: Vec<T>,
 }
 
 #[derive(Clone, Debug)]
 struct DState {
     label: usize,
-    nfa_states: HashSet<usize>,
+    /// The NFA states in this set state. Under leftmost-longest semantics, they are sorted by
+    /// label; under leftmost-first semantics, they are ordered by priority.
+    nfa_states: Vec<usize>,
 }
 
 impl DState {
     #[inline]
-    fn new(label: usize, nfa_states: HashSet<usize>) -> Self {
+    fn new(label: usize, nfa_states: Vec<usize>) -> Self {
         Self { label, nfa_states }
     }
 }
@@ -56,15 +80,107 @@ where
     }
 }
 
+/// Convert a DFA to an NFA with the same states and transitions.
+impl<T> From<&DFA<T>> for NFA<T>
+where
+    T: Clone + Eq + Hash,
+{
+    #[inline]
+    fn from(dfa: &DFA<T>) -> Self {
+        let mut nfa = NFA::new();
+        for _ in 1..dfa.total_states {
+            nfa.add_state(false);
+        }
+        nfa.start_state = dfa.start_state;
+        nfa.accepting_states = dfa.accepting_states.clone();
+        nfa.patterns = dfa.patterns.clone();
+
+        for (&state, t, &next) in &dfa.transition {
+            nfa.add_labeled_transition(state, next, t.0.clone());
+        }
+        nfa
+    }
+}
+
+/// Error returned when a conversion would produce an automaton with more states than allowed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StateLimitError {
+    limit: usize,
+}
+
+impl StateLimitError {
+    #[inline]
+    pub fn new(limit: usize) -> Self {
+        Self { limit }
+    }
+
+    /// The maximum number of states that was exceeded.
+    #[inline]
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl fmt::Display for StateLimitError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "automaton exceeds the limit of {} states", self.limit)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StateLimitError {}
+
 impl<T> From<NFA<T>> for DFAFromNFA<T>
 where
     T: Clone + Disjoin + Eq + Hash,
 {
+    #[inline]
+    fn from(nfa: NFA<T>) -> Self {
+        match Self::subset_construction(&nfa, MatchKind::LeftmostLongest, None) {
+            Ok(dfa_from_nfa) => dfa_from_nfa,
+            Err(_) => unreachable!("subset construction without a limit failed"),
+        }
+    }
+}
+
+impl<T> DFAFromNFA<T>
+where
+    T: Clone + Disjoin + Eq + Hash,
+{
+    /// Create an equivalent DFA from an NFA, failing if the DFA would have more than `limit`
+    /// states.
+    #[inline]
+    pub fn with_state_limit(nfa: &NFA<T>, limit: usize) -> Result<Self, StateLimitError> {
+        Self::subset_construction(nfa, MatchKind::LeftmostLongest, Some(limit))
+    }
+
+    /// Create a DFA from an NFA that searches using the given match semantics, failing if the DFA
+    /// would have more than `limit` states.
+    ///
+    /// A DFA built with [`MatchKind::LeftmostFirst`] stops at the match preferred by that
+    /// semantics, so it should only be used for searching, and not to determine if the entire input
+    /// is accepted.
+    #[inline]
+    pub fn with_match_kind(
+        nfa: &NFA<T>,
+        kind: MatchKind,
+        limit: Option<usize>,
+    ) -> Result<Self, StateLimitError> {
+        Self::subset_construction(nfa, kind, limit)
+    }
+
     // Create an equivalent DFA from an NFA using the subset construction described by Algorithm
     // 3.20. The construction is slightly modified, with inspiration from [this Stack Overflow
-    //   answer](https://stackoverflow.com/a/25832898/8955108) to accomodate character ranges.
+    //   answer](https://stackoverflow.com/a/25832898/8955108) to accomodate character ranges: the
+    //   transition symbols are first partitioned into an alphabet of disjoint classes.
     #[inline]
-    fn from(nfa: NFA<T>) -> Self {
+    fn subset_construction(
+        nfa: &NFA<T>,
+        kind: MatchKind,
+        limit: Option<usize>,
+    ) -> Result<Self, StateLimitError> {
+        let alphabet = alphabet(nfa);
         let mut dfa = DFA::new();
         let mut nfa_mapping = HashMap::new();
 
@@ -72,83 +188,63 @@ where
         let mut unmarked_states = VecDeque::new();
 
         let label = 0;
-        let initial_e_closure = nfa.epsilon_closure(nfa.start_state);
+        let initial_e_closure = start(nfa, kind);
         let initial_unmarked = DState::new(label, initial_e_closure);
 
-        if initial_unmarked
-            .nfa_states
-            .iter()
-            .any(|i| nfa.is_accepting_state(i))
-        {
-            dfa.accepting_states.insert(initial_unmarked.label);
-        }
+        mark_accepting(
+            &mut dfa,
+            initial_unmarked.label,
+            &initial_unmarked.nfa_states,
+            nfa,
+        );
 
-        nfa_mapping.insert(initial_unmarked.label, initial_unmarked.nfa_states.clone());
+        nfa_mapping.insert(
+            initial_unmarked.label,
+            initial_unmarked.nfa_states.iter().cloned().collect(),
+        );
         unmarked_states.push_back(initial_unmarked);
 
         while let Some(s) = unmarked_states.pop_front() {
-            // Get all non-epsilon transitions and destinations from the NFA states in this set
-            // state.
-            let transition_map: Vec<(&T, &HashSet<usize>)> = s
-                .clone()
-                .nfa_states
-                .into_iter()
-                // Union of transitions from each NFA state
-                .flat_map(|nfa_state| nfa.transitions_from(nfa_state))
-                // Filter out epsilon transitions
-                .filter_map(|(t, v)| match t {
-                    nfa::Transition::Some(a) => Some((a, v)),
-                    nfa::Transition::Epsilon => None,
-                })
-                .collect();
-
-            // Isolate transitions.
-            let transitions: Vec<&T> = transition_map.iter().map(|(t, _)| *t).collect();
-            // Disjoin transitions.
-            let disjoint_transitions = T::disjoin(transitions);
-
-            for t in disjoint_transitions {
-                let moved_set: HashSet<usize> = transition_map
-                    .iter()
-                    .filter(|(a, _)| a.contains(&t))
-                    .flat_map(|(_, v)| (*v).clone())
-                    .collect();
-                let epsilon_closure = nfa.epsilon_closure_set(&moved_set);
+            for t in &alphabet {
+                let epsilon_closure = step(nfa, &s.nfa_states, t, kind);
+                if epsilon_closure.is_empty() {
+                    continue;
+                }
                 let mut new_state = DState::new(0, epsilon_closure);
 
                 // If state already exists in unmarked or marked, change the label and do not push
                 // to unmarked.
                 if s.nfa_states == new_state.nfa_states {
                     new_state.label = s.label;
-                    dfa.add_transition(s.label, new_state.label, Transition(t));
+                    dfa.add_transition(s.label, new_state.label, Transition(t.clone()));
                 } else if let Some(existing) = marked_states
                     .iter()
                     .find(|ss: &&DState| ss.nfa_states == new_state.nfa_states)
                 {
                     new_state.label = existing.label;
-                    dfa.add_transition(s.label, new_state.label, Transition(t));
+                    dfa.add_transition(s.label, new_state.label, Transition(t.clone()));
                 } else if let Some(existing) = unmarked_states
                     .iter()
                     .find(|ss: &&DState| ss.nfa_states == new_state.nfa_states)
                 {
                     new_state.label = existing.label;
-                    dfa.add_transition(s.label, new_state.label, Transition(t));
+                    dfa.add_transition(s.label, new_state.label, Transition(t.clone()));
                 } else {
                     // If not found, set a new label and push to unmarked.
+                    if let Some(limit) = limit {
+                        if dfa.total_states >= limit {
+                            return Err(StateLimitError::new(limit));
+                        }
+                    }
                     new_state.label = dfa.add_state(false);
 
-                    // If this set state contains an accepting NFA state, set this set state
-                    // as accepting in the DFA.
-                    if new_state
-                        .nfa_states
-                        .iter()
-                        .any(|i| nfa.is_accepting_state(i))
-                    {
-                        dfa.accepting_states.insert(new_state.label);
-                    }
+                    mark_accepting(&mut dfa, new_state.label, &new_state.nfa_states, nfa);
 
-                    dfa.add_transition(s.label, new_state.label, Transition(t));
-                    nfa_mapping.insert(new_state.label, new_state.nfa_states.clone());
+                    dfa.add_transition(s.label, new_state.label, Transition(t.clone()));
+                    nfa_mapping.insert(
+                        new_state.label,
+                        new_state.nfa_states.iter().cloned().collect(),
+                    );
                     unmarked_states.push_back(new_state);
                 }
             }
@@ -157,6 +253,100 @@ where
             marked_states.push(s);
         }
 
-        Self { dfa, nfa_mapping }
+        Ok(Self {
+            dfa,
+            nfa_mapping,
+            alphabet,
+        })
+    }
+}
+
+/// If a set state contains an accepting NFA state, set it as accepting in the DFA, matching the
+/// patterns of all of the accepting NFA states it contains.
+#[inline]
+fn mark_accepting<T>(dfa: &mut DFA<T>, label: usize, nfa_states: &[usize], nfa: &NFA<T>)
+where
+    T: Clone + Eq + Hash,
+{
+    for nfa_state in nfa_states {
+        if nfa.is_accepting_state(nfa_state) {
+            dfa.accepting_states.insert(label);
+            for id in nfa.pattern_ids(nfa_state) {
+                dfa.add_pattern(label, id);
+            }
+        }
+    }
+}
+
+/// Partition the transition symbols of the NFA into disjoint classes, so that a DFA built from it
+/// has a small alphabet shared by all of its states.
+#[inline]
+pub(crate) fn alphabet<T>(nfa: &NFA<T>) -> Vec<T>
+where
+    T: Clone + Disjoin + Eq + Hash,
+{
+    let mut symbols = Vec::new();
+    for (_, t, _) in &nfa.transition {
+        if let nfa::Transition::Some(a) = t {
+            if !symbols.contains(&a) {
+                symbols.push(a);
+            }
+        }
+    }
+    T::disjoin(symbols)
+}
+
+/// The set state containing the epsilon closure of the NFA start state.
+#[inline]
+pub(crate) fn start<T>(nfa: &NFA<T>, kind: MatchKind) -> Vec<usize>
+where
+    T: Clone + Eq + Hash,
+{
+    let mut closure = Vec::new();
+    nfa.epsilon_closure_into(nfa.start_state, &mut closure, &mut HashSet::new());
+    canonicalize(nfa, closure, kind)
+}
+
+/// The set state reached from the given set state on a symbol class of the alphabet. The result
+/// is empty if no NFA state has a transition on the class.
+#[inline]
+pub(crate) fn step<T>(nfa: &NFA<T>, nfa_states: &[usize], t: &T, kind: MatchKind) -> Vec<usize>
+where
+    T: Clone + Disjoin + Eq + Hash,
+{
+    // Follow the transitions in the order of the states they leave from, to preserve priority.
+    let mut seen = HashSet::new();
+    let mut closure = Vec::new();
+    for &nfa_state in nfa_states {
+        for (a, v) in nfa.transitions_from(nfa_state) {
+            match a {
+                nfa::Transition::Some(a) if a.contains(t) => {
+                    for &dest in v.iter() {
+                        nfa.epsilon_closure_into(dest, &mut closure, &mut seen);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    canonicalize(nfa, closure, kind)
+}
+
+/// Put a set state in canonical form for the match semantics, so that equivalent set states
+/// compare equal.
+#[inline]
+fn canonicalize<T>(nfa: &NFA<T>, mut nfa_states: Vec<usize>, kind: MatchKind) -> Vec<usize>
+where
+    T: Clone + Eq + Hash,
+{
+    match kind {
+        MatchKind::LeftmostLongest => nfa_states.sort_unstable(),
+        // States after an accepting state can only produce lower-priority matches.
+        MatchKind::LeftmostFirst => {
+            if let Some(i) = nfa_states.iter().position(|s| nfa.is_accepting_state(s)) {
+                nfa_states.truncate(i + 1);
+            }
+        }
     }
+    nfa_states
 }
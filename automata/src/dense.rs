@@ -0,0 +1,749 @@
+use crate::collections::{hash_map::Entry, HashMap};
+use crate::convert::StateLimitError;
+use crate::dfa::DFA;
+use crate::matching::{self, Match};
+use crate::prelude::*;
+use crate::stateid::StateId;
+
+use core::hash::Hash;
+
+#[cfg(feature = "serde")]
+use serde::de::Error as _;
+
+/// A DFA whose transitions are stored in a flat table indexed by state and symbol class, for fast
+/// lookups while matching.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DenseDFA<T> {
+    /// The start state of anchored searches, which only match at the position they start at.
+    pub start_state: StateId,
+    /// The start state of unanchored searches, which is accepting after any input that ends with
+    /// a match. It is `None` if the DFA would have been too large with the states of unanchored
+    /// searches.
+    pub unanchored_start_state: Option<StateId>,
+    /// The number of total states in the DFA. There is a state labeled i for every i where 0 <= i
+    /// < total_states.
+    pub total_states: usize,
+    /// Whether each state is accepting, indexed by state.
+    pub accepting: Vec<bool>,
+    /// The symbol classes that label the transitions. Column i of the table holds the transitions
+    /// on `alphabet[i]`.
+    pub alphabet: Vec<T>,
+    /// The column of the alphabet containing each input symbol.
+    pub classes: ClassMap,
+    /// The row-major transition table, with a row of `alphabet.len()` entries for each state.
+    /// Missing transitions are stored as [`DenseDFA::DEAD`].
+    pub table: Vec<StateId>,
+    /// The state reached from each state on a symbol that isn't in the alphabet: dead for the states
+    /// of anchored searches, and the unanchored start state for the others.
+    pub fallback: Vec<StateId>,
+}
+
+/// The maximum number of states added to a [`DenseDFA`] for unanchored searches.
+const UNANCHORED_STATE_LIMIT: usize = 1 << 10;
+
+/// The configuration of a search with [`DenseDFA::search`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Search {
+    /// Only find a match that starts at the start position, rather than the leftmost one at or
+    /// after it.
+    pub anchored: bool,
+    /// Of the matches that start at the same position, find the shortest rather than the longest.
+    pub shortest: bool,
+}
+
+/// An input symbol with an integer code, used to look up the column of the alphabet containing it.
+///
+/// Symbols of a small alphabet, such as bytes or the tokens of a lexer, declare its size, and are
+/// looked up by indexing a table with their code. The choice is made at compile time, so matching
+/// them never searches the ranges of the alphabet, as it does for characters above `\u{FF}`.
+pub trait Symbol {
+    /// The number of input symbols, if their codes are the dense range `0..SIZE` and `SIZE` is at
+    /// most 256. The code of every symbol must be below it.
+    const SIZE: Option<u32> = None;
+
+    fn code(&self) -> u32;
+}
+
+impl Symbol for u8 {
+    const SIZE: Option<u32> = Some(256);
+
+    #[inline]
+    fn code(&self) -> u32 {
+        *self as u32
+    }
+}
+
+impl Symbol for char {
+    #[inline]
+    fn code(&self) -> u32 {
+        *self as u32
+    }
+}
+
+/// Must be implemented by transition symbol types of a [`DenseDFA`], which are sets of input
+/// symbols described by ranges of their codes.
+pub trait SymbolRanges {
+    /// The inclusive ranges of the codes of the input symbols in the set.
+    fn code_ranges(&self) -> Vec<(u32, u32)>;
+
+    /// The number of input symbols in the set. By default, this is the number of codes in its
+    /// ranges, which is too many if some codes in the ranges aren't input symbols.
+    #[inline]
+    fn symbol_count(&self) -> u128 {
+        self.code_ranges()
+            .iter()
+            .map(|&(start, end)| u128::from(end - start) + 1)
+            .sum()
+    }
+}
+
+impl SymbolRanges for u8 {
+    #[inline]
+    fn code_ranges(&self) -> Vec<(u32, u32)> {
+        vec![(*self as u32, *self as u32)]
+    }
+}
+
+impl SymbolRanges for char {
+    #[inline]
+    fn code_ranges(&self) -> Vec<(u32, u32)> {
+        vec![(*self as u32, *self as u32)]
+    }
+}
+
+/// A map from the codes of input symbols to the columns of an alphabet of disjoint symbol sets.
+/// Codes below 256 are looked up in a table, and others are found by binary search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ClassMap {
+    /// The column of each code below 256, or [`ClassMap::NONE`].
+    low: Vec<u32>,
+    /// The sorted ranges of codes of 256 and above, and the column of each.
+    high: Vec<(u32, u32, u32)>,
+}
+
+impl ClassMap {
+    /// The table entry of a code that isn't in any column.
+    const NONE: u32 = u32::MAX;
+    /// The number of codes looked up in the table.
+    const LOW: u32 = 256;
+
+    /// Create the map for an alphabet of disjoint symbol sets.
+    #[inline]
+    pub fn new<T>(alphabet: &[T]) -> Self
+    where
+        T: SymbolRanges,
+    {
+        let mut low = vec![Self::NONE; Self::LOW as usize];
+        let mut high = Vec::new();
+        for (column, symbol) in alphabet.iter().enumerate() {
+            let column = column as u32;
+            for (start, end) in symbol.code_ranges() {
+                for code in start..=end.min(Self::LOW - 1) {
+                    low[code as usize] = column;
+                }
+                if end >= Self::LOW {
+                    high.push((start.max(Self::LOW), end, column));
+                }
+            }
+        }
+        high.sort_unstable();
+
+        Self { low, high }
+    }
+
+    /// Get the column containing the input symbol, by indexing the table if it is from an
+    /// alphabet of at most 256 symbols.
+    #[inline]
+    pub fn lookup<S>(&self, symbol: &S) -> Option<usize>
+    where
+        S: Symbol,
+    {
+        match S::SIZE {
+            Some(size) if size <= Self::LOW => Self::column(self.low[symbol.code() as usize]),
+            _ => self.get(symbol.code()),
+        }
+    }
+
+    /// Get the column containing the input symbol with the given code.
+    #[inline]
+    pub fn get(&self, code: u32) -> Option<usize> {
+        let column = if code < Self::LOW {
+            self.low[code as usize]
+        } else {
+            let i = self.high.partition_point(|&(start, _, _)| start <= code);
+            match i.checked_sub(1).map(|i| self.high[i]) {
+                Some((_, end, column)) if code <= end => column,
+                _ => Self::NONE,
+            }
+        };
+        Self::column(column)
+    }
+
+    /// The columns that some code is mapped to.
+    #[cfg(feature = "serde")]
+    #[inline]
+    fn columns(&self) -> impl Iterator<Item = usize> + '_ {
+        let high = self.high.iter().map(|&(_, _, column)| column);
+        self.low
+            .iter()
+            .copied()
+            .chain(high)
+            .filter_map(Self::column)
+    }
+
+    #[inline]
+    fn column(entry: u32) -> Option<usize> {
+        if entry == Self::NONE {
+            None
+        } else {
+            Some(entry as usize)
+        }
+    }
+}
+
+impl<T> DenseDFA<T> {
+    /// The table entry of a missing transition.
+    pub const DEAD: StateId = StateId::MAX;
+
+    /// The state reached from `state` on the given input symbol, if any.
+    #[inline]
+    pub fn next_state<U>(&self, state: StateId, symbol: &U) -> Option<StateId>
+    where
+        U: Symbol,
+    {
+        let next = match self.classes.lookup(symbol) {
+            Some(class) => self.table[state.as_usize() * self.alphabet.len() + class],
+            None => self.fallback[state.as_usize()],
+        };
+        match next {
+            Self::DEAD => None,
+            next => Some(next),
+        }
+    }
+
+    #[inline]
+    pub fn is_accepting_state(&self, state: StateId) -> bool {
+        self.accepting[state.as_usize()]
+    }
+
+    /// Determine if an accepting state can be reached from the state. Since transitions to dead
+    /// states are left out, this is the case if it is accepting or has any transition.
+    #[inline]
+    pub fn is_live(&self, state: StateId) -> bool {
+        let (state, stride) = (state.as_usize(), self.alphabet.len());
+        self.accepting[state]
+            || self.fallback[state] != Self::DEAD
+            || self.table[state * stride..(state + 1) * stride]
+                .iter()
+                .any(|&next| next != Self::DEAD)
+    }
+}
+
+impl<T> DenseDFA<T>
+where
+    T: Clone + Eq + Hash + SymbolRanges,
+{
+    /// Create a dense DFA from a DFA, failing if it has more states than can be labeled by a
+    /// [`StateId`] other than [`DEAD`](Self::DEAD).
+    #[inline]
+    pub fn new(dfa: &DFA<T>) -> Result<Self, StateLimitError> {
+        let limit = StateId::MAX.as_usize();
+        if dfa.total_states > limit {
+            return Err(StateLimitError::new(limit));
+        }
+        let id = |state: usize| StateId::from_u32(state as u32);
+
+        let mut columns = HashMap::new();
+        let mut alphabet = Vec::new();
+        for (_, t, _) in &dfa.transition {
+            columns.entry(t).or_insert_with(|| {
+                alphabet.push(t.0.clone());
+                alphabet.len() - 1
+            });
+        }
+
+        // Transitions to dead states are left out, so matching stops as soon as a match becomes
+        // impossible.
+        let live = dfa.live_states();
+        let stride = alphabet.len();
+        let mut table = vec![Self::DEAD; dfa.total_states * stride];
+        for (&state, t, &next) in &dfa.transition {
+            if live[next] {
+                table[state * stride + columns[t]] = id(next);
+            }
+        }
+
+        let accepting = (0..dfa.total_states)
+            .map(|s| dfa.is_accepting_state(&s))
+            .collect();
+
+        let mut dense = Self {
+            start_state: id(dfa.start_state),
+            unanchored_start_state: None,
+            total_states: dfa.total_states,
+            accepting,
+            classes: ClassMap::new(&alphabet),
+            alphabet,
+            table,
+            fallback: vec![Self::DEAD; dfa.total_states],
+        };
+        if live[dfa.start_state] {
+            dense.add_unanchored_states();
+        }
+        Ok(dense)
+    }
+}
+
+/// Create a dense DFA from a DFA.
+///
+/// # Panics
+///
+/// Panics if the DFA has more than [`StateId::MAX`] states. Use [`DenseDFA::new`] to handle this
+/// as an error.
+impl<T> From<&DFA<T>> for DenseDFA<T>
+where
+    T: Clone + Eq + Hash + SymbolRanges,
+{
+    #[inline]
+    fn from(dfa: &DFA<T>) -> Self {
+        match Self::new(dfa) {
+            Ok(dense) => dense,
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+impl<T> DenseDFA<T> {
+    /// Add the states of unanchored searches, each of which is the set of states of anchored
+    /// searches started at every position so far. Nothing is added if there would be more than
+    /// [`UNANCHORED_STATE_LIMIT`] of them, or if they can't all be labeled.
+    #[inline]
+    fn add_unanchored_states(&mut self) {
+        let anchored_states = self.total_states;
+        let stride = self.alphabet.len();
+
+        let mut sets = vec![vec![self.start_state]];
+        let mut labels = HashMap::new();
+        labels.insert(sets[0].clone(), StateId::from_u32(anchored_states as u32));
+        let limit = UNANCHORED_STATE_LIMIT.min(StateId::MAX.as_usize() - anchored_states);
+        let mut i = 0;
+        while i < sets.len() {
+            if sets.len() > limit {
+                self.table.truncate(anchored_states * stride);
+                self.accepting.truncate(anchored_states);
+                self.fallback.truncate(anchored_states);
+                return;
+            }
+
+            let set = sets[i].clone();
+            self.accepting
+                .push(set.iter().any(|&s| self.is_accepting_state(s)));
+            self.fallback
+                .push(StateId::from_u32(anchored_states as u32));
+            let mut row = Vec::with_capacity(stride);
+            for column in 0..stride {
+                let mut next: Vec<_> = set
+                    .iter()
+                    .map(|&s| self.table[s.as_usize() * stride + column])
+                    .filter(|&next| next != Self::DEAD)
+                    .collect();
+                next.push(self.start_state);
+                next.sort_unstable();
+                next.dedup();
+
+                let label = match labels.entry(next) {
+                    Entry::Occupied(e) => *e.get(),
+                    Entry::Vacant(e) => {
+                        let label = StateId::from_u32((anchored_states + sets.len()) as u32);
+                        sets.push(e.key().clone());
+                        *e.insert(label)
+                    }
+                };
+                row.push(label);
+            }
+            self.table.extend(row);
+            i += 1;
+        }
+
+        self.total_states += sets.len();
+        self.unanchored_start_state = Some(StateId::from_u32(anchored_states as u32));
+    }
+}
+
+impl<T> From<DFA<T>> for DenseDFA<T>
+where
+    T: Clone + Eq + Hash + SymbolRanges,
+{
+    #[inline]
+    fn from(dfa: DFA<T>) -> Self {
+        Self::from(&dfa)
+    }
+}
+
+/// Convert the states of anchored searches back to a DFA, with a transition on each column of the
+/// alphabet that doesn't lead to the dead state.
+impl<T> From<&DenseDFA<T>> for DFA<T>
+where
+    T: Clone + Eq + Hash,
+{
+    #[inline]
+    fn from(dense: &DenseDFA<T>) -> Self {
+        let anchored_states = dense
+            .unanchored_start_state
+            .map_or(dense.total_states, StateId::as_usize);
+        let mut dfa = DFA::new();
+        for _ in 1..anchored_states {
+            dfa.add_state(false);
+        }
+        dfa.start_state = dense.start_state.as_usize();
+        dfa.accepting_states = (0..anchored_states)
+            .filter(|&state| dense.accepting[state])
+            .collect();
+
+        let columns = dense.alphabet.len();
+        for state in 0..anchored_states {
+            let row = &dense.table[state * columns..(state + 1) * columns];
+            for (symbol, &next) in dense.alphabet.iter().zip(row) {
+                if next != DenseDFA::<T>::DEAD {
+                    dfa.add_transition(state, next.as_usize(), symbol.clone());
+                }
+            }
+        }
+        dfa
+    }
+}
+
+impl<T> DenseDFA<T> {
+    /// Determine if the given input is accepted by the DFA.
+    #[inline]
+    pub fn is_match<I>(&self, input: I) -> bool
+    where
+        I::Item: Symbol,
+        I: IntoIterator,
+    {
+        let mut state = self.start_state;
+        for is in input {
+            state = match self.next_state(state, &is) {
+                Some(next) => next,
+                None => return false,
+            };
+        }
+        self.is_accepting_state(state)
+    }
+
+    #[inline]
+    pub fn find_shortest<I>(&self, input: I) -> Option<Match<I::Item>>
+    where
+        I::Item: Symbol,
+        I: IntoIterator,
+    {
+        self.find_shortest_at(input, 0)
+    }
+
+    #[inline]
+    pub fn find_shortest_at<I>(&self, input: I, start: usize) -> Option<Match<I::Item>>
+    where
+        I::Item: Symbol,
+        I: IntoIterator,
+    {
+        self.find_anchored_impl(matching::skip_to(input.into_iter(), start)?, start, true)
+    }
+
+    #[inline]
+    pub fn find<I>(&self, input: I) -> Option<Match<I::Item>>
+    where
+        I::Item: Symbol,
+        I: IntoIterator,
+    {
+        self.find_at(input, 0)
+    }
+
+    #[inline]
+    pub fn find_at<I>(&self, input: I, start: usize) -> Option<Match<I::Item>>
+    where
+        I::Item: Symbol,
+        I: IntoIterator,
+    {
+        self.find_anchored_impl(matching::skip_to(input.into_iter(), start)?, start, false)
+    }
+
+    /// Find the leftmost match anywhere in the input. Of the matches that start at the leftmost
+    /// position, the longest is returned.
+    #[inline]
+    pub fn find_unanchored<I>(&self, input: I) -> Option<Match<I::Item>>
+    where
+        I::Item: Symbol,
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        self.find_unanchored_at(input, 0)
+    }
+
+    /// Find the leftmost match that starts at or after the `start`-th input symbol. Of the
+    /// matches that start at the leftmost position, the longest is returned.
+    #[inline]
+    pub fn find_unanchored_at<I>(&self, input: I, start: usize) -> Option<Match<I::Item>>
+    where
+        I::Item: Symbol,
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        self.find_unanchored_impl(input, start, false)
+    }
+
+    /// Find the leftmost match that starts at or after the `start`-th input symbol. Of the
+    /// matches that start at the leftmost position, the shortest is returned.
+    #[inline]
+    pub fn find_shortest_unanchored_at<I>(&self, input: I, start: usize) -> Option<Match<I::Item>>
+    where
+        I::Item: Symbol,
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        self.find_unanchored_impl(input, start, true)
+    }
+
+    /// Find a match that starts at or after the `start`-th input symbol, as configured by the
+    /// search.
+    #[inline]
+    pub fn search<I>(&self, input: I, start: usize, search: &Search) -> Option<Match<I::Item>>
+    where
+        I::Item: Symbol,
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        if search.anchored {
+            self.find_anchored_impl(
+                matching::skip_to(input.into_iter(), start)?,
+                start,
+                search.shortest,
+            )
+        } else {
+            self.find_unanchored_impl(input, start, search.shortest)
+        }
+    }
+
+    /// Find the longest match that starts at each position of the input, including matches that
+    /// overlap.
+    #[inline]
+    pub fn find_overlapping<I>(&self, input: I) -> Vec<Match<I::Item>>
+    where
+        I::Item: Symbol,
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        let mut input = input.into_iter();
+        let mut pos = 0;
+        let mut matches = Vec::new();
+        loop {
+            matches.extend(self.find_anchored_impl(input.clone(), pos, false));
+
+            if input.next().is_none() {
+                return matches;
+            }
+            pos += 1;
+        }
+    }
+
+    #[inline]
+    fn find_unanchored_impl<I>(
+        &self,
+        input: I,
+        start: usize,
+        shortest: bool,
+    ) -> Option<Match<I::Item>>
+    where
+        I::Item: Symbol,
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        // Try each starting position in turn, including the position after the last symbol. A
+        // single pass from the unanchored start state first rules out inputs without any match.
+        let mut input = matching::skip_to(input.into_iter(), start)?;
+        if let Some(state) = self.unanchored_start_state {
+            if !self.is_match_from(input.clone(), state) {
+                return None;
+            }
+        }
+        let mut pos = start;
+        loop {
+            if let Some(m) = self.find_anchored_impl(input.clone(), pos, shortest) {
+                return Some(m);
+            }
+
+            input.next()?;
+            pos += 1;
+        }
+    }
+
+    /// Determine if an accepting state is reached on any prefix of the input, starting from the
+    /// given state.
+    #[inline]
+    fn is_match_from<I>(&self, input: I, mut state: StateId) -> bool
+    where
+        I::Item: Symbol,
+        I: Iterator,
+    {
+        if self.is_accepting_state(state) {
+            return true;
+        }
+        for is in input {
+            state = match self.next_state(state, &is) {
+                Some(next) => next,
+                None => return false,
+            };
+            if self.is_accepting_state(state) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Find a match that starts at the beginning of the given input, which begins at position
+    /// `start` of the original input.
+    #[inline]
+    fn find_anchored_impl<I>(
+        &self,
+        input: I,
+        start: usize,
+        shortest: bool,
+    ) -> Option<Match<I::Item>>
+    where
+        I::Item: Symbol,
+        I: Iterator,
+    {
+        let mut state = self.start_state;
+        let mut last_match = if self.is_accepting_state(state) {
+            Some(0)
+        } else {
+            None
+        };
+
+        let mut span = Vec::new();
+        if !(shortest && last_match.is_some()) {
+            for is in input {
+                state = match self.next_state(state, &is) {
+                    Some(next) => next,
+                    None => break,
+                };
+                span.push(is);
+
+                if self.is_accepting_state(state) {
+                    last_match = Some(span.len());
+                    if shortest {
+                        break;
+                    }
+                }
+            }
+        }
+
+        last_match.map(|len| {
+            span.truncate(len);
+            Match::new(start, start + len, span)
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ClassMap {
+    /// Deserialize the map, checking that the table has an entry for every code below 256, and
+    /// that the ranges of the codes above are sorted and disjoint.
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(serde::Deserialize)]
+        struct Fields {
+            low: Vec<u32>,
+            high: Vec<(u32, u32, u32)>,
+        }
+
+        let Fields { low, high } = Fields::deserialize(deserializer)?;
+        if low.len() != Self::LOW as usize {
+            return Err(D::Error::custom(
+                "class map has no column for some code below 256",
+            ));
+        }
+        let ordered = high
+            .iter()
+            .all(|&(start, end, _)| Self::LOW <= start && start <= end)
+            && high.windows(2).all(|pair| pair[0].1 < pair[1].0);
+        if !ordered {
+            return Err(D::Error::custom(
+                "class map ranges are not sorted and disjoint",
+            ));
+        }
+        Ok(Self { low, high })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for DenseDFA<T>
+where
+    T: serde::Deserialize<'de>,
+{
+    /// Deserialize the DFA, checking that its tables have an entry for every state and column, and
+    /// that every state and column they refer to exists, so that matching can't go out of bounds.
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(serde::Deserialize)]
+        struct Fields<T> {
+            start_state: StateId,
+            unanchored_start_state: Option<StateId>,
+            total_states: usize,
+            accepting: Vec<bool>,
+            alphabet: Vec<T>,
+            classes: ClassMap,
+            table: Vec<StateId>,
+            fallback: Vec<StateId>,
+        }
+
+        let fields = Fields::deserialize(deserializer)?;
+        let dfa = DenseDFA {
+            start_state: fields.start_state,
+            unanchored_start_state: fields.unanchored_start_state,
+            total_states: fields.total_states,
+            accepting: fields.accepting,
+            alphabet: fields.alphabet,
+            classes: fields.classes,
+            table: fields.table,
+            fallback: fields.fallback,
+        };
+
+        let states = dfa.total_states;
+        let exists = |state: StateId| state.as_usize() < states;
+        if dfa.accepting.len() != states || dfa.fallback.len() != states {
+            return Err(D::Error::custom(
+                "DFA is missing the acceptance or fallback of a state",
+            ));
+        }
+        if Some(dfa.table.len()) != states.checked_mul(dfa.alphabet.len()) {
+            return Err(D::Error::custom("DFA table is missing a row or column"));
+        }
+        if !exists(dfa.start_state) || !dfa.unanchored_start_state.into_iter().all(exists) {
+            return Err(D::Error::custom("DFA start state doesn't exist"));
+        }
+        let mut transitions = dfa.table.iter().chain(&dfa.fallback);
+        if !transitions.all(|&next| next == Self::DEAD || exists(next)) {
+            return Err(D::Error::custom(
+                "DFA transition leads to a state that doesn't exist",
+            ));
+        }
+        if dfa
+            .classes
+            .columns()
+            .any(|column| column >= dfa.alphabet.len())
+        {
+            return Err(D::Error::custom(
+                "DFA class map refers to a column that doesn't exist",
+            ));
+        }
+        Ok(dfa)
+    }
+}
@@ -1,13 +1,19 @@
-use crate::matching::Match;
-use crate::table::Table;
+use crate::collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use crate::dense::SymbolRanges;
+use crate::dot;
+use crate::matching::{self, Match};
+use crate::prelude::*;
+use crate::stateset::StateSet;
+use crate::table::{RowIterator, Table};
 
-use std::collections::{HashMap, HashSet};
-use std::hash::Hash;
-use std::iter::Peekable;
-use std::rc::Rc;
+use alloc::borrow::Cow;
+use core::fmt;
+use core::hash::Hash;
+use core::iter::Peekable;
 
 /// A deterministic finite automaton, or DFA.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DFA<T>
 where
     T: Clone + Eq + Hash,
@@ -18,16 +24,34 @@ where
     /// < total_states.
     pub total_states: usize,
     /// The set of accepting states.
-    pub accepting_states: HashSet<usize>,
+    pub accepting_states: StateSet,
+    /// The IDs of the patterns matched by accepting states, for DFAs that match several patterns.
+    /// Accepting states without an entry match no particular pattern.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub patterns: HashMap<usize, BTreeSet<usize>>,
     /// A lookup table for transitions between states.
     pub transition: Table<usize, Transition<T>, usize>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Transition<T>(pub T)
 where
     T: Clone + Eq + Hash;
 
+/// Must be implemented by DFA transition symbol types to support boolean operations on DFAs, such
+/// as the complement.
+pub trait SymbolSet: Sized {
+    /// Return a symbol that matches exactly the input symbols matched by none of the given symbols.
+    fn complement_of(symbols: &[&Self]) -> Self;
+
+    /// Return a symbol that matches exactly the input symbols matched by both symbols.
+    fn intersection(&self, other: &Self) -> Self;
+
+    /// Determine if the symbol matches no input symbols.
+    fn is_empty(&self) -> bool;
+}
+
 impl<T> From<T> for Transition<T>
 where
     T: Clone + Eq + Hash,
@@ -48,7 +72,8 @@ where
         Self {
             start_state: 0,
             total_states: 1,
-            accepting_states: HashSet::new(),
+            accepting_states: StateSet::new(),
+            patterns: HashMap::new(),
             transition: Table::new(),
         }
     }
@@ -91,14 +116,690 @@ where
         }
     }
 
+    /// Iterate over the transitions out of a state and the states they lead to.
     #[inline]
-    pub fn transitions_on(&self, state: &usize) -> HashMap<&Transition<T>, &usize> {
-        self.transition.get_row(state)
+    pub fn transitions_on(&self, state: &usize) -> RowIterator<'_, Transition<T>, usize> {
+        self.transition.row(state)
     }
 
     #[inline]
     pub fn is_accepting_state(&self, state: &usize) -> bool {
-        self.accepting_states.iter().any(|s| s == state)
+        self.accepting_states.contains(state)
+    }
+
+    /// Mark a state as accepting, matching the pattern with the given ID.
+    #[inline]
+    pub fn add_pattern(&mut self, state: usize, id: usize) {
+        self.accepting_states.insert(state);
+        self.patterns.entry(state).or_default().insert(id);
+    }
+
+    /// The IDs of the patterns matched by a state, which are empty unless it is accepting.
+    #[inline]
+    pub fn pattern_ids(&self, state: &usize) -> impl Iterator<Item = usize> + '_ {
+        let ids = if self.is_accepting_state(state) {
+            self.patterns.get(state)
+        } else {
+            None
+        };
+        ids.into_iter().flatten().copied()
+    }
+
+    /// Render the DFA in the Graphviz DOT language, labeling transitions with the `Display` form of
+    /// their symbols.
+    #[inline]
+    pub fn to_dot(&self) -> String
+    where
+        T: fmt::Display,
+    {
+        let edges = (&self.transition)
+            .into_iter()
+            .map(|(&from, t, &to)| (from, t.0.to_string(), to))
+            .collect();
+        dot::render(
+            self.total_states,
+            self.start_state,
+            |s| self.is_accepting_state(&s),
+            edges,
+        )
+    }
+
+    /// Determine if the DFA accepts no inputs at all, which is the case if no accepting state can
+    /// be reached from the start state.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        let mut seen = HashSet::new();
+        seen.insert(self.start_state);
+        let mut stack = vec![self.start_state];
+        while let Some(state) = stack.pop() {
+            if self.is_accepting_state(&state) {
+                return false;
+            }
+
+            for (_, &next) in self.transitions_on(&state) {
+                if seen.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+        true
+    }
+
+    /// Determine for each state whether an accepting state can be reached from it. States from
+    /// which none can be reached are dead: once one is entered, the input can't be accepted.
+    #[inline]
+    pub fn live_states(&self) -> Vec<bool> {
+        let mut incoming = vec![Vec::new(); self.total_states];
+        for (&state, _, &next) in &self.transition {
+            incoming[next].push(state);
+        }
+
+        // Search backwards from the accepting states.
+        let mut live = vec![false; self.total_states];
+        let mut stack: Vec<usize> = self.accepting_states.iter().collect();
+        for &state in &stack {
+            live[state] = true;
+        }
+        while let Some(state) = stack.pop() {
+            for &prev in &incoming[state] {
+                if !live[prev] {
+                    live[prev] = true;
+                    stack.push(prev);
+                }
+            }
+        }
+        live
+    }
+
+    /// Generate a random input of at most `max_len` symbols accepted by the DFA, as the sequence
+    /// of transition symbols taken from the start state to an accepting state, or return `None`
+    /// if there is none. `rng` is called for random numbers, which should be uniformly
+    /// distributed. Each step of the walk is weighted by the number of accepted inputs it leads
+    /// to, so the walk never gets stuck, and every path to an accepting state within the length
+    /// is equally likely.
+    ///
+    /// ```
+    /// use automata::DFA;
+    ///
+    /// // ab*
+    /// let mut dfa = DFA::new();
+    /// let s = dfa.add_state(true);
+    /// dfa.add_transition(dfa.start_state, s, 'a');
+    /// dfa.add_transition(s, s, 'b');
+    ///
+    /// let mut seed = 1u64;
+    /// let mut rng = || {
+    ///     seed ^= seed << 13;
+    ///     seed ^= seed >> 7;
+    ///     seed ^= seed << 17;
+    ///     seed
+    /// };
+    /// let sample = dfa.sample(&mut rng, 5).unwrap();
+    /// assert!(sample.len() <= 5);
+    /// assert!(dfa.is_match(sample));
+    /// assert_eq!(None, dfa.sample(&mut rng, 0));
+    /// ```
+    #[inline]
+    pub fn sample<R>(&self, mut rng: R, max_len: usize) -> Option<Vec<T>>
+    where
+        R: FnMut() -> u64,
+    {
+        // The number of paths of at most `k` transitions from each state to an accepting state,
+        // for each `k`. The counts grow exponentially, so they are approximated by floats.
+        let accepting: Vec<f64> = (0..self.total_states)
+            .map(|s| {
+                if self.is_accepting_state(&s) {
+                    1.0
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        let mut paths = vec![accepting.clone()];
+        for k in 0..max_len {
+            let row = (0..self.total_states)
+                .map(|s| {
+                    let next: f64 = self.transitions_on(&s).map(|(_, &n)| paths[k][n]).sum();
+                    accepting[s] + next
+                })
+                .collect();
+            paths.push(row);
+        }
+        if paths[max_len][self.start_state] == 0.0 {
+            return None;
+        }
+
+        let mut sample = Vec::new();
+        let mut state = self.start_state;
+        for remaining in (0..max_len).rev() {
+            let choices: Vec<_> = self
+                .transitions_on(&state)
+                .filter(|(_, &next)| paths[remaining][next] > 0.0)
+                .collect();
+
+            // Choose between stopping and each transition, in proportion to their paths.
+            let uniform = (rng() >> 11) as f64 / (1u64 << 53) as f64;
+            let mut r = uniform * paths[remaining + 1][state] - accepting[state];
+            if r < 0.0 {
+                break;
+            }
+            let (t, &next) = choices
+                .iter()
+                .find(|(_, &next)| {
+                    r -= paths[remaining][next];
+                    r < 0.0
+                })
+                .or_else(|| choices.last())
+                .copied()?;
+            sample.push(t.0.clone());
+            state = next;
+        }
+        Some(sample)
+    }
+
+    /// Find one of the shortest inputs accepted by the DFA, as the sequence of transition symbols
+    /// taken from the start state to an accepting state. Any input symbol matched by each
+    /// transition symbol may be used to construct a concrete input.
+    #[inline]
+    pub fn shortest_accepted(&self) -> Option<Vec<T>> {
+        // Breadth-first search, remembering the transition each state was first reached by.
+        let mut parents: HashMap<usize, (usize, &Transition<T>)> = HashMap::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(self.start_state);
+        while let Some(state) = queue.pop_front() {
+            if self.is_accepting_state(&state) {
+                let mut path = Vec::new();
+                let mut current = state;
+                while current != self.start_state {
+                    let (parent, t) = parents[&current];
+                    path.push(t.0.clone());
+                    current = parent;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            for (t, &next) in self.transitions_on(&state) {
+                if next != self.start_state && !parents.contains_key(&next) {
+                    parents.insert(next, (state, t));
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<T> DFA<T>
+where
+    T: Clone + Eq + Hash + SymbolRanges,
+{
+    /// Count the inputs of length `n` that the DFA accepts, or return `None` if the count
+    /// overflows a `u128`. The number of inputs of each length that lead from one state to another
+    /// form a matrix, whose `n`th power is computed by repeated squaring, so that only `O(log n)`
+    /// matrix products are needed. Since the intermediate powers count inputs shorter than `n`
+    /// that need not be accepted, they may overflow even if the result doesn't.
+    ///
+    /// ```
+    /// use automata::DFA;
+    ///
+    /// // (a|b)*a
+    /// let mut dfa = DFA::new();
+    /// let s = dfa.add_state(true);
+    /// for &(from, to) in &[(dfa.start_state, dfa.start_state), (s, dfa.start_state)] {
+    ///     dfa.add_transition(from, to, 'b');
+    /// }
+    /// for &from in &[dfa.start_state, s] {
+    ///     dfa.add_transition(from, s, 'a');
+    /// }
+    ///
+    /// assert_eq!(Some(0), dfa.count_accepted(0));
+    /// assert_eq!(Some(1 << 9), dfa.count_accepted(10));
+    /// assert_eq!(None, dfa.count_accepted(200));
+    /// ```
+    #[inline]
+    pub fn count_accepted(&self, n: usize) -> Option<u128> {
+        self.count(n)
+    }
+
+    /// Count the inputs of length `n` that the DFA accepts, like
+    /// [`count_accepted`](Self::count_accepted), but without overflowing.
+    #[cfg(feature = "bigint")]
+    #[inline]
+    pub fn count_accepted_big(&self, n: usize) -> num_bigint::BigUint {
+        self.count(n)
+            .expect("arbitrary-precision arithmetic shouldn't overflow")
+    }
+
+    #[inline]
+    fn count<C>(&self, mut n: usize) -> Option<C>
+    where
+        C: Count,
+    {
+        // Only the states on a path from the start state to an accepting state are counted, so
+        // that dead states don't overflow the counts.
+        let live = self.live_states();
+        let mut index = vec![None; self.total_states];
+        let mut states = Vec::new();
+        if live[self.start_state] {
+            index[self.start_state] = Some(0);
+            states.push(self.start_state);
+        }
+        let mut i = 0;
+        while let Some(&state) = states.get(i) {
+            for (_, &next) in self.transitions_on(&state) {
+                if live[next] && index[next].is_none() {
+                    index[next] = Some(states.len());
+                    states.push(next);
+                }
+            }
+            i += 1;
+        }
+
+        let k = states.len();
+        let mut counts = vec![vec![0u128; k]; k];
+        for (i, state) in states.iter().enumerate() {
+            for (t, next) in self.transitions_on(state) {
+                if let Some(j) = index[*next] {
+                    counts[i][j] += t.0.symbol_count();
+                }
+            }
+        }
+
+        // The counts of the inputs of length `n` from the start state, with the exponent in
+        // binary: the vector is multiplied by the power of the matrix for each bit that is set.
+        let mut base: Vec<Vec<C>> = counts
+            .into_iter()
+            .map(|row| row.into_iter().map(C::from_u128).collect())
+            .collect();
+        let mut reached: Vec<C> = (0..k)
+            .map(|i| C::from_u128(if i == 0 { 1 } else { 0 }))
+            .collect();
+        while n > 0 {
+            if n & 1 == 1 {
+                reached = (0..k)
+                    .map(|j| dot_product(reached.iter().zip(base.iter().map(|row| &row[j]))))
+                    .collect::<Option<_>>()?;
+            }
+            n >>= 1;
+            if n > 0 {
+                base = (0..k)
+                    .map(|i| {
+                        (0..k)
+                            .map(|j| {
+                                dot_product(base[i].iter().zip(base.iter().map(|row| &row[j])))
+                            })
+                            .collect()
+                    })
+                    .collect::<Option<_>>()?;
+            }
+        }
+
+        states
+            .iter()
+            .zip(&reached)
+            .filter(|(state, _)| self.is_accepting_state(state))
+            .try_fold(C::from_u128(0), |sum, (_, count)| sum.checked_add(count))
+    }
+}
+
+/// A number of inputs, which may be bounded.
+trait Count: Sized {
+    fn from_u128(n: u128) -> Self;
+
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+}
+
+impl Count for u128 {
+    #[inline]
+    fn from_u128(n: u128) -> Self {
+        n
+    }
+
+    #[inline]
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        u128::checked_add(*self, *other)
+    }
+
+    #[inline]
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        u128::checked_mul(*self, *other)
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl Count for num_bigint::BigUint {
+    #[inline]
+    fn from_u128(n: u128) -> Self {
+        n.into()
+    }
+
+    #[inline]
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+
+    #[inline]
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+}
+
+/// The sum of the products of the pairs, or `None` if it overflows.
+#[inline]
+fn dot_product<'a, C, I>(mut pairs: I) -> Option<C>
+where
+    C: Count + 'a,
+    I: Iterator<Item = (&'a C, &'a C)>,
+{
+    pairs.try_fold(C::from_u128(0), |sum, (a, b)| {
+        sum.checked_add(&a.checked_mul(b)?)
+    })
+}
+
+impl<T> DFA<T>
+where
+    T: Clone + Eq + Hash + SymbolSet,
+{
+    /// Produce a DFA that accepts exactly the inputs this DFA rejects. The DFA is first made total
+    /// with [`make_total`](Self::make_total), and then its accepting states are flipped.
+    #[inline]
+    pub fn complement(&self) -> Self {
+        let mut dfa = self.complete();
+        dfa.accepting_states = (0..dfa.total_states)
+            .filter(|s| !self.accepting_states.contains(s))
+            .collect();
+        dfa.patterns = HashMap::new();
+        dfa
+    }
+
+    /// Produce a DFA that accepts exactly the inputs accepted by both this DFA and `other`.
+    #[inline]
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.product(other, |a, b| a && b)
+    }
+
+    /// Produce a DFA that accepts exactly the inputs accepted by this DFA, `other`, or both. The
+    /// accepting states of the result match the pattern IDs of both.
+    #[inline]
+    pub fn union(&self, other: &Self) -> Self {
+        self.product(other, |a, b| a || b)
+    }
+
+    /// Produce a DFA that accepts exactly the inputs accepted by one of this DFA and `other`, but
+    /// not both.
+    #[inline]
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        self.product(other, |a, b| a != b)
+    }
+
+    /// Determine if every input accepted by this DFA is also accepted by `other`.
+    #[inline]
+    pub fn is_subset_of(&self, other: &Self) -> bool {
+        self.intersection(&other.complement()).is_empty()
+    }
+
+    /// Determine if this DFA and `other` accept exactly the same inputs.
+    #[inline]
+    pub fn is_equivalent(&self, other: &Self) -> bool {
+        self.is_subset_of(other) && other.is_subset_of(self)
+    }
+
+    /// Run both DFAs in parallel using the product construction. A pair of states is accepting if
+    /// `accept` returns true for whether each state is accepting, and then matches the pattern IDs
+    /// of both states. The result is minimized.
+    #[inline]
+    fn product<F>(&self, other: &Self, accept: F) -> Self
+    where
+        F: Fn(bool, bool) -> bool,
+    {
+        // Complete both DFAs, so that a pair of states only gets stuck when both states do.
+        let (a, b) = (self.complete(), other.complete());
+
+        let mut dfa = DFA::new();
+        let start = (a.start_state, b.start_state);
+        let mut labels = HashMap::new();
+        labels.insert(start, dfa.start_state);
+        let mut unmarked = vec![start];
+        let mark = |dfa: &mut DFA<T>, label: usize, (n1, n2): (usize, usize)| {
+            if accept(a.is_accepting_state(&n1), b.is_accepting_state(&n2)) {
+                dfa.accepting_states.insert(label);
+                for id in a.pattern_ids(&n1).chain(b.pattern_ids(&n2)) {
+                    dfa.add_pattern(label, id);
+                }
+            }
+        };
+        let start_label = dfa.start_state;
+        mark(&mut dfa, start_label, start);
+
+        while let Some(pair) = unmarked.pop() {
+            let label = labels[&pair];
+            for (t1, &n1) in a.transitions_on(&pair.0) {
+                for (t2, &n2) in b.transitions_on(&pair.1) {
+                    let t = t1.0.intersection(&t2.0);
+                    if t.is_empty() {
+                        continue;
+                    }
+
+                    let next = (n1, n2);
+                    let next_label = match labels.get(&next) {
+                        Some(&next_label) => next_label,
+                        None => {
+                            let next_label = dfa.add_state(false);
+                            mark(&mut dfa, next_label, next);
+                            labels.insert(next, next_label);
+                            unmarked.push(next);
+                            next_label
+                        }
+                    };
+                    dfa.transition.set(label, Transition(t), next_label);
+                }
+            }
+        }
+
+        dfa.minimize()
+    }
+
+    /// Determine if every state has a transition on every input symbol.
+    #[inline]
+    pub fn is_total(&self) -> bool {
+        (0..self.total_states).all(|state| self.missing_symbols(state).is_empty())
+    }
+
+    /// Add a dead state, with a transition to it on every input symbol for which a state has no
+    /// transition, so that every state has a transition on every input symbol. The dead state is
+    /// returned, or `None` if the DFA was already total and no state was added.
+    #[inline]
+    pub fn make_total(&mut self) -> Option<usize> {
+        if self.is_total() {
+            return None;
+        }
+
+        let dead = self.add_state(false);
+        for state in 0..self.total_states {
+            let rest = self.missing_symbols(state);
+            if !rest.is_empty() {
+                self.transition.set(state, Transition(rest), dead);
+            }
+        }
+        Some(dead)
+    }
+
+    /// Produce an equivalent DFA that is total. See [`make_total`](Self::make_total).
+    #[inline]
+    fn complete(&self) -> Self {
+        let mut dfa = self.clone();
+        dfa.make_total();
+        dfa
+    }
+
+    /// The input symbols on which the state has no transition.
+    #[inline]
+    fn missing_symbols(&self, state: usize) -> T {
+        let symbols: Vec<&T> = self.transitions_on(&state).map(|(t, _)| &t.0).collect();
+        T::complement_of(&symbols)
+    }
+}
+
+impl<T> DFA<T>
+where
+    T: Clone + Eq + Hash,
+{
+    /// Produce an equivalent DFA with the minimal number of states, using Hopcroft's algorithm.
+    ///
+    /// Transition labels are treated as opaque symbols, so the result is minimal with respect to
+    /// the labels that appear in the DFA. Unreachable states and states from which no accepting
+    /// state can be reached are removed.
+    #[inline]
+    pub fn minimize(&self) -> Self {
+        // Number the states reachable from the start state, with the start state first.
+        let mut index = HashMap::new();
+        let mut reachable = vec![self.start_state];
+        index.insert(self.start_state, 0);
+        let mut i = 0;
+        while i < reachable.len() {
+            for (_, &next) in self.transitions_on(&reachable[i]) {
+                if let Entry::Vacant(e) = index.entry(next) {
+                    e.insert(reachable.len());
+                    reachable.push(next);
+                }
+            }
+            i += 1;
+        }
+
+        // Number the distinct transition labels.
+        let mut symbols: HashMap<&Transition<T>, usize> = HashMap::new();
+        for (_, t, _) in &self.transition {
+            let len = symbols.len();
+            symbols.entry(t).or_insert(len);
+        }
+
+        // Compute the inverse transition function, with an implicit dead state that every missing
+        // transition leads to.
+        let dead = reachable.len();
+        let total = dead + 1;
+        let mut targets = vec![vec![dead; symbols.len()]; total];
+        for (s, state) in reachable.iter().enumerate() {
+            for (t, next) in self.transitions_on(state) {
+                targets[s][symbols[t]] = index[next];
+            }
+        }
+        let mut inverse = vec![vec![Vec::new(); total]; symbols.len()];
+        for (s, row) in targets.iter().enumerate() {
+            for (c, &next) in row.iter().enumerate() {
+                inverse[c][next].push(s);
+            }
+        }
+
+        // Start with the partition of non-accepting states and accepting states by the patterns
+        // they match.
+        let initial_key = |s: usize| {
+            if s != dead && self.is_accepting_state(&reachable[s]) {
+                Some(self.patterns.get(&reachable[s]))
+            } else {
+                None
+            }
+        };
+        let mut initial = BTreeMap::new();
+        let mut blocks: Vec<Vec<usize>> = Vec::new();
+        for s in 0..total {
+            let b = *initial.entry(initial_key(s)).or_insert_with(|| {
+                blocks.push(Vec::new());
+                blocks.len() - 1
+            });
+            blocks[b].push(s);
+        }
+        let mut block_of = vec![0; total];
+        for (b, block) in blocks.iter().enumerate() {
+            for &s in block {
+                block_of[s] = b;
+            }
+        }
+
+        let mut worklist: Vec<usize> = (0..blocks.len()).collect();
+        let mut in_worklist: Vec<bool> = vec![true; blocks.len()];
+        while let Some(splitter) = worklist.pop() {
+            in_worklist[splitter] = false;
+            let splitter_states = blocks[splitter].clone();
+
+            for sources in &inverse {
+                // The states that move into the splitter on this symbol.
+                let mut preimage = HashSet::new();
+                for &s in &splitter_states {
+                    preimage.extend(sources[s].iter().copied());
+                }
+
+                let touched: HashSet<usize> = preimage.iter().map(|&s| block_of[s]).collect();
+                for b in touched {
+                    let (inside, outside): (Vec<usize>, Vec<usize>) =
+                        blocks[b].iter().partition(|s| preimage.contains(s));
+                    if outside.is_empty() {
+                        continue;
+                    }
+
+                    // Split the block; the states outside the preimage form a new block.
+                    let new_block = blocks.len();
+                    for &s in &outside {
+                        block_of[s] = new_block;
+                    }
+                    let smaller_is_new = outside.len() <= inside.len();
+                    blocks[b] = inside;
+                    blocks.push(outside);
+                    in_worklist.push(false);
+
+                    if in_worklist[b] || smaller_is_new {
+                        worklist.push(new_block);
+                        in_worklist[new_block] = true;
+                    } else {
+                        worklist.push(b);
+                        in_worklist[b] = true;
+                    }
+                }
+            }
+        }
+
+        // Build the minimized DFA, numbering blocks in order of their first reachable state and
+        // dropping the block of the dead state.
+        let dead_block = block_of[dead];
+        let mut new_label = HashMap::new();
+        let mut dfa = DFA::new();
+        for s in 0..dead {
+            let b = block_of[s];
+            if b == dead_block || new_label.contains_key(&b) {
+                continue;
+            }
+
+            let label = if s == 0 { 0 } else { dfa.add_state(false) };
+            if self.is_accepting_state(&reachable[s]) {
+                dfa.accepting_states.insert(label);
+                for id in self.pattern_ids(&reachable[s]) {
+                    dfa.add_pattern(label, id);
+                }
+            }
+            new_label.insert(b, label);
+        }
+
+        // Equivalent states have equivalent transitions, so only one state of each block needs to
+        // contribute them.
+        let mut done = HashSet::new();
+        for (s, state) in reachable.iter().enumerate() {
+            let b = block_of[s];
+            if b == dead_block || !done.insert(b) {
+                continue;
+            }
+
+            for (t, next) in self.transitions_on(state) {
+                let next_block = block_of[index[next]];
+                if next_block != dead_block {
+                    dfa.transition
+                        .set(new_label[&b], t.clone(), new_label[&next_block]);
+                }
+            }
+        }
+
+        dfa
     }
 }
 
@@ -106,16 +807,51 @@ impl<T> DFA<T>
 where
     T: Clone + Eq + Hash,
 {
+    /// Iterate over the states the DFA passes through on the input, along with the positions of
+    /// the input symbols that lead to them. The iteration gets stuck as soon as there is no
+    /// transition on the next symbol or it leads to a dead state, from which no accepting state
+    /// can be reached.
+    ///
+    /// ```
+    /// use automata::dfa::IterState;
+    /// use automata::DFA;
+    ///
+    /// // ab*
+    /// let mut dfa = DFA::new();
+    /// let s = dfa.add_state(true);
+    /// dfa.add_transition(dfa.start_state, s, 'a');
+    /// dfa.add_transition(s, s, 'b');
+    ///
+    /// let positions: Vec<_> = dfa
+    ///     .iter_on("abba".chars())
+    ///     .map(|state| match state {
+    ///         IterState::Start(..) => None,
+    ///         IterState::Normal(_, pos, _, _) | IterState::Stuck(_, pos) => Some(pos),
+    ///     })
+    ///     .collect();
+    /// assert_eq!(vec![None, Some(0), Some(1), Some(2), Some(3)], positions);
+    /// ```
     #[inline]
     pub fn iter_on<I>(&self, input: I) -> Iter<'_, T, I::IntoIter>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+    {
+        self.iter_on_live(input, Cow::Owned(self.live_states()))
+    }
+
+    #[inline]
+    fn iter_on_live<'a, I>(&'a self, input: I, live: Cow<'a, [bool]>) -> Iter<'a, T, I::IntoIter>
     where
         T: PartialEq<I::Item>,
         I: IntoIterator,
     {
         Iter {
-            dfa: &self,
+            dfa: self,
+            live,
 
             input: input.into_iter().peekable(),
+            pos: 0,
             last: None,
         }
     }
@@ -127,9 +863,11 @@ where
         I: IntoIterator,
     {
         IntoIter {
+            live: self.live_states(),
             dfa: self,
 
             input: input.into_iter().peekable(),
+            pos: 0,
             last: None,
         }
     }
@@ -142,8 +880,12 @@ where
     I: Iterator,
 {
     dfa: &'a DFA<T>,
+    /// Whether each state is live, from [`DFA::live_states`].
+    live: Cow<'a, [bool]>,
 
     input: Peekable<I>,
+    /// The number of input symbols consumed so far.
+    pos: usize,
     last: Option<(LastIterState, usize)>,
 }
 
@@ -157,7 +899,13 @@ where
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        iter_on_next(&self.dfa, &mut self.input, &mut self.last)
+        iter_on_next(
+            self.dfa,
+            &self.live,
+            &mut self.input,
+            &mut self.pos,
+            &mut self.last,
+        )
     }
 }
 
@@ -168,8 +916,12 @@ where
     I: Iterator,
 {
     dfa: DFA<T>,
+    /// Whether each state is live, from [`DFA::live_states`].
+    live: Vec<bool>,
 
     input: Peekable<I>,
+    /// The number of input symbols consumed so far.
+    pos: usize,
     last: Option<(LastIterState, usize)>,
 }
 
@@ -183,18 +935,29 @@ where
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        iter_on_next(&self.dfa, &mut self.input, &mut self.last)
+        iter_on_next(
+            &self.dfa,
+            &self.live,
+            &mut self.input,
+            &mut self.pos,
+            &mut self.last,
+        )
     }
 }
 
+/// A step of the iteration over the states a DFA passes through on an input.
 #[derive(Debug)]
 pub enum IterState<I>
 where
     I: Iterator,
 {
+    /// The start state, and whether it is accepting.
     Start(usize, bool),
-    Normal(I::Item, usize, bool),
-    Stuck(usize),
+    /// An input symbol, its position in the input, the state it leads to, and whether that state
+    /// is accepting.
+    Normal(I::Item, usize, usize, bool),
+    /// The state the DFA got stuck in, and the position of the input symbol it couldn't consume.
+    Stuck(usize, usize),
 }
 
 enum LastIterState {
@@ -206,7 +969,9 @@ enum LastIterState {
 #[inline]
 fn iter_on_next<T, I>(
     dfa: &DFA<T>,
+    live: &[bool],
     input: &mut Peekable<I>,
+    pos: &mut usize,
     last: &mut Option<(LastIterState, usize)>,
 ) -> Option<IterState<I>>
 where
@@ -228,18 +993,15 @@ where
         Some((LastIterState::Stuck, _)) => return None,
     };
 
-    let peek_is = match input.peek() {
-        Some(v) => v,
-        // No more input, so last item was the final.
-        None => return None,
-    };
+    // No more input, so last item was the final.
+    let peek_is = input.peek()?;
 
-    let transitions = dfa.transitions_on(&current);
-    let next = match transitions
-        .iter()
-        .find(|(&Transition(t), _)| *t == *peek_is)
+    let next = match dfa
+        .transitions_on(&current)
+        .find(|(Transition(t), _)| *t == *peek_is)
+        .filter(|(_, &next_state)| live[next_state])
     {
-        Some((_, &&next_state)) => {
+        Some((_, &next_state)) => {
             // Consume input symbol.
             let is = input.next().unwrap();
 
@@ -247,12 +1009,13 @@ where
             let is_final = dfa.is_accepting_state(&next_state);
 
             *last = Some((LastIterState::Normal, next_state));
-            Some(IterState::Normal(is, next_state, is_final))
+            *pos += 1;
+            Some(IterState::Normal(is, *pos - 1, next_state, is_final))
         }
-        // No more transitions, so stuck.
+        // No more transitions, or only one to a dead state, so stuck.
         None => {
             *last = Some((LastIterState::Stuck, current));
-            Some(IterState::Stuck(current))
+            Some(IterState::Stuck(current, *pos))
         }
     };
 
@@ -271,8 +1034,8 @@ where
         I: IntoIterator,
     {
         match self.iter_on(input).last() {
-            Some(IterState::Start(_, is_final) | IterState::Normal(_, _, is_final)) => is_final,
-            Some(IterState::Stuck(_)) => false,
+            Some(IterState::Start(_, is_final) | IterState::Normal(_, _, _, is_final)) => is_final,
+            Some(IterState::Stuck(..)) => false,
             // None means that no movement happened; check if the current state (start state) is an
             // accepting state.
             None => unreachable!(),
@@ -288,6 +1051,8 @@ where
         self.find_shortest_at(input, 0)
     }
 
+    /// Find the shortest match that starts at the `start`-th input symbol. See
+    /// [`find_at`](Self::find_at).
     #[inline]
     pub fn find_shortest_at<I>(&self, input: I, start: usize) -> Option<Match<I::Item>>
     where
@@ -306,6 +1071,24 @@ where
         self.find_at(input, 0)
     }
 
+    /// Find the longest match that starts at the `start`-th input symbol. The input is the whole
+    /// input from its first symbol, of which the first `start` symbols are skipped, and the
+    /// positions of the match are indices into the whole input.
+    ///
+    /// ```
+    /// use automata::DFA;
+    ///
+    /// // ab
+    /// let mut dfa = DFA::new();
+    /// let (s1, s2) = (dfa.add_state(false), dfa.add_state(true));
+    /// dfa.add_transition(dfa.start_state, s1, 'a');
+    /// dfa.add_transition(s1, s2, 'b');
+    ///
+    /// let m = dfa.find_at("abab".chars(), 2).unwrap();
+    /// assert_eq!((2, 4), (m.start(), m.end()));
+    /// assert_eq!(None, dfa.find_at("abab".chars(), 1));
+    /// assert_eq!(None, dfa.find_at("abab".chars(), 5));
+    /// ```
     #[inline]
     pub fn find_at<I>(&self, input: I, start: usize) -> Option<Match<I::Item>>
     where
@@ -315,52 +1098,121 @@ where
         self.find_at_impl(input, start, false)
     }
 
+    /// Find the leftmost match anywhere in the input. Of the matches that start at the leftmost
+    /// position, the longest is returned.
+    #[inline]
+    pub fn find_unanchored<I>(&self, input: I) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        self.find_unanchored_at(input, 0)
+    }
+
+    /// Find the leftmost match that starts at or after the `start`-th input symbol. Of the
+    /// matches that start at the leftmost position, the longest is returned.
+    #[inline]
+    pub fn find_unanchored_at<I>(&self, input: I, start: usize) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        self.find_unanchored_impl(input, start, false)
+    }
+
+    /// Find the leftmost match that starts at or after the `start`-th input symbol. Of the
+    /// matches that start at the leftmost position, the shortest is returned.
+    #[inline]
+    pub fn find_shortest_unanchored_at<I>(&self, input: I, start: usize) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        self.find_unanchored_impl(input, start, true)
+    }
+
+    #[inline]
+    fn find_unanchored_impl<I>(
+        &self,
+        input: I,
+        start: usize,
+        shortest: bool,
+    ) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        // Try each starting position in turn, including the position after the last symbol.
+        let live = self.live_states();
+        let mut input = matching::skip_to(input.into_iter(), start)?;
+        let mut pos = start;
+        loop {
+            if let Some(m) = self.find_anchored_impl(input.clone(), pos, shortest, &live) {
+                return Some(m);
+            }
+
+            input.next()?;
+            pos += 1;
+        }
+    }
+
     #[inline]
     fn find_at_impl<I>(&self, input: I, start: usize, shortest: bool) -> Option<Match<I::Item>>
     where
         T: PartialEq<I::Item>,
         I: IntoIterator,
     {
+        let live = self.live_states();
+        self.find_anchored_impl(
+            matching::skip_to(input.into_iter(), start)?,
+            start,
+            shortest,
+            &live,
+        )
+    }
+
+    /// Find a match that starts at the beginning of the given input, which begins at position
+    /// `start` of the original input.
+    #[inline]
+    fn find_anchored_impl<I>(
+        &self,
+        input: I,
+        start: usize,
+        shortest: bool,
+        live: &[bool],
+    ) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: Iterator,
+    {
+        // Only the length of the last match is tracked, and the span is cut down to it at the end.
         let mut last_match = None;
-        let iter = self.iter_on(input).skip(start).enumerate();
-
-        // Ensure span dropped before unwrapping Rc's.
-        {
-            let mut span = Vec::new();
-            for (i, iter_state) in iter {
-                let is_final = match iter_state {
-                    IterState::Start(_, is_final) => is_final,
-                    IterState::Normal(is, _, is_final) => {
-                        let is_rc = Rc::new(is);
-                        span.push(is_rc);
-
-                        is_final
-                    }
-                    IterState::Stuck(_) => break,
-                };
+        let mut span = Vec::new();
+        for iter_state in self.iter_on_live(input, Cow::Borrowed(live)) {
+            let (len, is_final) = match iter_state {
+                IterState::Start(_, is_final) => (0, is_final),
+                IterState::Normal(is, pos, _, is_final) => {
+                    span.push(is);
+                    (pos + 1, is_final)
+                }
+                IterState::Stuck(..) => break,
+            };
 
-                if is_final {
-                    last_match = Some(Match::new(start, i + 1, span.clone()));
-                    if shortest {
-                        break;
-                    }
+            if is_final {
+                last_match = Some(len);
+                if shortest {
+                    break;
                 }
             }
         }
 
-        last_match.map(|m| {
-            Match::new(
-                m.start(),
-                m.end(),
-                m.span
-                    .into_iter()
-                    .map(|rc| match Rc::try_unwrap(rc) {
-                        Ok(v) => v,
-                        // Shouldn't ever have any lingering references.
-                        Err(_) => unreachable!("MatchRc somehow had lingering references"),
-                    })
-                    .collect(),
-            )
+        last_match.map(|len| {
+            span.truncate(len);
+            Match::new(start, start + len, span)
         })
     }
 }
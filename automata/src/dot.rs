@@ -0,0 +1,49 @@
+use crate::prelude::*;
+
+use core::fmt::Write;
+
+/// Render an automaton in the Graphviz DOT language. Edges are given as `(from, label, to)`.
+#[inline]
+pub(crate) fn render<F>(
+    total_states: usize,
+    start_state: usize,
+    is_accepting: F,
+    mut edges: Vec<(usize, String, usize)>,
+) -> String
+where
+    F: Fn(usize) -> bool,
+{
+    edges.sort();
+
+    let mut dot = String::new();
+    dot.push_str("digraph {\n");
+    dot.push_str("    rankdir=LR;\n");
+    dot.push_str("    start [shape=point];\n");
+    for state in 0..total_states {
+        let shape = if is_accepting(state) {
+            "doublecircle"
+        } else {
+            "circle"
+        };
+        writeln!(dot, "    {} [shape={}];", state, shape).unwrap();
+    }
+    writeln!(dot, "    start -> {};", start_state).unwrap();
+    for (from, label, to) in edges {
+        writeln!(
+            dot,
+            "    {} -> {} [label=\"{}\"];",
+            from,
+            to,
+            escape(&label)
+        )
+        .unwrap();
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Escape a string for use in a quoted DOT label.
+#[inline]
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,75 @@
+use crate::collections::HashSet;
+
+use core::hash::Hash;
+
+/// A pool of distinct transition symbols. Interning a symbol returns a clone of the equal symbol
+/// already in the pool, if there is one.
+///
+/// For symbols whose clones share their storage, such as reference-counted or persistent data
+/// structures, an automaton whose transitions are labeled with interned symbols stores each
+/// distinct symbol once, no matter how many transitions are labeled with it.
+///
+/// ```
+/// use automata::intern::Interner;
+/// use std::rc::Rc;
+///
+/// let mut interner = Interner::new();
+/// let a = interner.intern(Rc::<str>::from("a-z"));
+/// let b = interner.intern(Rc::<str>::from("a-z"));
+/// assert!(Rc::ptr_eq(&a, &b));
+/// assert_eq!(1, interner.len());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Interner<T>
+where
+    T: Clone + Eq + Hash,
+{
+    pool: HashSet<T>,
+}
+
+impl<T> Interner<T>
+where
+    T: Clone + Eq + Hash,
+{
+    /// Create an empty pool.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            pool: HashSet::new(),
+        }
+    }
+
+    /// Get a clone of the pooled symbol equal to the given one, adding it to the pool if there is
+    /// none.
+    #[inline]
+    pub fn intern(&mut self, symbol: T) -> T {
+        match self.pool.get(&symbol) {
+            Some(pooled) => pooled.clone(),
+            None => {
+                self.pool.insert(symbol.clone());
+                symbol
+            }
+        }
+    }
+
+    /// The number of distinct symbols in the pool.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.pool.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pool.is_empty()
+    }
+}
+
+impl<T> Default for Interner<T>
+where
+    T: Clone + Eq + Hash,
+{
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,403 @@
+use crate::collections::HashMap;
+use crate::convert::{self, Disjoin};
+use crate::matching::{self, Match, MatchKind};
+use crate::nfa::NFA;
+use crate::prelude::*;
+use crate::stateid::StateId;
+use crate::step::{DfaState, StepResult};
+
+use core::hash::Hash;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// A DFA that is constructed from an NFA on demand, as its states are reached by the input.
+///
+/// Constructed states are kept in a cache of bounded size. When the cache is full, it is cleared
+/// and construction starts over from the states currently needed. This avoids the exponential
+/// up-front cost of the subset construction for pathological patterns, while matching at the
+/// speed of a DFA once the states in use have been constructed.
+///
+/// Each search takes a cache from a pool and returns it when it is done, so searches on a lazy DFA
+/// shared between threads run in parallel, each with a cache of its own. The pool is only locked
+/// to take or return a cache.
+#[derive(Debug)]
+pub struct LazyDFA<T>
+where
+    T: Clone + Eq + Hash,
+{
+    nfa: NFA<T>,
+    kind: MatchKind,
+    /// The disjoint symbol classes that label the transitions.
+    alphabet: Vec<T>,
+    /// The set state of the start state.
+    start: Vec<usize>,
+    cache_capacity: usize,
+    /// The caches that are not in use by a search.
+    caches: Mutex<Vec<Cache>>,
+}
+
+#[derive(Debug, Default)]
+struct Cache {
+    /// The NFA states in each constructed state.
+    states: Vec<Vec<usize>>,
+    /// Whether each constructed state is accepting.
+    accepting: Vec<bool>,
+    /// The label of each constructed state.
+    labels: HashMap<Vec<usize>, StateId>,
+    /// The row-major transition table, with a row of `alphabet.len()` entries for each state.
+    table: Vec<StateId>,
+}
+
+impl Cache {
+    #[inline]
+    fn is_accepting_state(&self, state: StateId) -> bool {
+        self.accepting[state.as_usize()]
+    }
+}
+
+/// The table entry of a transition that has not been constructed yet.
+const UNKNOWN: StateId = StateId::MAX;
+/// The table entry of a missing transition.
+const DEAD: StateId = StateId::from_u32(u32::MAX - 1);
+/// The most states that can be cached, since the largest labels are entries of the table.
+const CACHE_CAPACITY_LIMIT: usize = (u32::MAX - 1) as usize;
+
+impl<T> LazyDFA<T>
+where
+    T: Clone + Disjoin + Eq + Hash,
+{
+    /// The number of states cached by default.
+    pub const DEFAULT_CACHE_CAPACITY: usize = 1 << 10;
+
+    /// Create a lazy DFA from an NFA, with leftmost-longest match semantics.
+    #[inline]
+    pub fn new(nfa: NFA<T>) -> Self {
+        Self::with_match_kind(nfa, MatchKind::LeftmostLongest)
+    }
+
+    /// Create a lazy DFA from an NFA that searches using the given match semantics.
+    ///
+    /// A DFA built with [`MatchKind::LeftmostFirst`] stops at the match preferred by that
+    /// semantics, so it should only be used for searching, and not to determine if the entire input
+    /// is accepted.
+    #[inline]
+    pub fn with_match_kind(mut nfa: NFA<T>, kind: MatchKind) -> Self {
+        // Each new state needs the closures of the NFA states it reaches.
+        if !nfa.has_cached_epsilon_closures() {
+            nfa.cache_epsilon_closures();
+        }
+        let alphabet = convert::alphabet(&nfa);
+        let start = convert::start(&nfa, kind);
+        Self {
+            nfa,
+            kind,
+            alphabet,
+            start,
+            cache_capacity: Self::DEFAULT_CACHE_CAPACITY,
+            caches: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Set the maximum number of states kept in the cache. The capacity is at least one state, and
+    /// at most `u32::MAX - 1` states.
+    #[inline]
+    pub fn set_cache_capacity(&mut self, capacity: usize) {
+        self.cache_capacity = capacity.clamp(1, CACHE_CAPACITY_LIMIT);
+        self.clear_cache();
+    }
+
+    #[inline]
+    pub fn cache_capacity(&self) -> usize {
+        self.cache_capacity
+    }
+
+    /// The number of states in the largest cache that is not in use by a search.
+    #[inline]
+    pub fn cached_states(&self) -> usize {
+        let caches = self.lock_caches();
+        caches
+            .iter()
+            .map(|cache| cache.states.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    #[inline]
+    pub fn clear_cache(&self) {
+        self.lock_caches().clear();
+    }
+
+    /// Lock the pool of caches. The pool is never left inconsistent, since caches are only pushed
+    /// and popped while it is locked.
+    #[inline]
+    fn lock_caches(&self) -> MutexGuard<'_, Vec<Cache>> {
+        self.caches.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Run a search with a cache taken from the pool, returning the cache afterwards. If the search
+    /// panics, the cache may be inconsistent, so it is dropped instead.
+    #[inline]
+    fn with_cache<R, F>(&self, search: F) -> R
+    where
+        F: FnOnce(&mut Cache) -> R,
+    {
+        let mut cache = self.lock_caches().pop().unwrap_or_default();
+        let result = search(&mut cache);
+        self.lock_caches().push(cache);
+        result
+    }
+
+    /// Get the label of the given set state, constructing it if it is not in the cache.
+    #[inline]
+    fn state(&self, cache: &mut Cache, nfa_states: Vec<usize>) -> StateId {
+        if let Some(&label) = cache.labels.get(&nfa_states) {
+            return label;
+        }
+
+        if cache.states.len() >= self.cache_capacity {
+            *cache = Cache::default();
+        }
+        self.insert(cache, nfa_states)
+    }
+
+    /// Add a set state that is not in the cache.
+    #[inline]
+    fn insert(&self, cache: &mut Cache, nfa_states: Vec<usize>) -> StateId {
+        // The cache is cleared before it holds more than `CACHE_CAPACITY_LIMIT` states.
+        let label = StateId::from_u32(cache.states.len() as u32);
+        let is_final = nfa_states.iter().any(|s| self.nfa.is_accepting_state(s));
+        cache.states.push(nfa_states.clone());
+        cache.accepting.push(is_final);
+        cache.labels.insert(nfa_states, label);
+        let len = cache.table.len() + self.alphabet.len();
+        cache.table.resize(len, UNKNOWN);
+        label
+    }
+
+    /// The state of the DFA before any input, constructing it if it is not in the cache.
+    #[inline]
+    fn start(&self, cache: &mut Cache) -> DfaState {
+        let state = self.state(cache, self.start.clone());
+        DfaState::new(Some(state), cache.is_accepting_state(state))
+    }
+
+    /// Feed the next input symbol to the DFA, advancing its state and constructing the state it
+    /// reaches if it is not in the cache. A dead state stays dead.
+    #[inline]
+    fn step<U>(&self, cache: &mut Cache, state: &mut DfaState, symbol: &U) -> StepResult
+    where
+        T: PartialEq<U>,
+    {
+        let next = state
+            .state()
+            .and_then(|current| self.next_state(cache, current, symbol));
+        *state = DfaState::new(next, matches!(next, Some(s) if cache.is_accepting_state(s)));
+        state.result()
+    }
+
+    /// Get the label of the state reached from `state` on the given input symbol, constructing it
+    /// if it is not in the cache. Constructing the state may clear the cache, invalidating all
+    /// other labels.
+    #[inline]
+    fn next_state<U>(&self, cache: &mut Cache, state: StateId, symbol: &U) -> Option<StateId>
+    where
+        T: PartialEq<U>,
+    {
+        let class = self.alphabet.iter().position(|t| *t == *symbol)?;
+        let index = state.as_usize() * self.alphabet.len() + class;
+        match cache.table[index] {
+            DEAD => None,
+            UNKNOWN => {
+                let nfa_states = convert::step(
+                    &self.nfa,
+                    &cache.states[state.as_usize()],
+                    &self.alphabet[class],
+                    self.kind,
+                );
+                if nfa_states.is_empty() {
+                    cache.table[index] = DEAD;
+                    return None;
+                }
+
+                let next = match cache.labels.get(&nfa_states) {
+                    Some(&next) => next,
+                    None if cache.states.len() >= self.cache_capacity => {
+                        // Clearing the cache invalidates `state`, so the transition can't be
+                        // recorded.
+                        *cache = Cache::default();
+                        return Some(self.insert(cache, nfa_states));
+                    }
+                    None => self.insert(cache, nfa_states),
+                };
+                cache.table[index] = next;
+                Some(next)
+            }
+            next => Some(next),
+        }
+    }
+}
+
+impl<T> LazyDFA<T>
+where
+    T: Clone + Disjoin + Eq + Hash,
+{
+    /// Determine if the given input is accepted by the DFA.
+    #[inline]
+    pub fn is_match<I>(&self, input: I) -> bool
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+    {
+        self.with_cache(|cache| {
+            let mut state = self.start(cache);
+            for is in input {
+                if self.step(cache, &mut state, &is) == StepResult::Dead {
+                    return false;
+                }
+            }
+            state.is_accepting()
+        })
+    }
+
+    #[inline]
+    pub fn find_shortest<I>(&self, input: I) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+    {
+        self.find_shortest_at(input, 0)
+    }
+
+    #[inline]
+    pub fn find_shortest_at<I>(&self, input: I, start: usize) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+    {
+        let input = matching::skip_to(input.into_iter(), start)?;
+        self.with_cache(|cache| self.find_anchored_impl(cache, input, start, true))
+    }
+
+    #[inline]
+    pub fn find<I>(&self, input: I) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+    {
+        self.find_at(input, 0)
+    }
+
+    #[inline]
+    pub fn find_at<I>(&self, input: I, start: usize) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+    {
+        let input = matching::skip_to(input.into_iter(), start)?;
+        self.with_cache(|cache| self.find_anchored_impl(cache, input, start, false))
+    }
+
+    /// Find the leftmost match anywhere in the input. Of the matches that start at the leftmost
+    /// position, the one preferred by the match semantics is returned.
+    #[inline]
+    pub fn find_unanchored<I>(&self, input: I) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        self.find_unanchored_at(input, 0)
+    }
+
+    /// Find the leftmost match that starts at or after the `start`-th input symbol. Of the
+    /// matches that start at the leftmost position, the one preferred by the match semantics is
+    /// returned.
+    #[inline]
+    pub fn find_unanchored_at<I>(&self, input: I, start: usize) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        self.find_unanchored_impl(input, start, false)
+    }
+
+    /// Find the leftmost match that starts at or after the `start`-th input symbol. Of the
+    /// matches that start at the leftmost position, the shortest is returned.
+    #[inline]
+    pub fn find_shortest_unanchored_at<I>(&self, input: I, start: usize) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        self.find_unanchored_impl(input, start, true)
+    }
+
+    #[inline]
+    fn find_unanchored_impl<I>(
+        &self,
+        input: I,
+        start: usize,
+        shortest: bool,
+    ) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        // Try each starting position in turn, including the position after the last symbol.
+        let mut input = matching::skip_to(input.into_iter(), start)?;
+        self.with_cache(|cache| {
+            let mut pos = start;
+            loop {
+                if let Some(m) = self.find_anchored_impl(cache, input.clone(), pos, shortest) {
+                    return Some(m);
+                }
+
+                input.next()?;
+                pos += 1;
+            }
+        })
+    }
+
+    /// Find a match that starts at the beginning of the given input, which begins at position
+    /// `start` of the original input.
+    #[inline]
+    fn find_anchored_impl<I>(
+        &self,
+        cache: &mut Cache,
+        input: I,
+        start: usize,
+        shortest: bool,
+    ) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: Iterator,
+    {
+        let mut state = self.start(cache);
+        let mut last_match = if state.is_accepting() { Some(0) } else { None };
+
+        let mut span = Vec::new();
+        if !(shortest && last_match.is_some()) {
+            for is in input {
+                let result = self.step(cache, &mut state, &is);
+                if result == StepResult::Dead {
+                    break;
+                }
+                span.push(is);
+
+                if result == StepResult::Accepting {
+                    last_match = Some(span.len());
+                    if shortest {
+                        break;
+                    }
+                }
+            }
+        }
+
+        last_match.map(|len| {
+            span.truncate(len);
+            Match::new(start, start + len, span)
+        })
+    }
+}
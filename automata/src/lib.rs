@@ -1,13 +1,61 @@
+//! Non-deterministic and deterministic automata.
+//!
+//! The `std` feature is enabled by default. Without it, the crate only depends on `alloc`, so it
+//! can be used in embedded and WebAssembly environments; [`LazyDFA`] is unavailable, since its
+//! cache is behind a lock, and the `serde` feature can't be enabled. The `bigint` feature adds
+//! [`DFA::count_accepted_big`], which counts accepted inputs without overflowing.
+
+#![cfg_attr(not(feature = "std"), no_std)]
 #![deny(rust_2018_idioms)]
 #![deny(future_incompatible)]
 
+#[cfg(all(feature = "serde", not(feature = "std")))]
+compile_error!("the `serde` feature requires the `std` feature");
+
+extern crate alloc;
+
+mod dot;
 mod matching;
 
+/// The collections used by the automata, from `hashbrown` if `std` is unavailable.
+mod collections {
+    pub(crate) use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
+    #[cfg(not(feature = "std"))]
+    pub(crate) use hashbrown::{hash_map, HashMap, HashSet};
+    #[cfg(feature = "std")]
+    pub(crate) use std::collections::{hash_map, HashMap, HashSet};
+}
+
+/// The items of the standard prelude that `no_std` crates must import from `alloc`.
+mod prelude {
+    pub(crate) use alloc::string::{String, ToString};
+    pub(crate) use alloc::vec;
+    pub(crate) use alloc::vec::Vec;
+}
+
+pub mod backtrack;
 pub mod convert;
+pub mod dense;
 pub mod dfa;
+pub mod intern;
+#[cfg(feature = "std")]
+pub mod lazy;
 pub mod nfa;
+pub mod onepass;
+pub mod pikevm;
+pub mod predicate;
+pub mod shiftand;
+pub mod stateid;
+pub mod stateset;
+pub mod step;
+pub mod stream;
 pub mod table;
 
+pub use dense::DenseDFA;
 pub use dfa::DFA;
-pub use matching::Match;
+#[cfg(feature = "std")]
+pub use lazy::LazyDFA;
+pub use matching::{Match, MatchKind};
 pub use nfa::NFA;
+pub use stateid::StateId;
+pub use stateset::StateSet;
@@ -1,4 +1,24 @@
-use std::ops::Range;
+use crate::prelude::*;
+
+use core::ops::Range;
+
+/// The semantics used to choose between several matches that start at the same position.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MatchKind {
+    /// Prefer the longest match, as specified by POSIX.
+    LeftmostLongest,
+    /// Prefer the match found first by a backtracking engine, as in Perl: earlier alternatives are
+    /// preferred over later ones, and repetitions are greedy.
+    LeftmostFirst,
+}
+
+impl Default for MatchKind {
+    #[inline]
+    fn default() -> Self {
+        MatchKind::LeftmostLongest
+    }
+}
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct Match<T> {
@@ -31,3 +51,16 @@ impl<T> Match<T> {
         self.end
     }
 }
+
+/// Skip the first `start` symbols of the input, or return `None` if it has fewer, so that a search
+/// never starts past the end of the input.
+#[inline]
+pub(crate) fn skip_to<I>(mut input: I, start: usize) -> Option<I>
+where
+    I: Iterator,
+{
+    if start > 0 {
+        input.nth(start - 1)?;
+    }
+    Some(input)
+}
@@ -1,17 +1,23 @@
-use crate::matching::Match;
+use crate::collections::{BTreeSet, HashMap, HashSet};
+use crate::dot;
+use crate::matching::{self, Match};
+use crate::prelude::*;
+use crate::stateset::StateSet;
 use crate::table::Table;
 
-use std::hash::Hash;
-use std::iter::Peekable;
-use std::{
-    collections::{HashMap, HashSet},
-    rc::Rc,
-};
+use core::fmt;
+use core::hash::Hash;
+use core::iter::Peekable;
+use core::mem;
+use core::ops::Range;
 
-include!("macros.rs");
+/// The most states, summed over the epsilon-closures of every state, cached by
+/// [`NFA::cache_epsilon_closures`].
+pub const EPSILON_CLOSURE_CACHE_LIMIT: usize = 1 << 20;
 
 /// A non-deterministic finite automaton, or NFA.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NFA<T: Clone + Eq + Hash> {
     /// An NFA has a single start state.
     pub start_state: usize,
@@ -19,13 +25,34 @@ pub struct NFA<T: Clone + Eq + Hash> {
     /// < total_states.
     pub total_states: usize,
     /// The set of accepting states.
-    pub accepting_states: HashSet<usize>,
-    /// A lookup table for transitions between states.
-    pub transition: Table<usize, Transition<T>, HashSet<usize>>,
+    pub accepting_states: StateSet,
+    /// The IDs of the patterns matched by accepting states, for NFAs that match several patterns.
+    /// Accepting states without an entry match no particular pattern.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub patterns: HashMap<usize, BTreeSet<usize>>,
+    /// The capture slots recorded when entering each state, for tracking the positions of
+    /// submatches with a [`PikeVM`](crate::pikevm::PikeVM). States without an entry record
+    /// nothing.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tags: HashMap<usize, Vec<usize>>,
+    /// A lookup table for transitions between states. The destinations of a transition are kept
+    /// in the order they were added, which is the priority of each epsilon transition under
+    /// leftmost-first semantics.
+    pub transition: Table<usize, Transition<T>, Vec<usize>>,
+    /// The epsilon-closure of every state, in order of priority, if cached. See
+    /// [`cache_epsilon_closures`](Self::cache_epsilon_closures).
+    #[cfg_attr(feature = "serde", serde(skip))]
+    closures: Option<Vec<Vec<usize>>>,
+    /// The start state, accepting state, and number of states of the NFA when it was last made a
+    /// union by [`union_into`](Self::union_into), which adds further branches to the same states.
+    /// Adding states, transitions, or tags drops it.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    open_union: Option<(usize, usize, usize)>,
 }
 
 /// A transition between states in an NFA.
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Transition<T: Clone + Eq + Hash> {
     /// A transition on some input symbol.
     Some(T),
@@ -34,6 +61,44 @@ pub enum Transition<T: Clone + Eq + Hash> {
     Epsilon,
 }
 
+impl<T> fmt::Display for Transition<T>
+where
+    T: Clone + Eq + Hash + fmt::Display,
+{
+    /// Format the symbol of the transition, or "ε" for an epsilon transition.
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Transition::Some(symbol) => symbol.fmt(f),
+            Transition::Epsilon => write!(f, "ε"),
+        }
+    }
+}
+
+impl<T> Transition<T>
+where
+    T: Clone + Eq + Hash,
+{
+    /// The symbol of the transition, or `None` for an epsilon transition.
+    #[inline]
+    pub fn symbol(&self) -> Option<&T> {
+        match self {
+            Transition::Some(symbol) => Some(symbol),
+            Transition::Epsilon => None,
+        }
+    }
+}
+
+impl<T> From<T> for Transition<T>
+where
+    T: Clone + Eq + Hash,
+{
+    #[inline]
+    fn from(t: T) -> Self {
+        Transition::Some(t)
+    }
+}
+
 impl<T> NFA<T>
 where
     T: Clone + Eq + Hash,
@@ -45,8 +110,12 @@ where
         NFA {
             start_state: 0,
             total_states: 1,
-            accepting_states: HashSet::new(),
+            accepting_states: StateSet::new(),
+            patterns: HashMap::new(),
+            tags: HashMap::new(),
             transition: Table::new(),
+            closures: None,
+            open_union: None,
         }
     }
 
@@ -61,10 +130,10 @@ where
         nfa
     }
 
-    /// Clone the states and transitions of an NFA into another. The start and accepting states of the
-    /// source are not marked as such in the destination. These states can be accessed by i +
-    /// offset, where i is the label of the state in the source NFA, and offset is the start
-    /// total number of states in the destination NFA.
+    /// Clone the states, transitions, and tags of an NFA into another. The start and accepting
+    /// states of the source are not marked as such in the destination. These states can be
+    /// accessed by i + offset, where i is the label of the state in the source NFA, and offset is
+    /// the start total number of states in the destination NFA.
     #[inline]
     pub fn copy_into(dest: &mut NFA<T>, src: &NFA<T>) {
         let offset = dest.total_states;
@@ -73,10 +142,16 @@ where
             dest.add_state(false);
         }
 
-        // Clone the transitions.
+        // Clone the transitions. The states are new, so the destinations of each can be set at once
+        // rather than added one at a time and checked for duplicates.
         for (start, label, ends) in src.transition.into_iter() {
-            for end in ends {
-                dest.add_transition(*start + offset, *end + offset, (*label).clone());
+            let ends = ends.iter().map(|end| end + offset).collect();
+            dest.transition.set(*start + offset, (*label).clone(), ends);
+        }
+
+        for (state, slots) in src.tags.iter() {
+            for &slot in slots {
+                dest.add_tag(state + offset, slot);
             }
         }
     }
@@ -95,7 +170,7 @@ where
         NFA::copy_into(&mut new_nfa, c1);
         new_nfa.add_epsilon_transition(start_state, c1.start_state + offset);
         for c1_final in c1.accepting_states.iter() {
-            new_nfa.add_epsilon_transition(*c1_final + offset, accepting_state);
+            new_nfa.add_epsilon_transition(c1_final + offset, accepting_state);
         }
 
         offset = new_nfa.total_states;
@@ -103,12 +178,72 @@ where
         NFA::copy_into(&mut new_nfa, c2);
         new_nfa.add_epsilon_transition(start_state, c2.start_state + offset);
         for c2_final in c2.accepting_states.iter() {
-            new_nfa.add_epsilon_transition(*c2_final + offset, accepting_state);
+            new_nfa.add_epsilon_transition(c2_final + offset, accepting_state);
         }
 
         new_nfa
     }
 
+    /// Turn an NFA into the union of itself and another, like [`union`](Self::union), without
+    /// copying its own states: the new start and accepting states are added after them, and the
+    /// other NFA is copied after those. If the NFA is itself a union made by this method, the
+    /// other NFA is added as a further branch of it instead, so an alternation of n branches built
+    /// up from the left has a single start state with n epsilon transitions. This keeps both the
+    /// construction and the epsilon-closures of its states linear in its size.
+    #[inline]
+    pub fn union_into(dest: &mut NFA<T>, c2: &NFA<T>) {
+        let (start_state, accepting_state) = match dest.open_union() {
+            Some(states) => states,
+            None => {
+                let c1_start = dest.start_state;
+                let c1_finals = mem::take(&mut dest.accepting_states);
+                dest.patterns = HashMap::new();
+
+                let start_state = dest.add_state(false);
+                let accepting_state = dest.add_state(true);
+                dest.start_state = start_state;
+
+                dest.add_epsilon_transition(start_state, c1_start);
+                for c1_final in c1_finals {
+                    dest.add_epsilon_transition(c1_final, accepting_state);
+                }
+                (start_state, accepting_state)
+            }
+        };
+
+        let offset = dest.total_states;
+        NFA::copy_into(dest, c2);
+        // The start state of the branch is new, so it isn't looked for among the branches already
+        // leaving the start state, which would take time quadratic in their number.
+        let branch = c2.start_state + offset;
+        dest.closures = None;
+        dest.transition
+            .set_or(start_state, Transition::Epsilon, vec![branch], |v| {
+                v.push(branch)
+            });
+        for c2_final in c2.accepting_states.iter() {
+            dest.add_epsilon_transition(c2_final + offset, accepting_state);
+        }
+        dest.open_union = Some((start_state, accepting_state, dest.total_states));
+    }
+
+    /// The start and accepting states of the union made by [`union_into`](Self::union_into), if
+    /// the NFA hasn't been changed since.
+    #[inline]
+    fn open_union(&self) -> Option<(usize, usize)> {
+        let (start, accepting, total_states) = self.open_union?;
+        let unchanged = self.start_state == start
+            && self.total_states == total_states
+            && self.accepting_states.len() == 1
+            && self.accepting_states.contains(&accepting)
+            && self.patterns.is_empty();
+        if unchanged {
+            Some((start, accepting))
+        } else {
+            None
+        }
+    }
+
     /// Construct a new NFA for the concatenation operator of two NFAs. The start state of the
     /// preceding NFA becomes the start state of the new NFA. The accepting states of the following NFA
     /// are the accepting states of the new NFA. There are epsilon transitions from the final states of
@@ -118,43 +253,119 @@ where
         let mut new_nfa = c1.clone();
 
         let offset = new_nfa.total_states;
-        NFA::copy_into(&mut new_nfa, &c2);
+        NFA::copy_into(&mut new_nfa, c2);
 
         // Epsilon transitions from c1 finals to start of c2
         for c1_final in c1.accepting_states.iter() {
-            new_nfa.add_epsilon_transition(*c1_final, c2.start_state + offset);
+            new_nfa.add_epsilon_transition(c1_final, c2.start_state + offset);
         }
-        new_nfa.accepting_states = HashSet::new();
+        new_nfa.accepting_states = StateSet::new();
+        new_nfa.patterns = HashMap::new();
 
         // Set accepting states
         for c2_final in c2.accepting_states.iter() {
             new_nfa.accepting_states.insert(c2_final + offset);
         }
+        for (c2_final, ids) in c2.patterns.iter() {
+            new_nfa.patterns.insert(c2_final + offset, ids.clone());
+        }
 
         new_nfa
     }
 
-    /// Construct a new NFA for the kleene star operator of an NFA.
+    /// Like [`concatenation`](Self::concatenation), but reuses the states of the preceding NFA
+    /// instead of copying them, so building a long concatenation from left to right takes time
+    /// linear in its size.
+    #[inline]
+    pub fn concatenation_owned(mut c1: NFA<T>, c2: NFA<T>) -> NFA<T> {
+        let offset = c1.total_states;
+        NFA::copy_into(&mut c1, &c2);
+
+        for c1_final in mem::take(&mut c1.accepting_states) {
+            c1.add_epsilon_transition(c1_final, c2.start_state + offset);
+        }
+        c1.accepting_states = c2
+            .accepting_states
+            .iter()
+            .map(|c2_final| c2_final + offset)
+            .collect();
+        c1.patterns = c2
+            .patterns
+            .into_iter()
+            .map(|(c2_final, ids)| (c2_final + offset, ids))
+            .collect();
+
+        c1
+    }
+
+    /// Construct a new NFA for the kleene star operator of an NFA. The repetition is greedy: entering
+    /// the operand is preferred over skipping it.
+    ///
+    /// The start state leads to a split state that enters the operand or skips it, and the final
+    /// states of the operand lead back to the split state rather than to the start of the operand.
+    /// An iteration that matches the empty string then reaches a split state it has already passed
+    /// through, so it is dropped whatever the input, and the choice between the branches of the
+    /// operand doesn't depend on whether they can match the empty string.
     #[inline]
     pub fn kleene_star(c1: &NFA<T>) -> NFA<T> {
-        let mut new_nfa = NFA::new_epsilon();
+        let mut new_nfa = NFA::new();
+        let split_state = new_nfa.add_state(false);
+        let accepting_state = new_nfa.add_state(true);
         let offset = new_nfa.total_states;
 
-        NFA::copy_into(&mut new_nfa, &c1);
-        new_nfa.add_epsilon_transition(new_nfa.start_state, c1.start_state + offset);
+        NFA::copy_into(&mut new_nfa, c1);
+        new_nfa.add_epsilon_transition(new_nfa.start_state, split_state);
+        new_nfa.add_epsilon_transition(split_state, c1.start_state + offset);
+        new_nfa.add_epsilon_transition(split_state, accepting_state);
 
         for c1_final in c1.accepting_states.iter() {
-            new_nfa.add_epsilon_transition(c1_final + offset, c1.start_state + offset);
-            for accepting_state in new_nfa.accepting_states.clone().iter() {
-                new_nfa.add_epsilon_transition(c1_final + offset, *accepting_state);
-            }
+            new_nfa.add_epsilon_transition(c1_final + offset, split_state);
         }
 
         new_nfa
     }
 
+    /// Like [`kleene_star`](Self::kleene_star), but reuses the states of the NFA instead of
+    /// copying them. The new start, split, and accepting states are added after them.
+    #[inline]
+    pub fn kleene_star_owned(mut c1: NFA<T>) -> NFA<T> {
+        let c1_start = c1.start_state;
+        let c1_finals = mem::take(&mut c1.accepting_states);
+        c1.patterns = HashMap::new();
+
+        let start_state = c1.add_state(false);
+        let split_state = c1.add_state(false);
+        let accepting_state = c1.add_state(true);
+        c1.start_state = start_state;
+
+        c1.add_epsilon_transition(start_state, split_state);
+        c1.add_epsilon_transition(split_state, c1_start);
+        c1.add_epsilon_transition(split_state, accepting_state);
+        for c1_final in c1_finals {
+            c1.add_epsilon_transition(c1_final, split_state);
+        }
+
+        c1
+    }
+
+    /// Tag an NFA to record where its matches start and end: the start state is tagged with
+    /// `start_slot`, and the accepting states with `end_slot`. This requires that no transition
+    /// leads back to the start state and none leaves an accepting state, which holds for the NFAs
+    /// built by the other constructions of this type. Then tagging takes no new states, so nested
+    /// groups stay as small as the expression they contain.
+    #[inline]
+    pub fn tagged(mut nfa: NFA<T>, start_slot: usize, end_slot: usize) -> NFA<T> {
+        nfa.add_tag(nfa.start_state, start_slot);
+        let accepting_states: Vec<_> = nfa.accepting_states.iter().collect();
+        for state in accepting_states {
+            nfa.add_tag(state, end_slot);
+        }
+        nfa
+    }
+
     /// Construct a new NFA with epsilon transitions from the start state to the initial states
-    /// of each child. The accepting states of the new NFA are the final states of the children.
+    /// of each child. The accepting states of the new NFA are the final states of the children,
+    /// which keep their pattern IDs.
     #[inline]
     pub fn combine(cc: &[&NFA<T>]) -> NFA<T> {
         let mut new_nfa = NFA::new();
@@ -166,12 +377,171 @@ where
             for c_final in c.accepting_states.iter() {
                 new_nfa.accepting_states.insert(c_final + offset);
             }
+            for (c_final, ids) in c.patterns.iter() {
+                new_nfa.patterns.insert(c_final + offset, ids.clone());
+            }
+            offset += c.total_states;
+        }
+
+        new_nfa
+    }
+
+    /// Construct a new NFA that matches any of several patterns, like [`combine`](Self::combine).
+    /// The accepting states of the i-th NFA carry only the pattern ID i.
+    #[inline]
+    pub fn from_patterns(cc: &[&NFA<T>]) -> NFA<T> {
+        let mut new_nfa = NFA::combine(cc);
+        new_nfa.patterns = HashMap::new();
+
+        let mut offset = NFA::<T>::new().total_states;
+        for (id, c) in cc.iter().enumerate() {
+            for c_final in c.accepting_states.iter() {
+                new_nfa.add_pattern(c_final + offset, id);
+            }
             offset += c.total_states;
         }
 
         new_nfa
     }
 
+    /// Construct a new NFA that accepts the reversal of every input accepted by an NFA. Every
+    /// transition is reversed, and there are epsilon transitions from the new start state to the
+    /// accepting states of the original. State i of the original is labeled i + 1 in the new NFA.
+    #[inline]
+    pub fn reverse(&self) -> NFA<T> {
+        let mut new_nfa = NFA::new();
+        for _ in 0..self.total_states {
+            new_nfa.add_state(false);
+        }
+        new_nfa.accepting_states.insert(self.start_state + 1);
+
+        for (start, label, ends) in self.transition.into_iter() {
+            for end in ends {
+                new_nfa.add_transition(*end + 1, *start + 1, (*label).clone());
+            }
+        }
+
+        for accepting_state in &self.accepting_states {
+            new_nfa.add_epsilon_transition(new_nfa.start_state, accepting_state + 1);
+        }
+
+        new_nfa
+    }
+
+    /// Construct an equivalent NFA without epsilon transitions. Each state keeps its label, and
+    /// gets the transitions on symbols of every state in its epsilon-closure, in order of
+    /// priority; it is accepting if any of them is, matching their patterns. The tags of the
+    /// states are dropped, since the states they are entered through may be skipped. States only
+    /// reached through epsilon transitions are left unreachable, and can be removed by
+    /// [`trim`](Self::trim).
+    ///
+    /// ```
+    /// use automata::NFA;
+    ///
+    /// // a*
+    /// let mut a = NFA::new();
+    /// let s = a.add_state(true);
+    /// a.add_labeled_transition(a.start_state, s, 'a');
+    /// let nfa = NFA::kleene_star(&a).remove_epsilon().trim();
+    ///
+    /// assert!(nfa.transitions().all(|(_, label, _)| label.symbol().is_some()));
+    /// assert!(nfa.is_match("aaa".chars()));
+    /// assert!(nfa.is_match("".chars()));
+    /// assert!(!nfa.is_match("ab".chars()));
+    /// ```
+    #[inline]
+    pub fn remove_epsilon(&self) -> NFA<T> {
+        let mut new_nfa = NFA::new();
+        for _ in 1..self.total_states {
+            new_nfa.add_state(false);
+        }
+        new_nfa.start_state = self.start_state;
+
+        for state in self.states() {
+            let mut closure = Vec::new();
+            self.epsilon_closure_into(state, &mut closure, &mut HashSet::new());
+            for s in closure {
+                if self.is_accepting_state(&s) {
+                    new_nfa.accepting_states.insert(state);
+                    if let Some(ids) = self.patterns.get(&s) {
+                        new_nfa
+                            .patterns
+                            .entry(state)
+                            .or_default()
+                            .extend(ids.iter().copied());
+                    }
+                }
+                for (label, ends) in self.transitions_from(s) {
+                    if let Transition::Some(_) = label {
+                        for &end in ends {
+                            new_nfa.add_transition(state, end, label.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        new_nfa
+    }
+
+    /// Construct an equivalent NFA without the states that can't be reached from the start state
+    /// or can't reach an accepting state, which take no part in any match. The start state is
+    /// always kept, and the other states keep their relative order.
+    #[inline]
+    pub fn trim(&self) -> NFA<T> {
+        let mut forward: Vec<Vec<usize>> = vec![Vec::new(); self.total_states];
+        let mut backward: Vec<Vec<usize>> = vec![Vec::new(); self.total_states];
+        for (&start, _, ends) in &self.transition {
+            for &end in ends {
+                forward[start].push(end);
+                backward[end].push(start);
+            }
+        }
+
+        let reachable = reach(&forward, Some(self.start_state));
+        let live = reach(&backward, self.accepting_states.iter());
+
+        let kept: Vec<_> = self
+            .states()
+            .filter(|&state| state == self.start_state || (reachable[state] && live[state]))
+            .collect();
+        let mut labels = vec![None; self.total_states];
+        for (label, &state) in kept.iter().enumerate() {
+            labels[state] = Some(label);
+        }
+
+        let mut new_nfa = NFA::new();
+        for _ in 1..kept.len() {
+            new_nfa.add_state(false);
+        }
+        new_nfa.start_state = labels[self.start_state].unwrap();
+
+        for (&start, label, ends) in &self.transition {
+            for &end in ends {
+                if let (Some(start), Some(end)) = (labels[start], labels[end]) {
+                    new_nfa.add_transition(start, end, label.clone());
+                }
+            }
+        }
+        for state in &self.accepting_states {
+            if let Some(label) = labels[state] {
+                new_nfa.accepting_states.insert(label);
+            }
+        }
+        for (state, ids) in &self.patterns {
+            if let Some(label) = labels[*state] {
+                new_nfa.patterns.insert(label, ids.clone());
+            }
+        }
+        for (state, slots) in &self.tags {
+            if let Some(label) = labels[*state] {
+                new_nfa.tags.insert(label, slots.clone());
+            }
+        }
+
+        new_nfa
+    }
+
     /// Add a state to the NFA. The label of the state is returned. The total number of states is
     /// always greater than the label of the newest state by 1.
     #[inline]
@@ -182,6 +552,8 @@ where
         }
 
         self.total_states += 1;
+        self.closures = None;
+        self.open_union = None;
         label
     }
 
@@ -191,8 +563,14 @@ where
         if self.total_states < start + 1 || self.total_states < end + 1 {
             None
         } else {
-            self.transition.set_or(start, label, hash_set![end], |v| {
-                v.insert(end);
+            if label == Transition::Epsilon {
+                self.closures = None;
+            }
+            self.open_union = None;
+            self.transition.set_or(start, label, vec![end], |v| {
+                if !v.contains(&end) {
+                    v.push(end);
+                }
             });
             Some(())
         }
@@ -210,14 +588,103 @@ where
         self.add_transition(start, end, Transition::Epsilon)
     }
 
+    /// The labels of the states of the NFA, which are `0` to `total_states - 1`.
+    #[inline]
+    pub fn states(&self) -> Range<usize> {
+        0..self.total_states
+    }
+
+    /// The accepting states of the NFA, in increasing order.
+    #[inline]
+    pub fn final_states(&self) -> impl Iterator<Item = usize> {
+        let states: Vec<_> = self.accepting_states.iter().collect();
+        states.into_iter()
+    }
+
+    /// The transitions of the NFA, as the state each leaves, its label, and the state it leads
+    /// to. The transitions are ordered by the state they leave, with epsilon transitions first,
+    /// and transitions with the same label in order of priority.
+    ///
+    /// ```
+    /// use automata::nfa::Transition;
+    /// use automata::NFA;
+    ///
+    /// let mut nfa = NFA::new();
+    /// let s = nfa.add_state(true);
+    /// nfa.add_labeled_transition(nfa.start_state, s, 'a');
+    /// nfa.add_epsilon_transition(nfa.start_state, s);
+    ///
+    /// let transitions: Vec<_> = nfa.transitions().collect();
+    /// assert_eq!(
+    ///     vec![(0, &Transition::Epsilon, 1), (0, &Transition::Some('a'), 1)],
+    ///     transitions
+    /// );
+    /// assert_eq!(vec![1], nfa.final_states().collect::<Vec<_>>());
+    /// ```
+    #[inline]
+    pub fn transitions(&self) -> impl Iterator<Item = (usize, &Transition<T>, usize)> + '_ {
+        let mut rows: Vec<_> = self.transition.into_iter().collect();
+        rows.sort_by_key(|&(&start, label, _)| (start, label.symbol().is_some()));
+        rows.into_iter()
+            .flat_map(|(&start, label, ends)| ends.iter().map(move |&end| (start, label, end)))
+    }
+
     #[inline]
     pub fn is_accepting_state(&self, label: &usize) -> bool {
         self.accepting_states.contains(label)
     }
 
+    /// Mark a state as accepting, matching the pattern with the given ID.
+    #[inline]
+    pub fn add_pattern(&mut self, label: usize, id: usize) {
+        self.accepting_states.insert(label);
+        self.patterns.entry(label).or_default().insert(id);
+    }
+
+    /// The IDs of the patterns matched by a state, which are empty unless it is accepting.
+    #[inline]
+    pub fn pattern_ids(&self, label: &usize) -> impl Iterator<Item = usize> + '_ {
+        let ids = if self.is_accepting_state(label) {
+            self.patterns.get(label)
+        } else {
+            None
+        };
+        ids.into_iter().flatten().copied()
+    }
+
+    /// Render the NFA in the Graphviz DOT language, labeling transitions with the `Display` form of
+    /// their symbols and epsilon transitions with "ε".
+    #[inline]
+    pub fn to_dot(&self) -> String
+    where
+        T: fmt::Display,
+    {
+        let edges = (&self.transition)
+            .into_iter()
+            .flat_map(|(&from, t, to)| {
+                let label = t.to_string();
+                to.iter().map(move |&to| (from, label.clone(), to))
+            })
+            .collect();
+        dot::render(
+            self.total_states,
+            self.start_state,
+            |s| self.is_accepting_state(&s),
+            edges,
+        )
+    }
+
+    /// Tag a state with a capture slot, which records the position of the input when the state is
+    /// entered.
+    #[inline]
+    pub fn add_tag(&mut self, label: usize, slot: usize) {
+        self.open_union = None;
+        self.tags.entry(label).or_default().push(slot);
+    }
+
     /// Returns the transitions and destinations from a specific state.
     #[inline]
-    pub fn transitions_from(&self, state: usize) -> HashMap<&Transition<T>, &HashSet<usize>> {
+    pub fn transitions_from(&self, state: usize) -> HashMap<&Transition<T>, &Vec<usize>> {
         self.transition.get_row(&state)
     }
 
@@ -225,25 +692,94 @@ where
     /// all states accessible from the given state on epsilon transitions only.
     #[inline]
     pub fn epsilon_closure(&self, state: usize) -> HashSet<usize> {
-        let transitions = self.transitions_from(state);
-        let mut closure: HashSet<_> = transitions
-            .into_iter()
-            .filter(|(t, _)| **t == Transition::Epsilon)
-            .flat_map(|(_, dest)| dest.iter().flat_map(|&i| self.epsilon_closure(i)))
-            .collect();
-        closure.insert(state);
-        closure
+        let mut closure = Vec::new();
+        self.epsilon_closure_into(state, &mut closure, &mut HashSet::new());
+        closure.into_iter().collect()
+    }
+
+    /// Computes the epsilon-closure of a state, appending the states to `closure` in order of
+    /// priority: states reached through an earlier epsilon transition precede those reached
+    /// through a later one. States in `seen` are skipped, and visited states are added to it.
+    /// `seen` is expected to contain the whole epsilon-closure of each state in it, as it does if
+    /// it is only filled by this method.
+    #[inline]
+    pub fn epsilon_closure_into(
+        &self,
+        state: usize,
+        closure: &mut Vec<usize>,
+        seen: &mut HashSet<usize>,
+    ) {
+        if let Some(closures) = &self.closures {
+            closure.extend(closures[state].iter().filter(|&&s| seen.insert(s)));
+            return;
+        }
+
+        let mut stack = vec![state];
+        while let Some(s) = stack.pop() {
+            if !seen.insert(s) {
+                continue;
+            }
+
+            closure.push(s);
+            if let Some(dests) = self.transition.get(&s, &Transition::Epsilon) {
+                // Push in reverse so that the first transition is explored first.
+                stack.extend(dests.iter().rev());
+            }
+        }
+    }
+
+    /// Compute and keep the epsilon-closure of every state, so that simulating the NFA or
+    /// constructing a DFA from it doesn't compute the closure of a state again each time it's
+    /// reached. Adding states or epsilon transitions drops the cache, though changing
+    /// [`transition`](Self::transition) directly doesn't, so the cache should be computed once
+    /// the NFA is complete. Nothing is cached if the closures hold more than
+    /// [`EPSILON_CLOSURE_CACHE_LIMIT`] states in total, as for long chains of optional parts.
+    ///
+    /// ```
+    /// use automata::NFA;
+    ///
+    /// let mut nfa: NFA<char> = NFA::new_epsilon();
+    /// nfa.cache_epsilon_closures();
+    /// assert!(nfa.has_cached_epsilon_closures());
+    /// assert_eq!(2, nfa.epsilon_closure(nfa.start_state).len());
+    ///
+    /// nfa.add_state(false);
+    /// assert!(!nfa.has_cached_epsilon_closures());
+    /// ```
+    #[inline]
+    pub fn cache_epsilon_closures(&mut self) {
+        self.closures = None;
+        let mut closures = Vec::with_capacity(self.total_states);
+        let mut size = 0;
+        for state in 0..self.total_states {
+            let mut closure = Vec::new();
+            self.epsilon_closure_into(state, &mut closure, &mut HashSet::new());
+            size += closure.len();
+            if size > EPSILON_CLOSURE_CACHE_LIMIT {
+                return;
+            }
+            closures.push(closure);
+        }
+        self.closures = Some(closures);
+    }
+
+    /// Whether the epsilon-closures of the states are cached. See
+    /// [`cache_epsilon_closures`](Self::cache_epsilon_closures).
+    #[inline]
+    pub fn has_cached_epsilon_closures(&self) -> bool {
+        self.closures.is_some()
     }
 
     /// Computes the union of epsilon-closures for each state in the given set of states.
     #[inline]
     pub fn epsilon_closure_set(&self, state_set: &HashSet<usize>) -> HashSet<usize> {
-        let mut set = state_set.clone();
-        for state in state_set.iter() {
-            let state_closure = self.epsilon_closure(*state);
-            set = set.union(&state_closure).cloned().collect();
+        // The closures share one set of visited states, so each state is visited once.
+        let mut closure = Vec::new();
+        let mut seen = HashSet::new();
+        for &state in state_set {
+            self.epsilon_closure_into(state, &mut closure, &mut seen);
         }
-        set
+        closure.into_iter().collect()
     }
 
     #[inline]
@@ -252,17 +788,16 @@ where
         T: PartialEq<S>,
     {
         let mut set = HashSet::new();
-        for state in state_set.iter() {
-            let transitions = self.transitions_from(*state);
-            let input_transitions = transitions
-                .into_iter()
+        for &state in state_set {
+            let input_transitions = self
+                .transition
+                .row(&state)
                 .filter(|(t, _)| match *t {
                     Transition::Some(symbol) => *symbol == *input,
                     Transition::Epsilon => false,
                 })
-                .flat_map(|(_, dest)| dest.iter().cloned())
-                .collect();
-            set = set.union(&input_transitions).cloned().collect();
+                .flat_map(|(_, dest)| dest.iter().copied());
+            set.extend(input_transitions);
         }
         set
     }
@@ -274,7 +809,7 @@ where
         T: PartialEq<I::Item>,
     {
         Iter {
-            nfa: &self,
+            nfa: self,
             input: input.into_iter().peekable(),
             last: None,
         }
@@ -294,6 +829,24 @@ where
     }
 }
 
+/// Mark the states reachable from the given states along the edges of a graph, given as the
+/// successors of each state.
+#[inline]
+fn reach<I>(edges: &[Vec<usize>], from: I) -> Vec<bool>
+where
+    I: IntoIterator<Item = usize>,
+{
+    let mut reached = vec![false; edges.len()];
+    let mut stack: Vec<usize> = from.into_iter().collect();
+    while let Some(state) = stack.pop() {
+        if !reached[state] {
+            reached[state] = true;
+            stack.extend(&edges[state]);
+        }
+    }
+    reached
+}
+
 pub struct Iter<'a, T, I>
 where
     T: Clone + Eq + Hash,
@@ -316,7 +869,7 @@ where
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        iter_on_next(&self.nfa, &mut self.input, &mut self.last)
+        iter_on_next(self.nfa, &mut self.input, &mut self.last)
     }
 }
 
@@ -389,13 +942,10 @@ where
         Some((LastIterState::Stuck, _)) => return None,
     };
 
-    let peek_is = match input.peek() {
-        Some(v) => v,
-        // No more input, so last item was the final.
-        None => return None,
-    };
+    // No more input, so last item was the final.
+    let peek_is = input.peek()?;
 
-    let moved_set = nfa.move_set(&current_set, peek_is);
+    let moved_set = nfa.move_set(current_set, peek_is);
     let next_set = nfa.epsilon_closure_set(&moved_set);
 
     let next = if !next_set.is_empty() {
@@ -422,7 +972,26 @@ impl<T> NFA<T>
 where
     T: Clone + Eq + Hash,
 {
-    /// Determines if the given input is accepted by the NFA.
+    /// Determines if the given input is accepted by the NFA. The NFA is simulated directly, by
+    /// tracking the set of states it may be in after each input symbol, so no DFA needs to be
+    /// constructed first.
+    ///
+    /// ```
+    /// use automata::NFA;
+    ///
+    /// // ab*
+    /// let mut a = NFA::new();
+    /// let s = a.add_state(true);
+    /// a.add_labeled_transition(a.start_state, s, 'a');
+    /// let mut b = NFA::new();
+    /// let s = b.add_state(true);
+    /// b.add_labeled_transition(b.start_state, s, 'b');
+    /// let nfa = NFA::concatenation(&a, &NFA::kleene_star(&b));
+    ///
+    /// assert!(nfa.is_match("abbb".chars()));
+    /// assert!(!nfa.is_match("aab".chars()));
+    /// assert_eq!(Some(1..3), nfa.find_unanchored("xabx".chars()).map(|m| m.range()));
+    /// ```
     #[inline]
     pub fn is_match<I>(&self, input: I) -> bool
     where
@@ -438,6 +1007,7 @@ where
         }
     }
 
+    /// Find the shortest match that starts at the beginning of the input.
     #[inline]
     pub fn find_shortest<I>(&self, input: I) -> Option<Match<I::Item>>
     where
@@ -447,6 +1017,7 @@ where
         self.find_shortest_at(input, 0)
     }
 
+    /// Find the shortest match that starts at the `start`-th input symbol.
     #[inline]
     pub fn find_shortest_at<I>(&self, input: I, start: usize) -> Option<Match<I::Item>>
     where
@@ -456,6 +1027,7 @@ where
         self.find_at_impl(input, start, true)
     }
 
+    /// Find the longest match that starts at the beginning of the input.
     #[inline]
     pub fn find<I>(&self, input: I) -> Option<Match<I::Item>>
     where
@@ -465,6 +1037,7 @@ where
         self.find_at(input, 0)
     }
 
+    /// Find the longest match that starts at the `start`-th input symbol.
     #[inline]
     pub fn find_at<I>(&self, input: I, start: usize) -> Option<Match<I::Item>>
     where
@@ -474,52 +1047,274 @@ where
         self.find_at_impl(input, start, false)
     }
 
+    /// Find the leftmost match anywhere in the input. Of the matches that start at the leftmost
+    /// position, the longest is returned. The search takes a single pass over the input,
+    /// following the paths from every starting position at once.
+    #[inline]
+    pub fn find_unanchored<I>(&self, input: I) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        self.find_unanchored_at(input, 0)
+    }
+
+    /// Find the leftmost match that starts at or after the `start`-th input symbol. Of the
+    /// matches that start at the leftmost position, the longest is returned.
+    #[inline]
+    pub fn find_unanchored_at<I>(&self, input: I, start: usize) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        self.find_unanchored_impl(input, start, false)
+    }
+
+    /// Find the leftmost match that starts at or after the `start`-th input symbol. Of the
+    /// matches that start at the leftmost position, the shortest is returned.
+    #[inline]
+    pub fn find_shortest_unanchored_at<I>(&self, input: I, start: usize) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        self.find_unanchored_impl(input, start, true)
+    }
+
+    #[inline]
+    fn find_unanchored_impl<I>(
+        &self,
+        input: I,
+        start: usize,
+        shortest: bool,
+    ) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        // Simulate the NFA from every starting position at once, in a single pass over the input.
+        // Each thread is a state tagged with the position its path started at, and the threads
+        // are kept in order of that position, so that of the paths reaching a state, only the
+        // one that started first is followed: the rest could only lead to matches further right.
+        let input = input.into_iter();
+        let mut rest = matching::skip_to(input.clone(), start)?;
+        let mut threads: Vec<(usize, usize)> = Vec::new();
+        let mut best: Option<(usize, usize)> = None;
+        let mut pos = start;
+        loop {
+            // A match that starts here is further right than one already found.
+            if best.is_none() {
+                let mut seen = threads.iter().map(|&(state, _)| state).collect();
+                let mut closure = Vec::new();
+                self.epsilon_closure_into(self.start_state, &mut closure, &mut seen);
+                threads.extend(closure.into_iter().map(|state| (state, pos)));
+            }
+
+            if let Some(&(_, from)) = threads
+                .iter()
+                .find(|(state, _)| self.is_accepting_state(state))
+            {
+                let better = match best {
+                    Some((leftmost, _)) => from < leftmost || (from == leftmost && !shortest),
+                    None => true,
+                };
+                if better {
+                    best = Some((from, pos));
+                }
+            }
+            if let Some((leftmost, _)) = best {
+                threads.retain(|&(_, from)| from < leftmost || (from == leftmost && !shortest));
+                if threads.is_empty() {
+                    break;
+                }
+            }
+
+            let symbol = match rest.next() {
+                Some(symbol) => symbol,
+                None => break,
+            };
+            let mut seen = HashSet::new();
+            let mut closure = Vec::new();
+            let mut next = Vec::new();
+            for &(state, from) in &threads {
+                for (t, dests) in self.transitions_from(state) {
+                    match t {
+                        Transition::Some(t) if *t == symbol => {}
+                        _ => continue,
+                    }
+                    for &dest in dests {
+                        closure.clear();
+                        self.epsilon_closure_into(dest, &mut closure, &mut seen);
+                        next.extend(closure.iter().map(|&state| (state, from)));
+                    }
+                }
+            }
+            threads = next;
+            pos += 1;
+        }
+
+        best.map(|(from, to)| Match::new(from, to, input.skip(from).take(to - from).collect()))
+    }
+
     #[inline]
     fn find_at_impl<I>(&self, input: I, start: usize, shortest: bool) -> Option<Match<I::Item>>
     where
         T: PartialEq<I::Item>,
         I: IntoIterator,
     {
+        self.find_anchored_impl(
+            matching::skip_to(input.into_iter(), start)?,
+            start,
+            shortest,
+        )
+    }
+
+    /// Find a match that starts at the beginning of the given input, which begins at position
+    /// `start` of the original input.
+    #[inline]
+    fn find_anchored_impl<I>(
+        &self,
+        input: I,
+        start: usize,
+        shortest: bool,
+    ) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: Iterator,
+    {
+        // Only the length of the last match is tracked, and the span is cut down to it at the end.
         let mut last_match = None;
-        let iter = self.iter_on(input).skip(start).enumerate();
-
-        // Ensure span dropped before unwrapping Rc's.
-        {
-            let mut span = Vec::new();
-            for (i, iter_state) in iter {
-                let is_final = match iter_state {
-                    IterState::Start(state_map) => state_map.values().any(|&b| b),
-                    IterState::Normal(is, state_map) => {
-                        let is_rc = Rc::new(is);
-                        span.push(is_rc);
-
-                        state_map.values().any(|&b| b)
-                    }
-                    IterState::Stuck(_) => break,
-                };
+        let mut span = Vec::new();
+        for (i, iter_state) in self.iter_on(input).enumerate() {
+            let is_final = match iter_state {
+                IterState::Start(state_map) => state_map.values().any(|&b| b),
+                IterState::Normal(is, state_map) => {
+                    span.push(is);
+                    state_map.values().any(|&b| b)
+                }
+                IterState::Stuck(_) => break,
+            };
+
+            if is_final {
+                last_match = Some(i);
+                if shortest {
+                    break;
+                }
+            }
+        }
+
+        last_match.map(|len| {
+            span.truncate(len);
+            Match::new(start, start + len, span)
+        })
+    }
+
+    /// Find the leftmost match in the input using leftmost-first semantics. See
+    /// [`MatchKind::LeftmostFirst`](crate::MatchKind::LeftmostFirst).
+    #[inline]
+    pub fn find_leftmost_first<I>(&self, input: I) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        self.find_leftmost_first_at(input, 0)
+    }
+
+    /// Find the leftmost match that starts at or after the `start`-th input symbol using
+    /// leftmost-first semantics. See
+    /// [`MatchKind::LeftmostFirst`](crate::MatchKind::LeftmostFirst).
+    #[inline]
+    pub fn find_leftmost_first_at<I>(&self, input: I, start: usize) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+        I::IntoIter: Clone,
+    {
+        let mut input = matching::skip_to(input.into_iter(), start)?;
+        let mut pos = start;
+        loop {
+            if let Some(m) = self.find_first_anchored_impl(input.clone(), pos) {
+                return Some(m);
+            }
 
-                if is_final {
-                    last_match = Some(Match::new(start, i + 1, span.clone()));
-                    if shortest {
-                        break;
+            input.next()?;
+            pos += 1;
+        }
+    }
+
+    /// Find a match that starts at the `start`-th input symbol using leftmost-first semantics.
+    #[inline]
+    pub fn find_leftmost_first_anchored_at<I>(
+        &self,
+        input: I,
+        start: usize,
+    ) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+    {
+        self.find_first_anchored_impl(matching::skip_to(input.into_iter(), start)?, start)
+    }
+
+    /// Simulate the NFA while keeping the set of current states ordered by priority. When a state
+    /// is accepting, the states after it can only produce lower-priority matches, so they are
+    /// dropped.
+    #[inline]
+    fn find_first_anchored_impl<I>(&self, input: I, start: usize) -> Option<Match<I::Item>>
+    where
+        T: PartialEq<I::Item>,
+        I: Iterator,
+    {
+        let mut input = input.peekable();
+        let mut span = Vec::new();
+        let mut last_match = None;
+
+        let mut seen = HashSet::new();
+        let mut states = Vec::new();
+        self.epsilon_closure_into(self.start_state, &mut states, &mut seen);
+
+        loop {
+            if let Some(i) = states.iter().position(|s| self.is_accepting_state(s)) {
+                states.truncate(i + 1);
+                last_match = Some(span.len());
+            }
+
+            let next = match input.peek() {
+                Some(next) => next,
+                None => break,
+            };
+
+            seen.clear();
+            let mut next_states = Vec::new();
+            for &state in states.iter() {
+                for (t, dests) in self.transitions_from(state) {
+                    match t {
+                        Transition::Some(symbol) if *symbol == *next => {
+                            for &dest in dests {
+                                self.epsilon_closure_into(dest, &mut next_states, &mut seen);
+                            }
+                        }
+                        _ => {}
                     }
                 }
             }
+
+            if next_states.is_empty() {
+                break;
+            }
+
+            span.push(input.next().unwrap());
+            states = next_states;
         }
 
-        last_match.map(|m| {
-            Match::new(
-                m.start(),
-                m.end(),
-                m.span
-                    .into_iter()
-                    .map(|rc| match Rc::try_unwrap(rc) {
-                        Ok(v) => v,
-                        // Shouldn't ever have any lingering references.
-                        Err(_) => unreachable!("Match Rc somehow had lingering references"),
-                    })
-                    .collect(),
-            )
+        last_match.map(|len| {
+            span.truncate(len);
+            Match::new(start, start + len, span)
         })
     }
 }
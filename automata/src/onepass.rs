@@ -0,0 +1,231 @@
+use crate::collections::{HashMap, HashSet};
+use crate::convert::Disjoin;
+use crate::nfa::{Transition, NFA};
+use crate::pikevm;
+use crate::prelude::*;
+
+use core::hash::Hash;
+
+/// A DFA that finds the same submatches as a [`PikeVM`](crate::pikevm::PikeVM) in a single
+/// deterministic pass, for NFAs that are one-pass: from each state reached on an input symbol,
+/// the next symbol determines the path taken through the epsilon transitions, and so the capture
+/// slots recorded on the way. The states of the DFA are those states of the NFA, so it is never
+/// larger than the NFA, and each symbol of the input is handled by a single transition without
+/// copying any slots.
+///
+/// ```
+/// use automata::onepass::OnePass;
+/// use automata::NFA;
+///
+/// // (a*)b, with the group recorded in slots 2 and 3.
+/// let mut a = NFA::new();
+/// let s = a.add_state(true);
+/// a.add_labeled_transition(a.start_state, s, b'a');
+/// let mut b = NFA::new();
+/// let s = b.add_state(true);
+/// b.add_labeled_transition(b.start_state, s, b'b');
+/// let nfa = NFA::concatenation(&NFA::tagged(NFA::kleene_star(&a), 2, 3), &b);
+///
+/// let onepass = OnePass::new(&nfa).unwrap();
+/// let slots = onepass.captures("aab".bytes()).unwrap();
+/// assert_eq!(vec![Some(0), Some(3), Some(0), Some(2)], slots);
+///
+/// // (a*)a isn't one-pass, since an `a` could be matched by either part.
+/// let nfa = NFA::concatenation(&NFA::tagged(NFA::kleene_star(&a), 2, 3), &a);
+/// assert!(OnePass::new(&nfa).is_none());
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OnePass<T: Clone + Eq + Hash> {
+    start_state: usize,
+    states: Vec<State<T>>,
+    slots: usize,
+}
+
+/// A state of a one-pass DFA.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct State<T: Clone + Eq + Hash> {
+    /// The transitions out of the state, in order of priority.
+    steps: Vec<Step<T>>,
+    /// The slots recorded on the path to the accepting state of the NFA reached from this state,
+    /// if any.
+    accept: Option<Vec<usize>>,
+    /// The number of steps with a higher priority than the match that ends at this state. When
+    /// searching for the leftmost-first match that need not span the whole input, the rest of
+    /// the steps are never taken, as the Pike VM drops its threads after an accepting one.
+    preferred: usize,
+}
+
+/// A transition of a one-pass DFA.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Step<T: Clone + Eq + Hash> {
+    symbol: T,
+    next: usize,
+    /// The slots recorded on the path through the epsilon transitions taken before the symbol.
+    slots: Vec<usize>,
+}
+
+impl<T> OnePass<T>
+where
+    T: Clone + Eq + Hash + Disjoin,
+{
+    /// Compile a one-pass DFA from the NFA, or return `None` if the NFA isn't one-pass.
+    #[inline]
+    pub fn new(nfa: &NFA<T>) -> Option<Self> {
+        let mut ids = HashMap::new();
+        ids.insert(nfa.start_state, 0);
+        let mut queue = vec![nfa.start_state];
+        let mut states = Vec::new();
+
+        while let Some(&state) = queue.get(states.len()) {
+            let mut steps: Vec<Step<T>> = Vec::new();
+            let mut accept = None;
+            let mut preferred = 0;
+
+            // Visit the epsilon-closure of the state in order of priority, like the Pike VM.
+            let mut seen = HashSet::new();
+            let mut stack = vec![(state, Vec::new())];
+            while let Some((s, mut slots)) = stack.pop() {
+                if !seen.insert(s) {
+                    continue;
+                }
+
+                if let Some(tags) = nfa.tags.get(&s) {
+                    slots.extend(tags);
+                }
+                if let Some(dests) = nfa.transition.get(&s, &Transition::Epsilon) {
+                    for &dest in dests.iter().rev() {
+                        stack.push((dest, slots.clone()));
+                    }
+                }
+
+                for (t, dests) in nfa.transitions_from(s) {
+                    let symbol = match t {
+                        Transition::Some(symbol) => symbol,
+                        Transition::Epsilon => continue,
+                    };
+                    for &dest in dests {
+                        // Another transition on the same symbol would make the path ambiguous.
+                        if steps.iter().any(|step| step.symbol.contains(symbol)) {
+                            return None;
+                        }
+
+                        let next = *ids.entry(dest).or_insert_with(|| {
+                            queue.push(dest);
+                            queue.len() - 1
+                        });
+                        steps.push(Step {
+                            symbol: symbol.clone(),
+                            next,
+                            slots: slots.clone(),
+                        });
+                    }
+                }
+
+                if accept.is_none() && nfa.is_accepting_state(&s) {
+                    accept = Some(slots);
+                    preferred = steps.len();
+                }
+            }
+
+            if accept.is_none() {
+                preferred = steps.len();
+            }
+            states.push(State {
+                steps,
+                accept,
+                preferred,
+            });
+        }
+
+        Some(Self {
+            start_state: 0,
+            states,
+            slots: pikevm::slots(nfa),
+        })
+    }
+}
+
+impl<T> OnePass<T>
+where
+    T: Clone + Eq + Hash,
+{
+    /// The number of capture slots of each match, including slots 0 and 1 for the match itself.
+    #[inline]
+    pub fn slots(&self) -> usize {
+        self.slots
+    }
+
+    /// Find the leftmost-first match that starts at the beginning of the input, and return its
+    /// capture slots. Slots whose tags weren't reached by the match are `None`.
+    #[inline]
+    pub fn captures<I>(&self, input: I) -> Option<Vec<Option<usize>>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+    {
+        self.run(input, false)
+    }
+
+    /// Determine if the whole input is accepted, and return the capture slots of the path that
+    /// accepts it.
+    #[inline]
+    pub fn captures_whole<I>(&self, input: I) -> Option<Vec<Option<usize>>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+    {
+        self.run(input, true)
+    }
+
+    #[inline]
+    fn run<I>(&self, input: I, whole: bool) -> Option<Vec<Option<usize>>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+    {
+        let mut slots = vec![None; self.slots];
+        slots[0] = Some(0);
+
+        let mut state = &self.states[self.start_state];
+        let mut pos = 0;
+        let mut matched = None;
+        for next in input {
+            let steps = if whole {
+                &state.steps[..]
+            } else {
+                if let Some(accept) = &state.accept {
+                    matched = Some(Self::accept(slots.clone(), accept, pos));
+                }
+                &state.steps[..state.preferred]
+            };
+
+            let step = match steps.iter().find(|step| step.symbol == next) {
+                Some(step) => step,
+                None => return if whole { None } else { matched },
+            };
+            for &slot in &step.slots {
+                slots[slot] = Some(pos);
+            }
+            state = &self.states[step.next];
+            pos += 1;
+        }
+
+        match &state.accept {
+            Some(accept) => Some(Self::accept(slots, accept, pos)),
+            None => matched,
+        }
+    }
+
+    /// Record the slots of a match that ends at the position.
+    #[inline]
+    fn accept(mut slots: Vec<Option<usize>>, accept: &[usize], pos: usize) -> Vec<Option<usize>> {
+        for &slot in accept {
+            slots[slot] = Some(pos);
+        }
+        slots[1] = Some(pos);
+        slots
+    }
+}
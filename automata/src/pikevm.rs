@@ -0,0 +1,214 @@
+use crate::collections::HashSet;
+use crate::nfa::{Transition, NFA};
+use crate::prelude::*;
+
+use core::hash::Hash;
+use core::iter::Peekable;
+
+/// An NFA simulation that tracks the positions of submatches, as recorded by the tags of the NFA
+/// (see [`NFA::tagged`]). Each thread of the simulation carries its own capture slots, and
+/// threads are kept in order of priority, so matching takes time linear in the length of the
+/// input and follows leftmost-first semantics: the slots of a match are those of the path that a
+/// backtracking engine would find first.
+///
+/// Slots 0 and 1 hold the start and end of the match itself, so the tags of the NFA should use
+/// the other slots. Positions are counted in input symbols.
+///
+/// ```
+/// use automata::pikevm::PikeVM;
+/// use automata::NFA;
+///
+/// // (a*)b, with the group recorded in slots 2 and 3.
+/// let mut a = NFA::new();
+/// let s = a.add_state(true);
+/// a.add_labeled_transition(a.start_state, s, 'a');
+/// let mut b = NFA::new();
+/// let s = b.add_state(true);
+/// b.add_labeled_transition(b.start_state, s, 'b');
+/// let nfa = NFA::concatenation(&NFA::tagged(NFA::kleene_star(&a), 2, 3), &b);
+///
+/// let vm = PikeVM::new(nfa);
+/// let slots = vm.captures("aab".chars()).unwrap();
+/// assert_eq!(vec![Some(0), Some(3), Some(0), Some(2)], slots);
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PikeVM<T: Clone + Eq + Hash> {
+    nfa: NFA<T>,
+    slots: usize,
+}
+
+/// The number of capture slots recorded by the tags of an NFA, including slots 0 and 1 for the
+/// match itself.
+#[inline]
+pub(crate) fn slots<T>(nfa: &NFA<T>) -> usize
+where
+    T: Clone + Eq + Hash,
+{
+    nfa.tags
+        .values()
+        .flatten()
+        .map(|&slot| slot + 1)
+        .fold(2, usize::max)
+}
+
+/// A thread of the simulation: a state and the capture slots of the path that reached it.
+type Thread = (usize, Vec<Option<usize>>);
+
+impl<T> PikeVM<T>
+where
+    T: Clone + Eq + Hash,
+{
+    /// Create a Pike VM that simulates the NFA.
+    #[inline]
+    pub fn new(nfa: NFA<T>) -> Self {
+        let slots = slots(&nfa);
+        Self { nfa, slots }
+    }
+
+    /// The NFA simulated by the VM.
+    #[inline]
+    pub fn nfa(&self) -> &NFA<T> {
+        &self.nfa
+    }
+
+    /// The number of capture slots of each match, including slots 0 and 1 for the match itself.
+    #[inline]
+    pub fn slots(&self) -> usize {
+        self.slots
+    }
+
+    /// Find the leftmost-first match that starts at the beginning of the input, and return its
+    /// capture slots. Slots whose tags weren't reached by the match are `None`.
+    #[inline]
+    pub fn captures<I>(&self, input: I) -> Option<Vec<Option<usize>>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+    {
+        self.run(input.into_iter().peekable(), true, false)
+    }
+
+    /// Find the leftmost-first match anywhere in the input, and return its capture slots.
+    #[inline]
+    pub fn captures_unanchored<I>(&self, input: I) -> Option<Vec<Option<usize>>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+    {
+        self.run(input.into_iter().peekable(), false, false)
+    }
+
+    /// Determine if the whole input is accepted, and return the capture slots of the highest
+    /// priority path that accepts it.
+    #[inline]
+    pub fn captures_whole<I>(&self, input: I) -> Option<Vec<Option<usize>>>
+    where
+        T: PartialEq<I::Item>,
+        I: IntoIterator,
+    {
+        self.run(input.into_iter().peekable(), true, true)
+    }
+
+    #[inline]
+    fn run<I>(
+        &self,
+        mut input: Peekable<I>,
+        anchored: bool,
+        whole: bool,
+    ) -> Option<Vec<Option<usize>>>
+    where
+        T: PartialEq<I::Item>,
+        I: Iterator,
+    {
+        let mut seen = HashSet::new();
+        let mut threads = Vec::new();
+
+        let mut pos = 0;
+        let mut matched = None;
+        loop {
+            // Until a match is found, an unanchored search starts a thread at each position, with
+            // a lower priority than the threads that started earlier.
+            if pos == 0 || (!anchored && matched.is_none()) {
+                let mut slots = vec![None; self.slots];
+                slots[0] = Some(pos);
+                self.add_thread(&mut threads, &mut seen, self.nfa.start_state, slots, pos);
+            }
+
+            // When a thread is accepting, the threads after it can only produce lower-priority
+            // matches, so they are dropped.
+            if !whole || input.peek().is_none() {
+                let accepting = threads
+                    .iter()
+                    .position(|(s, _)| self.nfa.is_accepting_state(s));
+                if let Some(i) = accepting {
+                    threads.truncate(i + 1);
+                    let mut slots = threads[i].1.clone();
+                    slots[1] = Some(pos);
+                    matched = Some(slots);
+                }
+            }
+
+            let next = match input.next() {
+                Some(next) => next,
+                None => break,
+            };
+            pos += 1;
+
+            seen.clear();
+            let mut next_threads = Vec::new();
+            for (state, slots) in threads {
+                for (t, dests) in self.nfa.transitions_from(state) {
+                    match t {
+                        Transition::Some(symbol) if *symbol == next => {
+                            for &dest in dests {
+                                let slots = slots.clone();
+                                self.add_thread(&mut next_threads, &mut seen, dest, slots, pos);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            if next_threads.is_empty() && (anchored || matched.is_some()) {
+                break;
+            }
+            threads = next_threads;
+        }
+
+        matched
+    }
+
+    /// Add the threads for the epsilon-closure of a state in order of priority, like
+    /// [`NFA::epsilon_closure_into`], recording the position in the slots tagged on the way.
+    #[inline]
+    fn add_thread(
+        &self,
+        threads: &mut Vec<Thread>,
+        seen: &mut HashSet<usize>,
+        state: usize,
+        slots: Vec<Option<usize>>,
+        pos: usize,
+    ) {
+        let mut stack = vec![(state, slots)];
+        while let Some((s, mut slots)) = stack.pop() {
+            if !seen.insert(s) {
+                continue;
+            }
+
+            if let Some(tags) = self.nfa.tags.get(&s) {
+                for &slot in tags {
+                    slots[slot] = Some(pos);
+                }
+            }
+            if let Some(dests) = self.nfa.transition.get(&s, &Transition::Epsilon) {
+                // Push in reverse so that the first transition is explored first.
+                for &dest in dests.iter().rev() {
+                    stack.push((dest, slots.clone()));
+                }
+            }
+            threads.push((s, slots));
+        }
+    }
+}
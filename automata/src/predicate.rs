@@ -0,0 +1,100 @@
+use alloc::sync::Arc;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+
+/// A transition symbol that matches the input symbols for which a function returns `true`, for
+/// automata over input types whose sets of symbols are impractical to enumerate.
+///
+/// An NFA labeled with predicates dispatches each input symbol through the predicates of its
+/// transitions. Predicates are opaque, so they can't be disjoined, and such an NFA can't be
+/// converted to a DFA.
+///
+/// Predicates are compared by identity: clones of a predicate are equal, but two predicates
+/// created separately are not, even from the same function.
+///
+/// ```
+/// use automata::predicate::Predicate;
+/// use automata::NFA;
+///
+/// let even = Predicate::new(|n: &u64| n % 2 == 0);
+/// let large = Predicate::new(|n: &u64| *n > 1000);
+///
+/// let mut nfa = NFA::new();
+/// let s1 = nfa.add_state(false);
+/// let s2 = nfa.add_state(true);
+/// nfa.add_labeled_transition(nfa.start_state, s1, even);
+/// nfa.add_labeled_transition(s1, s2, large);
+///
+/// assert!(nfa.is_match(vec![2, 1001]));
+/// assert!(!nfa.is_match(vec![3, 1001]));
+/// assert!(!nfa.is_match(vec![2, 10]));
+/// ```
+pub struct Predicate<U> {
+    f: Arc<dyn Fn(&U) -> bool + Send + Sync>,
+}
+
+impl<U> Predicate<U> {
+    /// Create a predicate that matches the input symbols for which `f` returns `true`.
+    #[inline]
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&U) -> bool + Send + Sync + 'static,
+    {
+        Self { f: Arc::new(f) }
+    }
+
+    /// Determine if the predicate matches the input symbol.
+    #[inline]
+    pub fn matches(&self, symbol: &U) -> bool {
+        (self.f)(symbol)
+    }
+
+    #[inline]
+    fn addr(&self) -> *const u8 {
+        Arc::as_ptr(&self.f) as *const u8
+    }
+}
+
+impl<U> Clone for Predicate<U> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self { f: self.f.clone() }
+    }
+}
+
+impl<U> PartialEq for Predicate<U> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.addr() == other.addr()
+    }
+}
+
+impl<U> Eq for Predicate<U> {}
+
+impl<U> Hash for Predicate<U> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.addr().hash(state);
+    }
+}
+
+impl<U> PartialEq<U> for Predicate<U> {
+    #[inline]
+    fn eq(&self, other: &U) -> bool {
+        self.matches(other)
+    }
+}
+
+impl<U> fmt::Debug for Predicate<U> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Predicate({:p})", self.addr())
+    }
+}
+
+impl<U> fmt::Display for Predicate<U> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:p}", self.addr())
+    }
+}
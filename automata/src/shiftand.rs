@@ -0,0 +1,254 @@
+use crate::convert::{self, Disjoin};
+use crate::dense::{ClassMap, Symbol, SymbolRanges};
+use crate::nfa::NFA;
+use crate::prelude::*;
+
+use core::hash::Hash;
+
+/// The most positions of an NFA that a [`ShiftAnd`] matcher can track, one for each bit of a
+/// `u128`.
+pub const MAX_POSITIONS: usize = 128;
+
+/// A bit-parallel matcher for small NFAs. Each transition of the NFA on a symbol is a position,
+/// and the set of positions just passed is kept in the bits of a word. On each input symbol, the
+/// set advances to the positions that may follow one in it and are labeled with the symbol:
+/// consecutive positions, as in a literal string, follow each other with a shift and a mask, and
+/// the others are looked up one at a time. Only NFAs with at most [`MAX_POSITIONS`] positions
+/// can be matched.
+///
+/// Since the set of positions doesn't record where the paths through them started, the matcher
+/// finds where matches end, but only finds where they start for matches anchored at the start of
+/// the input.
+///
+/// ```
+/// use automata::shiftand::ShiftAnd;
+/// use automata::NFA;
+///
+/// // ab*c
+/// let mut nfa = NFA::new();
+/// let (s1, s2) = (nfa.add_state(false), nfa.add_state(true));
+/// nfa.add_labeled_transition(nfa.start_state, s1, b'a');
+/// nfa.add_labeled_transition(s1, s1, b'b');
+/// nfa.add_labeled_transition(s1, s2, b'c');
+///
+/// let shift_and = ShiftAnd::new(&nfa).unwrap();
+/// assert_eq!(3, shift_and.positions());
+/// assert!(shift_and.is_match("abbc".bytes()));
+/// assert_eq!(Some(2), shift_and.find_anchored("acbc".bytes()));
+/// assert_eq!(Some(6), shift_and.find_earliest_end("xxabbcabc".bytes()));
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShiftAnd {
+    /// The positions labeled with each column of the alphabet.
+    masks: Vec<u128>,
+    /// The column of the alphabet containing each input symbol.
+    classes: ClassMap,
+    /// The positions that may be passed first.
+    first: u128,
+    /// The positions after which a match may end.
+    last: u128,
+    /// Whether the empty input is matched.
+    empty: bool,
+    /// The positions that may be followed by the next position.
+    shift: u128,
+    /// The positions other than the next one that may follow each position.
+    follow: Vec<u128>,
+    /// The positions with a non-empty entry in `follow`.
+    irregular: u128,
+}
+
+impl ShiftAnd {
+    /// Compile a matcher for the NFA, or return `None` if it has more than [`MAX_POSITIONS`]
+    /// positions.
+    #[inline]
+    pub fn new<T>(nfa: &NFA<T>) -> Option<Self>
+    where
+        T: Clone + Eq + Hash + Disjoin + SymbolRanges,
+    {
+        let positions: Vec<_> = nfa
+            .transitions()
+            .filter_map(|(start, label, end)| label.symbol().map(|symbol| (start, symbol, end)))
+            .collect();
+        if positions.len() > MAX_POSITIONS {
+            return None;
+        }
+
+        // The positions that may be passed right after a state are the transitions leaving its
+        // epsilon-closure, and a match may end at the state if the closure is accepting.
+        let after = |state: usize| {
+            let closure = nfa.epsilon_closure(state);
+            let bits = positions
+                .iter()
+                .enumerate()
+                .filter(|(_, (start, _, _))| closure.contains(start))
+                .fold(0, |bits, (i, _)| bits | 1u128 << i);
+            let accepting = closure.iter().any(|s| nfa.is_accepting_state(s));
+            (bits, accepting)
+        };
+
+        let (first, empty) = after(nfa.start_state);
+        let mut last = 0;
+        let mut shift = 0;
+        let mut irregular = 0;
+        let mut follow = Vec::with_capacity(positions.len());
+        for (i, &(_, _, end)) in positions.iter().enumerate() {
+            let (mut bits, accepting) = after(end);
+            if accepting {
+                last |= 1 << i;
+            }
+
+            let next = 1u128.checked_shl(i as u32 + 1).unwrap_or(0);
+            if bits & next != 0 {
+                shift |= 1 << i;
+                bits &= !next;
+            }
+            if bits != 0 {
+                irregular |= 1 << i;
+            }
+            follow.push(bits);
+        }
+
+        let alphabet = convert::alphabet(nfa);
+        let masks = alphabet
+            .iter()
+            .map(|column| {
+                positions
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (_, symbol, _))| symbol.contains(column))
+                    .fold(0, |mask, (i, _)| mask | 1u128 << i)
+            })
+            .collect();
+
+        Some(Self {
+            masks,
+            classes: ClassMap::new(&alphabet),
+            first,
+            last,
+            empty,
+            shift,
+            follow,
+            irregular,
+        })
+    }
+
+    /// The number of positions of the NFA, which is the number of its transitions on symbols.
+    #[inline]
+    pub fn positions(&self) -> usize {
+        self.follow.len()
+    }
+
+    /// Determine if the whole input is accepted.
+    #[inline]
+    pub fn is_match<I>(&self, input: I) -> bool
+    where
+        I: IntoIterator,
+        I::Item: Symbol,
+    {
+        let mut set = 0;
+        let mut accepted = self.empty;
+        for (i, symbol) in input.into_iter().enumerate() {
+            let next = if i == 0 { self.first } else { self.follow(set) };
+            set = next & self.mask(&symbol);
+            if set == 0 {
+                return false;
+            }
+            accepted = set & self.last != 0;
+        }
+        accepted
+    }
+
+    /// Find the longest match that starts at the beginning of the input, and return its length.
+    #[inline]
+    pub fn find_anchored<I>(&self, input: I) -> Option<usize>
+    where
+        I: IntoIterator,
+        I::Item: Symbol,
+    {
+        self.anchored(input, false)
+    }
+
+    /// Find the shortest match that starts at the beginning of the input, and return its length.
+    #[inline]
+    pub fn find_shortest_anchored<I>(&self, input: I) -> Option<usize>
+    where
+        I: IntoIterator,
+        I::Item: Symbol,
+    {
+        self.anchored(input, true)
+    }
+
+    /// Find the first position of the input at which a match ends, and return the number of
+    /// symbols before it. The match need not be the leftmost one, which may end later.
+    #[inline]
+    pub fn find_earliest_end<I>(&self, input: I) -> Option<usize>
+    where
+        I: IntoIterator,
+        I::Item: Symbol,
+    {
+        if self.empty {
+            return Some(0);
+        }
+
+        let mut set = 0;
+        for (i, symbol) in input.into_iter().enumerate() {
+            set = (self.follow(set) | self.first) & self.mask(&symbol);
+            if set & self.last != 0 {
+                return Some(i + 1);
+            }
+        }
+        None
+    }
+
+    #[inline]
+    fn anchored<I>(&self, input: I, shortest: bool) -> Option<usize>
+    where
+        I: IntoIterator,
+        I::Item: Symbol,
+    {
+        let mut found = if self.empty { Some(0) } else { None };
+        if shortest && found.is_some() {
+            return found;
+        }
+
+        let mut set = 0;
+        for (i, symbol) in input.into_iter().enumerate() {
+            let next = if i == 0 { self.first } else { self.follow(set) };
+            set = next & self.mask(&symbol);
+            if set == 0 {
+                break;
+            }
+            if set & self.last != 0 {
+                found = Some(i + 1);
+                if shortest {
+                    break;
+                }
+            }
+        }
+        found
+    }
+
+    /// The positions labeled with the input symbol.
+    #[inline]
+    fn mask<S>(&self, symbol: &S) -> u128
+    where
+        S: Symbol,
+    {
+        self.classes
+            .lookup(symbol)
+            .map_or(0, |column| self.masks[column])
+    }
+
+    /// The positions that may follow those in the set.
+    #[inline]
+    fn follow(&self, set: u128) -> u128 {
+        let mut next = (set & self.shift) << 1;
+        let mut rest = set & self.irregular;
+        while rest != 0 {
+            next |= self.follow[rest.trailing_zeros() as usize];
+            rest &= rest - 1;
+        }
+        next
+    }
+}
@@ -0,0 +1,102 @@
+use core::convert::TryFrom;
+use core::fmt;
+
+/// The label of a state in a table-backed automaton, such as a [`DenseDFA`](crate::DenseDFA).
+///
+/// Labels are stored in 32 bits, so tables of them take half the memory of tables of `usize` on
+/// 64-bit targets. Converting a `usize` label is checked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct StateId(u32);
+
+impl StateId {
+    /// The largest label. Automata that use it as a sentinel have at most `StateId::MAX` states.
+    pub const MAX: StateId = StateId(u32::MAX);
+
+    /// Create a label, failing if it doesn't fit in 32 bits.
+    #[inline]
+    pub fn new(id: usize) -> Result<Self, StateIdError> {
+        u32::try_from(id).map(Self).map_err(|_| StateIdError { id })
+    }
+
+    #[inline]
+    pub const fn from_u32(id: u32) -> Self {
+        Self(id)
+    }
+
+    #[inline]
+    pub const fn as_u32(self) -> u32 {
+        self.0
+    }
+
+    #[inline]
+    pub const fn as_usize(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl fmt::Display for StateId {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<u32> for StateId {
+    #[inline]
+    fn from(id: u32) -> Self {
+        Self(id)
+    }
+}
+
+impl TryFrom<usize> for StateId {
+    type Error = StateIdError;
+
+    #[inline]
+    fn try_from(id: usize) -> Result<Self, Self::Error> {
+        Self::new(id)
+    }
+}
+
+impl From<StateId> for u32 {
+    #[inline]
+    fn from(id: StateId) -> Self {
+        id.0
+    }
+}
+
+impl From<StateId> for usize {
+    #[inline]
+    fn from(id: StateId) -> Self {
+        id.as_usize()
+    }
+}
+
+/// Error returned when a state label doesn't fit in a [`StateId`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StateIdError {
+    id: usize,
+}
+
+impl StateIdError {
+    /// The label that didn't fit.
+    #[inline]
+    pub fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl fmt::Display for StateIdError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "state label {} does not fit in 32 bits", self.id)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StateIdError {}
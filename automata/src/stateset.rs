@@ -0,0 +1,222 @@
+use crate::prelude::*;
+
+use core::fmt;
+use core::iter::FromIterator;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// A set of states, stored as a bitset keyed by state label.
+///
+/// Membership checks are a single word lookup, and iteration yields states in ascending order.
+#[derive(Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "Vec<usize>", into = "Vec<usize>")
+)]
+pub struct StateSet {
+    words: Vec<u64>,
+}
+
+impl StateSet {
+    /// Create an empty set.
+    #[inline]
+    pub fn new() -> Self {
+        Self { words: Vec::new() }
+    }
+
+    /// Add a state to the set, returning whether it was not already present.
+    #[inline]
+    pub fn insert(&mut self, state: usize) -> bool {
+        let (word, bit) = (state / WORD_BITS, 1 << (state % WORD_BITS));
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        let absent = self.words[word] & bit == 0;
+        self.words[word] |= bit;
+        absent
+    }
+
+    /// Remove a state from the set, returning whether it was present.
+    #[inline]
+    pub fn remove(&mut self, state: usize) -> bool {
+        match self.words.get_mut(state / WORD_BITS) {
+            Some(word) => {
+                let bit = 1 << (state % WORD_BITS);
+                let present = *word & bit != 0;
+                *word &= !bit;
+                present
+            }
+            None => false,
+        }
+    }
+
+    /// Determine if a state is in the set.
+    #[inline]
+    pub fn contains(&self, state: &usize) -> bool {
+        match self.words.get(state / WORD_BITS) {
+            Some(word) => word & (1 << (state % WORD_BITS)) != 0,
+            None => false,
+        }
+    }
+
+    /// The number of states in the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Determine if the set has no states.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    /// Remove every state from the set.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.words.clear();
+    }
+
+    /// Iterate over the states in the set, in ascending order.
+    #[inline]
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            words: self.words.iter(),
+            base: 0,
+            current: 0,
+        }
+    }
+}
+
+impl PartialEq for StateSet {
+    fn eq(&self, other: &Self) -> bool {
+        let (short, long) = if self.words.len() <= other.words.len() {
+            (&self.words, &other.words)
+        } else {
+            (&other.words, &self.words)
+        };
+        short.iter().zip(long.iter()).all(|(a, b)| a == b)
+            && long[short.len()..].iter().all(|&word| word == 0)
+    }
+}
+
+impl Eq for StateSet {}
+
+impl fmt::Debug for StateSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl Extend<usize> for StateSet {
+    #[inline]
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for state in iter {
+            self.insert(state);
+        }
+    }
+}
+
+impl FromIterator<usize> for StateSet {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<'a> IntoIterator for &'a StateSet {
+    type Item = usize;
+    type IntoIter = Iter<'a>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for StateSet {
+    type Item = usize;
+    type IntoIter = IntoIter;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            words: self.words.into_iter(),
+            base: 0,
+            current: 0,
+        }
+    }
+}
+
+impl From<Vec<usize>> for StateSet {
+    #[inline]
+    fn from(states: Vec<usize>) -> Self {
+        states.into_iter().collect()
+    }
+}
+
+impl From<StateSet> for Vec<usize> {
+    #[inline]
+    fn from(set: StateSet) -> Self {
+        set.iter().collect()
+    }
+}
+
+/// An iterator over the states in a [`StateSet`], in ascending order.
+#[derive(Debug, Clone)]
+pub struct Iter<'a> {
+    words: core::slice::Iter<'a, u64>,
+    /// The label one word past the lowest bit of `current`.
+    base: usize,
+    /// The bits of the current word that are yet to be yielded.
+    current: u64,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let words = &mut self.words;
+        next_state(|| words.next().copied(), &mut self.base, &mut self.current)
+    }
+}
+
+/// An owning iterator over the states in a [`StateSet`], in ascending order.
+#[derive(Debug, Clone)]
+pub struct IntoIter {
+    words: alloc::vec::IntoIter<u64>,
+    base: usize,
+    current: u64,
+}
+
+impl Iterator for IntoIter {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let words = &mut self.words;
+        next_state(|| words.next(), &mut self.base, &mut self.current)
+    }
+}
+
+/// Yield the lowest state left in `current`, loading further words as needed.
+#[inline]
+fn next_state<F>(mut next_word: F, base: &mut usize, current: &mut u64) -> Option<usize>
+where
+    F: FnMut() -> Option<u64>,
+{
+    while *current == 0 {
+        *current = next_word()?;
+        *base += WORD_BITS;
+    }
+    let bit = current.trailing_zeros() as usize;
+    *current &= *current - 1;
+    Some(*base - WORD_BITS + bit)
+}
@@ -0,0 +1,135 @@
+use crate::dense::{DenseDFA, Symbol};
+use crate::dfa::DFA;
+use crate::stateid::StateId;
+
+use core::hash::Hash;
+
+/// The state of a DFA that is driven one input symbol at a time, which can be kept between inputs.
+/// See [`DFA::step`] and [`DenseDFA::step`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DfaState {
+    /// The current state of the DFA, or `None` if it got stuck.
+    state: Option<StateId>,
+    /// Whether the current state is accepting.
+    accepting: bool,
+}
+
+/// The outcome of feeding a symbol to a DFA with [`DFA::step`] or [`DenseDFA::step`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StepResult {
+    /// The input so far is accepted.
+    Accepting,
+    /// The input so far is not accepted, but may be after more input.
+    Running,
+    /// The DFA got stuck, so the input is not accepted no matter what follows.
+    Dead,
+}
+
+impl DfaState {
+    #[inline]
+    pub(crate) fn new(state: Option<StateId>, accepting: bool) -> Self {
+        Self { state, accepting }
+    }
+
+    /// The current state of the DFA, or `None` if it is dead.
+    #[inline]
+    pub fn state(&self) -> Option<StateId> {
+        self.state
+    }
+
+    /// Determine if the input so far is accepted.
+    #[inline]
+    pub fn is_accepting(&self) -> bool {
+        self.accepting
+    }
+
+    /// Determine if the DFA got stuck, so no further input can be accepted.
+    #[inline]
+    pub fn is_dead(&self) -> bool {
+        self.state.is_none()
+    }
+
+    /// Whether the input so far is accepted, may still be accepted, or can't be.
+    #[inline]
+    pub fn result(&self) -> StepResult {
+        if self.accepting {
+            StepResult::Accepting
+        } else if self.is_dead() {
+            StepResult::Dead
+        } else {
+            StepResult::Running
+        }
+    }
+}
+
+impl<T> DFA<T>
+where
+    T: Clone + Eq + Hash,
+{
+    /// The state of the DFA before any input, to be driven by [`step`](Self::step).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start state doesn't fit in a [`StateId`].
+    #[inline]
+    pub fn start(&self) -> DfaState {
+        DfaState::new(
+            Some(step_label(self.start_state)),
+            self.is_accepting_state(&self.start_state),
+        )
+    }
+
+    /// Feed the next input symbol to the DFA, advancing its state. A dead state stays dead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the next state doesn't fit in a [`StateId`].
+    #[inline]
+    pub fn step<U>(&self, state: &mut DfaState, symbol: &U) -> StepResult
+    where
+        T: PartialEq<U>,
+    {
+        let next = state.state.and_then(|current| {
+            self.transitions_on(&current.as_usize())
+                .find(|(t, _)| t.0 == *symbol)
+                .map(|(_, &next)| next)
+        });
+        let accepting = matches!(next, Some(s) if self.is_accepting_state(&s));
+        *state = DfaState::new(next.map(step_label), accepting);
+        state.result()
+    }
+}
+
+impl<T> DenseDFA<T> {
+    /// The state of the DFA before any input, to be driven by [`step`](Self::step).
+    #[inline]
+    pub fn start(&self) -> DfaState {
+        DfaState::new(
+            Some(self.start_state),
+            self.is_accepting_state(self.start_state),
+        )
+    }
+
+    /// Feed the next input symbol to the DFA, advancing its state. A dead state stays dead.
+    #[inline]
+    pub fn step<U>(&self, state: &mut DfaState, symbol: &U) -> StepResult
+    where
+        U: Symbol,
+    {
+        let next = state
+            .state
+            .and_then(|current| self.next_state(current, symbol));
+        *state = DfaState::new(next, matches!(next, Some(s) if self.is_accepting_state(s)));
+        state.result()
+    }
+}
+
+/// Label a state of a sparse DFA that is being stepped.
+#[inline]
+fn step_label(state: usize) -> StateId {
+    match StateId::new(state) {
+        Ok(id) => id,
+        Err(e) => panic!("{}", e),
+    }
+}
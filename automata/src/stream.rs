@@ -0,0 +1,147 @@
+use crate::collections::VecDeque;
+use crate::dense::{DenseDFA, Symbol};
+use crate::prelude::*;
+use crate::stateid::StateId;
+use crate::stateset::StateSet;
+
+use core::ops::Range;
+
+/// The state of a search for matches in an input that is given in chunks. See
+/// [`DenseDFA::feed`].
+#[derive(Debug, Clone, Default)]
+pub struct StreamState {
+    /// The position of the next input symbol.
+    pos: usize,
+    /// Matches may only start at or after this position.
+    min_start: usize,
+    /// The end of the last match reported.
+    last_end: Option<usize>,
+    /// Runs of the DFA that started at different positions, ordered by start position. Runs that
+    /// got stuck are kept until they are resolved only if they found a match.
+    candidates: VecDeque<Candidate>,
+}
+
+#[derive(Debug, Clone)]
+struct Candidate {
+    start: usize,
+    /// The current DFA state, or `None` if the run got stuck.
+    state: Option<StateId>,
+    /// The end of the longest match found by the run so far.
+    end: Option<usize>,
+}
+
+impl StreamState {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The position of the next input symbol.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Start a run of the DFA at the current position, if a match may start there.
+    #[inline]
+    fn start<T>(&mut self, dfa: &DenseDFA<T>) {
+        if self.pos >= self.min_start {
+            let end = if dfa.is_accepting_state(dfa.start_state) {
+                Some(self.pos)
+            } else {
+                None
+            };
+            self.candidates.push_back(Candidate {
+                start: self.pos,
+                state: Some(dfa.start_state),
+                end,
+            });
+        }
+    }
+
+    /// Report the matches that can no longer be extended or preceded by a match further to the
+    /// left.
+    #[inline]
+    fn resolve(&mut self, matches: &mut Vec<Range<usize>>) {
+        while let Some(front) = self.candidates.front() {
+            if front.state.is_some() {
+                break;
+            }
+
+            let front = self.candidates.pop_front().unwrap();
+            if let Some(end) = front.end {
+                // An empty match where the last match ended is skipped, as when iterating over the
+                // matches of a regular expression.
+                if end == front.start && Some(end) == self.last_end {
+                    continue;
+                }
+                matches.push(front.start..end);
+                self.last_end = Some(end);
+
+                // Skip past empty matches, so the same one isn't reported again.
+                self.min_start = if end == front.start { end + 1 } else { end };
+                let min_start = self.min_start;
+                self.candidates.retain(|c| c.start >= min_start);
+            }
+        }
+    }
+}
+
+impl<T> DenseDFA<T> {
+    /// Feed the next chunk of input to a search, returning the leftmost-longest, non-overlapping
+    /// matches that are completed by the chunk. Matches that may still be extended by later input
+    /// are reported by later calls, or by [`finish`](Self::finish).
+    ///
+    /// Each input symbol is given with its width, which is the amount the position advances by
+    /// after the symbol. Matches are reported as ranges of positions.
+    #[inline]
+    pub fn feed<I, U>(&self, stream: &mut StreamState, input: I) -> Vec<Range<usize>>
+    where
+        U: Symbol,
+        I: IntoIterator<Item = (U, usize)>,
+    {
+        let mut matches = Vec::new();
+        let mut states = StateSet::new();
+        for (is, width) in input {
+            stream.start(self);
+
+            // Runs in the same state match the same input from here on, so only the earliest of
+            // them is continued, and there are at most as many running as there are states. The
+            // later ones only keep the matches they have found already, in case the earliest
+            // finds none, and runs that got stuck without a match are dropped.
+            let end = stream.pos + width;
+            states.clear();
+            for c in stream.candidates.iter_mut() {
+                if let Some(state) = c.state {
+                    c.state = self
+                        .next_state(state, &is)
+                        .filter(|next| states.insert(next.as_usize()));
+                    if let Some(next) = c.state {
+                        if self.is_accepting_state(next) {
+                            c.end = Some(end);
+                        }
+                    }
+                }
+            }
+            stream
+                .candidates
+                .retain(|c| c.state.is_some() || c.end.is_some());
+            stream.pos = end;
+
+            stream.resolve(&mut matches);
+        }
+        matches
+    }
+
+    /// End a search, returning the remaining matches.
+    #[inline]
+    pub fn finish(&self, stream: &mut StreamState) -> Vec<Range<usize>> {
+        let mut matches = Vec::new();
+        stream.start(self);
+        for c in stream.candidates.iter_mut() {
+            c.state = None;
+        }
+        stream.resolve(&mut matches);
+        matches
+    }
+}
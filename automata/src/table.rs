@@ -1,5 +1,7 @@
-use std::collections::HashMap;
-use std::hash::Hash;
+use crate::collections::{hash_map, HashMap};
+use crate::prelude::*;
+
+use core::hash::Hash;
 
 /// A two-way lookup table.
 #[derive(Debug)]
@@ -92,12 +94,13 @@ where
     /// Retrieve an immutable reference to a row of values.
     #[inline]
     pub fn get_row(&self, row: &T) -> HashMap<&U, &V> {
-        let row_map = match self.map.get(row) {
-            Some(m) => m,
-            None => return HashMap::new(),
-        };
+        self.row(row).collect()
+    }
 
-        row_map.iter().collect()
+    /// Iterate over the column keys and values of a row, without allocating.
+    #[inline]
+    pub fn row(&self, row: &T) -> RowIterator<'_, U, V> {
+        RowIterator(self.map.get(row).map(HashMap::iter))
     }
 
     /// Retrieve an immutable reference to a column of values.
@@ -152,7 +155,7 @@ where
 
 /// An iterator on the the values stored in the table. Each item is a tuple consisting of each
 /// set of keys and value.
-pub struct TableIterator<T, U, V>(std::vec::IntoIter<(T, U, V)>)
+pub struct TableIterator<T, U, V>(alloc::vec::IntoIter<(T, U, V)>)
 where
     T: Eq + Hash,
     U: Eq + Hash;
@@ -169,3 +172,65 @@ where
         self.0.next()
     }
 }
+
+/// An iterator on the column keys and values of a row of the table, created by [`Table::row`].
+#[derive(Debug, Clone)]
+pub struct RowIterator<'a, U, V>(Option<hash_map::Iter<'a, U, V>>);
+
+impl<'a, U, V> Iterator for RowIterator<'a, U, V> {
+    type Item = (&'a U, &'a V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.as_mut()?.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.0 {
+            Some(iter) => iter.size_hint(),
+            None => (0, Some(0)),
+        }
+    }
+}
+
+impl<'a, U, V> ExactSizeIterator for RowIterator<'a, U, V> {}
+
+#[cfg(feature = "serde")]
+impl<T, U, V> serde::Serialize for Table<T, U, V>
+where
+    T: Clone + Eq + Hash + serde::Serialize,
+    U: Eq + Hash + serde::Serialize,
+    V: serde::Serialize,
+{
+    /// Serialize the table as a sequence of `(row, col, val)` entries, so that keys need not be
+    /// representable as strings.
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_seq(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T, U, V> serde::Deserialize<'de> for Table<T, U, V>
+where
+    T: Eq + Hash + serde::Deserialize<'de>,
+    U: Eq + Hash + serde::Deserialize<'de>,
+    V: serde::Deserialize<'de>,
+{
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let entries: Vec<(T, U, V)> = serde::Deserialize::deserialize(deserializer)?;
+        let mut table = Table::new();
+        for (row, col, val) in entries {
+            table.set(row, col, val);
+        }
+        Ok(table)
+    }
+}
@@ -0,0 +1,18 @@
+//! Fixtures shared by the tests of the capturing engines.
+
+use automata::NFA;
+
+use std::hash::Hash;
+
+/// An NFA that matches a single symbol.
+pub fn symbol<T: Clone + Eq + Hash>(c: T) -> NFA<T> {
+    let mut nfa = NFA::new();
+    let s = nfa.add_state(true);
+    nfa.add_labeled_transition(nfa.start_state, s, c);
+    nfa
+}
+
+/// Capture the matches of an NFA as the group with the given index.
+pub fn group<T: Clone + Eq + Hash>(nfa: NFA<T>, index: usize) -> NFA<T> {
+    NFA::tagged(nfa, 2 * index, 2 * index + 1)
+}
@@ -0,0 +1,115 @@
+use automata::backtrack::BoundedBacktracker;
+use automata::pikevm::PikeVM;
+use automata::NFA;
+
+mod common;
+
+use common::{group, symbol};
+
+#[test]
+fn test_backtrack_captures() {
+    // (a|ab)(c|bcd)
+    let first = group(
+        NFA::union(
+            &symbol('a'),
+            &NFA::concatenation(&symbol('a'), &symbol('b')),
+        ),
+        1,
+    );
+    let bcd = NFA::concatenation(
+        &NFA::concatenation(&symbol('b'), &symbol('c')),
+        &symbol('d'),
+    );
+    let second = group(NFA::union(&symbol('c'), &bcd), 2);
+    let nfa = NFA::concatenation(&first, &second);
+    let bt = BoundedBacktracker::new(&nfa);
+    assert_eq!(6, bt.slots());
+
+    let chars = |s: &str| s.chars().collect::<Vec<_>>();
+    assert_eq!(
+        Some(vec![Some(0), Some(4), Some(0), Some(1), Some(1), Some(4)]),
+        bt.captures(&chars("abcd"))
+    );
+    assert_eq!(
+        Some(vec![Some(0), Some(3), Some(0), Some(2), Some(2), Some(3)]),
+        bt.captures(&chars("abcx"))
+    );
+    assert_eq!(None, bt.captures(&chars("ax")));
+
+    assert_eq!(
+        Some(vec![Some(0), Some(3), Some(0), Some(2), Some(2), Some(3)]),
+        bt.captures_whole(&chars("abc"))
+    );
+    assert_eq!(None, bt.captures_whole(&chars("abcx")));
+}
+
+#[test]
+fn test_backtrack_agrees_with_pikevm() {
+    // ((a)|b)*(a*)
+    let inner = group(NFA::union(&group(symbol('a'), 2), &symbol('b')), 1);
+    let nfa = NFA::concatenation(
+        &NFA::kleene_star(&inner),
+        &group(NFA::kleene_star(&symbol('a')), 3),
+    );
+    let bt = BoundedBacktracker::new(&nfa);
+    let vm = PikeVM::new(nfa.clone());
+    assert_eq!(vm.slots(), bt.slots());
+
+    for input in &["", "a", "ab", "ba", "abab", "bbaa", "abc", "c", "aabba"] {
+        let chars: Vec<_> = input.chars().collect();
+        assert_eq!(vm.captures(input.chars()), bt.captures(&chars), "{}", input);
+        assert_eq!(
+            vm.captures_whole(input.chars()),
+            bt.captures_whole(&chars),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_backtrack_capacity() {
+    let nfa = NFA::kleene_star(&symbol('a'));
+    let bt = BoundedBacktracker::with_capacity(&nfa, 10 * nfa.total_states);
+    assert_eq!(9, bt.max_input_len());
+    assert!(bt.captures_whole(&['a'; 9]).is_some());
+}
+
+#[test]
+#[should_panic]
+fn test_backtrack_input_too_long() {
+    let nfa = NFA::kleene_star(&symbol('a'));
+    let bt = BoundedBacktracker::with_capacity(&nfa, 10 * nfa.total_states);
+    bt.captures(&['a'; 10]);
+}
+
+#[test]
+fn test_backtrack_unanchored() {
+    // (a|ab)(c|d)
+    let first = group(
+        NFA::union(
+            &symbol('a'),
+            &NFA::concatenation(&symbol('a'), &symbol('b')),
+        ),
+        1,
+    );
+    let second = group(NFA::union(&symbol('c'), &symbol('d')), 2);
+    let nfa = NFA::concatenation(&first, &second);
+    let bt = BoundedBacktracker::new(&nfa);
+    let vm = PikeVM::new(nfa.clone());
+
+    for input in &["xxacac", "aabd", "abab", "", "ad"] {
+        let chars: Vec<_> = input.chars().collect();
+        assert_eq!(
+            vm.captures_unanchored(input.chars()),
+            bt.captures_unanchored(&chars),
+            "{}",
+            input
+        );
+    }
+    let chars: Vec<_> = "aabd".chars().collect();
+    assert_eq!(
+        Some(vec![Some(1), Some(4), Some(1), Some(3), Some(3), Some(4)]),
+        bt.captures_unanchored(&chars)
+    );
+}
@@ -0,0 +1,429 @@
+use automata::dense::{ClassMap, Search, Symbol, SymbolRanges};
+use automata::{DenseDFA, StateId, DFA};
+
+#[test]
+fn test_minimize_merges_equivalent_states() {
+    // Accepts "ab" and "cb" through separate but equivalent paths.
+    let mut d: DFA<char> = DFA::new();
+    let s1 = d.add_state(false);
+    let s2 = d.add_state(false);
+    let s3 = d.add_state(true);
+    let s4 = d.add_state(true);
+    d.add_transition(d.start_state, s1, 'a');
+    d.add_transition(d.start_state, s2, 'c');
+    d.add_transition(s1, s3, 'b');
+    d.add_transition(s2, s4, 'b');
+
+    let m = d.minimize();
+    assert_eq!(3, m.total_states);
+    assert_eq!(1, m.accepting_states.len());
+    assert_eq!(0, m.start_state);
+
+    for input in &["ab", "cb"] {
+        assert!(m.is_match(input.chars()));
+    }
+    for input in &["", "a", "b", "ac", "abb"] {
+        assert!(!m.is_match(input.chars()));
+    }
+}
+
+#[test]
+fn test_minimize_removes_useless_states() {
+    let mut d: DFA<char> = DFA::new();
+    let s1 = d.add_state(true);
+    // Unreachable from the start state.
+    let s2 = d.add_state(true);
+    // Cannot reach an accepting state.
+    let s3 = d.add_state(false);
+    d.add_transition(d.start_state, s1, 'a');
+    d.add_transition(s2, s1, 'a');
+    d.add_transition(d.start_state, s3, 'b');
+
+    let m = d.minimize();
+    assert_eq!(2, m.total_states);
+    assert!(m.is_match("a".chars()));
+    assert!(!m.is_match("b".chars()));
+}
+
+#[test]
+fn test_minimize_loops() {
+    // Accepts strings of a's with even length, using four states for what needs two.
+    let mut d: DFA<char> = DFA::new();
+    d.accepting_states.insert(d.start_state);
+    let s1 = d.add_state(false);
+    let s2 = d.add_state(true);
+    let s3 = d.add_state(false);
+    d.add_transition(d.start_state, s1, 'a');
+    d.add_transition(s1, s2, 'a');
+    d.add_transition(s2, s3, 'a');
+    d.add_transition(s3, d.start_state, 'a');
+
+    let m = d.minimize();
+    assert_eq!(2, m.total_states);
+    for (len, expected) in (0..8).map(|n| (n, n % 2 == 0)) {
+        let input = "a".repeat(len);
+        assert_eq!(expected, m.is_match(input.chars()));
+    }
+}
+
+#[test]
+fn test_minimize_empty_language() {
+    let mut d: DFA<char> = DFA::new();
+    let s1 = d.add_state(false);
+    d.add_transition(d.start_state, s1, 'a');
+
+    let m = d.minimize();
+    assert_eq!(1, m.total_states);
+    assert!(m.accepting_states.is_empty());
+    assert!(!m.is_match("a".chars()));
+}
+
+#[test]
+fn test_dense() {
+    // Accepts "ab*c".
+    let mut d: DFA<char> = DFA::new();
+    let s1 = d.add_state(false);
+    let s2 = d.add_state(true);
+    d.add_transition(d.start_state, s1, 'a');
+    d.add_transition(s1, s1, 'b');
+    d.add_transition(s1, s2, 'c');
+
+    let dense: DenseDFA<char> = d.into();
+    let s1 = StateId::new(s1).unwrap();
+    assert_eq!(3, dense.alphabet.len());
+    assert_eq!(dense.total_states * 3, dense.table.len());
+    assert_eq!(Some(s1), dense.next_state(s1, &'b'));
+    assert_eq!(None, dense.next_state(s1, &'a'));
+    assert_eq!(None, dense.next_state(s1, &'z'));
+
+    for input in &["ac", "abc", "abbbc"] {
+        assert!(dense.is_match(input.chars()));
+    }
+    for input in &["", "a", "abb", "bc", "acc"] {
+        assert!(!dense.is_match(input.chars()));
+    }
+
+    let m = dense.find_unanchored("xxabbcab".chars()).unwrap();
+    assert_eq!((2, 6), (m.start(), m.end()));
+    assert_eq!(vec!['a', 'b', 'b', 'c'], m.span);
+    assert!(dense.find_unanchored("xxabb".chars()).is_none());
+}
+
+#[test]
+fn test_dense_class_map() {
+    let map = ClassMap::new(&['a', 'é', '\u{2603}', '\u{1f600}']);
+    assert_eq!(Some(0), map.get('a' as u32));
+    assert_eq!(Some(1), map.get('é' as u32));
+    assert_eq!(Some(2), map.get('\u{2603}' as u32));
+    assert_eq!(Some(3), map.get('\u{1f600}' as u32));
+    assert_eq!(None, map.get('b' as u32));
+    assert_eq!(None, map.get('\u{2604}' as u32));
+    assert_eq!(None, map.get(0x10ffff));
+
+    let mut d: DFA<char> = DFA::new();
+    let s1 = d.add_state(true);
+    d.add_transition(d.start_state, s1, '\u{2603}');
+    let dense: DenseDFA<char> = d.into();
+    assert!(dense.is_match("\u{2603}".chars()));
+    assert!(!dense.is_match("\u{2602}".chars()));
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+enum Token {
+    Num,
+    Plus,
+    Times,
+}
+
+impl Symbol for Token {
+    const SIZE: Option<u32> = Some(3);
+
+    fn code(&self) -> u32 {
+        *self as u32
+    }
+}
+
+impl SymbolRanges for Token {
+    fn code_ranges(&self) -> Vec<(u32, u32)> {
+        vec![(self.code(), self.code())]
+    }
+}
+
+#[test]
+fn test_dense_token_alphabet() {
+    let map = ClassMap::new(&[Token::Times, Token::Num]);
+    assert_eq!(Some(1), map.lookup(&Token::Num));
+    assert_eq!(None, map.lookup(&Token::Plus));
+    assert_eq!(Some(0), map.lookup(&Token::Times));
+    assert_eq!(Some(1), map.lookup(&b'\0'));
+    assert_eq!(None, map.lookup(&'\u{2603}'));
+
+    // Num ((Plus | Times) Num)*
+    let mut d: DFA<Token> = DFA::new();
+    let s1 = d.add_state(true);
+    let s2 = d.add_state(false);
+    d.add_transition(d.start_state, s1, Token::Num);
+    d.add_transition(s1, s2, Token::Plus);
+    d.add_transition(s1, s2, Token::Times);
+    d.add_transition(s2, s1, Token::Num);
+    let dense: DenseDFA<Token> = d.into();
+    assert!(dense.is_match(vec![Token::Num, Token::Times, Token::Num]));
+    assert!(!dense.is_match(vec![Token::Num, Token::Plus]));
+    let m = dense
+        .find_unanchored(vec![Token::Plus, Token::Num, Token::Plus, Token::Num])
+        .unwrap();
+    assert_eq!((1, 4), (m.start(), m.end()));
+}
+
+#[test]
+fn test_transitions_on() {
+    let mut d: DFA<char> = DFA::new();
+    let s1 = d.add_state(true);
+    d.add_transition(d.start_state, s1, 'a');
+    d.add_transition(d.start_state, s1, 'b');
+
+    let mut transitions: Vec<_> = d
+        .transitions_on(&d.start_state)
+        .map(|(t, &next)| (t.0, next))
+        .collect();
+    transitions.sort_unstable();
+    assert_eq!(vec![('a', s1), ('b', s1)], transitions);
+    assert_eq!(2, d.transitions_on(&d.start_state).len());
+    assert_eq!(0, d.transitions_on(&s1).count());
+    assert_eq!(0, d.transitions_on(&42).count());
+}
+
+#[test]
+fn test_find_long_span() {
+    // Accepts "a(a|b)*", which is accepting after every symbol of the input.
+    let mut d: DFA<char> = DFA::new();
+    let s1 = d.add_state(true);
+    d.add_transition(d.start_state, s1, 'a');
+    d.add_transition(s1, s1, 'a');
+    d.add_transition(s1, s1, 'b');
+
+    let input = format!("{}c", "ab".repeat(5000));
+    let m = d.find(input.chars()).unwrap();
+    assert_eq!((0, 10000), (m.start(), m.end()));
+    assert_eq!(10000, m.span.len());
+
+    let m = d.find_shortest(input.chars()).unwrap();
+    assert_eq!(vec!['a'], m.span);
+}
+
+#[test]
+fn test_dead_state_bailout() {
+    // Accepts "ab", with a dead state that every other input leads to.
+    let mut d: DFA<char> = DFA::new();
+    let s1 = d.add_state(false);
+    let s2 = d.add_state(true);
+    let dead = d.add_state(false);
+    d.add_transition(d.start_state, s1, 'a');
+    d.add_transition(s1, s2, 'b');
+    for &(state, c) in &[
+        (d.start_state, 'b'),
+        (s1, 'a'),
+        (s2, 'a'),
+        (s2, 'b'),
+        (dead, 'a'),
+    ] {
+        d.add_transition(state, dead, c);
+    }
+    d.add_transition(dead, dead, 'b');
+
+    assert_eq!(vec![true, true, true, false], d.live_states());
+
+    // Matching stops at the first symbol that leads to the dead state.
+    let consumed = std::cell::Cell::new(0);
+    let input = "abab".chars().inspect(|_| consumed.set(consumed.get() + 1));
+    assert!(!d.is_match(input));
+    assert_eq!(3, consumed.get());
+
+    let m = d.find("abbbbb".chars()).unwrap();
+    assert_eq!((0, 2), (m.start(), m.end()));
+
+    let dense: DenseDFA<char> = (&d).into();
+    let (s1, s2) = (StateId::new(s1).unwrap(), StateId::new(s2).unwrap());
+    assert_eq!(None, dense.next_state(s2, &'a'));
+    assert_eq!(Some(s2), dense.next_state(s1, &'b'));
+    assert!(dense.is_match("ab".chars()));
+    assert!(!dense.is_match("aba".chars()));
+}
+
+#[test]
+fn test_dense_unanchored_start() {
+    // Accepts "ab*c".
+    let mut d: DFA<char> = DFA::new();
+    let s1 = d.add_state(false);
+    let s2 = d.add_state(true);
+    d.add_transition(d.start_state, s1, 'a');
+    d.add_transition(s1, s1, 'b');
+    d.add_transition(s1, s2, 'c');
+    let dense: DenseDFA<char> = d.into();
+
+    // The unanchored start state is accepting after input that ends with a match, including
+    // symbols outside the alphabet.
+    let mut state = dense.unanchored_start_state.unwrap();
+    assert!(state >= StateId::from_u32(3));
+    for (c, accepting) in "xabxabbc!".chars().zip(vec![
+        false, false, false, false, false, false, false, true, false,
+    ]) {
+        state = dense.next_state(state, &c).unwrap();
+        assert_eq!(accepting, dense.is_accepting_state(state));
+    }
+
+    let anchored = Search {
+        anchored: true,
+        ..Search::default()
+    };
+    assert!(dense.search("xxabc".chars(), 0, &anchored).is_none());
+    let m = dense.search("xxabc".chars(), 2, &anchored).unwrap();
+    assert_eq!((2, 5), (m.start(), m.end()));
+
+    let m = dense
+        .search("xxabcabbc".chars(), 3, &Search::default())
+        .unwrap();
+    assert_eq!((5, 9), (m.start(), m.end()));
+    assert!(dense
+        .search("abbbb".chars(), 0, &Search::default())
+        .is_none());
+}
+
+#[test]
+fn test_count_accepted() {
+    // Accepts "ab*c", with a state from which nothing is accepted.
+    let mut d: DFA<char> = DFA::new();
+    let s1 = d.add_state(false);
+    let s2 = d.add_state(true);
+    let stuck = d.add_state(false);
+    d.add_transition(d.start_state, s1, 'a');
+    d.add_transition(s1, s1, 'b');
+    d.add_transition(s1, s2, 'c');
+    d.add_transition(s2, stuck, 'a');
+    d.add_transition(stuck, stuck, 'a');
+
+    let counts: Vec<_> = (0..5).map(|n| d.count_accepted(n)).collect();
+    assert_eq!(vec![Some(0), Some(0), Some(1), Some(1), Some(1)], counts);
+    assert_eq!(Some(1), d.count_accepted(usize::MAX));
+
+    // Accepts every input of a's and b's, of which there are 2^n.
+    let mut d: DFA<char> = DFA::new();
+    d.accepting_states.insert(d.start_state);
+    d.add_transition(d.start_state, d.start_state, 'a');
+    d.add_transition(d.start_state, d.start_state, 'b');
+    assert_eq!(Some(1), d.count_accepted(0));
+    assert_eq!(Some(1 << 127), d.count_accepted(127));
+    assert_eq!(None, d.count_accepted(128));
+
+    #[cfg(feature = "bigint")]
+    assert_eq!(
+        "340282366920938463463374607431768211456",
+        d.count_accepted_big(128).to_string()
+    );
+
+    // Nothing is accepted.
+    assert_eq!(Some(0), DFA::<char>::new().count_accepted(3));
+}
+
+#[test]
+fn test_sample() {
+    // Accepts "a", "bc", and "bd", with a state from which nothing is accepted.
+    let mut d: DFA<char> = DFA::new();
+    let s1 = d.add_state(true);
+    let s2 = d.add_state(false);
+    let s3 = d.add_state(true);
+    let stuck = d.add_state(false);
+    d.add_transition(d.start_state, s1, 'a');
+    d.add_transition(d.start_state, s2, 'b');
+    d.add_transition(s2, s3, 'c');
+    d.add_transition(s2, s3, 'd');
+    d.add_transition(s2, stuck, 'e');
+
+    let mut seed = 0x2545_f491_4f6c_dd1du64;
+    let mut rng = || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+
+    // Every accepted input is about equally likely.
+    let mut counts = std::collections::HashMap::new();
+    for _ in 0..3000 {
+        let sample = d.sample(&mut rng, 2).unwrap();
+        assert!(d.is_match(sample.iter().copied()));
+        *counts
+            .entry(sample.into_iter().collect::<String>())
+            .or_insert(0) += 1;
+    }
+    assert_eq!(3, counts.len());
+    for count in counts.values() {
+        assert!((800..1200).contains(count), "{:?}", counts);
+    }
+
+    // Only "a" is short enough.
+    for _ in 0..10 {
+        assert_eq!(Some(vec!['a']), d.sample(&mut rng, 1));
+    }
+    assert_eq!(None, d.sample(&mut rng, 0));
+    assert_eq!(None, DFA::<char>::new().sample(&mut rng, 10));
+}
+
+#[test]
+fn test_find_at_offsets() {
+    // Accepts "b*".
+    let mut d: DFA<char> = DFA::new();
+    d.accepting_states.insert(d.start_state);
+    d.add_transition(d.start_state, d.start_state, 'b');
+    let dense: DenseDFA<char> = (&d).into();
+
+    let input = "abb";
+    for start in 0..=input.len() {
+        let expected = if start == 0 { 0 } else { input.len() };
+        let m = d.find_at(input.chars(), start).unwrap();
+        assert_eq!((start, expected.max(start)), (m.start(), m.end()));
+        let m = dense.find_at(input.chars(), start).unwrap();
+        assert_eq!((start, expected.max(start)), (m.start(), m.end()));
+    }
+
+    // A search can start at the end of the input, but not past it.
+    for start in 4..6 {
+        assert_eq!(None, d.find_at(input.chars(), start));
+        assert_eq!(None, d.find_unanchored_at(input.chars(), start));
+        assert_eq!(None, dense.find_at(input.chars(), start));
+        assert_eq!(None, dense.find_unanchored_at(input.chars(), start));
+    }
+}
+
+#[test]
+fn test_iter_on_positions() {
+    use automata::dfa::IterState;
+
+    // Accepts "ab*c".
+    let mut d: DFA<char> = DFA::new();
+    let s1 = d.add_state(false);
+    let s2 = d.add_state(true);
+    d.add_transition(d.start_state, s1, 'a');
+    d.add_transition(s1, s1, 'b');
+    d.add_transition(s1, s2, 'c');
+
+    let steps: Vec<_> = d
+        .into_iter_on("abbcx".chars())
+        .map(|state| match state {
+            IterState::Start(state, accepting) => ('^', 0, state, accepting),
+            IterState::Normal(c, pos, state, accepting) => (c, pos, state, accepting),
+            IterState::Stuck(state, pos) => ('!', pos, state, false),
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            ('^', 0, 0, false),
+            ('a', 0, s1, false),
+            ('b', 1, s1, false),
+            ('b', 2, s1, false),
+            ('c', 3, s2, true),
+            ('!', 4, s2, false),
+        ],
+        steps
+    );
+}
@@ -0,0 +1,24 @@
+use automata::intern::Interner;
+
+use std::rc::Rc;
+
+#[test]
+fn test_intern() {
+    let mut interner = Interner::new();
+    assert!(interner.is_empty());
+
+    let symbols: Vec<Rc<str>> = ["ab", "cd", "ab", "ab", "cd", "ef"]
+        .iter()
+        .map(|&s| interner.intern(Rc::from(s)))
+        .collect();
+    assert_eq!(3, interner.len());
+
+    assert!(Rc::ptr_eq(&symbols[0], &symbols[2]));
+    assert!(Rc::ptr_eq(&symbols[0], &symbols[3]));
+    assert!(Rc::ptr_eq(&symbols[1], &symbols[4]));
+    assert!(!Rc::ptr_eq(&symbols[0], &symbols[1]));
+
+    // The pool and the three interned copies of "ab".
+    assert_eq!(4, Rc::strong_count(&symbols[0]));
+    assert_eq!(2, Rc::strong_count(&symbols[5]));
+}
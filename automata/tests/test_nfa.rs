@@ -65,7 +65,7 @@ fn test_kleene_star() {
     let c1: NFA<bool> = NFA::new_epsilon();
 
     let kleene = NFA::kleene_star(&c1);
-    assert_eq!(4, kleene.total_states);
+    assert_eq!(5, kleene.total_states);
     assert_eq!(1, kleene.accepting_states.len());
 }
 
@@ -80,3 +80,221 @@ fn test_combine() {
     assert_eq!(5, combined.total_states);
     assert_eq!(2, combined.accepting_states.len());
 }
+
+/// An NFA that matches a string of characters.
+fn literal(s: &str) -> NFA<char> {
+    let mut nfa = NFA::new();
+    let mut state = nfa.start_state;
+    for c in s.chars() {
+        let next = nfa.add_state(false);
+        nfa.add_labeled_transition(state, next, c);
+        state = next;
+    }
+    nfa.accepting_states.insert(state);
+    nfa
+}
+
+#[test]
+fn test_find_unanchored() {
+    // abcd|b|(xy)*z
+    let nfa = NFA::union(
+        &NFA::union(&literal("abcd"), &literal("b")),
+        &NFA::concatenation(&NFA::kleene_star(&literal("xy")), &literal("z")),
+    );
+    let inputs = [
+        "", "b", "abcd", "abce", "xabcdb", "xyxyz", "xyxz", "bxyz", "axyxyzb",
+    ];
+
+    for input in &inputs {
+        let chars: Vec<_> = input.chars().collect();
+        for &shortest in &[false, true] {
+            // The leftmost start of a match, and the longest or shortest match from there.
+            let expected = (0..=chars.len()).find_map(|start| {
+                let ends = (start..=chars.len())
+                    .filter(|&end| nfa.is_match(chars[start..end].iter().copied()));
+                let end = if shortest { ends.min() } else { ends.max() };
+                end.map(|end| (start, end))
+            });
+
+            let found = if shortest {
+                nfa.find_shortest_unanchored_at(chars.iter().copied(), 0)
+            } else {
+                nfa.find_unanchored(chars.iter().copied())
+            };
+            assert_eq!(
+                expected,
+                found.as_ref().map(|m| (m.start(), m.end())),
+                "wrong match in {:?}",
+                input
+            );
+            if let Some(m) = found {
+                assert_eq!(&chars[m.range()], &m.span[..]);
+            }
+        }
+    }
+
+    let m = nfa.find_unanchored_at("babcd".chars(), 1).unwrap();
+    assert_eq!(1..5, m.range());
+}
+
+#[test]
+fn test_cache_epsilon_closures() {
+    // (ab|a)*(b|)
+    let nfa = NFA::concatenation(
+        &NFA::kleene_star(&NFA::union(&literal("ab"), &literal("a"))),
+        &NFA::union(&literal("b"), &NFA::new_epsilon()),
+    );
+    let mut cached = nfa.clone();
+    cached.cache_epsilon_closures();
+    assert!(cached.has_cached_epsilon_closures());
+
+    for state in 0..nfa.total_states {
+        let mut expected = Vec::new();
+        nfa.epsilon_closure_into(state, &mut expected, &mut Default::default());
+        let mut closure = Vec::new();
+        cached.epsilon_closure_into(state, &mut closure, &mut Default::default());
+        assert_eq!(expected, closure, "wrong closure of state {}", state);
+    }
+
+    for input in &["", "a", "ab", "abb", "aab", "ba", "abab"] {
+        assert_eq!(nfa.is_match(input.chars()), cached.is_match(input.chars()));
+        assert_eq!(
+            nfa.find_unanchored(input.chars()).map(|m| m.range()),
+            cached.find_unanchored(input.chars()).map(|m| m.range())
+        );
+    }
+
+    // Adding an epsilon transition changes the closures, so the cache is dropped.
+    let accepting = cached.accepting_states.iter().next().unwrap();
+    cached.add_epsilon_transition(accepting, cached.start_state);
+    assert!(!cached.has_cached_epsilon_closures());
+    assert!(cached
+        .epsilon_closure(accepting)
+        .contains(&cached.start_state));
+}
+
+#[test]
+fn test_union_into_branches() {
+    // a|a|...|a, built up from the left as the parser does.
+    let mut union = literal("a");
+    for _ in 1..1000 {
+        NFA::union_into(&mut union, &literal("a"));
+    }
+
+    // The branches share one start state, so the closures stay small enough to be cached.
+    let start_closure = union.epsilon_closure(union.start_state);
+    assert_eq!(1001, start_closure.len());
+    union.cache_epsilon_closures();
+    assert!(union.has_cached_epsilon_closures());
+    assert!(union.is_match("a".chars()));
+    assert!(!union.is_match("aa".chars()));
+
+    // Branches added after a change aren't merged into the earlier union: tagging it as a group
+    // keeps its slots to its own branches.
+    let mut group = NFA::tagged(union, 2, 3);
+    let total_states = group.total_states;
+    NFA::union_into(&mut group, &literal("b"));
+    assert_eq!(total_states + 4, group.total_states);
+    assert!(group.is_match("b".chars()));
+}
+
+#[test]
+fn test_owned_combinators() {
+    let (ab, c) = (literal("ab"), literal("c"));
+
+    let mut union = ab.clone();
+    NFA::union_into(&mut union, &c);
+    let concatenation = NFA::concatenation_owned(ab.clone(), c.clone());
+    let star = NFA::kleene_star_owned(ab.clone());
+
+    let cases = [
+        (NFA::union(&ab, &c), union),
+        (NFA::concatenation(&ab, &c), concatenation),
+        (NFA::kleene_star(&ab), star),
+    ];
+    for (expected, nfa) in &cases {
+        assert_eq!(expected.total_states, nfa.total_states);
+        for input in &["", "ab", "c", "abc", "abab", "ababc", "ca"] {
+            assert_eq!(
+                expected.is_match(input.chars()),
+                nfa.is_match(input.chars()),
+                "wrong result on {:?}",
+                input
+            );
+        }
+    }
+}
+
+#[test]
+fn test_introspection() {
+    let nfa = NFA::union(&literal("ab"), &literal("c"));
+    assert_eq!(0..nfa.total_states, nfa.states());
+    assert_eq!(vec![1], nfa.final_states().collect::<Vec<_>>());
+
+    let transitions: Vec<_> = nfa.transitions().collect();
+    assert_eq!(
+        nfa.transition
+            .into_iter()
+            .map(|(_, _, ends)| ends.len())
+            .sum::<usize>(),
+        transitions.len()
+    );
+    for (start, label, end) in &transitions {
+        assert!(nfa.transitions_from(*start)[label].contains(end));
+    }
+    assert!(transitions.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+
+    let symbols: Vec<_> = transitions
+        .iter()
+        .filter_map(|(_, label, _)| label.symbol())
+        .collect();
+    assert_eq!(3, symbols.len());
+    for c in &['a', 'b', 'c'] {
+        assert!(symbols.contains(&c));
+    }
+    assert_eq!(
+        4,
+        transitions
+            .iter()
+            .filter(|(_, label, _)| **label == Transition::Epsilon)
+            .count()
+    );
+}
+
+#[test]
+fn test_remove_epsilon_and_trim() {
+    // (ab|c)*(b|)
+    let nfa = NFA::concatenation(
+        &NFA::kleene_star(&NFA::union(&literal("ab"), &literal("c"))),
+        &NFA::union(&literal("b"), &NFA::new_epsilon()),
+    );
+    let removed = nfa.remove_epsilon();
+    assert_eq!(nfa.total_states, removed.total_states);
+    assert!(removed
+        .transitions()
+        .all(|(_, label, _)| *label != Transition::Epsilon));
+
+    let trimmed = removed.trim();
+    assert!(trimmed.total_states < removed.total_states);
+
+    for input in &["", "ab", "c", "abc", "cb", "abab", "abb", "ba", "cbb"] {
+        let expected = nfa.is_match(input.chars());
+        assert_eq!(expected, removed.is_match(input.chars()), "{:?}", input);
+        assert_eq!(expected, trimmed.is_match(input.chars()), "{:?}", input);
+    }
+
+    // The states after the dead end aren't reachable, and the dead end can't reach a match.
+    let mut nfa = literal("ab");
+    let dead = nfa.add_state(false);
+    let unreachable = nfa.add_state(true);
+    nfa.add_labeled_transition(nfa.start_state, dead, 'x');
+    nfa.add_labeled_transition(unreachable, nfa.start_state, 'y');
+    let trimmed = nfa.trim();
+    assert_eq!(3, trimmed.total_states);
+    assert_eq!(2, trimmed.transitions().count());
+    assert!(trimmed.is_match("ab".chars()));
+
+    // Nothing is accepted, but the start state is kept.
+    let trimmed = NFA::<char>::new().trim();
+    assert_eq!(1, trimmed.total_states);
+}
@@ -0,0 +1,101 @@
+use automata::onepass::OnePass;
+use automata::pikevm::PikeVM;
+use automata::NFA;
+
+mod common;
+
+use common::{group, symbol};
+
+fn assert_agrees(nfa: NFA<u8>, inputs: &[&str]) {
+    let onepass = OnePass::new(&nfa).expect("the NFA should be one-pass");
+    let vm = PikeVM::new(nfa);
+    assert_eq!(vm.slots(), onepass.slots());
+
+    for input in inputs {
+        let bytes = input.bytes();
+        assert_eq!(
+            vm.captures(bytes.clone()),
+            onepass.captures(bytes.clone()),
+            "{}",
+            input
+        );
+        assert_eq!(
+            vm.captures_whole(bytes.clone()),
+            onepass.captures_whole(bytes),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_onepass_captures() {
+    // (a*)-(b|(c))
+    let nfa = NFA::concatenation(
+        &NFA::concatenation(&group(NFA::kleene_star(&symbol(b'a')), 1), &symbol(b'-')),
+        &group(NFA::union(&symbol(b'b'), &group(symbol(b'c'), 3)), 2),
+    );
+    let onepass = OnePass::new(&nfa).unwrap();
+    assert_eq!(8, onepass.slots());
+    assert_eq!(
+        Some(vec![
+            Some(0),
+            Some(4),
+            Some(0),
+            Some(2),
+            Some(3),
+            Some(4),
+            Some(3),
+            Some(4)
+        ]),
+        onepass.captures("aa-cx".bytes())
+    );
+    assert_eq!(
+        Some(vec![
+            Some(0),
+            Some(2),
+            Some(0),
+            Some(0),
+            Some(1),
+            Some(2),
+            None,
+            None
+        ]),
+        onepass.captures_whole("-b".bytes())
+    );
+    assert_eq!(None, onepass.captures_whole("-bx".bytes()));
+    assert_eq!(None, onepass.captures("a-".bytes()));
+
+    assert_agrees(nfa, &["", "-", "-b", "a-c", "aa-cx", "aab", "-bb", "a-c-"]);
+}
+
+#[test]
+fn test_onepass_repetition() {
+    // ((a)|b)*c?
+    let inner = group(NFA::union(&group(symbol(b'a'), 2), &symbol(b'b')), 1);
+    let c = NFA::union(&symbol(b'c'), &NFA::new_epsilon());
+    let nfa = NFA::concatenation(&NFA::kleene_star(&inner), &c);
+    assert_agrees(
+        nfa,
+        &["", "a", "ab", "ba", "abab", "bbac", "abcx", "c", "cc"],
+    );
+}
+
+#[test]
+fn test_onepass_ambiguous() {
+    // (a|ab)(c|bcd)
+    let first = group(
+        NFA::union(
+            &symbol(b'a'),
+            &NFA::concatenation(&symbol(b'a'), &symbol(b'b')),
+        ),
+        1,
+    );
+    let second = group(NFA::union(&symbol(b'c'), &symbol(b'b')), 2);
+    assert!(OnePass::new(&NFA::concatenation(&first, &second)).is_none());
+
+    // (a*)(a*)
+    let star = NFA::kleene_star(&symbol(b'a'));
+    let nfa = NFA::concatenation(&group(star.clone(), 1), &group(star, 2));
+    assert!(OnePass::new(&nfa).is_none());
+}
@@ -0,0 +1,78 @@
+use automata::convert::DFAFromNFA;
+use automata::{DFA, NFA};
+
+/// An NFA that accepts exactly the given bytes.
+fn literal(bytes: &[u8]) -> NFA<u8> {
+    let mut nfa = NFA::new();
+    let mut state = nfa.start_state;
+    for &b in bytes {
+        let next = nfa.add_state(false);
+        nfa.add_labeled_transition(state, next, b);
+        state = next;
+    }
+    nfa.accepting_states.insert(state);
+    nfa
+}
+
+/// The pattern IDs of the state reached on the input, sorted.
+fn patterns_after(dfa: &DFA<u8>, input: &[u8]) -> Vec<usize> {
+    let mut state = dfa.start_state;
+    for b in input {
+        state = match dfa.transitions_on(&state).find(|(t, _)| t.0 == *b) {
+            Some((_, &next)) => next,
+            None => return Vec::new(),
+        };
+    }
+    dfa.pattern_ids(&state).collect()
+}
+
+#[test]
+fn test_nfa_from_patterns() {
+    let (ab, abc) = (literal(b"ab"), literal(b"abc"));
+    let nfa = NFA::from_patterns(&[&ab, &abc, &ab]);
+    assert_eq!(3, nfa.accepting_states.len());
+
+    let mut ids: Vec<_> = nfa
+        .accepting_states
+        .iter()
+        .flat_map(|s| nfa.pattern_ids(&s))
+        .collect();
+    ids.sort_unstable();
+    assert_eq!(vec![0, 1, 2], ids);
+    assert_eq!(0, nfa.pattern_ids(&nfa.start_state).count());
+
+    // Combining keeps the pattern IDs of the operands.
+    let combined = NFA::combine(&[&nfa, &literal(b"x")]);
+    assert_eq!(4, combined.accepting_states.len());
+    assert_eq!(3, combined.patterns.len());
+}
+
+#[test]
+fn test_dfa_patterns() {
+    let (ab, abc, a_or_ab) = (
+        literal(b"ab"),
+        literal(b"abc"),
+        NFA::union(&literal(b"a"), &literal(b"ab")),
+    );
+    let nfa = NFA::from_patterns(&[&ab, &abc, &a_or_ab]);
+    let dfa: DFA<u8> = DFAFromNFA::from(nfa).into();
+    let minimized = dfa.minimize();
+
+    for d in &[&dfa, &minimized] {
+        assert_eq!(vec![2], patterns_after(d, b"a"));
+        assert_eq!(vec![0, 2], patterns_after(d, b"ab"));
+        assert_eq!(vec![1], patterns_after(d, b"abc"));
+        assert!(patterns_after(d, b"").is_empty());
+    }
+}
+
+#[test]
+fn test_minimize_keeps_patterns_apart() {
+    // Both patterns accept after one byte, with no transitions afterwards, so the accepting states
+    // would be merged if not for their patterns.
+    let nfa = NFA::from_patterns(&[&literal(b"a"), &literal(b"b")]);
+    let dfa = DFA::from(DFAFromNFA::from(nfa)).minimize();
+    assert_eq!(3, dfa.total_states);
+    assert_eq!(vec![0], patterns_after(&dfa, b"a"));
+    assert_eq!(vec![1], patterns_after(&dfa, b"b"));
+}
@@ -0,0 +1,84 @@
+use automata::pikevm::PikeVM;
+use automata::NFA;
+
+mod common;
+
+use common::{group, symbol};
+
+#[test]
+fn test_pikevm_captures() {
+    // (a|ab)(c|bcd)
+    let first = group(
+        NFA::union(
+            &symbol('a'),
+            &NFA::concatenation(&symbol('a'), &symbol('b')),
+        ),
+        1,
+    );
+    let bcd = NFA::concatenation(
+        &NFA::concatenation(&symbol('b'), &symbol('c')),
+        &symbol('d'),
+    );
+    let second = group(NFA::union(&symbol('c'), &bcd), 2);
+    let vm = PikeVM::new(NFA::concatenation(&first, &second));
+    assert_eq!(6, vm.slots());
+
+    // The first alternative is preferred, even though the second gives a longer match.
+    assert_eq!(
+        Some(vec![Some(0), Some(4), Some(0), Some(1), Some(1), Some(4)]),
+        vm.captures("abcd".chars())
+    );
+    assert_eq!(
+        Some(vec![Some(0), Some(3), Some(0), Some(2), Some(2), Some(3)]),
+        vm.captures("abcx".chars())
+    );
+    assert_eq!(None, vm.captures("ax".chars()));
+
+    // The whole input must be matched, which only the second alternative does here.
+    assert_eq!(
+        Some(vec![Some(0), Some(3), Some(0), Some(2), Some(2), Some(3)]),
+        vm.captures_whole("abc".chars())
+    );
+    assert_eq!(None, vm.captures_whole("abcx".chars()));
+}
+
+#[test]
+fn test_pikevm_repetition() {
+    // (a|(b))*
+    let inner = NFA::union(&symbol('a'), &group(symbol('b'), 2));
+    let vm = PikeVM::new(NFA::kleene_star(&group(inner, 1)));
+
+    // A repeated group holds its last repetition.
+    assert_eq!(
+        Some(vec![Some(0), Some(3), Some(2), Some(3), Some(1), Some(2)]),
+        vm.captures("aba".chars())
+    );
+    assert_eq!(
+        Some(vec![Some(0), Some(0), None, None, None, None]),
+        vm.captures("".chars())
+    );
+}
+
+#[test]
+fn test_pikevm_unanchored() {
+    // (a|ab)(c|bcd)
+    let first = group(
+        NFA::union(
+            &symbol('a'),
+            &NFA::concatenation(&symbol('a'), &symbol('b')),
+        ),
+        1,
+    );
+    let second = group(NFA::union(&symbol('c'), &symbol('d')), 2);
+    let vm = PikeVM::new(NFA::concatenation(&first, &second));
+
+    assert_eq!(
+        Some(vec![Some(2), Some(4), Some(2), Some(3), Some(3), Some(4)]),
+        vm.captures_unanchored("xxacac".chars())
+    );
+    assert_eq!(
+        Some(vec![Some(1), Some(4), Some(1), Some(3), Some(3), Some(4)]),
+        vm.captures_unanchored("aabd".chars())
+    );
+    assert_eq!(None, vm.captures_unanchored("abab".chars()));
+}
@@ -0,0 +1,53 @@
+use automata::predicate::Predicate;
+use automata::NFA;
+
+/// Accepts a capitalized word followed by one or more numbers.
+fn nfa() -> NFA<Predicate<String>> {
+    let capitalized = Predicate::new(|s: &String| s.starts_with(char::is_uppercase));
+    let number = Predicate::new(|s: &String| s.parse::<i64>().is_ok());
+
+    let mut nfa = NFA::new();
+    let s1 = nfa.add_state(false);
+    let s2 = nfa.add_state(true);
+    nfa.add_labeled_transition(nfa.start_state, s1, capitalized);
+    nfa.add_labeled_transition(s1, s2, number.clone());
+    nfa.add_labeled_transition(s2, s2, number);
+    nfa
+}
+
+fn words(s: &str) -> Vec<String> {
+    s.split_whitespace().map(String::from).collect()
+}
+
+#[test]
+fn test_predicate_match() {
+    let nfa = nfa();
+    assert!(nfa.is_match(words("Route 66")));
+    assert!(nfa.is_match(words("Lat 40 -73")));
+    assert!(!nfa.is_match(words("route 66")));
+    assert!(!nfa.is_match(words("Route")));
+    assert!(!nfa.is_match(words("Route 66 north")));
+
+    let input = words("take Route 66 west");
+    let m = nfa.find_unanchored(input).unwrap();
+    assert_eq!(1..3, m.range());
+    assert_eq!(words("Route 66"), m.span);
+}
+
+#[test]
+fn test_predicate_combinators() {
+    let nfa = NFA::kleene_star(&nfa());
+    assert!(nfa.is_match(Vec::<String>::new()));
+    assert!(nfa.is_match(words("A 1 2 B 3")));
+    assert!(!nfa.is_match(words("A 1 B")));
+}
+
+#[test]
+fn test_predicate_identity() {
+    let p = Predicate::new(|n: &u8| *n > 0);
+    let q = Predicate::new(|n: &u8| *n > 0);
+    assert_eq!(p, p.clone());
+    assert_ne!(p, q);
+    assert!(p.matches(&1));
+    assert!(!p.matches(&0));
+}
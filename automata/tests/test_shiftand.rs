@@ -0,0 +1,73 @@
+use automata::shiftand::{ShiftAnd, MAX_POSITIONS};
+use automata::NFA;
+
+/// An NFA that matches a string of bytes.
+fn literal(s: &str) -> NFA<u8> {
+    let mut nfa = NFA::new();
+    let mut state = nfa.start_state;
+    for b in s.bytes() {
+        let next = nfa.add_state(false);
+        nfa.add_labeled_transition(state, next, b);
+        state = next;
+    }
+    nfa.accepting_states.insert(state);
+    nfa
+}
+
+#[test]
+fn test_shift_and() {
+    // (ab|b)*c|(ba)?
+    let nfa = NFA::union(
+        &NFA::concatenation(
+            &NFA::kleene_star(&NFA::union(&literal("ab"), &literal("b"))),
+            &literal("c"),
+        ),
+        &NFA::union(&literal("ba"), &NFA::new_epsilon()),
+    );
+    let shift_and = ShiftAnd::new(&nfa).unwrap();
+    assert_eq!(6, shift_and.positions());
+
+    let inputs = ["", "c", "abc", "bbabc", "ba", "bab", "abac", "xabc", "aab"];
+    for input in &inputs {
+        assert_eq!(
+            nfa.is_match(input.bytes()),
+            shift_and.is_match(input.bytes()),
+            "wrong result on {:?}",
+            input
+        );
+        assert_eq!(
+            nfa.find(input.bytes()).map(|m| m.end()),
+            shift_and.find_anchored(input.bytes()),
+            "wrong match in {:?}",
+            input
+        );
+        assert_eq!(Some(0), shift_and.find_shortest_anchored(input.bytes()));
+        assert_eq!(Some(0), shift_and.find_earliest_end(input.bytes()));
+    }
+
+    let shift_and = ShiftAnd::new(&NFA::kleene_star(&literal("ab"))).unwrap();
+    assert_eq!(Some(4), shift_and.find_anchored("ababa".bytes()));
+    let shift_and = ShiftAnd::new(&NFA::concatenation(
+        &NFA::kleene_star(&literal("ab")),
+        &literal("c"),
+    ))
+    .unwrap();
+    assert_eq!(Some(3), shift_and.find_shortest_anchored("abcabc".bytes()));
+    assert_eq!(Some(4), shift_and.find_earliest_end("xabcabc".bytes()));
+    assert_eq!(None, shift_and.find_earliest_end("ababab".bytes()));
+}
+
+#[test]
+fn test_shift_and_positions() {
+    let long = "a".repeat(MAX_POSITIONS);
+    let shift_and = ShiftAnd::new(&literal(&long)).unwrap();
+    assert!(shift_and.is_match(long.bytes()));
+    assert!(!shift_and.is_match(long[1..].bytes()));
+    assert_eq!(
+        Some(MAX_POSITIONS),
+        shift_and.find_earliest_end(long.bytes())
+    );
+
+    let longer = "a".repeat(MAX_POSITIONS + 1);
+    assert!(ShiftAnd::new(&literal(&longer)).is_none());
+}
@@ -0,0 +1,24 @@
+use automata::StateId;
+
+use std::convert::TryFrom;
+
+#[test]
+fn test_stateid() {
+    let id = StateId::new(7).unwrap();
+    assert_eq!(7, id.as_usize());
+    assert_eq!(7, u32::from(id));
+    assert_eq!(Ok(id), StateId::try_from(7usize));
+    assert_eq!(StateId::MAX, StateId::new(u32::MAX as usize).unwrap());
+    assert_eq!("7", id.to_string());
+}
+
+#[test]
+#[cfg(target_pointer_width = "64")]
+fn test_stateid_overflow() {
+    let err = StateId::new(u32::MAX as usize + 1).unwrap_err();
+    assert_eq!(u32::MAX as usize + 1, err.id());
+    assert_eq!(
+        "state label 4294967296 does not fit in 32 bits",
+        err.to_string()
+    );
+}
@@ -0,0 +1,36 @@
+use automata::StateSet;
+
+#[test]
+fn test_stateset() {
+    let mut set = StateSet::new();
+    assert!(set.is_empty());
+
+    assert!(set.insert(70));
+    assert!(set.insert(3));
+    assert!(set.insert(64));
+    assert!(!set.insert(3));
+    assert_eq!(3, set.len());
+
+    assert!(set.contains(&3));
+    assert!(set.contains(&64));
+    assert!(!set.contains(&4));
+    assert!(!set.contains(&1000));
+    assert_eq!(vec![3, 64, 70], set.iter().collect::<Vec<_>>());
+
+    assert!(set.remove(64));
+    assert!(!set.remove(64));
+    assert!(!set.remove(1000));
+    assert_eq!(vec![3, 70], set.into_iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_stateset_eq() {
+    let mut a: StateSet = vec![1, 200].into();
+    let b: StateSet = vec![1].into();
+    assert_ne!(a, b);
+
+    // Removing the highest state leaves trailing empty words behind.
+    a.remove(200);
+    assert_eq!(a, b);
+    assert_eq!(b, a);
+}
@@ -0,0 +1,55 @@
+use automata::step::{DfaState, StepResult};
+use automata::{DenseDFA, StateId, DFA};
+
+/// Accepts "ab*c".
+fn dfa() -> DFA<char> {
+    let mut d: DFA<char> = DFA::new();
+    let s1 = d.add_state(false);
+    let s2 = d.add_state(true);
+    d.add_transition(d.start_state, s1, 'a');
+    d.add_transition(s1, s1, 'b');
+    d.add_transition(s1, s2, 'c');
+    d
+}
+
+#[test]
+fn test_step() {
+    let d = dfa();
+    let mut state = d.start();
+    assert_eq!(StepResult::Running, state.result());
+    assert_eq!(Some(StateId::new(d.start_state).unwrap()), state.state());
+
+    assert_eq!(StepResult::Running, d.step(&mut state, &'a'));
+    assert_eq!(StepResult::Running, d.step(&mut state, &'b'));
+    assert_eq!(StepResult::Accepting, d.step(&mut state, &'c'));
+    assert!(state.is_accepting());
+
+    assert_eq!(StepResult::Dead, d.step(&mut state, &'c'));
+    assert!(state.is_dead());
+    assert_eq!(None, state.state());
+    assert_eq!(StepResult::Dead, d.step(&mut state, &'a'));
+}
+
+#[test]
+fn test_step_dense_across_inputs() {
+    let d = dfa();
+    let dense: DenseDFA<char> = (&d).into();
+
+    // The state is kept between chunks of input, and agrees with the sparse DFA.
+    let mut sparse_state = d.start();
+    let mut dense_state = dense.start();
+    for chunk in &["a", "bb", "", "bc"] {
+        for c in chunk.chars() {
+            assert_eq!(
+                d.step(&mut sparse_state, &c),
+                dense.step(&mut dense_state, &c)
+            );
+        }
+    }
+    assert_eq!(sparse_state, dense_state);
+    assert_eq!(StepResult::Accepting, dense_state.result());
+
+    let saved: DfaState = dense_state;
+    assert_eq!(StepResult::Dead, dense.step(&mut dense_state, &'x'));
+    assert!(saved.is_accepting());
+}
@@ -0,0 +1,339 @@
+//! The `regexp2!` macro, which compiles a regular expression while building the crate that uses
+//! it. The pattern is parsed and checked at build time, so a malformed pattern is a compile error,
+//! and the compiled DFA tables are emitted as static data, so no parsing or determinization
+//! happens at runtime.
+//!
+//! The expansion refers to the `regexp2` crate, which must also be a dependency of the crate
+//! that uses the macro.
+//!
+//! ```
+//! use regexp2_macros::regexp2;
+//!
+//! let re = regexp2!(r"(a|b)*abb");
+//! assert!(re.is_match("aababb"));
+//! assert_eq!(Some(1..4), re.find("xabb").map(|m| m.range()));
+//! ```
+//!
+//! ```compile_fail
+//! use regexp2_macros::regexp2;
+//!
+//! let re = regexp2!("(a|b");
+//! ```
+
+#![deny(rust_2018_idioms)]
+#![deny(future_incompatible)]
+
+use std::fmt::Write;
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+use regexp2::automata::nfa::Transition;
+use regexp2::automata::pikevm::PikeVM;
+use regexp2::automata::{DenseDFA, StateId};
+use regexp2::class::CharClass;
+use regexp2::RegExp;
+
+/// Compile a regular expression given as a string literal into a
+/// `regexp2::RegExp<DenseDFA<CharClass>>`, the same as `RegExp::new` would at runtime.
+#[proc_macro]
+pub fn regexp2(input: TokenStream) -> TokenStream {
+    let (pattern, span) = match pattern(input) {
+        Ok(pattern) => pattern,
+        Err((msg, span)) => return compile_error(&msg, span),
+    };
+
+    match RegExp::new(&pattern) {
+        Ok(re) => expand(re)
+            .parse()
+            .expect("emitted code should be valid tokens"),
+        Err(err) => compile_error(&format!("invalid regular expression: {}", err), span),
+    }
+}
+
+/// Read the pattern from the single string literal of the macro input.
+#[inline]
+fn pattern(input: TokenStream) -> Result<(String, Span), (String, Span)> {
+    let mut tokens = input.into_iter();
+    let token = match (tokens.next(), tokens.next()) {
+        (Some(token), None) => token,
+        (Some(_), Some(extra)) => {
+            return Err(("expected a single string literal".into(), extra.span()))
+        }
+        (None, _) => return Err(("expected a string literal".into(), Span::call_site())),
+    };
+
+    match token {
+        // Literals passed through `macro_rules!` arrive wrapped in an invisible group.
+        TokenTree::Group(group) if group.delimiter() == Delimiter::None => pattern(group.stream()),
+        TokenTree::Literal(lit) => match unescape(&lit.to_string()) {
+            Some(pattern) => Ok((pattern, lit.span())),
+            None => Err(("expected a string literal".into(), lit.span())),
+        },
+        token => Err(("expected a string literal".into(), token.span())),
+    }
+}
+
+/// The value of a string literal or raw string literal, given as it appears in source code.
+#[inline]
+fn unescape(lit: &str) -> Option<String> {
+    if let Some(raw) = lit.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let body = raw[hashes..].strip_prefix('"')?;
+        let body = body.strip_suffix(&raw[..hashes])?.strip_suffix('"')?;
+        return Some(body.to_owned());
+    }
+
+    let body = lit.strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::with_capacity(body.len());
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+
+        let escaped = match chars.next()? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            '\\' => '\\',
+            '"' => '"',
+            '\'' => '\'',
+            'x' => {
+                let hex: String = chars.by_ref().take(2).collect();
+                char::from(u8::from_str_radix(&hex, 16).ok()?)
+            }
+            'u' => {
+                let hex: String = chars
+                    .by_ref()
+                    .skip(1)
+                    .take_while(|&c| c != '}')
+                    .filter(|&c| c != '_')
+                    .collect();
+                std::char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+            }
+            // A line continuation skips the newline and the leading whitespace of the next line.
+            '\n' => {
+                while matches!(chars.peek(), Some(c) if c.is_whitespace()) {
+                    chars.next();
+                }
+                continue;
+            }
+            _ => return None,
+        };
+        value.push(escaped);
+    }
+
+    Some(value)
+}
+
+/// Emit `compile_error!` with the message, pointing at the span.
+#[inline]
+fn compile_error(msg: &str, span: Span) -> TokenStream {
+    let mut lit = Literal::string(msg);
+    lit.set_span(span);
+    let mut body = Group::new(Delimiter::Brace, TokenTree::from(lit).into());
+    body.set_span(span);
+
+    let mut bang = Punct::new('!', Spacing::Alone);
+    bang.set_span(span);
+
+    vec![
+        TokenTree::from(Ident::new("compile_error", span)),
+        bang.into(),
+        body.into(),
+    ]
+    .into_iter()
+    .collect()
+}
+
+/// Emit an expression that reconstructs the compiled regular expression.
+#[inline]
+fn expand(re: RegExp<DenseDFA<CharClass>>) -> String {
+    let parts = re.into_parts();
+    let mut code = String::new();
+    code.push_str(
+        "{
+        use ::regexp2::automata::dense::{ClassMap, DenseDFA};
+        use ::regexp2::automata::nfa::Transition;
+        use ::regexp2::automata::onepass::OnePass;
+        use ::regexp2::automata::pikevm::PikeVM;
+        use ::regexp2::automata::{StateId, NFA};
+        use ::regexp2::class::{CharClass, CharRange};
+
+        const DEAD: u32 = DenseDFA::<CharClass>::DEAD.as_u32();
+
+        fn dense(
+            start_state: u32,
+            unanchored_start_state: ::core::option::Option<u32>,
+            accepting: &'static [bool],
+            alphabet: ::std::vec::Vec<CharClass>,
+            table: &'static [u32],
+            fallback: &'static [u32],
+        ) -> DenseDFA<CharClass> {
+            DenseDFA {
+                start_state: StateId::from_u32(start_state),
+                unanchored_start_state: unanchored_start_state.map(StateId::from_u32),
+                total_states: accepting.len(),
+                accepting: accepting.to_vec(),
+                classes: ClassMap::new(&alphabet),
+                alphabet,
+                table: table.iter().copied().map(StateId::from_u32).collect(),
+                fallback: fallback.iter().copied().map(StateId::from_u32).collect(),
+            }
+        }
+
+        fn pikevm(
+            total_states: usize,
+            start_state: usize,
+            accepting: &'static [usize],
+            transitions: ::std::vec::Vec<(usize, usize, Transition<CharClass>)>,
+            tags: &'static [(usize, usize)],
+        ) -> PikeVM<CharClass> {
+            let mut nfa = NFA::new();
+            while nfa.total_states < total_states {
+                nfa.add_state(false);
+            }
+            nfa.start_state = start_state;
+            nfa.accepting_states.extend(accepting.iter().copied());
+            for (start, end, label) in transitions {
+                nfa.add_transition(start, end, label);
+            }
+            for &(state, slot) in tags {
+                nfa.add_tag(state, slot);
+            }
+            PikeVM::new(nfa)
+        }
+
+",
+    );
+    // The one-pass DFA is compiled from the NFA of the Pike VM when the expansion is evaluated.
+    match &parts.pikevm {
+        Some(vm) => write!(
+            code,
+            "let pikevm = ::core::option::Option::Some({});",
+            pikevm(vm)
+        ),
+        None => write!(code, "let pikevm = ::core::option::Option::None;"),
+    }
+    .unwrap();
+    code.push_str("::regexp2::RegExp::from_parts(::regexp2::RegExpParts {");
+    write!(code, "expr: {:?}.into(),", parts.expr).unwrap();
+    write!(code, "engine: {},", dense(&parts.engine)).unwrap();
+    write!(
+        code,
+        "match_kind: ::regexp2::MatchKind::{:?},",
+        parts.match_kind
+    )
+    .unwrap();
+    write!(code, "searcher: {},", option(parts.searcher.as_ref())).unwrap();
+    write!(code, "prefix: {:?}.into(),", parts.prefix).unwrap();
+    code.push_str("prefixes: ::std::vec![");
+    for prefix in &parts.prefixes {
+        write!(code, "{:?}.into(),", prefix).unwrap();
+    }
+    code.push_str("],");
+    code.push_str("literals: ::std::vec![");
+    for literal in &parts.literals {
+        write!(code, "{:?}.into(),", literal).unwrap();
+    }
+    code.push_str("],");
+    write!(code, "reverse: {},", option(parts.reverse.as_ref())).unwrap();
+    code.push_str(
+        "onepass: pikevm.as_ref().and_then(|vm: &PikeVM<CharClass>| OnePass::new(vm.nfa())),
+        pikevm,
+        })}",
+    );
+    code
+}
+
+#[inline]
+fn option(dfa: Option<&DenseDFA<CharClass>>) -> String {
+    match dfa {
+        Some(dfa) => format!("::core::option::Option::Some({})", dense(dfa)),
+        None => "::core::option::Option::None".into(),
+    }
+}
+
+/// Emit a call to the `pikevm` function of the expansion that reconstructs the Pike VM.
+#[inline]
+fn pikevm(vm: &PikeVM<CharClass>) -> String {
+    let nfa = vm.nfa();
+    let mut code = String::from("pikevm(");
+    write!(code, "{},", nfa.total_states).unwrap();
+    write!(code, "{},", nfa.start_state).unwrap();
+
+    let accepting: Vec<_> = nfa.accepting_states.iter().collect();
+    write!(code, "&{:?},", accepting).unwrap();
+
+    // The destinations of each transition are emitted in order, which is their priority.
+    code.push_str("::std::vec![");
+    for (start, label, ends) in &nfa.transition {
+        let label = match label {
+            Transition::Some(class) => format!("Transition::Some({})", char_class(class)),
+            Transition::Epsilon => "Transition::Epsilon".into(),
+        };
+        for end in ends {
+            write!(code, "({}, {}, {}),", start, end, label).unwrap();
+        }
+    }
+    code.push_str("],");
+
+    let mut tags: Vec<_> = nfa
+        .tags
+        .iter()
+        .flat_map(|(&state, slots)| slots.iter().map(move |&slot| (state, slot)))
+        .collect();
+    tags.sort_unstable();
+    write!(code, "&{:?},", tags).unwrap();
+
+    code.push(')');
+    code
+}
+
+/// Emit an expression that constructs the class.
+#[inline]
+fn char_class(class: &CharClass) -> String {
+    let mut code = String::from("CharClass::from(::std::vec![");
+    for range in class {
+        write!(code, "CharRange::new({:?}, {:?}),", range.start, range.end).unwrap();
+    }
+    code.push_str("])");
+    code
+}
+
+/// Emit a call to the `dense` function of the expansion that reconstructs the DFA.
+#[inline]
+fn dense(dfa: &DenseDFA<CharClass>) -> String {
+    let mut code = String::from("dense(");
+    write!(code, "{},", dfa.start_state).unwrap();
+    write!(
+        code,
+        "{:?},",
+        dfa.unanchored_start_state.map(StateId::as_u32)
+    )
+    .unwrap();
+    write!(code, "&{:?},", dfa.accepting).unwrap();
+
+    code.push_str("::std::vec![");
+    for class in &dfa.alphabet {
+        write!(code, "{},", char_class(class)).unwrap();
+    }
+    code.push_str("],");
+
+    for states in [&dfa.table, &dfa.fallback] {
+        code.push_str("&[");
+        for &state in states.iter() {
+            if state == DenseDFA::<CharClass>::DEAD {
+                code.push_str("DEAD,");
+            } else {
+                write!(code, "{},", state).unwrap();
+            }
+        }
+        code.push_str("],");
+    }
+
+    code.push(')');
+    code
+}
@@ -0,0 +1,72 @@
+use regexp2::RegExp;
+use regexp2_macros::regexp2;
+
+#[test]
+fn test_macro_matches_runtime() {
+    let cases = vec![
+        (regexp2!("(a|b)*abb"), "(a|b)*abb"),
+        (regexp2!(r"\d+\.\d*"), r"\d+\.\d*"),
+        (regexp2!("[^B-Fa-z]*"), "[^B-Fa-z]*"),
+        (regexp2!("(?i)héllo.wörld"), "(?i)héllo.wörld"),
+        (regexp2!("x\u{3A9}+\ty?"), "x\u{3A9}+\ty?"),
+        (regexp2!("(x)((Ω)|(a)b)+"), "(x)((Ω)|(a)b)+"),
+        (regexp2!("abb|xΩ|3"), "abb|xΩ|3"),
+        (regexp2!("(abb|xΩ)[0-9]*"), "(abb|xΩ)[0-9]*"),
+    ];
+    let inputs = [
+        "",
+        "abb",
+        "aababb",
+        "xabbx",
+        "3.14",
+        "pi is 3.",
+        "AGAQR",
+        "abc",
+        "HÉLLO WÖRLD",
+        "say héllo\nwörld",
+        "xΩΩ\t",
+        "xΩ\ty",
+        "x\t",
+        "xΩabΩ",
+    ];
+
+    for (re, expr) in &cases {
+        let expected = RegExp::new(expr).unwrap();
+        assert_eq!(*expr, re.as_str());
+        for input in &inputs {
+            assert_eq!(
+                expected.is_match(input),
+                re.is_match(input),
+                "{} on {:?}",
+                expr,
+                input
+            );
+            assert_eq!(
+                expected.find(input),
+                re.find(input),
+                "{} on {:?}",
+                expr,
+                input
+            );
+            assert_eq!(
+                expected.captures(input),
+                re.captures(input),
+                "{} on {:?}",
+                expr,
+                input
+            );
+        }
+    }
+}
+
+macro_rules! wrapped {
+    ($expr:expr) => {
+        regexp2!($expr)
+    };
+}
+
+#[test]
+fn test_macro_through_macro_rules() {
+    let re = wrapped!(r#"a"+"#);
+    assert!(re.is_match(r#"a"""#));
+}
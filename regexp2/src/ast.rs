@@ -1,21 +1,993 @@
+//! Abstract syntax trees of regular expressions, produced by
+//! [`ASTParser`](crate::parser::ast::ASTParser).
+//!
+//! Expressions built programmatically may use any type of atom in place of character classes,
+//! such as the tokens produced by a lexer, and compile to automata over that alphabet.
+//!
+//! ```
+//! use regexp2::ast::Expr;
+//! use regexp2::automata::NFA;
+//!
+//! #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+//! enum Token {
+//!     Ident,
+//!     Comma,
+//!     Semi,
+//! }
+//!
+//! // A comma-separated list of identifiers terminated by a semicolon.
+//! let list = Expr::from(Token::Ident)
+//!     .then(Expr::sequence(vec![Token::Comma, Token::Ident]).star())
+//!     .then(Token::Semi);
+//! let nfa: NFA<Token> = list.to_nfa().unwrap();
+//! assert!(nfa.is_match(vec![Token::Ident, Token::Comma, Token::Ident, Token::Semi]));
+//! assert!(!nfa.is_match(vec![Token::Ident, Token::Comma, Token::Semi]));
+//! ```
+
 use crate::class::CharClass;
+use crate::parser::Anchor;
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::ops::Range;
 
-#[derive(Clone, Debug, PartialEq)]
-pub enum Expr {
+use automata::intern::Interner;
+use automata::nfa::Transition;
+use automata::{DFA, NFA};
+
+/// A regular expression abstract syntax tree whose atoms are of type `A`. The parser produces
+/// atoms that are character classes. With the `serde` feature, expressions can be serialized and
+/// deserialized.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub enum Expr<A = CharClass> {
+    /// A postfix operator applied to an expression.
     Unary(UnaryOp, Box<Self>),
+    /// An infix operator applied to two expressions.
     Binary(BinaryOp, Box<Self>, Box<Self>),
-    Atom(CharClass),
+    /// A single input symbol matched by the atom, such as a character from a class.
+    Atom(A),
+    /// A parenthesized group and its capture index.
+    Group(Box<Self>, usize),
+    /// The empty expression, which matches only the empty string.
+    Empty,
+    Anchor(Anchor),
+    /// An expression and the byte range of the regular expression that it was parsed from.
+    Spanned(Box<Self>, Range<usize>),
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub enum UnaryOp {
     Star,
     Plus,
     Optional,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub enum BinaryOp {
     Concat,
     Alternate,
 }
+
+impl<A> From<A> for Expr<A> {
+    #[inline]
+    fn from(atom: A) -> Self {
+        Expr::Atom(atom)
+    }
+}
+
+impl From<char> for Expr {
+    #[inline]
+    fn from(c: char) -> Self {
+        Expr::Atom(c.into())
+    }
+}
+
+impl From<&str> for Expr {
+    #[inline]
+    fn from(s: &str) -> Self {
+        Expr::literal(s)
+    }
+}
+
+/// Error returned when an expression can't be compiled to an automaton.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum CompileError {
+    /// NFAs can't represent anchors.
+    #[error("anchors are not supported")]
+    UnsupportedAnchor(Anchor),
+}
+
+impl<A> Expr<A> {
+    /// An expression that matches the atoms in order. The expression is empty if there are no
+    /// atoms.
+    #[inline]
+    pub fn sequence<I>(atoms: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Self>,
+    {
+        atoms
+            .into_iter()
+            .map(Into::into)
+            .reduce(Self::then)
+            .unwrap_or(Expr::Empty)
+    }
+
+    /// Match the expression zero or more times.
+    #[inline]
+    pub fn star(self) -> Self {
+        Expr::Unary(UnaryOp::Star, Box::new(self))
+    }
+
+    /// Match the expression one or more times.
+    #[inline]
+    pub fn plus(self) -> Self {
+        Expr::Unary(UnaryOp::Plus, Box::new(self))
+    }
+
+    /// Match the expression zero or one times.
+    #[inline]
+    pub fn optional(self) -> Self {
+        Expr::Unary(UnaryOp::Optional, Box::new(self))
+    }
+
+    /// Match the expression followed by another.
+    #[inline]
+    pub fn then<E>(self, next: E) -> Self
+    where
+        E: Into<Self>,
+    {
+        Expr::Binary(BinaryOp::Concat, Box::new(self), Box::new(next.into()))
+    }
+
+    /// Match either the expression or another, preferring the expression.
+    #[inline]
+    pub fn or<E>(self, other: E) -> Self
+    where
+        E: Into<Self>,
+    {
+        Expr::Binary(BinaryOp::Alternate, Box::new(self), Box::new(other.into()))
+    }
+
+    /// Wrap the expression in a group with the given capture index.
+    #[inline]
+    pub fn group(self, index: usize) -> Self {
+        Expr::Group(Box::new(self), index)
+    }
+
+    /// The byte range of the regular expression that the expression was parsed from, if it was
+    /// parsed with spans.
+    #[inline]
+    pub fn span(&self) -> Option<&Range<usize>> {
+        match self {
+            Expr::Spanned(_, span) => Some(span),
+            _ => None,
+        }
+    }
+
+    /// Compile the expression to an NFA that accepts the same language, with transitions labeled
+    /// by the atoms. The NFA is constructed the same way as by
+    /// [`NFAParser`](crate::parser::nfa::NFAParser), with each group tagged with the capture
+    /// slots of its index.
+    #[inline]
+    pub fn to_nfa<T>(&self) -> Result<NFA<T>, CompileError>
+    where
+        A: Clone + Eq + Hash,
+        T: Clone + Eq + Hash,
+        Transition<T>: From<A>,
+    {
+        self.compile(&mut Interner::new())
+    }
+
+    /// Compile the expression bottom-up with an explicit stack of pending nodes, so deeply nested
+    /// expressions don't overflow the call stack. Operands are combined in place, so a long
+    /// concatenation or alternation takes time linear in its size.
+    #[inline]
+    fn compile<T>(&self, atoms: &mut Interner<A>) -> Result<NFA<T>, CompileError>
+    where
+        A: Clone + Eq + Hash,
+        T: Clone + Eq + Hash,
+        Transition<T>: From<A>,
+    {
+        enum Frame<'a, A> {
+            Visit(&'a Expr<A>),
+            Unary(&'a UnaryOp),
+            /// A chain of the same binary operator, and the number of its operands.
+            Binary(&'a BinaryOp, usize),
+            Group(usize),
+        }
+
+        let mut frames = vec![Frame::Visit(self)];
+        let mut nfas: Vec<NFA<T>> = Vec::new();
+        while let Some(frame) = frames.pop() {
+            let nfa = match frame {
+                Frame::Visit(Expr::Unary(op, operand)) => {
+                    frames.push(Frame::Unary(op));
+                    frames.push(Frame::Visit(operand));
+                    continue;
+                }
+                Frame::Visit(expr @ Expr::Binary(op, _, _)) => {
+                    // The operands of a chain are combined from left to right however it's
+                    // nested, so each is copied at most once.
+                    let mut operands = Vec::new();
+                    let mut pending = vec![expr];
+                    while let Some(expr) = pending.pop() {
+                        match expr {
+                            Expr::Binary(inner, lhs, rhs) if inner == op => {
+                                pending.push(rhs);
+                                pending.push(lhs);
+                            }
+                            Expr::Spanned(inner, _) => pending.push(inner),
+                            expr => operands.push(Frame::Visit(expr)),
+                        }
+                    }
+                    frames.push(Frame::Binary(op, operands.len()));
+                    frames.extend(operands.into_iter().rev());
+                    continue;
+                }
+                Frame::Visit(Expr::Group(inner, index)) => {
+                    frames.push(Frame::Group(*index));
+                    frames.push(Frame::Visit(inner));
+                    continue;
+                }
+                Frame::Visit(Expr::Spanned(inner, _)) => {
+                    frames.push(Frame::Visit(inner));
+                    continue;
+                }
+                Frame::Visit(Expr::Atom(atom)) => {
+                    let mut nfa = NFA::new();
+                    let f = nfa.add_state(true);
+                    nfa.add_transition(nfa.start_state, f, atoms.intern(atom.clone()).into());
+                    nfa
+                }
+                Frame::Visit(Expr::Empty) => NFA::new_epsilon(),
+                Frame::Visit(Expr::Anchor(anchor)) => {
+                    return Err(CompileError::UnsupportedAnchor(*anchor))
+                }
+                Frame::Unary(op) => {
+                    let mut operand = nfas.pop().unwrap();
+                    match op {
+                        UnaryOp::Star => NFA::kleene_star_owned(operand),
+                        UnaryOp::Plus => {
+                            let star = NFA::kleene_star(&operand);
+                            NFA::concatenation_owned(operand, star)
+                        }
+                        // Prefer matching the operand over matching nothing.
+                        UnaryOp::Optional => {
+                            NFA::union_into(&mut operand, &NFA::new_epsilon());
+                            operand
+                        }
+                    }
+                }
+                Frame::Binary(op, len) => {
+                    let mut operands = nfas.drain(nfas.len() - len..);
+                    let first = operands.next().unwrap();
+                    operands.fold(first, |mut lhs, rhs| match op {
+                        BinaryOp::Concat => NFA::concatenation_owned(lhs, rhs),
+                        BinaryOp::Alternate => {
+                            NFA::union_into(&mut lhs, &rhs);
+                            lhs
+                        }
+                    })
+                }
+                Frame::Group(index) => NFA::tagged(nfas.pop().unwrap(), 2 * index, 2 * index + 1),
+            };
+            nfas.push(nfa);
+        }
+        Ok(nfas.pop().unwrap())
+    }
+}
+
+impl Expr {
+    /// An expression that matches exactly the given string.
+    ///
+    /// ```
+    /// use regexp2::ast::Expr;
+    /// use regexp2::class::CharClass;
+    /// use regexp2::RegExp;
+    ///
+    /// let expr = Expr::literal("ab")
+    ///     .star()
+    ///     .then(CharClass::decimal_number())
+    ///     .or(Expr::literal("a.c"));
+    /// let re = RegExp::new(&expr.to_string()).unwrap();
+    /// assert!(re.is_match("abab1"));
+    /// assert!(re.is_match("a.c"));
+    /// assert!(!re.is_match("abc"));
+    /// ```
+    #[inline]
+    pub fn literal(s: &str) -> Expr {
+        Self::sequence(s.chars())
+    }
+
+    /// An expression that matches any character in the class.
+    #[inline]
+    pub fn class<C>(class: C) -> Expr
+    where
+        C: Into<CharClass>,
+    {
+        Expr::Atom(class.into())
+    }
+
+    /// An expression that matches any character.
+    #[inline]
+    pub fn any() -> Expr {
+        Expr::Atom(CharClass::all())
+    }
+
+    /// Rewrite the expression into a smaller one that matches the same strings. Nested
+    /// repetitions are collapsed (`(a*)*` to `a*`), nested concatenations and alternations are
+    /// flattened, duplicate alternatives are removed, and adjacent alternatives of single classes
+    /// are merged into one class. Groups and spans are removed, so capture indices are not
+    /// preserved.
+    #[inline]
+    pub fn simplify(self) -> Expr {
+        Simplify::simplify(self)
+    }
+
+    /// Convert a DFA to an expression that matches the same strings, by state elimination: the
+    /// states are removed one at a time, and each path through a removed state is replaced by a
+    /// transition labeled with the expression the path matches. The result is simplified, but
+    /// may still be exponentially larger than the DFA, so minimizing the DFA first helps. A DFA
+    /// that accepts nothing gives an empty class.
+    ///
+    /// ```
+    /// use regexp2::ast::Expr;
+    /// use regexp2::automata::DFA;
+    /// use regexp2::class::CharClass;
+    /// use regexp2::parser::nfa::NFAParser;
+    ///
+    /// let dfa: DFA<CharClass> = NFAParser::new().parse("a(b|c)*|a").unwrap().into();
+    /// let expr = Expr::from_dfa(&dfa.minimize());
+    /// assert_eq!("a[b-c]*", expr.to_string());
+    /// ```
+    #[inline]
+    pub fn from_dfa(dfa: &DFA<CharClass>) -> Expr {
+        let mut transitions = Vec::new();
+        for state in 0..dfa.total_states {
+            for (t, &next) in dfa.transitions_on(&state) {
+                transitions.push((state, Expr::Atom(t.0.clone()), next));
+            }
+        }
+        eliminate(
+            dfa.total_states,
+            dfa.start_state,
+            dfa.accepting_states.iter(),
+            transitions,
+        )
+    }
+
+    /// Convert an NFA to an expression that matches the same strings, by state elimination like
+    /// [`from_dfa`](Self::from_dfa). Epsilon transitions match the empty expression.
+    #[inline]
+    pub fn from_nfa(nfa: &NFA<CharClass>) -> Expr {
+        let transitions = nfa
+            .transitions()
+            .map(|(start, label, end)| {
+                let label = label
+                    .symbol()
+                    .map_or(Expr::Empty, |class| Expr::Atom(class.clone()));
+                (start, label, end)
+            })
+            .collect();
+        eliminate(
+            nfa.total_states,
+            nfa.start_state,
+            nfa.final_states(),
+            transitions,
+        )
+    }
+
+    /// Remove the spans from the expression and all of its children.
+    #[inline]
+    pub fn strip_spans(self) -> Expr {
+        struct Strip;
+        impl Fold for Strip {
+            #[inline]
+            fn fold_spanned(&mut self, inner: Expr, _span: Range<usize>) -> Expr {
+                self.fold_expr(inner)
+            }
+        }
+
+        Strip.fold_expr(self)
+    }
+
+    /// The binding power of the outermost operator of the expression. Operands that bind less
+    /// tightly than their operator must be parenthesized.
+    #[inline]
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Spanned(inner, _) => inner.precedence(),
+            Expr::Binary(BinaryOp::Alternate, _, _) => 0,
+            Expr::Binary(BinaryOp::Concat, _, _) => 1,
+            Expr::Unary(_, _) => 2,
+            _ => 3,
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    /// Format the expression in the syntax of a regular expression, which parses to an equivalent
+    /// expression with the default options. Parentheses are added around operands that would
+    /// otherwise bind to the wrong operator, so the parsed expression may contain extra groups.
+    ///
+    /// The parser rejects empty expressions by default, so an alternative with the empty
+    /// expression, as in `x|()`, is written as `x?`, and the empty expression elsewhere is written
+    /// as a repetition of the empty class.
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        /// The pieces left to write, each an expression with the least precedence it may have
+        /// without parentheses, or text that follows one.
+        enum Piece<'a> {
+            Expr(&'a Expr, u8),
+            Text(&'static str),
+        }
+
+        let mut pieces = vec![Piece::Expr(self, 0)];
+        while let Some(piece) = pieces.pop() {
+            let (expr, min) = match piece {
+                Piece::Expr(expr, min) => (expr, min),
+                Piece::Text(text) => {
+                    f.write_str(text)?;
+                    continue;
+                }
+            };
+            if expr.precedence() < min {
+                f.write_str("(")?;
+                pieces.push(Piece::Text(")"));
+                pieces.push(Piece::Expr(expr, 0));
+                continue;
+            }
+
+            match expr {
+                Expr::Unary(op, operand) => {
+                    pieces.push(Piece::Text(match op {
+                        UnaryOp::Star => "*",
+                        UnaryOp::Plus => "+",
+                        UnaryOp::Optional => "?",
+                    }));
+                    pieces.push(Piece::Expr(operand, 2));
+                }
+                Expr::Binary(BinaryOp::Alternate, lhs, rhs) if is_empty(rhs) => {
+                    pieces.push(Piece::Text("?"));
+                    pieces.push(Piece::Expr(lhs, 2));
+                }
+                Expr::Binary(op, lhs, rhs) => {
+                    let min = expr.precedence();
+                    pieces.push(Piece::Expr(rhs, min));
+                    if *op == BinaryOp::Alternate {
+                        pieces.push(Piece::Text("|"));
+                    }
+                    pieces.push(Piece::Expr(lhs, min));
+                }
+                // The parser rejects `[]`, so write the empty class as the complement of every
+                // character.
+                Expr::Atom(class) if class.is_empty() => f.write_str("[^\0-\u{10ffff}]")?,
+                Expr::Atom(class) => write!(f, "{}", class)?,
+                Expr::Group(inner, _) => {
+                    f.write_str("(")?;
+                    pieces.push(Piece::Text(")"));
+                    pieces.push(Piece::Expr(inner, 0));
+                }
+                Expr::Empty => f.write_str("[^\0-\u{10ffff}]*")?,
+                Expr::Anchor(Anchor::Start) => f.write_str("^")?,
+                Expr::Anchor(Anchor::End) => f.write_str("$")?,
+                Expr::Spanned(inner, _) => pieces.push(Piece::Expr(inner, 0)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Convert the graph of an automaton, with transitions labeled by expressions, to an expression
+/// that matches the labels of the paths from the start state to an accepting state.
+#[inline]
+fn eliminate<I>(
+    total_states: usize,
+    start: usize,
+    accepting: I,
+    transitions: Vec<(usize, Expr, usize)>,
+) -> Expr
+where
+    I: IntoIterator<Item = usize>,
+{
+    // Two states are added, an initial state with a transition to the start state and a final
+    // state with transitions from the accepting states, so that once the others are eliminated,
+    // the transition between them matches every path.
+    let (initial, last) = (total_states, total_states + 1);
+    let mut graph = Graph {
+        out: vec![BTreeMap::new(); total_states + 2],
+        into: vec![BTreeSet::new(); total_states + 2],
+    };
+    graph.add(initial, Expr::Empty, start);
+    for state in accepting {
+        graph.add(state, Expr::Empty, last);
+    }
+    for (from, label, to) in transitions {
+        graph.add(from, label, to);
+    }
+
+    // Eliminating the state with the fewest paths through it first keeps the labels small.
+    let mut remaining: BTreeSet<_> = (0..total_states).collect();
+    while let Some(k) = remaining
+        .iter()
+        .copied()
+        .min_by_key(|&k| (graph.into[k].len() * graph.out[k].len(), k))
+    {
+        remaining.remove(&k);
+        let looped = graph.out[k].remove(&k).map(Expr::star);
+        graph.into[k].remove(&k);
+
+        let outgoing = mem::take(&mut graph.out[k]);
+        for &i in &mem::take(&mut graph.into[k]) {
+            let first = graph.out[i].remove(&k).unwrap();
+            for (&j, rest) in &outgoing {
+                let label = match &looped {
+                    Some(looped) => first.clone().then(looped.clone()),
+                    None => first.clone(),
+                };
+                graph.add(i, label.then(rest.clone()), j);
+            }
+        }
+        for j in outgoing.keys() {
+            graph.into[*j].remove(&k);
+        }
+    }
+
+    graph.out[initial]
+        .remove(&last)
+        .unwrap_or_else(|| Expr::Atom(CharClass::new()))
+}
+
+/// The transitions of an automaton during state elimination.
+struct Graph {
+    /// The label of the transition from each state to each other.
+    out: Vec<BTreeMap<usize, Expr>>,
+    /// The states with a transition into each state.
+    into: Vec<BTreeSet<usize>>,
+}
+
+impl Graph {
+    /// Add a transition, as an alternative to the existing one between the states, if any.
+    /// Transitions that match nothing are left out.
+    #[inline]
+    fn add(&mut self, from: usize, label: Expr, to: usize) {
+        // The parser doesn't accept an empty alternative, so `x|()` is written `x?` instead.
+        let label = label.simplify();
+        let label = match self.out[from].remove(&to) {
+            Some(existing) if is_empty(&existing) && is_empty(&label) => Expr::Empty,
+            Some(existing) if is_empty(&existing) => label.optional(),
+            Some(existing) if is_empty(&label) => existing.optional(),
+            Some(existing) => existing.or(label),
+            None => label,
+        }
+        .simplify();
+        if is_nothing(&label) {
+            return;
+        }
+
+        self.out[from].insert(to, label);
+        self.into[to].insert(from);
+    }
+}
+
+/// Whether the expression is [`Expr::Empty`], ignoring spans.
+#[inline]
+fn is_empty(expr: &Expr) -> bool {
+    match expr {
+        Expr::Empty => true,
+        Expr::Spanned(inner, _) => is_empty(inner),
+        _ => false,
+    }
+}
+
+/// A traversal over the nodes of an [`Expr`] by reference. Each method visits the children of its
+/// node by default; override a method to inspect a kind of node, and call the matching `walk_*`
+/// function to continue into its children.
+///
+/// ```
+/// use regexp2::ast::{self, Expr, Visit};
+/// use regexp2::parser::ast::ASTParser;
+///
+/// struct Groups(usize);
+///
+/// impl Visit for Groups {
+///     fn visit_group(&mut self, inner: &Expr, index: usize) {
+///         self.0 += 1;
+///         ast::walk_group(self, inner, index);
+///     }
+/// }
+///
+/// let expr = ASTParser::<char>::new().parse("(a(b))|c").unwrap();
+/// let mut groups = Groups(0);
+/// groups.visit_expr(&expr);
+/// assert_eq!(2, groups.0);
+/// ```
+pub trait Visit {
+    #[inline]
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr)
+    }
+
+    #[inline]
+    fn visit_unary(&mut self, op: &UnaryOp, operand: &Expr) {
+        walk_unary(self, op, operand)
+    }
+
+    #[inline]
+    fn visit_binary(&mut self, op: &BinaryOp, lhs: &Expr, rhs: &Expr) {
+        walk_binary(self, op, lhs, rhs)
+    }
+
+    #[inline]
+    fn visit_atom(&mut self, _class: &CharClass) {}
+
+    #[inline]
+    fn visit_group(&mut self, inner: &Expr, index: usize) {
+        walk_group(self, inner, index)
+    }
+
+    #[inline]
+    fn visit_empty(&mut self) {}
+
+    #[inline]
+    fn visit_anchor(&mut self, _anchor: Anchor) {}
+
+    #[inline]
+    fn visit_spanned(&mut self, inner: &Expr, span: &Range<usize>) {
+        walk_spanned(self, inner, span)
+    }
+}
+
+/// Dispatch to the [`Visit`] method for the kind of an expression.
+#[inline]
+pub fn walk_expr<V>(visitor: &mut V, expr: &Expr)
+where
+    V: Visit + ?Sized,
+{
+    match expr {
+        Expr::Unary(op, operand) => visitor.visit_unary(op, operand),
+        Expr::Binary(op, lhs, rhs) => visitor.visit_binary(op, lhs, rhs),
+        Expr::Atom(class) => visitor.visit_atom(class),
+        Expr::Group(inner, index) => visitor.visit_group(inner, *index),
+        Expr::Empty => visitor.visit_empty(),
+        Expr::Anchor(anchor) => visitor.visit_anchor(*anchor),
+        Expr::Spanned(inner, span) => visitor.visit_spanned(inner, span),
+    }
+}
+
+/// Visit the operand of a unary operator.
+#[inline]
+pub fn walk_unary<V>(visitor: &mut V, _op: &UnaryOp, operand: &Expr)
+where
+    V: Visit + ?Sized,
+{
+    visitor.visit_expr(operand)
+}
+
+/// Visit the operands of a binary operator, from left to right.
+#[inline]
+pub fn walk_binary<V>(visitor: &mut V, _op: &BinaryOp, lhs: &Expr, rhs: &Expr)
+where
+    V: Visit + ?Sized,
+{
+    visitor.visit_expr(lhs);
+    visitor.visit_expr(rhs);
+}
+
+/// Visit the expression inside a group.
+#[inline]
+pub fn walk_group<V>(visitor: &mut V, inner: &Expr, _index: usize)
+where
+    V: Visit + ?Sized,
+{
+    visitor.visit_expr(inner)
+}
+
+/// Visit the expression that a span annotates.
+#[inline]
+pub fn walk_spanned<V>(visitor: &mut V, inner: &Expr, _span: &Range<usize>)
+where
+    V: Visit + ?Sized,
+{
+    visitor.visit_expr(inner)
+}
+
+/// A rewrite of an [`Expr`] that consumes it and builds a new one. Each method rebuilds its node
+/// from its folded children by default; override a method to replace a kind of node, and call the
+/// matching `fold_*` function to fold its children as usual.
+///
+/// ```
+/// use regexp2::ast::{Expr, Fold, UnaryOp};
+/// use regexp2::parser::ast::ASTParser;
+///
+/// // Replace `e?` with `e*`.
+/// struct Greedy;
+///
+/// impl Fold for Greedy {
+///     fn fold_unary(&mut self, op: UnaryOp, operand: Expr) -> Expr {
+///         let op = if op == UnaryOp::Optional { UnaryOp::Star } else { op };
+///         regexp2::ast::fold_unary(self, op, operand)
+///     }
+/// }
+///
+/// let parser = ASTParser::<char>::new();
+/// let expr = Greedy.fold_expr(parser.parse("ab?").unwrap());
+/// assert_eq!(parser.parse("ab*").unwrap(), expr);
+/// ```
+pub trait Fold {
+    #[inline]
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        fold_expr(self, expr)
+    }
+
+    #[inline]
+    fn fold_unary(&mut self, op: UnaryOp, operand: Expr) -> Expr {
+        fold_unary(self, op, operand)
+    }
+
+    #[inline]
+    fn fold_binary(&mut self, op: BinaryOp, lhs: Expr, rhs: Expr) -> Expr {
+        fold_binary(self, op, lhs, rhs)
+    }
+
+    #[inline]
+    fn fold_atom(&mut self, class: CharClass) -> Expr {
+        Expr::Atom(class)
+    }
+
+    #[inline]
+    fn fold_group(&mut self, inner: Expr, index: usize) -> Expr {
+        fold_group(self, inner, index)
+    }
+
+    #[inline]
+    fn fold_empty(&mut self) -> Expr {
+        Expr::Empty
+    }
+
+    #[inline]
+    fn fold_anchor(&mut self, anchor: Anchor) -> Expr {
+        Expr::Anchor(anchor)
+    }
+
+    #[inline]
+    fn fold_spanned(&mut self, inner: Expr, span: Range<usize>) -> Expr {
+        fold_spanned(self, inner, span)
+    }
+}
+
+/// Dispatch to the [`Fold`] method for the kind of an expression.
+#[inline]
+pub fn fold_expr<F>(folder: &mut F, expr: Expr) -> Expr
+where
+    F: Fold + ?Sized,
+{
+    match expr {
+        Expr::Unary(op, operand) => folder.fold_unary(op, *operand),
+        Expr::Binary(op, lhs, rhs) => folder.fold_binary(op, *lhs, *rhs),
+        Expr::Atom(class) => folder.fold_atom(class),
+        Expr::Group(inner, index) => folder.fold_group(*inner, index),
+        Expr::Empty => folder.fold_empty(),
+        Expr::Anchor(anchor) => folder.fold_anchor(anchor),
+        Expr::Spanned(inner, span) => folder.fold_spanned(*inner, span),
+    }
+}
+
+/// Fold the operand of a unary operator, and apply the operator to the result.
+#[inline]
+pub fn fold_unary<F>(folder: &mut F, op: UnaryOp, operand: Expr) -> Expr
+where
+    F: Fold + ?Sized,
+{
+    Expr::Unary(op, Box::new(folder.fold_expr(operand)))
+}
+
+/// Fold the operands of a binary operator from left to right, and apply the operator to the
+/// results.
+#[inline]
+pub fn fold_binary<F>(folder: &mut F, op: BinaryOp, lhs: Expr, rhs: Expr) -> Expr
+where
+    F: Fold + ?Sized,
+{
+    let lhs = folder.fold_expr(lhs);
+    let rhs = folder.fold_expr(rhs);
+    Expr::Binary(op, Box::new(lhs), Box::new(rhs))
+}
+
+/// Fold the expression inside a group, and wrap the result in the group.
+#[inline]
+pub fn fold_group<F>(folder: &mut F, inner: Expr, index: usize) -> Expr
+where
+    F: Fold + ?Sized,
+{
+    Expr::Group(Box::new(folder.fold_expr(inner)), index)
+}
+
+/// Fold the expression that a span annotates, and annotate the result with the span.
+#[inline]
+pub fn fold_spanned<F>(folder: &mut F, inner: Expr, span: Range<usize>) -> Expr
+where
+    F: Fold + ?Sized,
+{
+    Expr::Spanned(Box::new(folder.fold_expr(inner)), span)
+}
+
+/// The rewrites applied by [`Expr::simplify`]. Children are simplified before their parents, with
+/// an explicit stack of pending nodes so deeply nested expressions don't overflow the call stack.
+struct Simplify;
+
+/// A node of [`Simplify`] waiting for its children, or an expression yet to be visited.
+enum Frame {
+    Visit(Expr),
+    Unary(UnaryOp),
+    /// A chain of the same binary operator, and the number of its operands.
+    Binary(BinaryOp, usize),
+}
+
+impl Simplify {
+    #[inline]
+    fn simplify(expr: Expr) -> Expr {
+        let mut frames = vec![Frame::Visit(expr)];
+        let mut simplified: Vec<Expr> = Vec::new();
+        while let Some(frame) = frames.pop() {
+            match frame {
+                Frame::Visit(Expr::Unary(op, operand)) => {
+                    frames.push(Frame::Unary(op));
+                    frames.push(Frame::Visit(*operand));
+                }
+                Frame::Visit(Expr::Binary(op, lhs, rhs)) => {
+                    let mut operands = Vec::new();
+                    Self::flatten(&op, Expr::Binary(op.clone(), lhs, rhs), &mut operands);
+                    frames.push(Frame::Binary(op, operands.len()));
+                    frames.extend(operands.into_iter().rev().map(Frame::Visit));
+                }
+                Frame::Visit(Expr::Group(inner, _)) | Frame::Visit(Expr::Spanned(inner, _)) => {
+                    frames.push(Frame::Visit(*inner));
+                }
+                Frame::Visit(expr) => simplified.push(expr),
+                Frame::Unary(op) => {
+                    let operand = simplified.pop().unwrap();
+                    simplified.push(Self::unary(op, operand));
+                }
+                Frame::Binary(op, len) => {
+                    // Operands may have become chains of the same operator once simplified, such
+                    // as groups that were removed.
+                    let mut operands = Vec::new();
+                    for operand in simplified.drain(simplified.len() - len..) {
+                        Self::flatten(&op, operand, &mut operands);
+                    }
+                    simplified.push(match op {
+                        BinaryOp::Concat => Self::concat(operands),
+                        BinaryOp::Alternate => Self::alternate(operands),
+                    });
+                }
+            }
+        }
+        simplified.pop().unwrap()
+    }
+
+    /// Collect the operands of a chain of the same binary operator, from left to right. Groups and
+    /// spans in the chain are looked through, since they are removed anyway.
+    #[inline]
+    fn flatten(op: &BinaryOp, expr: Expr, operands: &mut Vec<Expr>) {
+        let mut pending = vec![expr];
+        while let Some(expr) = pending.pop() {
+            match expr {
+                Expr::Binary(ref inner, lhs, rhs) if inner == op => {
+                    pending.push(*rhs);
+                    pending.push(*lhs);
+                }
+                Expr::Group(inner, _) | Expr::Spanned(inner, _) => pending.push(*inner),
+                expr => operands.push(expr),
+            }
+        }
+    }
+
+    /// Join operands with a binary operator, associating to the left like the parser.
+    #[inline]
+    fn join(op: BinaryOp, operands: Vec<Expr>) -> Option<Expr> {
+        operands.into_iter().fold(None, |lhs, rhs| match lhs {
+            Some(lhs) => Some(Expr::Binary(op.clone(), Box::new(lhs), Box::new(rhs))),
+            None => Some(rhs),
+        })
+    }
+
+    #[inline]
+    fn unary(op: UnaryOp, operand: Expr) -> Expr {
+        match operand {
+            Expr::Empty => Expr::Empty,
+            operand if is_nothing(&operand) => match op {
+                UnaryOp::Plus => operand,
+                _ => Expr::Empty,
+            },
+            // Any two different repetitions combine to `*`.
+            Expr::Unary(inner, operand) => {
+                let op = if op == inner { op } else { UnaryOp::Star };
+                Expr::Unary(op, operand)
+            }
+            operand => Expr::Unary(op, Box::new(operand)),
+        }
+    }
+
+    #[inline]
+    fn concat(operands: Vec<Expr>) -> Expr {
+        // An empty class matches nothing, so neither does the concatenation.
+        if operands.iter().any(is_nothing) {
+            return Expr::Atom(CharClass::new());
+        }
+
+        let operands = operands.into_iter().filter(|e| *e != Expr::Empty).collect();
+        Self::join(BinaryOp::Concat, operands).unwrap_or(Expr::Empty)
+    }
+
+    #[inline]
+    fn alternate(operands: Vec<Expr>) -> Expr {
+        let mut merged: Vec<Expr> = Vec::new();
+        // The alternatives before the last, indexed by hash. The last may still have classes
+        // merged into it, so it's compared separately.
+        let mut earlier: HashMap<u64, Vec<usize>> = HashMap::new();
+        for expr in operands {
+            if is_nothing(&expr) || merged.last() == Some(&expr) {
+                continue;
+            }
+            let duplicate = earlier
+                .get(&hash(&expr))
+                .into_iter()
+                .flatten()
+                .any(|&i| merged[i] == expr);
+            if duplicate {
+                continue;
+            }
+
+            // Only adjacent classes are merged, since moving an alternative ahead of another
+            // could change which one is preferred.
+            match (merged.last_mut(), expr) {
+                (Some(Expr::Atom(prev)), Expr::Atom(class)) => prev.add_other(class),
+                (_, expr) => {
+                    if let Some(last) = merged.last() {
+                        earlier
+                            .entry(hash(last))
+                            .or_default()
+                            .push(merged.len() - 1);
+                    }
+                    merged.push(expr);
+                }
+            }
+        }
+
+        Self::join(BinaryOp::Alternate, merged).unwrap_or_else(|| Expr::Atom(CharClass::new()))
+    }
+}
+
+/// The hash of an expression, for finding equal ones.
+#[inline]
+fn hash(expr: &Expr) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    expr.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Whether the expression is an empty class, which matches nothing.
+#[inline]
+fn is_nothing(expr: &Expr) -> bool {
+    matches!(expr, Expr::Atom(class) if class.is_empty())
+}
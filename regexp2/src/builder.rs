@@ -0,0 +1,450 @@
+use crate::bytes;
+use crate::class::CharClass;
+#[cfg(feature = "grapheme")]
+use crate::grapheme;
+use crate::meta::{self, Meta, Strategy};
+use crate::parser::{nfa::NFAParser, validate::Validator, Config, LineTerminator, ParseError};
+use crate::prefilter::{self, Literals};
+use crate::regexp::{self, Error, MatchKind, RegExp};
+use crate::utf8;
+
+use std::hash::Hash;
+
+use automata::convert::{DFAFromNFA, Disjoin};
+use automata::dense::SymbolRanges;
+use automata::pikevm::PikeVM;
+use automata::shiftand::ShiftAnd;
+use automata::{DenseDFA, LazyDFA, DFA, NFA};
+
+/// A builder for configuring the compilation of a regular expression.
+///
+/// ```
+/// use regexp2::RegExpBuilder;
+///
+/// let re = RegExpBuilder::new("hello.world")
+///     .case_insensitive(true)
+///     .dot_matches_new_line(true)
+///     .build()
+///     .unwrap();
+/// assert!(re.is_match("HeLLo\nWorld"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct RegExpBuilder<'r> {
+    expr: &'r str,
+    config: Config,
+    match_kind: MatchKind,
+    nfa_size_limit: Option<usize>,
+    dfa_size_limit: Option<usize>,
+    cache_capacity: usize,
+    strategy: Option<Strategy>,
+}
+
+impl<'r> RegExpBuilder<'r> {
+    /// Create a builder for the given regular expression with the default options.
+    #[inline]
+    pub fn new(expr: &'r str) -> Self {
+        Self {
+            expr,
+            config: Config::default(),
+            match_kind: MatchKind::default(),
+            nfa_size_limit: None,
+            dfa_size_limit: None,
+            cache_capacity: LazyDFA::<CharClass>::DEFAULT_CACHE_CAPACITY,
+            strategy: None,
+        }
+    }
+
+    /// Compile a regular expression that uses a DFA to evaluate input strings.
+    #[inline]
+    pub fn build(&self) -> Result<RegExp<DenseDFA<CharClass>>, Error<'r>> {
+        let nfa = self.parse()?;
+        let prefix = prefilter::literal_prefix(&nfa);
+        let dfa = self.determinize(&nfa, MatchKind::LeftmostLongest)?;
+        let searcher = match self.match_kind {
+            MatchKind::LeftmostLongest => None,
+            kind => Some(self.determinize(&nfa, kind)?),
+        };
+        let reverse = self.reverse(&nfa);
+
+        Ok(RegExp::from_engine(
+            self.expr,
+            dfa,
+            self.match_kind,
+            searcher,
+            prefix,
+            prefilter::literal_prefixes(&nfa),
+            prefilter::literals(&nfa),
+            reverse,
+            regexp::pikevm(&nfa),
+        ))
+    }
+
+    /// Compile a regular expression that uses a DFA over the bytes of UTF-8 encoded input to
+    /// evaluate input strings. This avoids decoding characters while matching, and allows
+    /// searching byte strings that aren't entirely valid UTF-8.
+    #[inline]
+    pub fn build_bytes(&self) -> Result<RegExp<DenseDFA<u8>>, Error<'r>> {
+        let nfa = self.parse()?;
+        let prefix = prefilter::literal_prefix(&nfa);
+        let prefixes = prefilter::literal_prefixes(&nfa);
+        let literals = prefilter::literals(&nfa);
+        let pikevm = regexp::pikevm(&nfa);
+        self.compile_bytes(utf8::compile(&nfa), prefix, prefixes, literals, pikevm)
+    }
+
+    /// Compile a regular expression for matching byte strings that need not be valid UTF-8. If
+    /// Unicode is disabled, classes match single bytes rather than UTF-8 encodings; see the
+    /// [`bytes`](crate::bytes) module.
+    #[inline]
+    pub fn build_byte_regexp(&self) -> Result<bytes::RegExp, Error<'r>> {
+        let nfa = self.parse()?;
+        let prefix = prefilter::literal_prefix(&nfa);
+        let re = if self.config.unicode {
+            let prefixes = prefilter::literal_prefixes(&nfa);
+            let literals = prefilter::literals(&nfa);
+            self.compile_bytes(utf8::compile(&nfa), prefix, prefixes, literals, None)?
+        } else {
+            // Non-ASCII characters of the prefix stand for single bytes rather than their
+            // encodings, so only the ASCII part can be searched for, and the literal strings
+            // matched by the regular expression or its prefixes can't be searched for directly.
+            let prefix = prefix.chars().take_while(char::is_ascii).collect();
+            self.compile_bytes(bytes::compile(&nfa), prefix, None, None, None)?
+        };
+        Ok(bytes::RegExp::from_inner(re))
+    }
+
+    #[inline]
+    fn compile_bytes(
+        &self,
+        nfa: NFA<u8>,
+        prefix: String,
+        prefixes: Option<Vec<String>>,
+        literals: Option<Vec<String>>,
+        pikevm: Option<PikeVM<CharClass>>,
+    ) -> Result<RegExp<DenseDFA<u8>>, Error<'r>> {
+        let mut nfa = self.check_nfa_size(nfa)?;
+        nfa.cache_epsilon_closures();
+        let dfa = self.determinize(&nfa, MatchKind::LeftmostLongest)?;
+        let searcher = match self.match_kind {
+            MatchKind::LeftmostLongest => None,
+            kind => Some(self.determinize(&nfa, kind)?),
+        };
+
+        // A reverse DFA over bytes is too expensive to construct, since the leading `.*` needs a
+        // transition on every byte.
+        Ok(RegExp::from_engine(
+            self.expr,
+            dfa,
+            self.match_kind,
+            searcher,
+            prefix,
+            prefixes,
+            literals,
+            None,
+            pikevm,
+        ))
+    }
+
+    /// Compile a regular expression that uses an NFA to evaluate input strings.
+    #[inline]
+    pub fn build_nfa(&self) -> Result<RegExp<NFA<CharClass>>, Error<'r>> {
+        let nfa = self.parse()?;
+        let prefix = prefilter::literal_prefix(&nfa);
+        let prefixes = prefilter::literal_prefixes(&nfa);
+        let literals = prefilter::literals(&nfa);
+        let pikevm = regexp::pikevm(&nfa);
+        Ok(RegExp::from_engine(
+            self.expr,
+            nfa,
+            self.match_kind,
+            None,
+            prefix,
+            prefixes,
+            literals,
+            None,
+            pikevm,
+        ))
+    }
+
+    /// Compile a regular expression that matches the extended grapheme clusters of the input
+    /// rather than its characters; see the [`grapheme`](crate::grapheme) module. Requires the
+    /// `grapheme` feature.
+    #[cfg(feature = "grapheme")]
+    #[inline]
+    pub fn build_grapheme_regexp(&self) -> Result<grapheme::RegExp, Error<'r>> {
+        // The literal prefilters search for characters, which might start in the middle of a
+        // cluster, so they aren't used.
+        let nfa = self.parse()?;
+        Ok(grapheme::RegExp::from_inner(RegExp::from_engine(
+            self.expr,
+            nfa,
+            self.match_kind,
+            None,
+            String::new(),
+            None,
+            None,
+            None,
+            None,
+        )))
+    }
+
+    /// Compile a regular expression that uses a DFA constructed on demand to evaluate input strings.
+    /// This avoids constructing the whole DFA up front, which can take time exponential in the
+    /// size of the regular expression.
+    #[inline]
+    pub fn build_lazy(&self) -> Result<RegExp<LazyDFA<CharClass>>, Error<'r>> {
+        let nfa = self.parse()?;
+        let prefix = prefilter::literal_prefix(&nfa);
+        let prefixes = prefilter::literal_prefixes(&nfa);
+        let literals = prefilter::literals(&nfa);
+        let pikevm = regexp::pikevm(&nfa);
+        let searcher = match self.match_kind {
+            MatchKind::LeftmostLongest => None,
+            kind => Some(self.lazy(nfa.clone(), kind)),
+        };
+        let lazy = self.lazy(nfa, MatchKind::LeftmostLongest);
+
+        Ok(RegExp::from_engine(
+            self.expr,
+            lazy,
+            self.match_kind,
+            searcher,
+            prefix,
+            prefixes,
+            literals,
+            None,
+            pikevm,
+        ))
+    }
+
+    /// Compile a regular expression that chooses how to search each input string, based on the
+    /// regular expression and the length of the input. See the [`meta`](crate::meta) module.
+    ///
+    /// ```
+    /// use regexp2::meta::Strategy;
+    /// use regexp2::RegExpBuilder;
+    ///
+    /// let re = RegExpBuilder::new("hello").build_meta().unwrap();
+    /// assert_eq!(Strategy::Literal, re.strategy(100));
+    /// assert_eq!(Some(6..11), re.find("Well, hello!").map(|m| m.range()));
+    /// ```
+    #[inline]
+    pub fn build_meta(&self) -> Result<RegExp<Meta>, Error<'r>> {
+        let nfa = self.parse()?;
+        let prefix = prefilter::literal_prefix(&nfa);
+        let pikevm = regexp::pikevm(&nfa);
+        let searcher = match self.match_kind {
+            MatchKind::LeftmostLongest => None,
+            kind => Some(self.meta(&nfa, kind)?),
+        };
+        let meta = self.meta(&nfa, MatchKind::LeftmostLongest)?;
+
+        Ok(RegExp::from_engine(
+            self.expr,
+            meta,
+            self.match_kind,
+            searcher,
+            prefix,
+            prefilter::literal_prefixes(&nfa),
+            prefilter::literals(&nfa),
+            None,
+            pikevm,
+        ))
+    }
+
+    #[inline]
+    fn meta(&self, nfa: &NFA<CharClass>, kind: MatchKind) -> Result<Meta, Error<'r>> {
+        // Unless the DFA is asked for, it is only compiled if it is small.
+        let dfa = if self.strategy == Some(Strategy::DFA) {
+            Some(self.determinize(nfa, kind)?)
+        } else {
+            let limit = self.dfa_size_limit.map_or(meta::DFA_SIZE_LIMIT, |limit| {
+                limit.min(meta::DFA_SIZE_LIMIT)
+            });
+            DFAFromNFA::with_match_kind(nfa, kind, Some(limit))
+                .ok()
+                .map(|dfa| DFA::from(dfa).minimize().into())
+        };
+
+        Ok(Meta::new(
+            kind,
+            self.strategy,
+            prefilter::literals(nfa).and_then(|literals| Literals::new(literals, kind)),
+            dfa,
+            ShiftAnd::new(nfa),
+            self.lazy(nfa.clone(), kind),
+            PikeVM::new(nfa.clone()),
+        ))
+    }
+
+    /// Check that the regular expression is well-formed with the configured options, without
+    /// compiling it. See [`RegExp::validate`].
+    #[inline]
+    pub fn validate(&self) -> Result<(), ParseError<'r>> {
+        Validator::with_config(self.config.clone()).parse(self.expr)
+    }
+
+    #[inline]
+    fn parse(&self) -> Result<NFA<CharClass>, Error<'r>> {
+        let parser = NFAParser::with_config(self.config.clone());
+        let mut nfa = self.check_nfa_size(parser.parse(self.expr)?)?;
+        nfa.cache_epsilon_closures();
+        Ok(nfa)
+    }
+
+    #[inline]
+    fn check_nfa_size<T>(&self, nfa: NFA<T>) -> Result<NFA<T>, Error<'r>>
+    where
+        T: Clone + Eq + Hash,
+    {
+        match self.nfa_size_limit {
+            Some(limit) if nfa.total_states > limit => Err(Error::NFASizeLimitExceeded { limit }),
+            _ => Ok(nfa),
+        }
+    }
+
+    #[inline]
+    fn determinize<T>(&self, nfa: &NFA<T>, kind: MatchKind) -> Result<DenseDFA<T>, Error<'r>>
+    where
+        T: Clone + Disjoin + Eq + Hash + SymbolRanges,
+    {
+        let dfa = DFAFromNFA::with_match_kind(nfa, kind, self.dfa_size_limit)
+            .map_err(|e| Error::DFASizeLimitExceeded { limit: e.limit() })?;
+        DenseDFA::new(&DFA::from(dfa).minimize())
+            .map_err(|e| Error::DFASizeLimitExceeded { limit: e.limit() })
+    }
+
+    /// Compile a DFA used to find the start of the leftmost match by scanning backwards. `None` is
+    /// returned if the DFA would be too large.
+    #[inline]
+    fn reverse(&self, nfa: &NFA<CharClass>) -> Option<DenseDFA<CharClass>> {
+        let limit = self
+            .dfa_size_limit
+            .map_or(regexp::REVERSE_SIZE_LIMIT, |limit| {
+                limit.min(regexp::REVERSE_SIZE_LIMIT)
+            });
+        let reverse = regexp::unanchored_reverse(nfa);
+        let dfa = DFAFromNFA::with_state_limit(&reverse, limit).ok()?;
+        Some(DFA::from(dfa).minimize().into())
+    }
+
+    #[inline]
+    fn lazy(&self, nfa: NFA<CharClass>, kind: MatchKind) -> LazyDFA<CharClass> {
+        let mut lazy = LazyDFA::with_match_kind(nfa, kind);
+        lazy.set_cache_capacity(self.cache_capacity);
+        lazy
+    }
+
+    /// Set the semantics used to choose between matches that start at the same position. The
+    /// default is [`MatchKind::LeftmostLongest`].
+    #[inline]
+    pub fn match_kind(&mut self, kind: MatchKind) -> &mut Self {
+        self.match_kind = kind;
+        self
+    }
+
+    /// Match letters regardless of their case.
+    #[inline]
+    pub fn case_insensitive(&mut self, yes: bool) -> &mut Self {
+        self.config.case_insensitive = yes;
+        self
+    }
+
+    /// Allow `.` to match the newline character. This can be overridden within the expression with
+    /// the `s` flag, as in `(?-s:.)`.
+    #[inline]
+    pub fn dot_matches_new_line(&mut self, yes: bool) -> &mut Self {
+        self.config.dot_matches_new_line = yes;
+        self
+    }
+
+    /// Set the characters that end a line, which `.` doesn't match unless
+    /// [`dot_matches_new_line`](Self::dot_matches_new_line) is enabled. The default is
+    /// [`LineTerminator::Newline`].
+    ///
+    /// ```
+    /// use regexp2::parser::LineTerminator;
+    /// use regexp2::RegExpBuilder;
+    ///
+    /// let re = RegExpBuilder::new("a.b")
+    ///     .line_terminator(LineTerminator::CRLF)
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(re.is_match("a-b"));
+    /// assert!(!re.is_match("a\rb"));
+    /// ```
+    #[inline]
+    pub fn line_terminator(&mut self, terminator: LineTerminator) -> &mut Self {
+        self.config.line_terminator = terminator;
+        self
+    }
+
+    /// Use the Unicode definitions of `\d`, `\s`, and `\w`. Enabled by default. This can be
+    /// overridden within the expression with the `u` flag, as in `(?-u:\d)`.
+    #[inline]
+    pub fn unicode(&mut self, yes: bool) -> &mut Self {
+        self.config.unicode = yes;
+        self
+    }
+
+    /// Allow empty regular expressions, groups, and branches, as in `""`, `()`, and `a|`, which
+    /// match the empty string. Disabled by default, in which case these are errors.
+    #[inline]
+    pub fn empty_matches(&mut self, yes: bool) -> &mut Self {
+        self.config.empty_matches = yes;
+        self
+    }
+
+    /// Set the maximum size of the parsed regular expression. Parsing fails with
+    /// [`ParseError::SizeLimitExceeded`](crate::parser::ParseError::SizeLimitExceeded) if the
+    /// limit is exceeded. See [`Config::size_limit`](crate::parser::Config::size_limit).
+    #[inline]
+    pub fn size_limit(&mut self, limit: usize) -> &mut Self {
+        self.config.size_limit = Some(limit);
+        self
+    }
+
+    /// Set the maximum number of states the compiled NFA may have. Compilation fails with
+    /// [`Error::NFASizeLimitExceeded`] if the limit is exceeded.
+    #[inline]
+    pub fn nfa_size_limit(&mut self, limit: usize) -> &mut Self {
+        self.nfa_size_limit = Some(limit);
+        self
+    }
+
+    /// Set the maximum number of states the compiled DFA may have. Compilation fails with
+    /// [`Error::DFASizeLimitExceeded`] if the limit is exceeded.
+    #[inline]
+    pub fn dfa_size_limit(&mut self, limit: usize) -> &mut Self {
+        self.dfa_size_limit = Some(limit);
+        self
+    }
+
+    /// Set the maximum depth to which groups may be nested. Parsing fails with
+    /// [`ParseError::NestLimitExceeded`](crate::parser::ParseError::NestLimitExceeded) if the
+    /// limit is exceeded.
+    #[inline]
+    pub fn nest_limit(&mut self, limit: usize) -> &mut Self {
+        self.config.nest_limit = Some(limit);
+        self
+    }
+
+    /// Set the maximum number of states cached by the DFA of a regular expression compiled with
+    /// [`build_lazy`](Self::build_lazy) or [`build_meta`](Self::build_meta).
+    #[inline]
+    pub fn cache_capacity(&mut self, capacity: usize) -> &mut Self {
+        self.cache_capacity = capacity;
+        self
+    }
+
+    /// Override the strategy chosen by a regular expression compiled with
+    /// [`build_meta`](Self::build_meta). The strategy is still not used for searches it doesn't
+    /// apply to, such as [`Strategy::Literal`] for a regular expression that matches more than
+    /// one string. Asking for [`Strategy::DFA`] compiles the DFA regardless of its size, up to
+    /// the [`dfa_size_limit`](Self::dfa_size_limit).
+    #[inline]
+    pub fn strategy(&mut self, strategy: Strategy) -> &mut Self {
+        self.strategy = Some(strategy);
+        self
+    }
+}
@@ -0,0 +1,226 @@
+//! Regular expressions for matching byte strings that need not be valid UTF-8.
+//!
+//! By default, the character classes of a [`RegExp`] match the UTF-8 encodings of their
+//! characters, as with [`RegExpBuilder::build_bytes`]. When Unicode is disabled with
+//! [`RegExpBuilder::unicode`], classes match single bytes instead: the characters `\0` to `\u{FF}`
+//! stand for the bytes with the same values, and other characters never match. In this mode, `.`
+//! and negated classes match any byte outside of them, including bytes that aren't valid UTF-8.
+//!
+//! ```
+//! use regexp2::bytes::RegExp;
+//! use regexp2::RegExpBuilder;
+//!
+//! let re = RegExpBuilder::new("ÿ[^a]+").unicode(false).build_byte_regexp().unwrap();
+//! let m = re.find(b"ab\xff\x00\x80a").unwrap();
+//! assert_eq!(m.range(), 2..5);
+//! assert_eq!(m.as_bytes(), b"\xff\x00\x80");
+//! ```
+
+use crate::builder::RegExpBuilder;
+use crate::class::{ByteClass, ByteRange, CharClass};
+use crate::regexp::{self, Error};
+
+use std::fmt;
+use std::hash::Hash;
+use std::ops::Range;
+use std::str::FromStr;
+
+use automata::nfa::Transition;
+use automata::{DenseDFA, NFA};
+
+/// A single match of a regular expression in a byte string.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Match<'t> {
+    haystack: &'t [u8],
+    start: usize,
+    end: usize,
+}
+
+impl<'t> Match<'t> {
+    #[inline]
+    fn new(haystack: &'t [u8], range: Range<usize>) -> Self {
+        Self {
+            haystack,
+            start: range.start,
+            end: range.end,
+        }
+    }
+
+    /// The byte offset of the start of the match.
+    #[inline]
+    pub const fn start(&self) -> usize {
+        self.start
+    }
+
+    /// The byte offset of the end of the match.
+    #[inline]
+    pub const fn end(&self) -> usize {
+        self.end
+    }
+
+    /// The byte range of the match.
+    #[inline]
+    pub const fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// The matched bytes.
+    #[inline]
+    pub fn as_bytes(&self) -> &'t [u8] {
+        &self.haystack[self.range()]
+    }
+}
+
+/// A compiled regular expression for matching byte strings. See the [module
+/// documentation](self) for how classes are interpreted over bytes.
+#[derive(Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub struct RegExp {
+    inner: regexp::RegExp<DenseDFA<u8>>,
+}
+
+impl RegExp {
+    /// Compile a regular expression whose classes match the UTF-8 encodings of their characters.
+    /// Use [`RegExpBuilder::build_byte_regexp`] to configure the compilation.
+    #[inline]
+    pub fn new(expr: &'_ str) -> Result<Self, Error<'_>> {
+        RegExpBuilder::new(expr).build_byte_regexp()
+    }
+
+    #[inline]
+    pub(crate) fn from_inner(inner: regexp::RegExp<DenseDFA<u8>>) -> Self {
+        Self { inner }
+    }
+
+    /// The pattern the regular expression was compiled from.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.inner.as_str()
+    }
+
+    /// Determine if the given bytes are within the language described by the regular expression.
+    #[inline]
+    pub fn is_match(&self, input: &[u8]) -> bool {
+        self.inner.is_match_bytes(input)
+    }
+
+    /// Find the leftmost match in the given bytes.
+    #[inline]
+    pub fn find<'t>(&self, input: &'t [u8]) -> Option<Match<'t>> {
+        self.find_at(input, 0)
+    }
+
+    /// Find the leftmost match that starts at or after the byte offset `start` of the given bytes.
+    #[inline]
+    pub fn find_at<'t>(&self, input: &'t [u8], start: usize) -> Option<Match<'t>> {
+        self.inner
+            .find_bytes_at(input, start)
+            .map(|range| Match::new(input, range))
+    }
+}
+
+impl FromStr for RegExp {
+    type Err = Error<'static>;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RegExp::new(s).map_err(Error::into_owned)
+    }
+}
+
+impl fmt::Display for RegExp {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+/// Compile an NFA over characters to an NFA over bytes in which each character from `\0` to
+/// `\u{FF}` reads the byte with the same value. Transitions on other characters are dropped.
+#[inline]
+pub fn compile(nfa: &NFA<CharClass>) -> NFA<u8> {
+    expand(&compile_classes(nfa))
+}
+
+/// Compile an NFA over characters to an NFA over byte classes, as in [`compile`], with each class
+/// mapped to the class of bytes with the values of its characters up to `\u{FF}`.
+#[inline]
+pub fn compile_classes(nfa: &NFA<CharClass>) -> NFA<ByteClass> {
+    map_classes(nfa, |bytes, from, class, to| {
+        let class: ByteClass = class
+            .iter()
+            .filter(|r| r.start as u32 <= 0xFF)
+            .map(|r| ByteRange::new(r.start as u8, (r.end as u32).min(0xFF) as u8))
+            .collect();
+        if !class.is_empty() {
+            for &to in to {
+                bytes.add_labeled_transition(from, to, class.clone());
+            }
+        }
+    })
+}
+
+/// Expand an NFA over byte classes to an NFA over bytes, with a transition on each byte of a
+/// class in place of the transition on the class.
+#[inline]
+pub fn expand(nfa: &NFA<ByteClass>) -> NFA<u8> {
+    let mut bytes = empty_like(nfa);
+    for (&from, t, to) in &nfa.transition {
+        match t {
+            Transition::Epsilon => {
+                for &to in to {
+                    bytes.add_epsilon_transition(from, to);
+                }
+            }
+            Transition::Some(class) => {
+                for b in class.bytes() {
+                    for &to in to {
+                        bytes.add_labeled_transition(from, to, b);
+                    }
+                }
+            }
+        }
+    }
+    bytes
+}
+
+/// Build an NFA over byte classes with the states of the NFA over characters, keeping its epsilon
+/// transitions and calling `add` with each labeled transition, as the state it leaves, its class,
+/// and the states it enters.
+#[inline]
+pub(crate) fn map_classes<F>(nfa: &NFA<CharClass>, mut add: F) -> NFA<ByteClass>
+where
+    F: FnMut(&mut NFA<ByteClass>, usize, &CharClass, &[usize]),
+{
+    let mut bytes = empty_like(nfa);
+    for (&from, t, to) in &nfa.transition {
+        match t {
+            Transition::Epsilon => {
+                for &to in to {
+                    bytes.add_epsilon_transition(from, to);
+                }
+            }
+            Transition::Some(class) => add(&mut bytes, from, class, to),
+        }
+    }
+    bytes
+}
+
+/// An NFA without transitions, with the same states, accepting states, and patterns as `nfa`.
+#[inline]
+fn empty_like<T, U>(nfa: &NFA<T>) -> NFA<U>
+where
+    T: Clone + Eq + Hash,
+    U: Clone + Eq + Hash,
+{
+    let mut empty = NFA::new();
+    empty.start_state = nfa.start_state;
+    empty.total_states = nfa.total_states;
+    empty.accepting_states = nfa.accepting_states.clone();
+    empty.patterns = nfa.patterns.clone();
+    empty
+}
@@ -1,12 +1,23 @@
+#[cfg(feature = "unicode-case")]
+use crate::case_folding::CASE_FOLDING_SIMPLE;
 use crate::mergeset::{self, MergeSet, Value as MergeSetValue};
+#[cfg(feature = "unicode-properties")]
+use crate::property_values::PROPERTY_VALUES;
 use crate::ranges::{DECIMAL_NUMBER, LETTER};
+#[cfg(feature = "unicode-properties")]
+use crate::{general_category, script};
 
 use std::cmp;
+use std::collections::HashMap;
 use std::convert::TryInto;
-use std::hash::Hash;
+use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::iter;
+use std::ops::RangeInclusive;
 
 use automata::convert::Disjoin;
+use automata::dense::SymbolRanges;
+use automata::dfa::SymbolSet;
 
 /// The lowest Unicode scalar value.
 const USV_START_1: char = '\u{0}';
@@ -16,20 +27,37 @@ const USV_END_1: char = '\u{d7ff}';
 const USV_START_2: char = '\u{e000}';
 /// The upper limit of the upper interval of Unicode scalar values.
 const USV_END_2: char = '\u{10ffff}';
+/// The number of surrogate code points between the two intervals of Unicode scalar values.
+const SURROGATES: u32 = USV_START_2 as u32 - USV_END_1 as u32 - 1;
+
+/// Error returned by [`CharClass::from_unicode_property`] for a name that isn't a Unicode
+/// general category or script.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("unknown Unicode property `{0}`")]
+pub struct UnknownPropertyError(pub String);
 
 /// A set of character ranges that represent one character class. A CharClass contains all the
 /// ranges in a single bracketed segment of character ranges in a regular expression.
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+///
+/// Ranges that overlap or are adjacent are merged as they are added, so the ranges of a class are
+/// sorted and minimal, and two classes are equal if they contain the same characters.
+///
+/// The ranges are stored in a persistent tree, so cloning a class is cheap and the clones share
+/// their ranges until they are modified.
+#[derive(Clone)]
 pub struct CharClass {
     /// The ranges included in the character class.
     pub ranges: MergeSet<char, CharRange>,
 }
 
 impl CharClass {
-    /// Determine if the given char is within any of the character class's ranges.
+    /// Determine if the given char is within any of the character class's ranges. The ranges are
+    /// sorted and disjoint, so only the last range starting at or before the char is searched for
+    /// in the tree and checked.
     #[inline]
     pub fn contains(&self, c: char) -> bool {
-        self.ranges.iter().any(|r| r.contains(c))
+        let range = self.ranges.iter_to(&c).find(|r| !r.is_inverted());
+        matches!(range, Some(r) if r.contains(c))
     }
 
     #[inline]
@@ -49,6 +77,35 @@ impl CharClass {
         })
     }
 
+    /// Return the characters in this class that are not in `other`.
+    #[inline]
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut difference = CharClass::new();
+        for r in self {
+            // The lowest character of `r` that hasn't been removed yet.
+            let mut start = r.start as u32;
+            for o in other
+                .iter()
+                .filter(|o| o.end >= r.start && o.start <= r.end)
+            {
+                if (o.start as u32) > start {
+                    difference.extend(scalar_range(start, o.start as u32 - 1));
+                }
+                start = cmp::max(start, o.end as u32 + 1);
+            }
+            difference.extend(scalar_range(start, r.end as u32));
+        }
+        difference
+    }
+
+    /// Return the characters that are in exactly one of this class and `other`.
+    #[inline]
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        let mut symmetric_difference = self.difference(other);
+        symmetric_difference.add_other(other.difference(self));
+        symmetric_difference
+    }
+
     /// Return the complement of the union of the ranges in the character class.
     #[inline]
     pub fn complement(&self) -> Self {
@@ -64,6 +121,20 @@ impl CharClass {
             .unwrap_or_else(CharClass::new)
     }
 
+    /// Remove any inverted ranges, such as `z-a`, which contain no characters. These are dropped
+    /// as ranges are added, so they can only be present if inserted into `ranges` directly.
+    #[inline]
+    pub fn normalize(&mut self) {
+        let inverted: Vec<_> = self
+            .iter()
+            .filter(|r| r.is_inverted())
+            .map(|r| r.start)
+            .collect();
+        for start in inverted {
+            self.ranges.remove(start);
+        }
+    }
+
     /// Copy the ranges in `other` to this `Self`.
     #[inline]
     pub fn copy_from(&mut self, other: &CharClass) {
@@ -72,10 +143,13 @@ impl CharClass {
         }
     }
 
-    /// Add a character range to the set.
+    /// Add a character range to the set. Inverted ranges contain no characters, so they are
+    /// dropped.
     #[inline]
     pub fn add_range(&mut self, range: CharRange) {
-        self.ranges.insert(range);
+        if !range.is_inverted() {
+            self.ranges.insert(range);
+        }
     }
 
     #[inline]
@@ -83,18 +157,28 @@ impl CharClass {
         class.ranges.into_iter().for_each(|r| self.add_range(r));
     }
 
+    /// Return the smallest character class that contains this class and is closed under simple
+    /// case folding: every character with the same simple case folding as a character of this
+    /// class is also in the class, as in `[kK\u{212A}]` for `k`.
+    ///
+    /// The Unicode case folding tables are only included with the `unicode-case` feature, which
+    /// is enabled by default. Without it, only the simple lowercase and uppercase mappings of
+    /// each character are added, which misses characters like the Kelvin sign above.
+    #[inline]
+    pub fn case_fold(&self) -> Self {
+        let mut folded = self.clone();
+        for r in self {
+            folded.extend(case_fold_range(r).map(CharRange::from));
+        }
+        folded
+    }
+
     #[inline]
     pub fn is_single(&self) -> bool {
         let mut iter = self.ranges.iter();
         let c = match iter.next() {
-            Some(r) => {
-                if r.start == r.end {
-                    r.start
-                } else {
-                    return false;
-                }
-            }
-            None => return false,
+            Some(r) if r.start == r.end => r.start,
+            _ => return false,
         };
 
         iter.all(|range| c == range.start && c == range.end)
@@ -102,6 +186,22 @@ impl CharClass {
 }
 
 impl CharClass {
+    /// Create a character class of all Unicode scalar values.
+    #[inline]
+    pub fn all() -> Self {
+        vec![
+            CharRange::new(USV_START_1, USV_END_1),
+            CharRange::new(USV_START_2, USV_END_2),
+        ]
+        .into()
+    }
+
+    /// Create a character class of all ASCII characters.
+    #[inline]
+    pub fn ascii() -> Self {
+        CharRange::new('\u{0}', '\u{7f}').into()
+    }
+
     /// Create a character class of all characters except the newline character.
     #[inline]
     pub fn newline() -> Self {
@@ -119,6 +219,45 @@ impl CharClass {
         LETTER.iter().map(|&r| r.into()).collect()
     }
 
+    /// Create a character class of the characters with a Unicode general category or script,
+    /// given by any of its names or aliases, such as `Greek`, `Grek`, `Lu`, or
+    /// `Uppercase_Letter`. Names are matched loosely, ignoring case, spaces, underscores, and
+    /// hyphens. Requires the `unicode-properties` feature, which is enabled by default.
+    ///
+    /// ```
+    /// use regexp2::class::CharClass;
+    ///
+    /// let greek = CharClass::from_unicode_property("Greek").unwrap();
+    /// assert!(greek.contains('λ'));
+    /// assert_eq!(greek, CharClass::from_unicode_property("grek").unwrap());
+    ///
+    /// let upper = CharClass::from_unicode_property("Uppercase Letter").unwrap();
+    /// assert!(upper.contains('Λ'));
+    /// assert!(!upper.contains('λ'));
+    ///
+    /// assert!(CharClass::from_unicode_property("Klingon").is_err());
+    /// ```
+    #[cfg(feature = "unicode-properties")]
+    #[inline]
+    pub fn from_unicode_property(name: &str) -> Result<Self, UnknownPropertyError> {
+        let loose: String = name
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '_' | '-'))
+            .flat_map(char::to_lowercase)
+            .collect();
+        [
+            ("General_Category", general_category::BY_NAME),
+            ("Script", script::BY_NAME),
+        ]
+        .iter()
+        .find_map(|&(property, by_name)| {
+            let value = lookup(property_values(property), &loose)?;
+            lookup(by_name, value)
+        })
+        .map(|ranges| ranges.iter().map(|&r| r.into()).collect())
+        .ok_or_else(|| UnknownPropertyError(name.to_owned()))
+    }
+
     /// Create a character class consisting of all alphanumerics and the underscore.
     #[inline]
     pub fn word() -> Self {
@@ -137,6 +276,27 @@ impl CharClass {
         DECIMAL_NUMBER.iter().map(|&r| r.into()).collect()
     }
 
+    /// Create a character class consisting of the ASCII digits, `[0-9]`.
+    #[inline]
+    pub fn ascii_digit() -> Self {
+        CharRange::new('0', '9').into()
+    }
+
+    /// Create a character class consisting of the ASCII alphanumerics and the underscore. This is
+    /// the same as [`word`](Self::word).
+    #[inline]
+    pub fn ascii_word() -> Self {
+        Self::word()
+    }
+
+    /// Create a character class consisting of the ASCII whitespace characters, `[\t\n\v\f\r ]`.
+    #[inline]
+    pub fn ascii_whitespace() -> Self {
+        let mut cc: Self = CharRange::new('\t', '\r').into();
+        cc.add_range(CharRange::new_single(' '));
+        cc
+    }
+
     /// Create a character class consisting of whitespace characters.
     #[inline]
     pub fn whitespace() -> Self {
@@ -149,6 +309,165 @@ impl CharClass {
         cc.add_range(CharRange::new('\u{2000}', '\u{200a}'));
         cc
     }
+
+    /// Create a character class consisting of the ASCII horizontal whitespace characters, `[\t ]`.
+    #[inline]
+    pub fn ascii_horizontal_whitespace() -> Self {
+        vec!['\t', ' '].into()
+    }
+
+    /// Create a character class consisting of the horizontal whitespace characters, the
+    /// whitespace characters that don't end a line.
+    #[inline]
+    pub fn horizontal_whitespace() -> Self {
+        let chars = vec![
+            '\t', ' ', '\u{00a0}', '\u{1680}', '\u{180e}', '\u{202f}', '\u{205f}', '\u{3000}',
+        ];
+
+        let mut cc: Self = chars.into();
+        cc.add_range(CharRange::new('\u{2000}', '\u{200a}'));
+        cc
+    }
+
+    /// Create a character class consisting of the ASCII vertical whitespace characters,
+    /// `[\n\v\f\r]`.
+    #[inline]
+    pub fn ascii_vertical_whitespace() -> Self {
+        CharRange::new('\n', '\r').into()
+    }
+
+    /// Create a character class consisting of the vertical whitespace characters, the single
+    /// characters that end a line.
+    #[inline]
+    pub fn vertical_whitespace() -> Self {
+        let mut cc = Self::ascii_vertical_whitespace();
+        cc.add_range(CharRange::new_single('\u{0085}'));
+        cc.add_range(CharRange::new('\u{2028}', '\u{2029}'));
+        cc
+    }
+}
+
+/// The characters outside of the range that have the same simple case folding as one inside it.
+#[cfg(feature = "unicode-case")]
+#[inline]
+fn case_fold_range(range: &CharRange) -> impl Iterator<Item = char> + '_ {
+    let start = CASE_FOLDING_SIMPLE.partition_point(|&(c, _)| c < range.start);
+    CASE_FOLDING_SIMPLE[start..]
+        .iter()
+        .take_while(move |&&(c, _)| c <= range.end)
+        .flat_map(|(_, equivalents)| equivalents.iter().copied())
+}
+
+/// The spans of characters that may have a case mapping, which cover every character with a simple
+/// case mapping as of Unicode 17.0. Without the tables, only these are mapped.
+#[cfg(not(feature = "unicode-case"))]
+const CASED_SPANS: &[(char, char)] = &[
+    ('\u{41}', '\u{29e}'),
+    ('\u{345}', '\u{586}'),
+    ('\u{10a0}', '\u{10ff}'),
+    ('\u{13a0}', '\u{13fd}'),
+    ('\u{1c80}', '\u{1cbf}'),
+    ('\u{1d79}', '\u{1d8e}'),
+    ('\u{1e00}', '\u{1ffc}'),
+    ('\u{2126}', '\u{2184}'),
+    ('\u{24b6}', '\u{24e9}'),
+    ('\u{2c00}', '\u{2d2d}'),
+    ('\u{a640}', '\u{a69b}'),
+    ('\u{a722}', '\u{a7f6}'),
+    ('\u{ab53}', '\u{abbf}'),
+    ('\u{fb05}', '\u{fb06}'),
+    ('\u{ff21}', '\u{ff5a}'),
+    ('\u{10400}', '\u{1044f}'),
+    ('\u{104b0}', '\u{104fb}'),
+    ('\u{10570}', '\u{105bc}'),
+    ('\u{10c80}', '\u{10cf2}'),
+    ('\u{10d50}', '\u{10d85}'),
+    ('\u{118a0}', '\u{118df}'),
+    ('\u{16e40}', '\u{16e7f}'),
+    ('\u{16ea0}', '\u{16ed3}'),
+    ('\u{1e900}', '\u{1e943}'),
+];
+
+/// The simple lowercase and uppercase mappings of the characters in the range, skipping the parts
+/// of the range outside of [`CASED_SPANS`].
+#[cfg(not(feature = "unicode-case"))]
+#[inline]
+fn case_fold_range(range: &CharRange) -> impl Iterator<Item = char> + '_ {
+    let start = CASED_SPANS.partition_point(|&(_, end)| end < range.start);
+    CASED_SPANS[start..]
+        .iter()
+        .take_while(move |&&(start, _)| start <= range.end)
+        .flat_map(move |&(start, end)| {
+            cmp::max(start, range.start) as u32..=cmp::min(end, range.end) as u32
+        })
+        .filter_map(char::from_u32)
+        .flat_map(|c| {
+            let mappings = [single_char(c.to_lowercase()), single_char(c.to_uppercase())];
+            IntoIterator::into_iter(mappings)
+                .flatten()
+                .filter(move |&m| m != c)
+        })
+}
+
+/// The aliases of the values of a Unicode property, by their loose names.
+#[cfg(feature = "unicode-properties")]
+#[inline]
+fn property_values(property: &str) -> &'static [(&'static str, &'static str)] {
+    lookup(PROPERTY_VALUES, property).unwrap_or(&[])
+}
+
+/// Look up the value of a key in a table sorted by key.
+#[cfg(feature = "unicode-properties")]
+#[inline]
+fn lookup<T>(table: &[(&str, T)], key: &str) -> Option<T>
+where
+    T: Copy,
+{
+    table
+        .binary_search_by_key(&key, |&(k, _)| k)
+        .ok()
+        .map(|i| table[i].1)
+}
+
+/// Return the only character produced by a case mapping, or `None` if it maps to several.
+#[cfg(not(feature = "unicode-case"))]
+#[inline]
+fn single_char<I>(mut mapping: I) -> Option<char>
+where
+    I: ExactSizeIterator<Item = char>,
+{
+    if mapping.len() == 1 {
+        mapping.next()
+    } else {
+        None
+    }
+}
+
+impl CharClass {
+    /// Iterate over the ranges that aren't inverted.
+    #[inline]
+    fn nonempty_ranges(&self) -> impl Iterator<Item = &CharRange> {
+        self.iter().filter(|r| !r.is_inverted())
+    }
+}
+
+impl PartialEq for CharClass {
+    /// Compare the characters contained by the classes, ignoring inverted ranges.
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.nonempty_ranges().eq(other.nonempty_ranges())
+    }
+}
+
+impl Eq for CharClass {}
+
+impl Hash for CharClass {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for r in self.nonempty_ranges() {
+            r.hash(state);
+        }
+    }
 }
 
 impl CharClass {
@@ -236,6 +555,25 @@ impl CharClass {
     pub fn iter(&self) -> CharClassIter<'_> {
         self.ranges.iter().into()
     }
+
+    /// Iterate over the ranges of the class in ascending order. This is the same as
+    /// [`iter`](Self::iter).
+    #[inline]
+    pub fn iter_ranges(&self) -> CharClassIter<'_> {
+        self.iter()
+    }
+
+    /// Iterate over the characters in the class in ascending order.
+    #[inline]
+    pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.nonempty_ranges().flat_map(CharRange::chars)
+    }
+
+    /// The number of characters in the class.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.nonempty_ranges().map(CharRange::len).sum()
+    }
 }
 
 impl<'a> IntoIterator for &'a CharClass {
@@ -298,33 +636,99 @@ impl From<mergeset::IntoIter<char, CharRange>> for CharClassIntoIter {
     }
 }
 
-impl Disjoin for CharClass {
-    /// Create a set of disjoint CharClass from a set of CharClass. Algorithm inspired by [this
-    /// Stack Overflow answer](https://stackoverflow.com/a/55482655/8955108).
+impl fmt::Debug for CharClass {
+    /// Format the character class as its [`Display`](fmt::Display) form, as in
+    /// `CharClass([0-9A-Z_a-z])`, rather than as the tree of its ranges.
     #[inline]
-    fn disjoin(vec: Vec<&Self>) -> Vec<Self> {
-        let ranges: Vec<_> = vec.iter().flat_map(|cc| cc.ranges.clone()).collect();
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CharClass({})", self)
+    }
+}
 
-        let mut starts: Vec<_> = ranges.iter().map(|r| (r.start as u32, 1)).collect();
-        let mut ends: Vec<_> = ranges.iter().map(|r| (r.end as u32 + 1, -1)).collect();
-        starts.append(&mut ends);
-        starts.sort_by(|a, b| a.0.cmp(&b.0));
+impl fmt::Display for CharClass {
+    /// Format the character class in the syntax of a regular expression. A class of a single
+    /// character is written as that character, and a class that is simpler to describe by what it
+    /// excludes is written as a negated class. The class of all characters is written as the range
+    /// of all code points, since an empty negated class doesn't parse.
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_single() {
+            let c = self.ranges.iter().next().unwrap().start;
+            if "\\.*+?()|[]{}^$".contains(c) {
+                return write!(f, "\\{}", c);
+            }
+            return write_class_char(f, c);
+        }
 
-        let mut prev = 0;
-        let mut count = 0;
-        starts
+        let complement = self.complement();
+        if complement.is_empty() && !self.is_empty() {
+            return write!(f, "[{}]", CharRange::new('\u{0}', '\u{10ffff}'));
+        }
+        let (negated, class) =
+            if !complement.is_empty() && complement.ranges.len() < self.ranges.len() {
+                (true, &complement)
+            } else {
+                (false, self)
+            };
+
+        write!(f, "[")?;
+        if negated {
+            write!(f, "^")?;
+        }
+        for r in class {
+            write!(f, "{}", r)?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// Create the range of characters between two code points, inclusive, excluding the surrogate
+/// code points at either end. `None` is returned if the range contains no characters.
+#[inline]
+fn scalar_range(start: u32, end: u32) -> Option<CharRange> {
+    let start = if start > USV_END_1 as u32 && start < USV_START_2 as u32 {
+        USV_START_2
+    } else {
+        char::from_u32(start)?
+    };
+    let end = if end > USV_END_1 as u32 && end < USV_START_2 as u32 {
+        USV_END_1
+    } else {
+        char::from_u32(end)?
+    };
+
+    if start <= end {
+        Some(CharRange::new(start, end))
+    } else {
+        None
+    }
+}
+
+/// Write a character of a bracketed character class, escaping it if necessary. Other characters
+/// are written as themselves, since the parser doesn't support numeric escapes.
+#[inline]
+fn write_class_char(f: &mut fmt::Formatter<'_>, c: char) -> fmt::Result {
+    match c {
+        '\\' | '[' | ']' | '-' | '^' => write!(f, "\\{}", c),
+        '\n' => write!(f, "\\n"),
+        '\t' => write!(f, "\\t"),
+        '\r' => write!(f, "\\r"),
+        c => write!(f, "{}", c),
+    }
+}
+
+impl Disjoin for CharClass {
+    /// Create a set of disjoint CharClass from a set of CharClass. A range of the result may span
+    /// the surrogate code points, which are then excluded from its ends.
+    #[inline]
+    fn disjoin(vec: Vec<&Self>) -> Vec<Self> {
+        let ranges = vec
+            .iter()
+            .flat_map(|cc| cc.nonempty_ranges())
+            .map(|r| (r.start as u32, r.end as u32));
+        disjoint_pieces(ranges)
             .into_iter()
-            .filter_map(|(x, c)| {
-                let ret = if x > prev && count != 0 {
-                    let ret = CharRange::new(prev.try_into().unwrap(), (x - 1).try_into().unwrap());
-                    Some(ret.into())
-                } else {
-                    None
-                };
-                prev = x;
-                count += c;
-                ret
-            })
+            .filter_map(|(start, end)| scalar_range(start, end).map(CharClass::from))
             .collect()
     }
 
@@ -334,9 +738,216 @@ impl Disjoin for CharClass {
     }
 }
 
+/// Split the union of ranges of codes into disjoint pieces, so that each range is the union of
+/// some of the pieces. Algorithm inspired by [this Stack Overflow
+/// answer](https://stackoverflow.com/a/55482655/8955108).
+#[inline]
+fn disjoint_pieces<I>(ranges: I) -> Vec<(u32, u32)>
+where
+    I: IntoIterator<Item = (u32, u32)>,
+{
+    let mut bounds: Vec<_> = ranges
+        .into_iter()
+        .flat_map(|(start, end)| vec![(start, 1), (end + 1, -1)])
+        .collect();
+    bounds.sort_by_key(|a| a.0);
+
+    let mut prev = 0;
+    let mut count = 0;
+    bounds
+        .into_iter()
+        .filter_map(|(x, c)| {
+            let piece = if x > prev && count != 0 {
+                Some((prev, x - 1))
+            } else {
+                None
+            };
+            prev = x;
+            count += c;
+            piece
+        })
+        .collect()
+}
+
+impl SymbolRanges for CharClass {
+    #[inline]
+    fn code_ranges(&self) -> Vec<(u32, u32)> {
+        self.nonempty_ranges()
+            .map(|r| (r.start as u32, r.end as u32))
+            .collect()
+    }
+
+    /// The number of characters in the class, which leaves out the surrogate codes.
+    #[inline]
+    fn symbol_count(&self) -> u128 {
+        self.len() as u128
+    }
+}
+
+impl SymbolSet for CharClass {
+    #[inline]
+    fn complement_of(symbols: &[&Self]) -> Self {
+        let mut union = CharClass::new();
+        for cc in symbols {
+            union.copy_from(cc);
+        }
+
+        if union.is_empty() {
+            CharClass::all()
+        } else {
+            union.complement()
+        }
+    }
+
+    #[inline]
+    fn intersection(&self, other: &Self) -> Self {
+        CharClass::intersection(self, other)
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+}
+
+/// A partition of the characters contained by a set of classes into disjoint blocks, where two
+/// characters are in the same block if and only if they are contained by the same classes. The
+/// blocks form a compressed alphabet for automata whose transitions are labeled with the classes,
+/// since no transition can distinguish between characters of the same block.
+///
+/// ```
+/// use regexp2::class::{CharClass, CharRange, Partition};
+///
+/// let lower = CharClass::from(CharRange::new('a', 'z'));
+/// let vowels = CharClass::from(vec!['a', 'e', 'i', 'o', 'u']);
+/// let partition = Partition::new(vec![&lower, &vowels]);
+///
+/// // The vowels, and the other lowercase letters.
+/// assert_eq!(2, partition.len());
+/// assert_eq!(partition.block_of('a'), partition.block_of('u'));
+/// assert_ne!(partition.block_of('a'), partition.block_of('b'));
+/// assert_eq!(None, partition.block_of('A'));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Partition {
+    blocks: Vec<CharClass>,
+    /// The disjoint ranges of all of the blocks in ascending order, and the block of each.
+    ranges: Vec<(CharRange, usize)>,
+    /// The blocks that make up each class.
+    members: Vec<Vec<usize>>,
+}
+
+impl Partition {
+    /// Partition the characters contained by any of the classes.
+    #[inline]
+    pub fn new<'a, I>(classes: I) -> Self
+    where
+        I: IntoIterator<Item = &'a CharClass>,
+    {
+        let classes: Vec<_> = classes.into_iter().collect();
+
+        let mut blocks: Vec<CharClass> = Vec::new();
+        let mut ranges = Vec::new();
+        let mut members = vec![Vec::new(); classes.len()];
+        let mut signatures = HashMap::new();
+        // Every character of a range produced by `disjoin` is contained by the same classes.
+        for piece in CharClass::disjoin(classes.clone()) {
+            let range = piece.iter().next().unwrap().clone();
+            let signature: Vec<_> = (0..classes.len())
+                .filter(|&i| classes[i].contains(range.start))
+                .collect();
+            let block = *signatures.entry(signature).or_insert_with_key(|signature| {
+                for &i in signature {
+                    members[i].push(blocks.len());
+                }
+                blocks.push(CharClass::new());
+                blocks.len() - 1
+            });
+            blocks[block].add_range(range.clone());
+            ranges.push((range, block));
+        }
+
+        Self {
+            blocks,
+            ranges,
+            members,
+        }
+    }
+
+    /// The disjoint blocks of the partition.
+    #[inline]
+    pub fn blocks(&self) -> &[CharClass] {
+        &self.blocks
+    }
+
+    /// The number of blocks.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Find the index of the block containing the character, or `None` if it isn't contained by
+    /// any of the classes.
+    #[inline]
+    pub fn block_of(&self, c: char) -> Option<usize> {
+        let i = match self.ranges.binary_search_by(|(r, _)| r.start.cmp(&c)) {
+            Ok(i) => i,
+            Err(0) => return None,
+            Err(i) => i - 1,
+        };
+        let (range, block) = &self.ranges[i];
+        if range.contains(c) {
+            Some(*block)
+        } else {
+            None
+        }
+    }
+
+    /// The indices of the blocks whose union is the class at the given index of the partitioned
+    /// classes.
+    #[inline]
+    pub fn blocks_of_class(&self, class: usize) -> &[usize] {
+        &self.members[class]
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde_crate::Serialize for CharClass {
+    /// Serialize the character class as a sequence of its ranges.
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde_crate::Serializer,
+    {
+        serializer.collect_seq(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde_crate::Deserialize<'de> for CharClass {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde_crate::Deserializer<'de>,
+    {
+        let ranges: Vec<CharRange> = serde_crate::Deserialize::deserialize(deserializer)?;
+        Ok(ranges.into())
+    }
+}
+
 /// A range of characters representing all characters from the lower bound to the upper bound,
 /// inclusive.
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct CharRange {
     pub start: char,
     pub end: char,
@@ -355,6 +966,38 @@ impl CharRange {
         CharRange { start: c, end: c }
     }
 
+    /// Determine if the range contains no characters, because its lower bound is greater than its
+    /// upper bound.
+    #[inline]
+    pub fn is_inverted(&self) -> bool {
+        self.start > self.end
+    }
+
+    /// The number of characters in the range. Surrogate code points aren't characters, so they
+    /// aren't counted.
+    #[inline]
+    pub fn len(&self) -> usize {
+        if self.is_inverted() {
+            0
+        } else if self.start <= USV_END_1 && self.end >= USV_START_2 {
+            (self.end as u32 - self.start as u32 + 1 - SURROGATES) as usize
+        } else {
+            (self.end as u32 - self.start as u32 + 1) as usize
+        }
+    }
+
+    /// Determine if the range contains no characters, which is only the case if it is inverted.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.is_inverted()
+    }
+
+    /// Iterate over the characters in the range in ascending order.
+    #[inline]
+    pub fn chars(&self) -> RangeInclusive<char> {
+        self.start..=self.end
+    }
+
     /// Determine if the given character is within the range.
     #[inline]
     pub fn contains(&self, c: char) -> bool {
@@ -392,6 +1035,9 @@ impl CharRange {
 
             let r2 = Self::new(USV_START_1, USV_END_1);
             ranges.push(r2);
+        } else if self.start == USV_START_2 {
+            let r = Self::new(USV_START_1, USV_END_1);
+            ranges.push(r);
         } else if self.start > USV_START_1 {
             let r = Self::new(USV_START_1, shift_char(self.start, false));
             ranges.push(r);
@@ -403,6 +1049,9 @@ impl CharRange {
 
             let r2 = Self::new(USV_START_2, USV_END_2);
             ranges.push(r2);
+        } else if self.end == USV_END_1 {
+            let r = Self::new(USV_START_2, USV_END_2);
+            ranges.push(r);
         } else if self.end < USV_END_2 {
             let r = Self::new(shift_char(self.end, true), USV_END_2);
             ranges.push(r);
@@ -413,9 +1062,21 @@ impl CharRange {
 }
 
 impl MergeSetValue<char> for CharRange {
+    /// Ranges can be merged if they overlap or are adjacent. Ranges on either side of the
+    /// surrogate code points are adjacent, since there are no characters between them.
     #[inline]
-    fn intersects_with(&self, other: &Self) -> bool {
-        self.intersection(other).is_some()
+    fn can_merge_with(&self, other: &Self) -> bool {
+        let (lower, upper) = if self.start <= other.start {
+            (self, other)
+        } else {
+            (other, self)
+        };
+
+        !self.is_inverted()
+            && !other.is_inverted()
+            && (upper.start <= lower.end
+                || upper.start as u32 == lower.end as u32 + 1
+                || (lower.end == USV_END_1 && upper.start == USV_START_2))
     }
 
     #[inline]
@@ -447,3 +1108,362 @@ impl From<(char, char)> for CharRange {
         Self::new(range.0, range.1)
     }
 }
+
+impl fmt::Display for CharRange {
+    /// Format the range as it is written inside a bracketed class, as in `a-z`, or as the single
+    /// character it contains.
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_class_char(f, self.start)?;
+        if self.end != self.start {
+            write!(f, "-")?;
+            write_class_char(f, self.end)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for CharRange {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CharRange({})", self)
+    }
+}
+
+/// A set of byte ranges, the counterpart of [`CharClass`] for automata over bytes. The transitions
+/// of an automaton compiled to match bytes are labeled with byte classes, so that a range of bytes
+/// takes a single transition rather than one for each byte.
+///
+/// ```
+/// use regexp2::class::{ByteClass, ByteRange};
+///
+/// let class: ByteClass = vec![ByteRange::new(b'a', b'f'), ByteRange::new(b'0', b'9')].into();
+/// assert!(class.contains(b'c'));
+/// assert!(!class.contains(b'g'));
+/// assert_eq!(16, class.len());
+/// assert_eq!("[0-9a-f]", class.to_string());
+/// assert_eq!(240, class.complement().len());
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct ByteClass {
+    /// The disjoint ranges of the class, in ascending order.
+    pub ranges: MergeSet<u8, ByteRange>,
+}
+
+impl ByteClass {
+    /// Create an empty byte class.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            ranges: MergeSet::new(),
+        }
+    }
+
+    /// Create a byte class of all bytes.
+    #[inline]
+    pub fn all() -> Self {
+        ByteRange::new(0, u8::MAX).into()
+    }
+
+    /// Determine if the byte is within any of the ranges of the class.
+    #[inline]
+    pub fn contains(&self, b: u8) -> bool {
+        matches!(self.ranges.iter_to(&b).next(), Some(r) if r.contains(b))
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Add a range to the class. An inverted range, whose start is greater than its end, contains
+    /// no bytes and is ignored.
+    #[inline]
+    pub fn add_range(&mut self, range: ByteRange) {
+        if range.start <= range.end {
+            self.ranges.insert(range);
+        }
+    }
+
+    /// Return the bytes in both this class and `other`.
+    #[inline]
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.iter()
+            .flat_map(|r| other.iter().filter_map(move |o| r.intersection(o)))
+            .collect()
+    }
+
+    /// Return the bytes not in the class.
+    #[inline]
+    pub fn complement(&self) -> Self {
+        let mut complement = ByteClass::new();
+        // The lowest byte that may not be in the class.
+        let mut start: u16 = 0;
+        for r in self {
+            if r.start as u16 > start {
+                complement.add_range(ByteRange::new(start as u8, r.start - 1));
+            }
+            start = r.end as u16 + 1;
+        }
+        if start <= u8::MAX as u16 {
+            complement.add_range(ByteRange::new(start as u8, u8::MAX));
+        }
+        complement
+    }
+
+    /// Iterate over the ranges of the class in ascending order.
+    #[inline]
+    pub fn iter(&self) -> mergeset::Iter<'_, u8, ByteRange> {
+        self.ranges.iter()
+    }
+
+    /// Iterate over the bytes in the class in ascending order.
+    #[inline]
+    pub fn bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.iter().flat_map(|r| r.start..=r.end)
+    }
+
+    /// The number of bytes in the class.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.iter().map(ByteRange::len).sum()
+    }
+}
+
+impl From<ByteRange> for ByteClass {
+    /// Create a byte class with a single range.
+    #[inline]
+    fn from(range: ByteRange) -> Self {
+        let mut class = ByteClass::new();
+        class.add_range(range);
+        class
+    }
+}
+
+impl From<u8> for ByteClass {
+    #[inline]
+    fn from(b: u8) -> Self {
+        ByteRange::new(b, b).into()
+    }
+}
+
+impl From<Vec<ByteRange>> for ByteClass {
+    #[inline]
+    fn from(vec: Vec<ByteRange>) -> Self {
+        vec.into_iter().collect()
+    }
+}
+
+impl Extend<ByteRange> for ByteClass {
+    #[inline]
+    fn extend<I: IntoIterator<Item = ByteRange>>(&mut self, iter: I) {
+        for r in iter {
+            self.add_range(r);
+        }
+    }
+}
+
+impl iter::FromIterator<ByteRange> for ByteClass {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = ByteRange>>(iter: I) -> Self {
+        let mut class = Self::new();
+        class.extend(iter);
+        class
+    }
+}
+
+impl<'a> IntoIterator for &'a ByteClass {
+    type Item = &'a ByteRange;
+    type IntoIter = mergeset::Iter<'a, u8, ByteRange>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl PartialEq<u8> for ByteClass {
+    #[inline]
+    fn eq(&self, other: &u8) -> bool {
+        self.contains(*other)
+    }
+}
+
+impl fmt::Display for ByteClass {
+    /// Format the byte class like a character class, with bytes outside of printable ASCII
+    /// written as hexadecimal escapes, as in `[\x00-\x1F]`.
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.ranges.iter().next() {
+            Some(r) if self.ranges.len() == 1 && r.start == r.end => write!(f, "{}", r),
+            _ => {
+                write!(f, "[")?;
+                for r in self {
+                    write!(f, "{}", r)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ByteClass {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ByteClass({})", self)
+    }
+}
+
+impl Disjoin for ByteClass {
+    #[inline]
+    fn disjoin(vec: Vec<&Self>) -> Vec<Self> {
+        let ranges = vec
+            .iter()
+            .flat_map(|class| class.iter())
+            .map(|r| (r.start as u32, r.end as u32));
+        disjoint_pieces(ranges)
+            .into_iter()
+            .map(|(start, end)| ByteRange::new(start as u8, end as u8).into())
+            .collect()
+    }
+
+    #[inline]
+    fn contains(&self, other: &Self) -> bool {
+        !self.intersection(other).is_empty()
+    }
+}
+
+impl SymbolRanges for ByteClass {
+    #[inline]
+    fn code_ranges(&self) -> Vec<(u32, u32)> {
+        self.iter()
+            .map(|r| (r.start as u32, r.end as u32))
+            .collect()
+    }
+}
+
+impl SymbolSet for ByteClass {
+    #[inline]
+    fn complement_of(symbols: &[&Self]) -> Self {
+        symbols
+            .iter()
+            .flat_map(|class| class.iter().copied())
+            .collect::<ByteClass>()
+            .complement()
+    }
+
+    #[inline]
+    fn intersection(&self, other: &Self) -> Self {
+        ByteClass::intersection(self, other)
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+}
+
+/// A range of bytes representing all bytes from the lower bound to the upper bound, inclusive.
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+pub struct ByteRange {
+    pub start: u8,
+    pub end: u8,
+}
+
+impl ByteRange {
+    /// Create a new byte range with the given bounds.
+    #[inline]
+    pub fn new(start: u8, end: u8) -> Self {
+        ByteRange { start, end }
+    }
+
+    /// Determine if the given byte is within the range.
+    #[inline]
+    pub fn contains(&self, b: u8) -> bool {
+        self.start <= b && b <= self.end
+    }
+
+    /// The number of bytes in the range.
+    #[inline]
+    pub fn len(&self) -> usize {
+        (self.end as usize + 1).saturating_sub(self.start as usize)
+    }
+
+    /// Determine if the range contains no bytes, because its start is greater than its end.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.start > self.end
+    }
+
+    /// Return the bytes in both ranges, if any.
+    #[inline]
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let range = Self::new(
+            cmp::max(self.start, other.start),
+            cmp::min(self.end, other.end),
+        );
+        if range.is_empty() {
+            None
+        } else {
+            Some(range)
+        }
+    }
+}
+
+impl MergeSetValue<u8> for ByteRange {
+    /// Ranges can be merged if they overlap or are adjacent.
+    #[inline]
+    fn can_merge_with(&self, other: &Self) -> bool {
+        let (lower, upper) = if self.start <= other.start {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        upper.start as u16 <= lower.end as u16 + 1
+    }
+
+    #[inline]
+    fn union(&self, other: &Self) -> Self {
+        Self::new(
+            cmp::min(self.start, other.start),
+            cmp::max(self.end, other.end),
+        )
+    }
+
+    #[inline]
+    fn key(&self) -> u8 {
+        self.start
+    }
+}
+
+impl fmt::Display for ByteRange {
+    /// Format the range as it is written inside a bracketed class, as in `a-z` or `\x80-\xBF`, or
+    /// as the single byte it contains.
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_class_byte(f, self.start)?;
+        if self.end != self.start {
+            write!(f, "-")?;
+            write_class_byte(f, self.end)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ByteRange {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ByteRange({})", self)
+    }
+}
+
+/// Write a byte of a bracketed byte class, as a character if it is printable ASCII, or as a
+/// hexadecimal escape otherwise.
+#[inline]
+fn write_class_byte(f: &mut fmt::Formatter<'_>, b: u8) -> fmt::Result {
+    if b.is_ascii_graphic() || b == b' ' {
+        write_class_char(f, b as char)
+    } else {
+        write!(f, "\\x{:02X}", b)
+    }
+}
@@ -0,0 +1,285 @@
+//! Matching by Brzozowski derivatives, which evaluates an [`Expr`] directly without constructing an
+//! automaton.
+
+use crate::ast::{BinaryOp, CompileError, Expr, UnaryOp};
+use crate::class::CharClass;
+
+use std::collections::HashMap;
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// A matcher that evaluates input by repeatedly taking the derivative of an expression with respect
+/// to each input character. The derivative of an expression with respect to `c` matches a string
+/// `s` if and only if the expression matches `cs`, so the input is accepted if the final derivative
+/// matches the empty string.
+///
+/// Derivatives are simplified as they are constructed and memoized, so repeated matching reuses
+/// previously computed derivatives much like a [`LazyDFA`](automata::LazyDFA) reuses its states.
+/// This avoids the up-front cost of constructing an automaton for one-off matches. The memoized
+/// terms are behind a read-write lock, so a matcher can be shared between threads. Memoized
+/// derivatives are looked up under the read lock, and the write lock is only taken for the one
+/// character whose derivative is missing.
+///
+/// ```
+/// use regexp2::derivative::DerivativeMatcher;
+/// use regexp2::parser::ast::ASTParser;
+///
+/// let expr = ASTParser::<char>::new().parse("(a|b)*abb").unwrap();
+/// let matcher = DerivativeMatcher::new(&expr).unwrap();
+/// assert!(matcher.is_match("babb".chars()));
+/// assert!(!matcher.is_match("abba".chars()));
+/// ```
+#[derive(Debug)]
+pub struct DerivativeMatcher {
+    /// The term of the expression.
+    start: usize,
+    terms: RwLock<Terms>,
+}
+
+/// An expression in the normal form produced by the smart constructors of [`Terms`]. Subterms are
+/// referred to by their index in the term table, so structurally equal terms have equal indices.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Term {
+    /// Matches nothing.
+    Null,
+    /// Matches only the empty string.
+    Epsilon,
+    /// Matches any single character in a non-empty class.
+    Class(CharClass),
+    /// A concatenation, associated to the right.
+    Concat(usize, usize),
+    /// An alternation of at least two terms, none of which are alternations, sorted and without
+    /// duplicates.
+    Alternate(Vec<usize>),
+    /// Zero or more repetitions of a term.
+    Star(usize),
+}
+
+/// The index of [`Term::Null`].
+const NULL: usize = 0;
+/// The index of [`Term::Epsilon`].
+const EPSILON: usize = 1;
+
+#[derive(Debug)]
+struct Terms {
+    terms: Vec<Term>,
+    /// Whether each term matches the empty string.
+    nullable: Vec<bool>,
+    /// The index of each term.
+    indices: HashMap<Term, usize>,
+    /// The derivative of each term with respect to a character.
+    derivatives: HashMap<(usize, char), usize>,
+}
+
+impl DerivativeMatcher {
+    /// Create a matcher for an expression. Anchors are not supported.
+    #[inline]
+    pub fn new(expr: &Expr) -> Result<Self, CompileError> {
+        let mut terms = Terms::new();
+        let start = terms.compile(expr)?;
+        Ok(Self {
+            start,
+            terms: RwLock::new(terms),
+        })
+    }
+
+    /// Determine if the entire input is matched by the expression.
+    #[inline]
+    pub fn is_match<I>(&self, input: I) -> bool
+    where
+        I: IntoIterator<Item = char>,
+    {
+        let mut term = self.start;
+        for c in input {
+            term = self.derivative(term, c);
+            if term == NULL {
+                return false;
+            }
+        }
+        self.read_terms().nullable[term]
+    }
+
+    /// The number of distinct terms constructed so far, including the expression and all of the
+    /// derivatives that have been taken.
+    #[inline]
+    pub fn total_terms(&self) -> usize {
+        self.read_terms().terms.len()
+    }
+
+    /// Get the derivative of a term with respect to a character, taking the write lock only if it
+    /// hasn't been memoized. Terms are never removed, so the term stays valid between locks.
+    #[inline]
+    fn derivative(&self, term: usize, c: char) -> usize {
+        let memoized = self.read_terms().derivatives.get(&(term, c)).copied();
+        match memoized {
+            Some(derivative) => derivative,
+            None => self.write_terms().derivative(term, c),
+        }
+    }
+
+    /// Lock the term table for reading. Terms are only added once they are complete, so the table
+    /// is still consistent if a search panicked while holding the write lock.
+    #[inline]
+    fn read_terms(&self) -> RwLockReadGuard<'_, Terms> {
+        self.terms.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    #[inline]
+    fn write_terms(&self) -> RwLockWriteGuard<'_, Terms> {
+        self.terms.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Terms {
+    #[inline]
+    fn new() -> Self {
+        let mut terms = Self {
+            terms: Vec::new(),
+            nullable: Vec::new(),
+            indices: HashMap::new(),
+            derivatives: HashMap::new(),
+        };
+        terms.intern(Term::Null);
+        terms.intern(Term::Epsilon);
+        terms
+    }
+
+    #[inline]
+    fn intern(&mut self, term: Term) -> usize {
+        if let Some(&index) = self.indices.get(&term) {
+            return index;
+        }
+
+        let nullable = match &term {
+            Term::Null | Term::Class(_) => false,
+            Term::Epsilon | Term::Star(_) => true,
+            Term::Concat(lhs, rhs) => self.nullable[*lhs] && self.nullable[*rhs],
+            Term::Alternate(alts) => alts.iter().any(|&t| self.nullable[t]),
+        };
+        let index = self.terms.len();
+        self.terms.push(term.clone());
+        self.nullable.push(nullable);
+        self.indices.insert(term, index);
+        index
+    }
+
+    #[inline]
+    fn compile(&mut self, expr: &Expr) -> Result<usize, CompileError> {
+        let term = match expr {
+            Expr::Unary(op, operand) => {
+                let operand = self.compile(operand)?;
+                match op {
+                    UnaryOp::Star => self.star(operand),
+                    UnaryOp::Plus => {
+                        let star = self.star(operand);
+                        self.concat(operand, star)
+                    }
+                    UnaryOp::Optional => self.alternate(operand, EPSILON),
+                }
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = self.compile(lhs)?;
+                let rhs = self.compile(rhs)?;
+                match op {
+                    BinaryOp::Concat => self.concat(lhs, rhs),
+                    BinaryOp::Alternate => self.alternate(lhs, rhs),
+                }
+            }
+            Expr::Atom(class) => self.class(class.clone()),
+            Expr::Group(inner, _) | Expr::Spanned(inner, _) => self.compile(inner)?,
+            Expr::Empty => EPSILON,
+            Expr::Anchor(anchor) => return Err(CompileError::UnsupportedAnchor(*anchor)),
+        };
+        Ok(term)
+    }
+
+    #[inline]
+    fn class(&mut self, class: CharClass) -> usize {
+        if class.is_empty() {
+            NULL
+        } else {
+            self.intern(Term::Class(class))
+        }
+    }
+
+    #[inline]
+    fn concat(&mut self, lhs: usize, rhs: usize) -> usize {
+        match (&self.terms[lhs], rhs) {
+            (Term::Null, _) | (_, NULL) => NULL,
+            (Term::Epsilon, _) => rhs,
+            (_, EPSILON) => lhs,
+            (&Term::Concat(first, rest), _) => {
+                let rest = self.concat(rest, rhs);
+                self.intern(Term::Concat(first, rest))
+            }
+            _ => self.intern(Term::Concat(lhs, rhs)),
+        }
+    }
+
+    #[inline]
+    fn alternate(&mut self, lhs: usize, rhs: usize) -> usize {
+        let mut alts = Vec::new();
+        for &term in &[lhs, rhs] {
+            match &self.terms[term] {
+                Term::Null => {}
+                Term::Alternate(inner) => alts.extend_from_slice(inner),
+                _ => alts.push(term),
+            }
+        }
+        alts.sort_unstable();
+        alts.dedup();
+
+        match alts.len() {
+            0 => NULL,
+            1 => alts[0],
+            _ => self.intern(Term::Alternate(alts)),
+        }
+    }
+
+    #[inline]
+    fn star(&mut self, operand: usize) -> usize {
+        match self.terms[operand] {
+            Term::Null | Term::Epsilon => EPSILON,
+            Term::Star(_) => operand,
+            _ => self.intern(Term::Star(operand)),
+        }
+    }
+
+    /// Get the derivative of a term with respect to a character.
+    #[inline]
+    fn derivative(&mut self, term: usize, c: char) -> usize {
+        if let Some(&derivative) = self.derivatives.get(&(term, c)) {
+            return derivative;
+        }
+
+        let derivative = match self.terms[term].clone() {
+            Term::Null | Term::Epsilon => NULL,
+            Term::Class(class) => {
+                if class.contains(c) {
+                    EPSILON
+                } else {
+                    NULL
+                }
+            }
+            Term::Concat(lhs, rhs) => {
+                let dlhs = self.derivative(lhs, c);
+                let derivative = self.concat(dlhs, rhs);
+                if self.nullable[lhs] {
+                    let drhs = self.derivative(rhs, c);
+                    self.alternate(derivative, drhs)
+                } else {
+                    derivative
+                }
+            }
+            Term::Alternate(alts) => alts.into_iter().fold(NULL, |acc, alt| {
+                let dalt = self.derivative(alt, c);
+                self.alternate(acc, dalt)
+            }),
+            Term::Star(operand) => {
+                let doperand = self.derivative(operand, c);
+                self.concat(doperand, term)
+            }
+        };
+        self.derivatives.insert((term, c), derivative);
+        derivative
+    }
+}
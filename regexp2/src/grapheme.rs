@@ -0,0 +1,128 @@
+//! Regular expressions that match the extended grapheme clusters of the input, rather than its
+//! characters. Requires the `grapheme` feature.
+//!
+//! The input is split into clusters, each of which is read by the automaton as a single symbol:
+//! `.` matches a whole cluster, quantifiers count clusters, and matches start and end only at
+//! the boundaries of clusters, so they never split an emoji or a character from its combining
+//! marks. A class matches a cluster if it contains every character of the cluster, so `.`
+//! matches any cluster without a line terminator. The pattern is still read a character at a
+//! time, so a cluster of several characters written in the pattern never matches.
+//!
+//! ```
+//! use regexp2::RegExpBuilder;
+//!
+//! let re = RegExpBuilder::new("a.c").build_grapheme_regexp().unwrap();
+//! assert!(re.is_match("a👍🏽c"));
+//! assert!(re.is_match("ae\u{301}c"));
+//!
+//! // Matching characters splits the cluster instead.
+//! let re = RegExpBuilder::new("a.c").build().unwrap();
+//! assert!(!re.is_match("ae\u{301}c"));
+//! ```
+
+use crate::builder::RegExpBuilder;
+use crate::class::CharClass;
+use crate::regexp::{self, Error, Match, MatchKind};
+
+use std::fmt;
+use std::str::FromStr;
+
+use automata::NFA;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// An extended grapheme cluster of the input, read as a single symbol.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Grapheme<'t>(pub &'t str);
+
+impl PartialEq<Grapheme<'_>> for CharClass {
+    #[inline]
+    fn eq(&self, other: &Grapheme<'_>) -> bool {
+        other.0.chars().all(|c| self.contains(c))
+    }
+}
+
+/// Split the input into its extended grapheme clusters.
+#[inline]
+pub fn graphemes(input: &str) -> impl Iterator<Item = Grapheme<'_>> + Clone {
+    input.graphemes(true).map(Grapheme)
+}
+
+/// A compiled regular expression that matches extended grapheme clusters. See the [module
+/// documentation](self) for how classes are interpreted over clusters.
+#[derive(Debug)]
+pub struct RegExp {
+    inner: regexp::RegExp<NFA<CharClass>>,
+}
+
+impl RegExp {
+    /// Compile a regular expression that matches extended grapheme clusters. Use
+    /// [`RegExpBuilder::build_grapheme_regexp`] to configure the compilation.
+    #[inline]
+    pub fn new(expr: &'_ str) -> Result<Self, Error<'_>> {
+        RegExpBuilder::new(expr).build_grapheme_regexp()
+    }
+
+    #[inline]
+    pub(crate) fn from_inner(inner: regexp::RegExp<NFA<CharClass>>) -> Self {
+        Self { inner }
+    }
+
+    /// The pattern the regular expression was compiled from.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.inner.as_str()
+    }
+
+    /// Determine if the clusters of the input string are within the language described by the
+    /// regular expression.
+    #[inline]
+    pub fn is_match(&self, input: &str) -> bool {
+        self.inner.nfa().is_match(graphemes(input))
+    }
+
+    /// Find the leftmost match in the input string.
+    #[inline]
+    pub fn find<'t>(&self, input: &'t str) -> Option<Match<'t>> {
+        self.find_at(input, 0)
+    }
+
+    /// Find the leftmost match that starts at or after the byte offset `start` of the input
+    /// string. The input is split into clusters from `start` onwards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start` is greater than the length of the input or not on a character
+    /// boundary.
+    #[inline]
+    pub fn find_at<'t>(&self, input: &'t str, start: usize) -> Option<Match<'t>> {
+        let offsets: Vec<_> = input[start..]
+            .grapheme_indices(true)
+            .map(|(i, _)| start + i)
+            .collect();
+        let nfa = self.inner.nfa();
+        let clusters = graphemes(&input[start..]);
+        let m = match self.inner.match_kind() {
+            MatchKind::LeftmostLongest => nfa.find_unanchored(clusters),
+            MatchKind::LeftmostFirst => nfa.find_leftmost_first(clusters),
+        }?;
+
+        let offset = |pos: usize| offsets.get(pos).copied().unwrap_or(input.len());
+        Some(Match::new(input, offset(m.start()), offset(m.end())))
+    }
+}
+
+impl FromStr for RegExp {
+    type Err = Error<'static>;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RegExp::new(s).map_err(Error::into_owned)
+    }
+}
+
+impl fmt::Display for RegExp {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
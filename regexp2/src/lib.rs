@@ -22,14 +22,34 @@
 #![deny(rust_2018_idioms)]
 #![deny(future_incompatible)]
 
+mod builder;
 mod regexp;
+mod stream;
 
-mod ast;
+#[cfg(feature = "unicode-case")]
+mod case_folding;
+#[cfg(feature = "unicode-properties")]
+mod general_category;
 mod mergeset;
+mod prefilter;
+#[cfg(feature = "unicode-properties")]
+mod property_values;
 mod ranges;
+#[cfg(feature = "unicode-properties")]
+mod script;
 
+pub mod ast;
+pub mod bytes;
 pub mod class;
+pub mod derivative;
+#[cfg(feature = "grapheme")]
+pub mod grapheme;
+pub mod meta;
 pub mod parser;
+pub mod sample;
+pub mod utf8;
 
 pub use automata;
+pub use builder::*;
 pub use regexp::*;
+pub use stream::*;
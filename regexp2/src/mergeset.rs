@@ -7,15 +7,17 @@ pub trait Key: Clone + Ord {}
 impl<T> Key for T where T: Clone + Ord {}
 
 pub trait Value<K>: Clone {
-    fn intersects_with(&self, other: &Self) -> bool;
+    /// Determine if the two elements can be replaced by their union, because they overlap or are
+    /// adjacent.
+    fn can_merge_with(&self, other: &Self) -> bool;
 
     fn union(&self, other: &Self) -> Self;
 
     fn key(&self) -> K;
 }
 
-// A data structure to maintain a minimal set of disjoint elements. It is implemented using a
-// binary search tree.
+// A data structure to maintain a minimal set of disjoint elements, where no two elements can be
+// merged. It is implemented using a binary search tree.
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct MergeSet<K, V>
 where
@@ -78,12 +80,13 @@ where
     pub fn insert(&mut self, mut item: V) {
         let mut priority = item.key();
 
-        // Check for intersection with predecessor.
+        // Check for intersection with predecessor. No element before it can intersect, since the
+        // elements are disjoint and sorted.
         let pred = self.tree.get_prev(&priority);
         if let Some((pred_pri, pred_v)) = pred {
             // If intersecting, merge and remove predecessor.
             // Set item's priority to that of predecessor.
-            if item.intersects_with(pred_v) {
+            if item.can_merge_with(pred_v) {
                 item = item.union(pred_v);
                 priority = pred_pri.clone();
 
@@ -91,15 +94,16 @@ where
             }
         }
 
-        // Check for intersection with successor.
-        let succ = self.tree.get_next(&priority);
-        if let Some((succ_pri, succ_v)) = succ {
-            // If intersecting, merge and remove successor.
-            if item.intersects_with(succ_v) {
-                item = item.union(succ_v);
-                let del_pri = succ_pri.clone();
-                self.tree.remove(&del_pri);
+        // Merge and remove successors until one doesn't intersect, since the item may span
+        // several of them.
+        while let Some((succ_pri, succ_v)) = self.tree.get_next(&priority) {
+            if !item.can_merge_with(succ_v) {
+                break;
             }
+
+            item = item.union(succ_v);
+            let del_pri = succ_pri.clone();
+            self.tree.remove(&del_pri);
         }
 
         self.tree.insert(priority, item);
@@ -110,6 +114,11 @@ where
         self.tree.remove(&priority)
     }
 
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
     #[inline]
     pub fn is_empty(&self) -> bool {
         self.tree.is_empty()
@@ -119,6 +128,13 @@ where
     pub fn iter(&self) -> Iter<'_, K, V> {
         self.tree.iter().into()
     }
+
+    /// Iterate in descending order over the elements with keys less than or equal to the given
+    /// key, which takes logarithmic time to start.
+    #[inline]
+    pub fn iter_to(&self, key: &K) -> impl Iterator<Item = &V> {
+        self.tree.range(..=key.clone()).rev().map(|(_, v)| v)
+    }
 }
 
 impl<'a, K, V> IntoIterator for &'a MergeSet<K, V>
@@ -135,7 +151,7 @@ where
     }
 }
 
-impl<'a, K, V> IntoIterator for MergeSet<K, V>
+impl<K, V> IntoIterator for MergeSet<K, V>
 where
     K: Key,
     V: Value<K>,
@@ -0,0 +1,315 @@
+//! A backend that chooses how to search each input string.
+//!
+//! Each of the other backends is best suited to some regular expressions and inputs: a literal
+//! string is found fastest by a substring search, a small DFA is fastest to run but may be
+//! exponentially large, a bit-parallel matcher runs nearly as fast for short patterns, a lazy DFA
+//! avoids that cost but builds its states while searching, and a bounded backtracker or Pike VM
+//! needs no construction at all. [`Meta`] compiles the backends that apply to the regular
+//! expression, and picks one for each search based on the regular expression and the length of
+//! the input. See [`RegExpBuilder::build_meta`].
+
+use crate::class::CharClass;
+use crate::prefilter::Literals;
+use crate::regexp::{self, Engine, Match, MatchKind};
+
+use automata::backtrack::BoundedBacktracker;
+use automata::pikevm::PikeVM;
+use automata::shiftand::ShiftAnd;
+use automata::{DenseDFA, LazyDFA};
+
+#[cfg(doc)]
+use crate::RegExpBuilder;
+
+use std::ops::Range;
+
+/// The maximum number of states of the DFA compiled by [`Meta`], unless the strategy is
+/// overridden. Larger DFAs take too long to construct to be worth it, so the lazy DFA is used
+/// instead.
+pub(crate) const DFA_SIZE_LIMIT: usize = 1 << 10;
+
+/// The minimum number of states of the NFA for which [`Meta`] uses the Pike VM rather than the
+/// lazy DFA. The states of the lazy DFA are sets of NFA states, so for large NFAs, each new state
+/// is expensive to construct, and few fit in its cache.
+pub(crate) const PIKEVM_MIN_STATES: usize = 1 << 12;
+
+/// A way of searching input strings. See [`RegExpBuilder::strategy`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Strategy {
+    /// A substring search, for regular expressions that match a few literal strings.
+    Literal,
+    /// A DFA compiled ahead of time.
+    DFA,
+    /// A bit-parallel simulation of the NFA, for regular expressions with at most
+    /// [`MAX_POSITIONS`](automata::shiftand::MAX_POSITIONS) characters and classes. Unanchored
+    /// searches use it to rule out inputs without a match, and the lazy DFA to find the match.
+    ShiftAnd,
+    /// A DFA constructed on demand.
+    LazyDFA,
+    /// A simulation of the NFA that follows every path at once. Only used for the leftmost-first
+    /// match kind.
+    PikeVM,
+    /// A backtracking search of the NFA, for inputs short enough for it to remember the states it
+    /// has visited. Only used for the leftmost-first match kind.
+    Backtrack,
+}
+
+/// A backend that chooses a strategy for each search.
+#[derive(Debug)]
+pub struct Meta {
+    match_kind: MatchKind,
+    /// The strategy used whenever it applies, if overridden.
+    strategy: Option<Strategy>,
+    /// The strings matched by the regular expression, if it matches only a few.
+    literals: Option<Literals>,
+    dfa: Option<DenseDFA<CharClass>>,
+    shift_and: Option<ShiftAnd>,
+    lazy: LazyDFA<CharClass>,
+    pikevm: PikeVM<CharClass>,
+}
+
+impl Meta {
+    #[inline]
+    pub(crate) fn new(
+        match_kind: MatchKind,
+        strategy: Option<Strategy>,
+        literals: Option<Literals>,
+        dfa: Option<DenseDFA<CharClass>>,
+        shift_and: Option<ShiftAnd>,
+        lazy: LazyDFA<CharClass>,
+        pikevm: PikeVM<CharClass>,
+    ) -> Self {
+        Self {
+            match_kind,
+            strategy,
+            literals,
+            dfa,
+            shift_and,
+            lazy,
+            pikevm,
+        }
+    }
+
+    /// The strategy used to search for matches in an input string of `len` bytes.
+    #[inline]
+    pub fn strategy(&self, len: usize) -> Strategy {
+        self.choose(len, self.match_kind == MatchKind::LeftmostFirst)
+    }
+
+    /// Choose a strategy for an input string of `len` bytes. `simulate` is `true` if the Pike VM
+    /// and the backtracker can be used, which find leftmost-first matches and whole matches.
+    #[inline]
+    fn choose(&self, len: usize, simulate: bool) -> Strategy {
+        let applies = |strategy| match strategy {
+            Strategy::Literal => self.literals.is_some(),
+            Strategy::DFA => self.dfa.is_some(),
+            Strategy::ShiftAnd => self.shift_and.is_some(),
+            Strategy::LazyDFA => true,
+            Strategy::PikeVM => simulate,
+            Strategy::Backtrack => simulate && len <= self.backtracker().max_input_len(),
+        };
+
+        match self.strategy {
+            Some(strategy) if applies(strategy) => strategy,
+            _ => [
+                Strategy::Literal,
+                Strategy::DFA,
+                Strategy::Backtrack,
+                Strategy::ShiftAnd,
+                Strategy::PikeVM,
+            ]
+            .iter()
+            .copied()
+            .filter(|&strategy| {
+                strategy != Strategy::PikeVM || self.pikevm.nfa().total_states >= PIKEVM_MIN_STATES
+            })
+            .find(|&strategy| applies(strategy))
+            .unwrap_or(Strategy::LazyDFA),
+        }
+    }
+
+    /// The searcher used by [`Strategy::Literal`], which is only chosen if there is one.
+    #[inline]
+    fn literals(&self) -> &Literals {
+        self.literals
+            .as_ref()
+            .expect("the literal strategy requires literal strings")
+    }
+
+    /// The matcher used by [`Strategy::ShiftAnd`], which is only chosen if there is one.
+    #[inline]
+    fn shift_and(&self) -> &ShiftAnd {
+        self.shift_and
+            .as_ref()
+            .expect("the shift-and strategy requires a small enough regular expression")
+    }
+
+    #[inline]
+    fn backtracker(&self) -> BoundedBacktracker<'_, CharClass> {
+        BoundedBacktracker::new(self.pikevm.nfa())
+    }
+
+    /// The engine used by a strategy that runs a DFA.
+    #[inline]
+    fn automaton(&self, strategy: Strategy) -> &dyn Engine {
+        match (strategy, &self.dfa) {
+            (Strategy::DFA, Some(dfa)) => dfa,
+            _ => &self.lazy,
+        }
+    }
+}
+
+impl Engine for Meta {
+    #[inline]
+    fn is_match(&self, input: &str) -> bool {
+        match self.choose(input.len(), true) {
+            Strategy::Literal => self.literals().is_match(input.as_bytes()),
+            Strategy::ShiftAnd => self.shift_and().is_match(input.chars()),
+            Strategy::PikeVM => self.pikevm.captures_whole(input.chars()).is_some(),
+            Strategy::Backtrack => {
+                let chars: Vec<_> = input.chars().collect();
+                self.backtracker().captures_whole(&chars).is_some()
+            }
+            strategy => self.automaton(strategy).is_match(input),
+        }
+    }
+
+    #[inline]
+    fn find_at<'t>(&self, input: &'t str, start: usize, kind: MatchKind) -> Option<Match<'t>> {
+        let haystack = &input[start..];
+        match self.strategy(haystack.len()) {
+            Strategy::Literal => {
+                literal_match(input, self.literals().find_at(input.as_bytes(), start))
+            }
+            Strategy::ShiftAnd => {
+                self.shift_and().find_earliest_end(haystack.chars())?;
+                self.automaton(Strategy::LazyDFA)
+                    .find_at(input, start, kind)
+            }
+            Strategy::PikeVM => {
+                let slots = self.pikevm.captures_unanchored(haystack.chars())?;
+                Some(slots_match(input, start, &slots))
+            }
+            Strategy::Backtrack => {
+                let chars: Vec<_> = haystack.chars().collect();
+                let slots = self.backtracker().captures_unanchored(&chars)?;
+                Some(slots_match(input, start, &slots))
+            }
+            strategy => self.automaton(strategy).find_at(input, start, kind),
+        }
+    }
+
+    #[inline]
+    fn find_shortest_at<'t>(&self, input: &'t str, start: usize) -> Option<Match<'t>> {
+        match self.choose(input.len() - start, false) {
+            Strategy::Literal => literal_match(
+                input,
+                self.literals().find_shortest_at(input.as_bytes(), start),
+            ),
+            Strategy::ShiftAnd => {
+                self.shift_and().find_earliest_end(input[start..].chars())?;
+                self.automaton(Strategy::LazyDFA)
+                    .find_shortest_at(input, start)
+            }
+            strategy => self.automaton(strategy).find_shortest_at(input, start),
+        }
+    }
+
+    #[inline]
+    fn find_anchored_at<'t>(
+        &self,
+        input: &'t str,
+        start: usize,
+        kind: MatchKind,
+    ) -> Option<Match<'t>> {
+        let haystack = &input[start..];
+        match self.strategy(haystack.len()) {
+            Strategy::Literal => literal_match(
+                input,
+                self.literals().find_anchored_at(input.as_bytes(), start),
+            ),
+            // The matcher finds the longest match, which is only right for leftmost-longest
+            // searches.
+            Strategy::ShiftAnd if kind == MatchKind::LeftmostLongest => {
+                let len = self.shift_and().find_anchored(haystack.chars())?;
+                Some(char_match(input, start, len))
+            }
+            Strategy::ShiftAnd => self
+                .automaton(Strategy::LazyDFA)
+                .find_anchored_at(input, start, kind),
+            Strategy::PikeVM => {
+                let slots = self.pikevm.captures(haystack.chars())?;
+                Some(slots_match(input, start, &slots))
+            }
+            Strategy::Backtrack => {
+                let chars: Vec<_> = haystack.chars().collect();
+                let slots = self.backtracker().captures(&chars)?;
+                Some(slots_match(input, start, &slots))
+            }
+            strategy => self
+                .automaton(strategy)
+                .find_anchored_at(input, start, kind),
+        }
+    }
+
+    #[inline]
+    fn find_shortest_anchored_at<'t>(&self, input: &'t str, start: usize) -> Option<Match<'t>> {
+        match self.choose(input.len() - start, false) {
+            Strategy::Literal => literal_match(
+                input,
+                self.literals()
+                    .find_shortest_anchored_at(input.as_bytes(), start),
+            ),
+            Strategy::ShiftAnd => {
+                let len = self
+                    .shift_and()
+                    .find_shortest_anchored(input[start..].chars())?;
+                Some(char_match(input, start, len))
+            }
+            strategy => self
+                .automaton(strategy)
+                .find_shortest_anchored_at(input, start),
+        }
+    }
+
+    #[inline]
+    fn rfind_start(&self, forward: &Self, input: &str, start: usize) -> Option<usize> {
+        regexp::forward_start(forward, input, start)
+    }
+}
+
+#[inline]
+fn literal_match(input: &str, m: Option<Range<usize>>) -> Option<Match<'_>> {
+    m.map(|m| Match::new(input, m.start, m.end))
+}
+
+/// Convert the character positions of a match in slots 0 and 1, relative to the byte offset
+/// `offset` of the input string, to a match.
+#[inline]
+fn slots_match<'t>(input: &'t str, offset: usize, slots: &[Option<usize>]) -> Match<'t> {
+    let byte_offset = |pos: Option<usize>| {
+        byte_offset(
+            input,
+            offset,
+            pos.expect("a match should record its start and end"),
+        )
+    };
+    Match::new(input, byte_offset(slots[0]), byte_offset(slots[1]))
+}
+
+/// Convert a match of `len` characters at the byte offset `offset` of the input string to a
+/// match.
+#[inline]
+fn char_match(input: &str, offset: usize, len: usize) -> Match<'_> {
+    Match::new(input, offset, byte_offset(input, offset, len))
+}
+
+/// The byte offset of the character `pos` characters after the byte offset `offset`.
+#[inline]
+fn byte_offset(input: &str, offset: usize, pos: usize) -> usize {
+    offset
+        + input[offset..]
+            .chars()
+            .take(pos)
+            .map(char::len_utf8)
+            .sum::<usize>()
+}
@@ -1,17 +1,63 @@
 use crate::class::CharClass;
 
+use std::borrow::Cow;
+use std::fmt;
 use std::iter::Peekable;
 use std::marker::PhantomData;
+use std::ops::Range;
 use std::str::CharIndices;
 
 /// Alias for [`Result`] for [`ParseError`].
 pub type ParseResult<'r, T> = std::result::Result<T, ParseError<'r>>;
 
+/// Options that control how a regular expression is parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    /// Match letters regardless of their case. This can be changed within an expression with the
+    /// `i` flag, as in `(?i)` or `(?i:abc)`.
+    pub case_insensitive: bool,
+    /// Allow `.` to match the newline character. This can be changed within an expression with the
+    /// `s` flag, as in `(?s)` or `(?s:.*)`.
+    pub dot_matches_new_line: bool,
+    /// The characters that end a line, which `.` doesn't match unless
+    /// [`Config::dot_matches_new_line`] is set.
+    pub line_terminator: LineTerminator,
+    /// Use the Unicode definitions of `\d`, `\s`, and `\w`. If disabled, these classes (and their
+    /// negations) only contain ASCII characters. This can be changed within an expression with the
+    /// `u` flag, as in `(?-u)` or `(?-u:\w+)`.
+    pub unicode: bool,
+    /// The maximum depth to which groups may be nested, or `None` for no limit.
+    pub nest_limit: Option<usize>,
+    /// The maximum size of the parsed expression, or `None` for no limit. Every character,
+    /// class, operator, and group counts towards the size, and the operand of `+` counts twice.
+    pub size_limit: Option<usize>,
+    /// Allow an empty regular expression, an empty group, or an empty branch of an alternation,
+    /// as in `""`, `()`, or `a|`, which match the empty string. If disabled, these are errors,
+    /// except for empty groups, which are handled by [`ParserEngine::handle_empty`].
+    pub empty_matches: bool,
+}
+
+impl Default for Config {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            case_insensitive: false,
+            dot_matches_new_line: false,
+            line_terminator: LineTerminator::default(),
+            unicode: true,
+            nest_limit: None,
+            size_limit: None,
+            empty_matches: false,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Parser<E>
 where
     E: ParserEngine,
 {
+    config: Config,
     _phantom: PhantomData<E>,
 }
 
@@ -22,16 +68,36 @@ where
     #[inline]
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
+        Self::with_config(Config::default())
+    }
+
+    /// Create a parser that uses the given options.
+    #[inline]
+    pub fn with_config(config: Config) -> Self {
         Self {
+            config,
             _phantom: PhantomData,
         }
     }
 
+    #[inline]
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
     #[inline]
     pub fn parse<'r>(&self, expr: &'r str) -> ParseResult<'r, E::Output> {
-        let mut state: ParserState<E> = ParserState::new();
+        let mut state: ParserState<E> = ParserState::with_config(self.config.clone());
         state.parse(expr)
     }
+
+    /// Parse a regular expression, continuing after each error to report every error in it. See
+    /// [`ParserState::parse_with_recovery`].
+    #[inline]
+    pub fn parse_with_recovery<'r>(&self, expr: &'r str) -> Result<E::Output, Vec<ParseError<'r>>> {
+        let mut state: ParserState<E> = ParserState::with_config(self.config.clone());
+        state.parse_with_recovery(expr)
+    }
 }
 
 #[derive(Debug)]
@@ -40,6 +106,97 @@ where
     E: ParserEngine,
 {
     engine: E,
+    config: Config,
+    /// The number of groups opened so far.
+    groups: usize,
+}
+
+/// Determine if a character has a special meaning in a pattern, so that it must be escaped with a
+/// backslash to be matched literally. Escaping any of these characters is always allowed, even
+/// where it isn't needed.
+#[inline]
+pub fn is_meta_character(c: char) -> bool {
+    matches!(
+        c,
+        '\\' | '.'
+            | '+'
+            | '*'
+            | '?'
+            | '('
+            | ')'
+            | '|'
+            | '['
+            | ']'
+            | '{'
+            | '}'
+            | '^'
+            | '$'
+            | '#'
+            | '&'
+            | '-'
+            | '~'
+    )
+}
+
+/// A zero-width assertion written in a regular expression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub enum Anchor {
+    /// `^`, which matches at the start of the input.
+    Start,
+    /// `$`, which matches at the end of the input.
+    End,
+}
+
+/// The characters that end a line, as set by [`Config::line_terminator`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
+pub enum LineTerminator {
+    /// `\n`. This is the default.
+    Newline,
+    /// `\n` or `\r`, which covers `\r\n` line endings.
+    CRLF,
+    /// The line terminators of [`LineTerminator::CRLF`], and the vertical tab, form feed, next
+    /// line (U+0085), line separator (U+2028), and paragraph separator (U+2029) characters.
+    Unicode,
+}
+
+impl Default for LineTerminator {
+    #[inline]
+    fn default() -> Self {
+        LineTerminator::Newline
+    }
+}
+
+impl LineTerminator {
+    /// The class of characters that end a line, which `.` doesn't match.
+    ///
+    /// ```
+    /// use regexp2::parser::LineTerminator;
+    ///
+    /// let class = LineTerminator::Unicode.class();
+    /// assert!(class.contains('\u{2028}'));
+    /// assert!(!LineTerminator::Newline.class().contains('\r'));
+    /// ```
+    #[inline]
+    pub fn class(self) -> CharClass {
+        let chars: &[char] = match self {
+            LineTerminator::Newline => &['\n'],
+            LineTerminator::CRLF => &['\n', '\r'],
+            LineTerminator::Unicode => &[
+                '\n', '\r', '\u{b}', '\u{c}', '\u{85}', '\u{2028}', '\u{2029}',
+            ],
+        };
+        chars.to_vec().into()
+    }
 }
 
 pub trait ParserEngine {
@@ -50,164 +207,434 @@ pub trait ParserEngine {
     fn handle_char<C>(&mut self, c: C) -> Self::Output
     where
         C: Into<CharClass>;
-    fn handle_wildcard(&mut self) -> Self::Output;
+    /// Handle `.`, which matches any character in the class. Unless
+    /// [`Config::dot_matches_new_line`] is set, the class excludes the
+    /// [`Config::line_terminator`].
+    fn handle_wildcard(&mut self, class: CharClass) -> Self::Output;
 
     fn handle_star(&mut self, lhs: Self::Output) -> Self::Output;
     fn handle_plus(&mut self, lhs: Self::Output) -> Self::Output;
     fn handle_optional(&mut self, lhs: Self::Output) -> Self::Output;
     fn handle_concat(&mut self, lhs: Self::Output, rhs: Self::Output) -> Self::Output;
     fn handle_alternate(&mut self, lhs: Self::Output, rhs: Self::Output) -> Self::Output;
+
+    /// Handle a parenthesized group. Groups are numbered by their opening parenthesis, starting
+    /// from 1.
+    #[inline]
+    fn handle_group(&mut self, inner: Self::Output, _capture_index: usize) -> Self::Output {
+        inner
+    }
+    /// Handle the empty expression inside `()`. If `None` is returned, the group is dropped from
+    /// the expression.
+    #[inline]
+    fn handle_empty(&mut self) -> Option<Self::Output> {
+        None
+    }
+    /// Handle an expression that matches only the empty string, for the empty regular
+    /// expressions, groups, and branches allowed by [`Config::empty_matches`]. If `None` is
+    /// returned, the engine doesn't support empty expressions and parsing fails as if the option
+    /// were disabled.
+    #[inline]
+    fn handle_epsilon(&mut self) -> Option<Self::Output> {
+        self.handle_empty()
+    }
+    /// Handle an anchor. If `None` is returned, the engine doesn't support the anchor and parsing
+    /// fails.
+    #[inline]
+    fn handle_anchor(&mut self, _kind: Anchor) -> Option<Self::Output> {
+        None
+    }
+    /// Handle the byte range of the regular expression that an expression was parsed from. This
+    /// is called for every expression produced by the other methods.
+    #[inline]
+    fn handle_span(&mut self, expr: Self::Output, _span: Range<usize>) -> Self::Output {
+        expr
+    }
 }
 
 impl<E> ParserState<E>
 where
     E: ParserEngine,
 {
-    const EXPR_START_EXPECTED: &'static [char] = &['(', '['];
-
     #[inline]
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
-        Self { engine: E::new() }
+        Self::with_config(Config::default())
+    }
+
+    #[inline]
+    pub fn with_config(config: Config) -> Self {
+        Self {
+            engine: E::new(),
+            config,
+            groups: 0,
+        }
     }
 
     /// Compile a regular expresion.
     #[inline]
     pub fn parse<'r>(&mut self, expr: &'r str) -> ParseResult<'r, E::Output> {
+        let expr = self.parse_from_start(expr, &mut None)?;
+        Ok(expr.expect("an expression is parsed unless an error is recovered from"))
+    }
+
+    /// Compile a regular expression, continuing after each error to report every error in it.
+    /// Parsing resumes right after an error, except that the rest of a malformed group or class is
+    /// skipped through its `)` or `]`, and a branch left empty by an error isn't reported again
+    /// at the `|` or `)` that ends it. Parsing stops if the size limit is exceeded.
+    ///
+    /// ```
+    /// use regexp2::parser::validate::Validator;
+    ///
+    /// let errors = Validator::new().parse_with_recovery("*a|b(c|)|[a-").unwrap_err();
+    /// assert_eq!(3, errors.len());
+    /// ```
+    #[inline]
+    pub fn parse_with_recovery<'r>(
+        &mut self,
+        expr: &'r str,
+    ) -> Result<E::Output, Vec<ParseError<'r>>> {
+        let mut errors = Some(Vec::new());
+        let result = self.parse_from_start(expr, &mut errors);
+        let mut errors = errors.unwrap_or_default();
+        match result {
+            Ok(Some(expr)) if errors.is_empty() => Ok(expr),
+            Ok(_) => Err(errors),
+            Err(err) => {
+                errors.push(err);
+                Err(errors)
+            }
+        }
+    }
+
+    #[inline]
+    fn parse_from_start<'r>(
+        &mut self,
+        expr: &'r str,
+        errors: &mut Option<Vec<ParseError<'r>>>,
+    ) -> ParseResult<'r, Option<E::Output>> {
+        self.groups = 0;
         let input = &mut ParseInput::new(expr);
-        self.parse_expr(input, 0, false)
+
+        // Flags in the expression change the configuration while it is parsed.
+        let config = self.config.clone();
+        let result = self.parse_expr(input, errors);
+        self.config = config;
+        result
     }
 
+    /// Parse the whole expression. Groups are tracked with an explicit stack instead of by
+    /// recursion, so deeply nested expressions can't overflow the call stack.
+    ///
+    /// If `errors` is `Some`, errors are collected into it and parsing continues after them, in
+    /// which case the parsed expression may be `None`.
     #[inline]
     fn parse_expr<'r>(
         &mut self,
         input: &mut ParseInput<'r>,
-        min_bp: u8,
-        parenthesized: bool,
-    ) -> ParseResult<'r, E::Output> {
-        let mut lhs = None;
-        while lhs.is_none() {
-            lhs = match input.peek() {
-                Some((_, c)) => match c {
-                    '\\' => Some(self.parse_escaped(input)?),
-                    // Beginning of a group.
-                    '(' => self.parse_group(input)?,
-                    ')' if !parenthesized => {
-                        let (_, c) = input.next_unchecked();
-                        return Err(ParseError::UnexpectedToken {
-                            span: input.current_span(),
-                            token: c,
-                            expected: Self::EXPR_START_EXPECTED.into(),
-                        });
+        errors: &mut Option<Vec<ParseError<'r>>>,
+    ) -> ParseResult<'r, Option<E::Output>> {
+        let mut stack = vec![Frame::new(None, 0, self.config.clone())];
+        while let Some(&(start, c)) = input.peek() {
+            let operand = match c {
+                '(' => {
+                    let _lp = input.next_unchecked();
+                    if let Some(limit) = self.config.nest_limit {
+                        if stack.len() > limit {
+                            let err = ParseError::NestLimitExceeded {
+                                span: input.current_span(),
+                                limit,
+                            };
+                            let frame = stack.last_mut().unwrap();
+                            self.recover(err, input, frame, Skip::Group, errors)?;
+                            continue;
+                        }
                     }
-                    '[' => self.parse_class(input)?,
-                    '.' => Some(self.parse_wildcard(input)?),
-                    '?' | '*' | '|' => {
-                        let (_, c) = input.next_unchecked();
-                        return Err(ParseError::UnexpectedToken {
-                            span: input.current_span(),
-                            token: c,
-                            expected: Self::EXPR_START_EXPECTED.into(),
-                        });
+
+                    let config = self.config.clone();
+                    if input.peek_is('?') {
+                        let _q = input.next_unchecked();
+                        match self.parse_flags(input) {
+                            Ok(true) => stack.push(Frame::new(None, start, config)),
+                            Ok(false) => {}
+                            Err(err) => {
+                                self.config = config;
+                                let frame = stack.last_mut().unwrap();
+                                self.recover(err, input, frame, Skip::Group, errors)?;
+                            }
+                        }
+                        continue;
                     }
-                    _ => Some(self.parse_single(input)?),
-                },
-                None => {
-                    return Err(ParseError::EmptyExpression {
+
+                    self.groups += 1;
+                    stack.push(Frame::new(Some(self.groups), start, config));
+                    continue;
+                }
+                ')' if stack.len() > 1 => {
+                    let mut frame = stack.pop().unwrap();
+                    let failed = frame.failed;
+                    let inner = if frame.is_empty() {
+                        let _rp = input.next_unchecked();
+                        let empty = if self.config.empty_matches {
+                            self.engine.handle_epsilon()
+                        } else {
+                            self.engine.handle_empty()
+                        };
+                        empty.map(|expr| self.node(expr, 1, start..start))
+                    } else if let Err(err) = self.end_branch(&mut frame, input) {
+                        // The closing parenthesis was consumed as the unexpected token.
+                        self.config = frame.config;
+                        let parent = stack.last_mut().unwrap();
+                        self.recover(err, input, parent, Skip::Nothing, errors)?;
+                        continue;
+                    } else {
+                        let _rp = input.next_unchecked();
+                        frame.alternation
+                    };
+
+                    self.config = frame.config;
+                    let group = match (inner, frame.index) {
+                        (Some(inner), Some(index)) => {
+                            let size = self.check_size(inner.size + 1, input)?;
+                            let expr = self.engine.handle_group(inner.expr, index);
+                            Some(self.node(expr, size, frame.start..input.offset()))
+                        }
+                        (inner, None) => inner,
+                        (None, _) => None,
+                    };
+
+                    let frame = stack.last_mut().unwrap();
+                    // A group emptied by an error leaves the enclosing branch without an operand.
+                    frame.failed |= failed && group.is_none();
+                    self.push_operand(frame, group, input)?;
+                    continue;
+                }
+                '|' => {
+                    let frame = stack.last_mut().unwrap();
+                    if let Err(err) = self.end_branch(frame, input) {
+                        // The bar was consumed as the unexpected token, and starts a new branch.
+                        self.recover(err, input, frame, Skip::Nothing, errors)?;
+                        frame.failed = false;
+                    } else {
+                        let _bar = input.next_unchecked();
+                    }
+                    continue;
+                }
+                '*' | '+' | '?' => {
+                    let (_, c) = input.next_unchecked();
+                    let frame = stack.last_mut().unwrap();
+                    let operand = match frame.operand.take() {
+                        Some(operand) => operand,
+                        // The operand was dropped because of an error.
+                        None if frame.failed => continue,
+                        None => {
+                            let err = ParseError::UnexpectedToken {
+                                span: input.current_span(),
+                                token: c,
+                                expected: vec![Expected::Expr],
+                            };
+                            self.recover(err, input, frame, Skip::Nothing, errors)?;
+                            continue;
+                        }
+                    };
+
+                    // The operand of `+` may be repeated by the engine, so count it twice.
+                    let size = match c {
+                        '+' => operand.size.saturating_mul(2).saturating_add(1),
+                        _ => operand.size.saturating_add(1),
+                    };
+                    let size = self.check_size(size, input)?;
+                    let expr = match c {
+                        '*' => self.engine.handle_star(operand.expr),
+                        '+' => self.engine.handle_plus(operand.expr),
+                        _ => self.engine.handle_optional(operand.expr),
+                    };
+                    let node = self.node(expr, size, operand.span.start..input.offset());
+                    stack.last_mut().unwrap().operand = Some(node);
+                    continue;
+                }
+                ')' => {
+                    let (_, c) = input.next_unchecked();
+                    let err = ParseError::UnexpectedToken {
                         span: input.current_span(),
-                    })
+                        token: c,
+                        expected: vec![Expected::Expr],
+                    };
+                    let frame = stack.last_mut().unwrap();
+                    self.recover(err, input, frame, Skip::Nothing, errors)?;
+                    continue;
                 }
+                '\\' => self.parse_escaped(input).map(Some),
+                '[' => self.parse_class(input).map(Some),
+                '.' => self.parse_wildcard(input).map(Some),
+                '^' | '$' => self.parse_anchor(input).map(Some),
+                _ => self.parse_single(input).map(Some),
             };
+            let operand = match operand {
+                Ok(operand) => operand,
+                Err(err) => {
+                    let skip = if c == '[' { Skip::Class } else { Skip::Nothing };
+                    let frame = stack.last_mut().unwrap();
+                    self.recover(err, input, frame, skip, errors)?;
+                    continue;
+                }
+            };
+            let end = input.offset();
+            let operand = operand.map(|expr| self.node(expr, 1, start..end));
+
+            let frame = stack.last_mut().unwrap();
+            self.push_operand(frame, operand, input)?;
         }
 
-        let mut lhs = lhs.unwrap();
-        while let Some((_, c)) = input.peek() {
-            lhs = match c {
-                ')' if parenthesized => break,
-                '*' => {
-                    if self.postfix_bp(&PostfixOp::Star).0 < min_bp {
-                        break;
-                    }
+        let mut frame = stack.pop().unwrap();
+        if let Err(err) = self.end_branch(&mut frame, input) {
+            self.recover(err, input, &mut frame, Skip::Nothing, errors)?;
+        } else if !stack.is_empty() {
+            let err = ParseError::UnexpectedEof {
+                span: input.current_eof_span(),
+                expected: vec![Expected::Char(')')],
+            };
+            self.recover(err, input, &mut frame, Skip::Nothing, errors)?;
+        }
 
-                    let _star = input.next_unchecked();
-                    self.engine.handle_star(lhs)
-                }
-                '+' => {
-                    if self.postfix_bp(&PostfixOp::Plus).0 < min_bp {
-                        break;
-                    }
+        Ok(frame.alternation.map(|node| node.expr))
+    }
 
-                    let _plus = input.next_unchecked();
-                    self.engine.handle_plus(lhs)
-                }
-                '?' => {
-                    if self.postfix_bp(&PostfixOp::Optional).0 < min_bp {
-                        break;
-                    }
+    /// Handle an error in the current branch of a frame. Without recovery, the error is returned.
+    /// With recovery, it is recorded, and the input is skipped to where parsing can resume.
+    #[inline]
+    fn recover<'r>(
+        &self,
+        err: ParseError<'r>,
+        input: &mut ParseInput<'r>,
+        frame: &mut Frame<E::Output>,
+        skip: Skip,
+        errors: &mut Option<Vec<ParseError<'r>>>,
+    ) -> ParseResult<'r, ()> {
+        match errors {
+            // The rest of the expression would only exceed the size limit further.
+            Some(errors) if !matches!(err, ParseError::SizeLimitExceeded { .. }) => {
+                errors.push(err);
+                frame.failed = true;
+                input.skip(skip);
+                Ok(())
+            }
+            _ => Err(err),
+        }
+    }
 
-                    let _question = input.next_unchecked();
-                    self.engine.handle_optional(lhs)
-                }
-                '|' => {
-                    let (lbp, rbp) = self.infix_bp(&InfixOp::Alternate);
-                    if lbp < min_bp {
-                        break;
-                    }
+    /// Report the span of a parsed expression to the engine.
+    #[inline]
+    fn node(&mut self, expr: E::Output, size: usize, span: Range<usize>) -> Node<E::Output> {
+        Node {
+            expr: self.engine.handle_span(expr, span.clone()),
+            size,
+            span,
+        }
+    }
 
-                    let _bar = input.next_unchecked();
-                    let rhs = self.parse_expr(input, rbp, parenthesized)?;
-                    self.engine.handle_alternate(lhs, rhs)
+    /// Add an operand to the current branch of a group. The previous operand is concatenated to
+    /// the branch, since no more postfix operators can apply to it.
+    #[inline]
+    fn push_operand<'r>(
+        &mut self,
+        frame: &mut Frame<E::Output>,
+        operand: Option<Node<E::Output>>,
+        input: &mut ParseInput<'r>,
+    ) -> ParseResult<'r, ()> {
+        if let Some(prev) = frame.operand.take() {
+            frame.concat = Some(match frame.concat.take() {
+                Some(lhs) => {
+                    let size = self.check_size(lhs.size.saturating_add(prev.size + 1), input)?;
+                    let expr = self.engine.handle_concat(lhs.expr, prev.expr);
+                    self.node(expr, size, lhs.span.start..prev.span.end)
                 }
-                _ => {
-                    let (lbp, rbp) = self.infix_bp(&InfixOp::Concat);
-                    if lbp < min_bp {
-                        break;
-                    }
+                None => prev,
+            });
+        }
+        frame.operand = operand;
+        Ok(())
+    }
 
-                    let rhs = self.parse_expr(input, rbp, parenthesized)?;
-                    self.engine.handle_concat(lhs, rhs)
+    /// Add the current branch of a group to its alternation. The branch must not be empty.
+    #[inline]
+    fn end_branch<'r>(
+        &mut self,
+        frame: &mut Frame<E::Output>,
+        input: &mut ParseInput<'r>,
+    ) -> ParseResult<'r, ()> {
+        self.push_operand(frame, None, input)?;
+        let failed = std::mem::replace(&mut frame.failed, false);
+        let branch = match frame.concat.take() {
+            Some(branch) => branch,
+            // The branch is empty because of an error that was already recovered from.
+            None if failed => return Ok(()),
+            None if self.config.empty_matches => match self.engine.handle_epsilon() {
+                Some(expr) => {
+                    let offset = input.offset();
+                    self.node(expr, 1, offset..offset)
                 }
-            }
-        }
+                None => return Err(Self::empty_branch_error(input)),
+            },
+            None => return Err(Self::empty_branch_error(input)),
+        };
 
-        Ok(lhs)
+        frame.alternation = Some(match frame.alternation.take() {
+            Some(lhs) => {
+                let size = self.check_size(lhs.size.saturating_add(branch.size + 1), input)?;
+                let expr = self.engine.handle_alternate(lhs.expr, branch.expr);
+                self.node(expr, size, lhs.span.start..branch.span.end)
+            }
+            None => branch,
+        });
+        Ok(())
     }
 
+    /// The error for an empty branch, which consumes the token that ends it.
     #[inline]
-    fn postfix_bp(&self, op: &PostfixOp) -> (u8, ()) {
-        match op {
-            PostfixOp::Star => (9, ()),
-            PostfixOp::Plus => (9, ()),
-            PostfixOp::Optional => (9, ()),
+    fn empty_branch_error<'r>(input: &mut ParseInput<'r>) -> ParseError<'r> {
+        match input.next() {
+            Some((_, c)) => ParseError::UnexpectedToken {
+                span: input.current_span(),
+                token: c,
+                expected: vec![Expected::Expr],
+            },
+            None => ParseError::EmptyExpression {
+                span: input.current_span(),
+            },
         }
     }
 
+    /// Check the size of an expression against the configured limit.
     #[inline]
-    fn infix_bp(&self, op: &InfixOp) -> (u8, u8) {
-        match op {
-            InfixOp::Concat => (7, 8),
-            InfixOp::Alternate => (5, 6),
+    fn check_size<'r>(&self, size: usize, input: &mut ParseInput<'r>) -> ParseResult<'r, usize> {
+        match self.config.size_limit {
+            Some(limit) if size > limit => Err(ParseError::SizeLimitExceeded {
+                span: input.current_span(),
+                limit,
+            }),
+            _ => Ok(size),
         }
     }
 
     #[inline]
     fn parse_single_char<'r>(&mut self, input: &mut ParseInput<'r>) -> ParseResult<'r, char> {
         // TODO: Expect any
-        let (_, c) = input.next_unwrap(Vec::new)?;
+        let (_, c) = input.next_unwrap(|| vec![Expected::AnyChar])?;
         Ok(c)
     }
 
     #[inline]
     fn parse_single<'r>(&mut self, input: &mut ParseInput<'r>) -> ParseResult<'r, E::Output> {
         let c = self.parse_single_char(input)?;
-        Ok(self.engine.handle_char(c))
+        let class = self.case_fold(c.into());
+        Ok(self.engine.handle_char(class))
     }
 
     #[inline]
     fn parse_escaped_char<'r>(&mut self, input: &mut ParseInput<'r>) -> ParseResult<'r, char> {
-        let _bs = input.next_checked('\\', || vec!['\\']);
-        // TODO: How to represent expected any character?
-        let (_, c) = input.next_unwrap(Vec::new)?;
+        let _bs = input.next_checked('\\', || vec![Expected::Char('\\')]);
+        let (_, c) = input.next_unwrap(|| vec![Expected::AnyChar])?;
         Ok(c)
     }
 
@@ -217,23 +644,102 @@ where
         input: &mut ParseInput<'r>,
     ) -> ParseResult<'r, CharClass> {
         let c = self.parse_escaped_char(input)?;
-        let c = match c {
-            'd' => CharClass::decimal_number(),
-            'D' => CharClass::decimal_number().complement(),
-            's' => CharClass::whitespace(),
-            'S' => CharClass::whitespace().complement(),
-            'w' => CharClass::word(),
-            'W' => CharClass::word().complement(),
+        if c == 'R' {
+            // `\R` can match two characters, so it isn't a class.
+            return Err(ParseError::UnexpectedToken {
+                span: input.current_span(),
+                token: c,
+                expected: vec![Expected::ClassItem],
+            });
+        }
+        Ok(self.escaped_class(c))
+    }
+
+    /// The class of an escape sequence, given the character after the backslash.
+    #[inline]
+    fn escaped_class(&self, c: char) -> CharClass {
+        match c {
+            'd' => self.perl_class(CharClass::decimal_number, CharClass::ascii_digit),
+            'D' => self
+                .perl_class(CharClass::decimal_number, CharClass::ascii_digit)
+                .complement(),
+            's' => self.perl_class(CharClass::whitespace, CharClass::ascii_whitespace),
+            'S' => self
+                .perl_class(CharClass::whitespace, CharClass::ascii_whitespace)
+                .complement(),
+            'w' => self.perl_class(CharClass::word, CharClass::ascii_word),
+            'W' => self
+                .perl_class(CharClass::word, CharClass::ascii_word)
+                .complement(),
+            'h' => self.perl_class(
+                CharClass::horizontal_whitespace,
+                CharClass::ascii_horizontal_whitespace,
+            ),
+            'H' => self
+                .perl_class(
+                    CharClass::horizontal_whitespace,
+                    CharClass::ascii_horizontal_whitespace,
+                )
+                .complement(),
+            'v' => self.perl_class(
+                CharClass::vertical_whitespace,
+                CharClass::ascii_vertical_whitespace,
+            ),
+            'V' => self
+                .perl_class(
+                    CharClass::vertical_whitespace,
+                    CharClass::ascii_vertical_whitespace,
+                )
+                .complement(),
             'n' => CharClass::newline(),
+            't' => '\t'.into(),
+            'r' => '\r'.into(),
             c => c.into(),
-        };
-        Ok(c)
+        }
+    }
+
+    /// Create one of the Perl character classes (`\d`, `\s`, `\w`, `\h`, and `\v`), using its ASCII
+    /// variant if Unicode is disabled.
+    #[inline]
+    fn perl_class(&self, unicode: fn() -> CharClass, ascii: fn() -> CharClass) -> CharClass {
+        if self.config.unicode {
+            unicode()
+        } else {
+            ascii()
+        }
+    }
+
+    /// Close the class under simple case mapping if case-insensitive matching is enabled.
+    #[inline]
+    fn case_fold(&self, class: CharClass) -> CharClass {
+        if self.config.case_insensitive {
+            class.case_fold()
+        } else {
+            class
+        }
     }
 
     #[inline]
     fn parse_escaped<'r>(&mut self, input: &mut ParseInput<'r>) -> ParseResult<'r, E::Output> {
-        let c = self.parse_escaped_class(input)?;
-        Ok(self.engine.handle_char(c))
+        let c = self.parse_escaped_char(input)?;
+        if c == 'R' {
+            return Ok(self.line_break());
+        }
+
+        let class = self.case_fold(self.escaped_class(c));
+        Ok(self.engine.handle_char(class))
+    }
+
+    /// Create `\R`, which matches any line break: `\r\n`, preferred as a unit, or a single vertical
+    /// whitespace character.
+    #[inline]
+    fn line_break(&mut self) -> E::Output {
+        let cr = self.engine.handle_char('\r');
+        let lf = self.engine.handle_char('\n');
+        let crlf = self.engine.handle_concat(cr, lf);
+        let vertical = self.escaped_class('v');
+        let vertical = self.engine.handle_char(vertical);
+        self.engine.handle_alternate(crlf, vertical)
     }
 
     #[allow(dead_code)]
@@ -247,7 +753,7 @@ where
             Some((_, _)) => self.parse_single_char(input),
             None => Err(ParseError::UnexpectedEof {
                 span: input.current_eof_span(),
-                expected: vec!['\\'],
+                expected: vec![Expected::AnyChar],
             }),
         }
     }
@@ -263,7 +769,7 @@ where
             None => {
                 return Err(ParseError::UnexpectedEof {
                     span: input.current_eof_span(),
-                    expected: vec!['\\'],
+                    expected: vec![Expected::AnyChar],
                 })
             }
         };
@@ -281,36 +787,66 @@ where
             Some((_, _)) => self.parse_single(input),
             None => Err(ParseError::UnexpectedEof {
                 span: input.current_eof_span(),
-                expected: vec!['\\'],
+                expected: vec![Expected::AnyChar],
             }),
         }
     }
 
+    /// Parse the flags of a group after its `(?`, and apply them to the configuration. Returns
+    /// `true` if the flags are followed by `:` and the group's expression, or `false` if the group
+    /// ends after the flags, in which case they apply to the rest of the enclosing group.
     #[inline]
-    fn parse_group<'r>(
-        &mut self,
-        input: &mut ParseInput<'r>,
-    ) -> ParseResult<'r, Option<E::Output>> {
-        let _lp = input.next_checked('(', || vec!['('])?;
-
-        let expr = if !input.peek_is(')') {
-            let expr = self.parse_expr(input, 0, true)?;
-            Some(expr)
-        } else {
-            None
+    fn parse_flags<'r>(&mut self, input: &mut ParseInput<'r>) -> ParseResult<'r, bool> {
+        let expected = || {
+            vec![
+                Expected::Char('i'),
+                Expected::Char('s'),
+                Expected::Char('u'),
+                Expected::Char('-'),
+                Expected::Char(':'),
+                Expected::Char(')'),
+            ]
         };
 
-        let _rp = input.next_checked(')', || vec![')'])?;
+        let mut enable = true;
+        loop {
+            let (_, c) = input.next_unwrap(expected)?;
+            match c {
+                'i' => self.config.case_insensitive = enable,
+                's' => self.config.dot_matches_new_line = enable,
+                'u' => self.config.unicode = enable,
+                '-' if enable => enable = false,
+                ':' => return Ok(true),
+                ')' => return Ok(false),
+                c => {
+                    return Err(ParseError::UnexpectedToken {
+                        span: input.current_span(),
+                        token: c,
+                        expected: expected(),
+                    })
+                }
+            }
+        }
+    }
 
-        Ok(expr)
+    #[inline]
+    fn parse_anchor<'r>(&mut self, input: &mut ParseInput<'r>) -> ParseResult<'r, E::Output> {
+        let (_, c) = input.next_unwrap(|| vec![Expected::Char('^'), Expected::Char('$')])?;
+        let kind = match c {
+            '$' => Anchor::End,
+            _ => Anchor::Start,
+        };
+        match self.engine.handle_anchor(kind) {
+            Some(expr) => Ok(expr),
+            None => Err(ParseError::UnsupportedAnchor {
+                span: input.current_span(),
+            }),
+        }
     }
 
     #[inline]
-    fn parse_class<'r>(
-        &mut self,
-        input: &mut ParseInput<'r>,
-    ) -> ParseResult<'r, Option<E::Output>> {
-        let _lb = input.next_checked('[', || vec!['['])?;
+    fn parse_class<'r>(&mut self, input: &mut ParseInput<'r>) -> ParseResult<'r, E::Output> {
+        let (lb, _) = input.next_checked('[', || vec![Expected::Char('[')])?;
 
         let negate = match input.peek() {
             Some((_, '^')) => {
@@ -321,14 +857,20 @@ where
             None => {
                 return Err(ParseError::UnexpectedEof {
                     span: input.current_eof_span(),
-                    // TODO: Expect any
-                    expected: vec![']', '^'],
+                    expected: vec![Expected::Char('^'), Expected::ClassItem, Expected::ClassEnd],
                 });
             }
         };
 
+        // The closing bracket is left for recovery to skip.
+        if input.peek_is(']') {
+            return Err(ParseError::EmptyCharacterClass {
+                span: input.span_to_next(lb),
+            });
+        }
+
         let mut class = CharClass::new();
-        while let Some((_, c)) = input.peek() {
+        while let Some(&(offset, c)) = input.peek() {
             let start = match c {
                 // LB indicates end of char class.
                 ']' => break,
@@ -345,6 +887,13 @@ where
             match input.peek() {
                 Some((_, '-')) => {
                     let _dash = input.next_unchecked();
+                    // A dash before the closing bracket is a literal dash.
+                    if input.peek_is(']') {
+                        class.add_other(start);
+                        class.add_range(('-', '-').into());
+                        continue;
+                    }
+
                     let end = self.parse_single_or_escaped_class(input)?;
 
                     if !end.is_single() {
@@ -356,6 +905,13 @@ where
                         // start and end are both single chars; create a range.
                         let s = start.ranges.into_iter().last().unwrap().start;
                         let e = end.ranges.into_iter().last().unwrap().start;
+                        if s > e {
+                            return Err(ParseError::InvalidClassRange {
+                                span: input.span_from(offset),
+                                start: s,
+                                end: e,
+                            });
+                        }
                         class.add_range((s, e).into());
                     }
                 }
@@ -366,46 +922,96 @@ where
                 None => {
                     return Err(ParseError::UnexpectedEof {
                         span: input.current_eof_span(),
-                        // TODO expect any char
-                        expected: vec![']', '-'],
+                        expected: vec![
+                            Expected::Char('-'),
+                            Expected::ClassItem,
+                            Expected::ClassEnd,
+                        ],
                     });
                 }
             };
         }
 
-        let _rb = input.next_checked(']', || vec![']']);
-        let v = if !class.is_empty() {
-            let class = if negate { class.complement() } else { class };
-            Some(self.engine.handle_char(class))
-        } else {
-            None
-        };
-
-        Ok(v)
+        let _rb = input.next_checked(']', || vec![Expected::ClassItem, Expected::ClassEnd])?;
+        let class = self.case_fold(class);
+        let class = if negate { class.complement() } else { class };
+        Ok(self.engine.handle_char(class))
     }
 
     #[inline]
     fn parse_wildcard_char<'r>(&mut self, input: &mut ParseInput<'r>) -> ParseResult<'r, char> {
-        let (_, c) = input.next_checked('.', || vec!['.'])?;
+        let (_, c) = input.next_checked('.', || vec![Expected::Char('.')])?;
         Ok(c)
     }
 
     #[inline]
     fn parse_wildcard<'r>(&mut self, input: &mut ParseInput<'r>) -> ParseResult<'r, E::Output> {
         let _ = self.parse_wildcard_char(input)?;
-        Ok(self.engine.handle_wildcard())
+        let class = if self.config.dot_matches_new_line {
+            CharClass::all()
+        } else {
+            self.config.line_terminator.class().complement()
+        };
+        Ok(self.engine.handle_wildcard(class))
     }
 }
 
-enum PostfixOp {
-    Star,
-    Plus,
-    Optional,
+/// A parsed expression, with its size as counted for [`Config::size_limit`] and the byte range of
+/// the regular expression that it was parsed from.
+struct Node<T> {
+    expr: T,
+    size: usize,
+    span: Range<usize>,
 }
 
-enum InfixOp {
-    Alternate,
-    Concat,
+/// A group whose closing parenthesis hasn't been parsed yet, or the whole expression.
+struct Frame<T> {
+    /// The capture index of the group, or `None` for the whole expression and non-capturing
+    /// groups.
+    index: Option<usize>,
+    /// The byte offset of the opening parenthesis of the group.
+    start: usize,
+    /// The configuration to restore when the group ends, undoing any flags set inside it.
+    config: Config,
+    /// The alternation of the branches that have been parsed.
+    alternation: Option<Node<T>>,
+    /// The concatenation of the operands of the current branch, except the last.
+    concat: Option<Node<T>>,
+    /// The last operand of the current branch, to which postfix operators apply.
+    operand: Option<Node<T>>,
+    /// Whether an error was recovered from in the current branch.
+    failed: bool,
+}
+
+/// The part of the input to skip after an error, so that parsing can resume after it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Skip {
+    /// Resume right after the error.
+    Nothing,
+    /// Skip the rest of a group whose `(` has been parsed, through its `)`.
+    Group,
+    /// Skip the rest of a class whose `[` has been parsed, through its `]`.
+    Class,
+}
+
+impl<T> Frame<T> {
+    #[inline]
+    fn new(index: Option<usize>, start: usize, config: Config) -> Self {
+        Self {
+            index,
+            start,
+            config,
+            alternation: None,
+            concat: None,
+            operand: None,
+            failed: false,
+        }
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.alternation.is_none() && self.concat.is_none() && self.operand.is_none()
+    }
 }
 
 struct ParseInput<'r> {
@@ -441,7 +1047,7 @@ impl<'r> ParseInput<'r> {
     #[inline]
     pub fn next_unwrap<F>(&mut self, expected: F) -> ParseResult<'r, (usize, char)>
     where
-        F: Fn() -> Vec<char>,
+        F: Fn() -> Vec<Expected>,
     {
         match self.next() {
             Some(c) => Ok(c),
@@ -460,7 +1066,7 @@ impl<'r> ParseInput<'r> {
     #[inline]
     pub fn next_checked<F>(&mut self, check: char, expected: F) -> ParseResult<'r, (usize, char)>
     where
-        F: Fn() -> Vec<char>,
+        F: Fn() -> Vec<Expected>,
     {
         match self.next() {
             Some(next) if next.1 == check => Ok(next),
@@ -476,6 +1082,16 @@ impl<'r> ParseInput<'r> {
         }
     }
 
+    /// The byte offset of the next character, or the length of the expression if there are no
+    /// more characters.
+    #[inline]
+    pub fn offset(&mut self) -> usize {
+        match self.input.peek() {
+            Some(&(i, _)) => i,
+            None => self.expr.len(),
+        }
+    }
+
     #[inline]
     pub fn peek(&mut self) -> Option<&(usize, char)> {
         self.input.peek()
@@ -489,6 +1105,49 @@ impl<'r> ParseInput<'r> {
         }
     }
 
+    /// Skip the input after an error.
+    #[inline]
+    fn skip(&mut self, skip: Skip) {
+        match skip {
+            Skip::Nothing => {}
+            Skip::Group => self.skip_group(),
+            Skip::Class => self.skip_class(),
+        }
+    }
+
+    /// Skip through the `)` that closes the current group, including any nested groups and
+    /// classes.
+    #[inline]
+    fn skip_group(&mut self) {
+        let mut depth = 1;
+        while let Some((_, c)) = self.next() {
+            match c {
+                '\\' => {
+                    self.next();
+                }
+                '[' => self.skip_class(),
+                '(' => depth += 1,
+                ')' if depth == 1 => return,
+                ')' => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    /// Skip through the `]` that closes the current class.
+    #[inline]
+    fn skip_class(&mut self) {
+        while let Some((_, c)) = self.next() {
+            match c {
+                '\\' => {
+                    self.next();
+                }
+                ']' => return,
+                _ => {}
+            }
+        }
+    }
+
     #[allow(dead_code)]
     #[inline]
     pub fn is_empty(&mut self) -> bool {
@@ -517,6 +1176,25 @@ impl<'r> ParseInput<'r> {
         Span::new(pos, pos, text)
     }
 
+    /// The span from the character at the byte offset through the last consumed character.
+    #[inline]
+    fn span_from(&mut self, offset: usize) -> Span<'r> {
+        let end = self.offset();
+        let start = self.expr[..offset].chars().count();
+        Span::new(start, self.next_pos - 1, &self.expr[offset..end])
+    }
+
+    /// The span from the character at the byte offset through the next character.
+    #[inline]
+    fn span_to_next(&mut self, offset: usize) -> Span<'r> {
+        let end = match self.input.peek() {
+            Some(&(i, c)) => i + c.len_utf8(),
+            None => self.expr.len(),
+        };
+        let start = self.expr[..offset].chars().count();
+        Span::new(start, self.next_pos, &self.expr[offset..end])
+    }
+
     #[inline]
     fn current_eof_span(&self) -> Span<'r> {
         let pos = self.next_pos;
@@ -524,6 +1202,42 @@ impl<'r> ParseInput<'r> {
     }
 }
 
+/// Something that the parser expected to find in place of an unexpected token or the end of the
+/// expression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Expected {
+    /// A specific character.
+    Char(char),
+    /// Any character.
+    AnyChar,
+    /// The start of an expression, such as a character, group, or class.
+    Expr,
+    /// A character, range, or escaped class inside a bracketed class.
+    ClassItem,
+    /// The `]` that closes a bracketed class.
+    ClassEnd,
+}
+
+impl From<char> for Expected {
+    #[inline]
+    fn from(c: char) -> Self {
+        Expected::Char(c)
+    }
+}
+
+impl fmt::Display for Expected {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expected::Char(c) => write!(f, "'{}'", c),
+            Expected::AnyChar => write!(f, "any character"),
+            Expected::Expr => write!(f, "an expression"),
+            Expected::ClassItem => write!(f, "a class item"),
+            Expected::ClassEnd => write!(f, "']'"),
+        }
+    }
+}
+
 /// Error returned when attempting to parse an invalid regular expression.
 #[derive(Debug, thiserror::Error)]
 pub enum ParseError<'r> {
@@ -534,10 +1248,13 @@ pub enum ParseError<'r> {
     UnexpectedToken {
         span: Span<'r>,
         token: char,
-        expected: Vec<char>,
+        expected: Vec<Expected>,
     },
     #[error("unexpected end-of-file")]
-    UnexpectedEof { span: Span<'r>, expected: Vec<char> },
+    UnexpectedEof {
+        span: Span<'r>,
+        expected: Vec<Expected>,
+    },
 
     /// There are an invalid number of operators, or operands are missing.
     #[error("unbalanced operators")]
@@ -548,20 +1265,197 @@ pub enum ParseError<'r> {
     /// Bracketed character classes may not empty.
     #[error("empty character class")]
     EmptyCharacterClass { span: Span<'r> },
+    /// The end of a range in a bracketed character class is less than its start.
+    #[error("invalid character class range '{start}-{end}'")]
+    InvalidClassRange {
+        span: Span<'r>,
+        start: char,
+        end: char,
+    },
+    /// Groups are nested more deeply than the configured limit.
+    #[error("exceeded the nesting limit of {limit}")]
+    NestLimitExceeded { span: Span<'r>, limit: usize },
+    /// The expression is larger than the configured limit.
+    #[error("exceeded the size limit of {limit}")]
+    SizeLimitExceeded { span: Span<'r>, limit: usize },
+    /// The parser engine can't represent an anchor.
+    #[error("anchors are not supported")]
+    UnsupportedAnchor { span: Span<'r> },
+}
+
+impl<'r> ParseError<'r> {
+    /// Convert the error into one that owns its span text, so that it doesn't borrow the
+    /// regular expression.
+    #[inline]
+    pub fn into_owned(self) -> ParseError<'static> {
+        match self {
+            ParseError::EmptyExpression { span } => ParseError::EmptyExpression {
+                span: span.into_owned(),
+            },
+            ParseError::UnexpectedToken {
+                span,
+                token,
+                expected,
+            } => ParseError::UnexpectedToken {
+                span: span.into_owned(),
+                token,
+                expected,
+            },
+            ParseError::UnexpectedEof { span, expected } => ParseError::UnexpectedEof {
+                span: span.into_owned(),
+                expected,
+            },
+            ParseError::UnbalancedOperators { span } => ParseError::UnbalancedOperators {
+                span: span.into_owned(),
+            },
+            ParseError::UnbalancedParentheses { span } => ParseError::UnbalancedParentheses {
+                span: span.into_owned(),
+            },
+            ParseError::EmptyCharacterClass { span } => ParseError::EmptyCharacterClass {
+                span: span.into_owned(),
+            },
+            ParseError::InvalidClassRange { span, start, end } => ParseError::InvalidClassRange {
+                span: span.into_owned(),
+                start,
+                end,
+            },
+            ParseError::NestLimitExceeded { span, limit } => ParseError::NestLimitExceeded {
+                span: span.into_owned(),
+                limit,
+            },
+            ParseError::SizeLimitExceeded { span, limit } => ParseError::SizeLimitExceeded {
+                span: span.into_owned(),
+                limit,
+            },
+            ParseError::UnsupportedAnchor { span } => ParseError::UnsupportedAnchor {
+                span: span.into_owned(),
+            },
+        }
+    }
+
+    /// The location of the error in the regular expression.
+    #[inline]
+    pub fn span(&self) -> &Span<'r> {
+        match self {
+            ParseError::EmptyExpression { span }
+            | ParseError::UnexpectedToken { span, .. }
+            | ParseError::UnexpectedEof { span, .. }
+            | ParseError::UnbalancedOperators { span }
+            | ParseError::UnbalancedParentheses { span }
+            | ParseError::EmptyCharacterClass { span }
+            | ParseError::InvalidClassRange { span, .. }
+            | ParseError::NestLimitExceeded { span, .. }
+            | ParseError::SizeLimitExceeded { span, .. }
+            | ParseError::UnsupportedAnchor { span } => span,
+        }
+    }
+
+    /// Render the error against the regular expression it came from, with the offending part of
+    /// the expression underlined.
+    #[inline]
+    pub fn diagnostic<'e>(&'e self, expr: &'e str) -> Diagnostic<'e, 'r> {
+        Diagnostic { error: self, expr }
+    }
+
+    /// A short description of the offending part of the expression.
+    #[inline]
+    fn label(&self) -> String {
+        match self {
+            ParseError::UnexpectedToken {
+                token, expected, ..
+            } => format!("found '{}'{}", token, expected_label(expected)),
+            ParseError::UnexpectedEof { expected, .. } => {
+                format!("expression ends here{}", expected_label(expected))
+            }
+            _ => String::new(),
+        }
+    }
+}
+
+#[inline]
+fn expected_label(expected: &[Expected]) -> String {
+    match expected {
+        [] => String::new(),
+        [e] => format!(", expected {}", e),
+        _ => {
+            let items: Vec<_> = expected.iter().map(Expected::to_string).collect();
+            format!(", expected one of {}", items.join(", "))
+        }
+    }
 }
 
+/// A [`ParseError`] rendered against its regular expression, similar to a compiler diagnostic.
 #[derive(Debug)]
+pub struct Diagnostic<'e, 'r> {
+    error: &'e ParseError<'r>,
+    expr: &'e str,
+}
+
+impl fmt::Display for Diagnostic<'_, '_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let span = self.error.span();
+
+        // Find the line of the expression that contains the span.
+        let mut line_start = 0;
+        let mut lines = self.expr.split('\n').enumerate().peekable();
+        let (line_no, line) = loop {
+            let (i, line) = lines.next().unwrap_or((0, ""));
+            let len = line.chars().count();
+            if lines.peek().is_none() || span.start() <= line_start + len {
+                break (i + 1, line);
+            }
+            line_start += len + 1;
+        };
+
+        let column = span.start().saturating_sub(line_start);
+        let width = span.text().chars().count().max(1);
+        let gutter = " ".repeat(line_no.to_string().len());
+
+        writeln!(f, "error: {}", self.error)?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", line_no, line)?;
+        write!(
+            f,
+            "{} | {}{}",
+            gutter,
+            " ".repeat(column),
+            "^".repeat(width)
+        )?;
+        let label = self.error.label();
+        if !label.is_empty() {
+            write!(f, " {}", label)?;
+        }
+        writeln!(f)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Span<'r> {
     start: usize,
     end: usize,
 
-    text: &'r str,
+    text: Cow<'r, str>,
 }
 
 impl<'r> Span<'r> {
     #[inline]
     pub fn new(start: usize, end: usize, text: &'r str) -> Self {
-        Self { start, end, text }
+        Self {
+            start,
+            end,
+            text: Cow::Borrowed(text),
+        }
+    }
+
+    /// Convert the span into one that owns its text.
+    #[inline]
+    pub fn into_owned(self) -> Span<'static> {
+        Span {
+            start: self.start,
+            end: self.end,
+            text: Cow::Owned(self.text.into_owned()),
+        }
     }
 
     #[inline]
@@ -576,7 +1470,7 @@ impl<'r> Span<'r> {
 
     #[inline]
     pub fn text(&self) -> &str {
-        self.text
+        &self.text
     }
 }
 
@@ -587,6 +1481,7 @@ pub mod nfa {
     use std::hash::Hash;
     use std::marker::PhantomData;
 
+    use automata::intern::Interner;
     use automata::nfa::Transition;
     use automata::NFA;
 
@@ -594,11 +1489,15 @@ pub mod nfa {
 
     /// A regular expression parser that produces an NFA that describes the same language as the
     /// regular expression. The transitions of the NFA must be derivable from CharClass.
+    ///
+    /// Equal classes are interned, so the transitions labeled with them share the storage of one
+    /// class instead of each holding a copy.
     pub struct NFAParserEngine<T>
     where
         T: Clone + Eq + Hash,
         Transition<T>: From<CharClass>,
     {
+        classes: Interner<CharClass>,
         _phantom: PhantomData<T>,
     }
 
@@ -612,6 +1511,7 @@ pub mod nfa {
         #[allow(clippy::new_without_default)]
         pub fn new() -> Self {
             NFAParserEngine {
+                classes: Interner::new(),
                 _phantom: PhantomData,
             }
         }
@@ -634,7 +1534,7 @@ pub mod nfa {
         where
             C: Into<CharClass>,
         {
-            let class: CharClass = c.into();
+            let class = self.classes.intern(c.into());
             let transition = class.into();
 
             let mut nfa = NFA::new();
@@ -644,48 +1544,65 @@ pub mod nfa {
         }
 
         #[inline]
-        fn handle_wildcard(&mut self) -> Self::Output {
-            let class = CharClass::all_but_newline();
+        fn handle_wildcard(&mut self, class: CharClass) -> Self::Output {
             self.handle_char(class)
         }
 
         #[inline]
         fn handle_star(&mut self, lhs: Self::Output) -> Self::Output {
-            NFA::kleene_star(&lhs)
+            NFA::kleene_star_owned(lhs)
         }
 
         #[inline]
         fn handle_plus(&mut self, lhs: Self::Output) -> Self::Output {
-            NFA::concatenation(&NFA::kleene_star(&lhs), &lhs)
+            let star = NFA::kleene_star(&lhs);
+            NFA::concatenation_owned(lhs, star)
         }
 
         #[inline]
-        fn handle_optional(&mut self, lhs: Self::Output) -> Self::Output {
-            let c1 = NFA::new_epsilon();
-            NFA::union(&c1, &lhs)
+        fn handle_optional(&mut self, mut lhs: Self::Output) -> Self::Output {
+            // Prefer matching the operand over matching nothing.
+            NFA::union_into(&mut lhs, &NFA::new_epsilon());
+            lhs
         }
 
+        // The operands are combined in place, so that a long concatenation or alternation, built
+        // up from the left, doesn't copy the states of the operands already combined at each step.
+
         #[inline]
         fn handle_concat(&mut self, lhs: Self::Output, rhs: Self::Output) -> Self::Output {
-            NFA::concatenation(&lhs, &rhs)
+            NFA::concatenation_owned(lhs, rhs)
         }
 
         #[inline]
-        fn handle_alternate(&mut self, lhs: Self::Output, rhs: Self::Output) -> Self::Output {
-            NFA::union(&lhs, &rhs)
+        fn handle_alternate(&mut self, mut lhs: Self::Output, rhs: Self::Output) -> Self::Output {
+            NFA::union_into(&mut lhs, &rhs);
+            lhs
+        }
+
+        #[inline]
+        fn handle_group(&mut self, inner: Self::Output, capture_index: usize) -> Self::Output {
+            NFA::tagged(inner, 2 * capture_index, 2 * capture_index + 1)
+        }
+
+        #[inline]
+        fn handle_epsilon(&mut self) -> Option<Self::Output> {
+            Some(NFA::new_epsilon())
         }
     }
 }
 
 pub mod ast {
-    use super::{Parser, ParserEngine};
+    use super::{Anchor, Parser, ParserEngine};
     use crate::ast;
     use crate::class::CharClass;
 
     use std::hash::Hash;
     use std::marker::PhantomData;
+    use std::ops::Range;
 
     pub type ASTParser<T> = Parser<ASTParserEngine<T>>;
+    pub type SpannedASTParser<T> = Parser<SpannedASTParserEngine<T>>;
 
     /// A regular expression parser that produces an AST that describes the same language as the
     /// regular expression. The transitions of the AST must be derivable from CharClass.
@@ -731,8 +1648,7 @@ pub mod ast {
         }
 
         #[inline]
-        fn handle_wildcard(&mut self) -> Self::Output {
-            let class = CharClass::all_but_newline();
+        fn handle_wildcard(&mut self, class: CharClass) -> Self::Output {
             self.handle_char(class)
         }
 
@@ -742,9 +1658,8 @@ pub mod ast {
         }
 
         #[inline]
-        fn handle_plus(&mut self, rhs: Self::Output) -> Self::Output {
-            let lhs = self.handle_star(rhs.clone());
-            self.handle_concat(lhs, rhs)
+        fn handle_plus(&mut self, lhs: Self::Output) -> Self::Output {
+            ast::Expr::Unary(ast::UnaryOp::Plus, Box::new(lhs))
         }
 
         #[inline]
@@ -761,5 +1676,166 @@ pub mod ast {
         fn handle_alternate(&mut self, lhs: Self::Output, rhs: Self::Output) -> Self::Output {
             ast::Expr::Binary(ast::BinaryOp::Alternate, Box::new(lhs), Box::new(rhs))
         }
+
+        #[inline]
+        fn handle_group(&mut self, inner: Self::Output, capture_index: usize) -> Self::Output {
+            ast::Expr::Group(Box::new(inner), capture_index)
+        }
+
+        #[inline]
+        fn handle_empty(&mut self) -> Option<Self::Output> {
+            Some(ast::Expr::Empty)
+        }
+
+        #[inline]
+        fn handle_anchor(&mut self, kind: Anchor) -> Option<Self::Output> {
+            Some(ast::Expr::Anchor(kind))
+        }
+    }
+
+    /// A regular expression parser that produces an AST like [`ASTParserEngine`], with every node
+    /// wrapped in [`ast::Expr::Spanned`] to record the part of the regular expression it was parsed
+    /// from.
+    pub struct SpannedASTParserEngine<T>
+    where
+        T: Clone + Eq + Hash,
+    {
+        inner: ASTParserEngine<T>,
+    }
+
+    impl<T> SpannedASTParserEngine<T>
+    where
+        T: Clone + Eq + Hash,
+    {
+        /// Create a new SpannedASTParser.
+        #[inline]
+        #[allow(clippy::new_without_default)]
+        pub fn new() -> Self {
+            SpannedASTParserEngine {
+                inner: ASTParserEngine::new(),
+            }
+        }
+    }
+
+    impl<T> ParserEngine for SpannedASTParserEngine<T>
+    where
+        T: Clone + Eq + Hash,
+    {
+        type Output = ast::Expr;
+
+        #[inline]
+        fn new() -> Self {
+            Self::new()
+        }
+
+        #[inline]
+        fn handle_char<C>(&mut self, c: C) -> Self::Output
+        where
+            C: Into<CharClass>,
+        {
+            self.inner.handle_char(c)
+        }
+
+        #[inline]
+        fn handle_wildcard(&mut self, class: CharClass) -> Self::Output {
+            self.inner.handle_wildcard(class)
+        }
+
+        #[inline]
+        fn handle_star(&mut self, lhs: Self::Output) -> Self::Output {
+            self.inner.handle_star(lhs)
+        }
+
+        #[inline]
+        fn handle_plus(&mut self, lhs: Self::Output) -> Self::Output {
+            self.inner.handle_plus(lhs)
+        }
+
+        #[inline]
+        fn handle_optional(&mut self, lhs: Self::Output) -> Self::Output {
+            self.inner.handle_optional(lhs)
+        }
+
+        #[inline]
+        fn handle_concat(&mut self, lhs: Self::Output, rhs: Self::Output) -> Self::Output {
+            self.inner.handle_concat(lhs, rhs)
+        }
+
+        #[inline]
+        fn handle_alternate(&mut self, lhs: Self::Output, rhs: Self::Output) -> Self::Output {
+            self.inner.handle_alternate(lhs, rhs)
+        }
+
+        #[inline]
+        fn handle_group(&mut self, inner: Self::Output, capture_index: usize) -> Self::Output {
+            self.inner.handle_group(inner, capture_index)
+        }
+
+        #[inline]
+        fn handle_empty(&mut self) -> Option<Self::Output> {
+            self.inner.handle_empty()
+        }
+
+        #[inline]
+        fn handle_anchor(&mut self, kind: Anchor) -> Option<Self::Output> {
+            self.inner.handle_anchor(kind)
+        }
+
+        #[inline]
+        fn handle_span(&mut self, expr: Self::Output, span: Range<usize>) -> Self::Output {
+            ast::Expr::Spanned(Box::new(expr), span)
+        }
+    }
+}
+
+pub mod validate {
+    use super::{Parser, ParserEngine};
+    use crate::class::CharClass;
+
+    pub type Validator = Parser<ValidatorEngine>;
+
+    /// A regular expression parser that only checks that the regular expression is well-formed,
+    /// without constructing anything. It accepts exactly the regular expressions accepted by
+    /// [`NFAParser`](super::nfa::NFAParser).
+    #[derive(Debug)]
+    pub struct ValidatorEngine;
+
+    impl ParserEngine for ValidatorEngine {
+        type Output = ();
+
+        #[inline]
+        fn new() -> Self {
+            ValidatorEngine
+        }
+
+        #[inline]
+        fn handle_char<C>(&mut self, _c: C) -> Self::Output
+        where
+            C: Into<CharClass>,
+        {
+        }
+
+        #[inline]
+        fn handle_wildcard(&mut self, _class: CharClass) -> Self::Output {}
+
+        #[inline]
+        fn handle_star(&mut self, _lhs: Self::Output) -> Self::Output {}
+
+        #[inline]
+        fn handle_plus(&mut self, _lhs: Self::Output) -> Self::Output {}
+
+        #[inline]
+        fn handle_optional(&mut self, _lhs: Self::Output) -> Self::Output {}
+
+        #[inline]
+        fn handle_concat(&mut self, _lhs: Self::Output, _rhs: Self::Output) -> Self::Output {}
+
+        #[inline]
+        fn handle_alternate(&mut self, _lhs: Self::Output, _rhs: Self::Output) -> Self::Output {}
+
+        #[inline]
+        fn handle_epsilon(&mut self) -> Option<Self::Output> {
+            Some(())
+        }
     }
 }
@@ -0,0 +1,385 @@
+use crate::class::CharClass;
+use crate::regexp::MatchKind;
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder};
+use automata::nfa::Transition;
+use automata::NFA;
+use memchr::memmem;
+
+/// The most strings a regular expression may match for them to be searched for directly.
+const LITERALS_LIMIT: usize = 64;
+
+/// The most steps taken to find the strings matched by an NFA, so that NFAs with cycles, which
+/// match infinitely many strings, are given up on.
+const LITERALS_STEP_LIMIT: usize = 1 << 12;
+
+/// The most characters of each literal prefix found by [`literal_prefixes`]. Longer prefixes
+/// are no more selective in practice.
+const PREFIX_LEN_LIMIT: usize = 16;
+
+/// Find the literal string that every match of the NFA starts with. The string is empty if
+/// matches may start with different characters.
+#[inline]
+pub(crate) fn literal_prefix(nfa: &NFA<CharClass>) -> String {
+    let mut prefix = String::new();
+    let mut states = sorted(nfa.epsilon_closure(nfa.start_state));
+
+    // Stop on a cycle, which is only possible if the NFA accepts nothing.
+    let mut seen = HashSet::new();
+    while seen.insert(states.clone()) {
+        if states.iter().any(|s| nfa.is_accepting_state(s)) {
+            break;
+        }
+
+        let mut next_char = None;
+        let mut next_states = HashSet::new();
+        for &state in &states {
+            for (t, dests) in nfa.transitions_from(state) {
+                if let Transition::Some(class) = t {
+                    match single_char(class) {
+                        Some(c) if next_char.is_none() || next_char == Some(c) => {
+                            next_char = Some(c)
+                        }
+                        _ => return prefix,
+                    }
+                    next_states.extend(dests.iter().copied());
+                }
+            }
+        }
+
+        match next_char {
+            Some(c) => prefix.push(c),
+            None => break,
+        }
+        states = sorted(nfa.epsilon_closure_set(&next_states));
+    }
+
+    prefix
+}
+
+/// Find a few literal strings, none empty, such that every match of the NFA starts with one of
+/// them, as for an alternation of literals followed by anything else, like `(error|warn).*x`.
+/// `None` is returned if there are too many such strings, or if some match may start with any
+/// character.
+#[inline]
+pub(crate) fn literal_prefixes(nfa: &NFA<CharClass>) -> Option<Vec<String>> {
+    let mut prefixes = Vec::new();
+
+    let mut seen = HashSet::new();
+    let mut stack = vec![(nfa.start_state, String::new(), 0)];
+    while let Some((state, prefix, len)) = stack.pop() {
+        if seen.len() > LITERALS_STEP_LIMIT {
+            return None;
+        }
+        // If a match may end here, the prefix is long enough, or the state was already visited,
+        // as in a loop, the prefix so far is all that is needed of the matches through this
+        // state.
+        if !seen.insert(state) || len == PREFIX_LEN_LIMIT || nfa.is_accepting_state(&state) {
+            prefixes.push(prefix);
+            continue;
+        }
+
+        if let Some(dests) = nfa.transition.get(&state, &Transition::Epsilon) {
+            stack.extend(dests.iter().map(|&dest| (dest, prefix.clone(), len)));
+        }
+        for (t, dests) in nfa.transitions_from(state) {
+            if let Transition::Some(class) = t {
+                match single_char(class) {
+                    Some(c) => {
+                        let mut prefix = prefix.clone();
+                        prefix.push(c);
+                        stack.extend(dests.iter().map(|&dest| (dest, prefix.clone(), len + 1)));
+                    }
+                    None => prefixes.push(prefix.clone()),
+                }
+            }
+        }
+    }
+
+    // A prefix that starts with another is redundant, since the occurrences of the shorter one
+    // include its own.
+    prefixes.sort_unstable();
+    prefixes.dedup();
+    let mut reduced: Vec<String> = Vec::new();
+    for prefix in prefixes {
+        if !reduced
+            .last()
+            .iter()
+            .any(|last| prefix.starts_with(last.as_str()))
+        {
+            reduced.push(prefix);
+        }
+    }
+
+    if reduced.is_empty() || reduced.len() > LITERALS_LIMIT || reduced[0].is_empty() {
+        None
+    } else {
+        Some(reduced)
+    }
+}
+
+#[inline]
+fn single_char(class: &CharClass) -> Option<char> {
+    if class.is_single() {
+        class.iter().next().map(|r| r.start)
+    } else {
+        None
+    }
+}
+
+#[inline]
+fn sorted(states: HashSet<usize>) -> Vec<usize> {
+    let mut states: Vec<_> = states.into_iter().collect();
+    states.sort_unstable();
+    states
+}
+
+/// Scan the haystack for occurrences of any of the prefixes at or after `start`, and return the
+/// first result of `find` at the offset of an occurrence. See [`find`].
+#[inline]
+pub(crate) fn find_any<T, F>(
+    prefixes: &Literals,
+    haystack: &[u8],
+    start: usize,
+    mut find: F,
+) -> Option<T>
+where
+    F: FnMut(usize) -> Option<T>,
+{
+    let mut pos = start;
+    while let Some(m) = prefixes.find_at(haystack, pos) {
+        if let Some(found) = find(m.start) {
+            return Some(found);
+        }
+        // Occurrences may overlap, so continue from the next character, which is the first
+        // character of the prefix that occurred.
+        let first_len = std::str::from_utf8(&haystack[m.clone()])
+            .ok()
+            .and_then(|prefix| prefix.chars().next())
+            .map_or(1, char::len_utf8);
+        pos = m.start + first_len;
+    }
+    None
+}
+
+/// Scan the haystack for occurrences of the prefix at or after `start`, and return the first
+/// result of `find` at the offset of an occurrence. The prefix must not be empty.
+#[inline]
+pub(crate) fn find<T, F>(prefix: &str, haystack: &[u8], start: usize, mut find: F) -> Option<T>
+where
+    F: FnMut(usize) -> Option<T>,
+{
+    let first_len = prefix.chars().next().map_or(1, char::len_utf8);
+    let finder = memmem::Finder::new(prefix);
+
+    let mut pos = start;
+    while let Some(i) = finder.find(&haystack[pos..]) {
+        let candidate = pos + i;
+        if let Some(found) = find(candidate) {
+            return Some(found);
+        }
+        // Occurrences may overlap, so continue from the next character.
+        pos = candidate + first_len;
+    }
+    None
+}
+
+/// Find the strings that the NFA matches, in order of priority under leftmost-first semantics, if
+/// it matches a few literal strings and nothing else.
+#[inline]
+pub(crate) fn literals(nfa: &NFA<CharClass>) -> Option<Vec<String>> {
+    let mut literals: Vec<String> = Vec::new();
+
+    // The frames are visited in the same order as the threads of the Pike VM: the strings that
+    // continue from a state come before the string that ends there, followed by those through its
+    // epsilon transitions. `None` stands for the string ending at the state.
+    let mut stack = vec![(Some(nfa.start_state), String::new())];
+    let mut steps = 0;
+    while let Some((state, literal)) = stack.pop() {
+        steps += 1;
+        if steps > LITERALS_STEP_LIMIT {
+            return None;
+        }
+
+        let state = match state {
+            Some(state) => state,
+            None => {
+                if !literals.contains(&literal) {
+                    if literals.len() == LITERALS_LIMIT {
+                        return None;
+                    }
+                    literals.push(literal);
+                }
+                continue;
+            }
+        };
+
+        if let Some(dests) = nfa.transition.get(&state, &Transition::Epsilon) {
+            for &dest in dests.iter().rev() {
+                stack.push((Some(dest), literal.clone()));
+            }
+        }
+        if nfa.is_accepting_state(&state) {
+            stack.push((None, literal.clone()));
+        }
+        for (t, dests) in nfa.transitions_from(state) {
+            if let Transition::Some(class) = t {
+                let c = single_char(class)?;
+                for &dest in dests.iter().rev() {
+                    let mut literal = literal.clone();
+                    literal.push(c);
+                    stack.push((Some(dest), literal));
+                }
+            }
+        }
+    }
+
+    if literals.is_empty() {
+        None
+    } else {
+        Some(literals)
+    }
+}
+
+/// A searcher for regular expressions that match only a few literal strings, which finds them with
+/// a substring search instead of running an automaton.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate", try_from = "Patterns", into = "Patterns")
+)]
+pub(crate) struct Literals {
+    patterns: Vec<String>,
+    searcher: Searcher,
+}
+
+#[derive(Clone, Debug)]
+enum Searcher {
+    One(memmem::Finder<'static>),
+    Many(AhoCorasick),
+}
+
+/// The strings searched for by [`Literals`], which are all that is serialized of it.
+#[cfg(feature = "serde")]
+#[derive(Clone, serde_crate::Serialize, serde_crate::Deserialize)]
+#[serde(crate = "serde_crate")]
+struct Patterns {
+    patterns: Vec<String>,
+    match_kind: MatchKind,
+}
+
+impl Literals {
+    /// Create a searcher for the strings, given in order of priority. `None` is returned if there
+    /// are none, or if there are several and one is empty, since the Aho-Corasick automaton
+    /// doesn't report empty matches in its leftmost modes.
+    #[inline]
+    pub(crate) fn new(patterns: Vec<String>, match_kind: MatchKind) -> Option<Self> {
+        let searcher = match patterns.as_slice() {
+            [] => return None,
+            [pattern] => Searcher::One(memmem::Finder::new(pattern).into_owned()),
+            patterns if patterns.iter().any(String::is_empty) => return None,
+            patterns => {
+                let kind = match match_kind {
+                    MatchKind::LeftmostLongest => aho_corasick::MatchKind::LeftmostLongest,
+                    MatchKind::LeftmostFirst => aho_corasick::MatchKind::LeftmostFirst,
+                };
+                Searcher::Many(AhoCorasickBuilder::new().match_kind(kind).build(patterns))
+            }
+        };
+        Some(Self { patterns, searcher })
+    }
+
+    /// The strings searched for.
+    #[inline]
+    pub(crate) fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    /// Determine if the input is one of the strings.
+    #[inline]
+    pub(crate) fn is_match(&self, input: &[u8]) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| pattern.as_bytes() == input)
+    }
+
+    /// Find the leftmost occurrence of one of the strings at or after `start`. Of the strings that
+    /// occur at the leftmost position, the one preferred by the match kind is returned.
+    #[inline]
+    pub(crate) fn find_at(&self, haystack: &[u8], start: usize) -> Option<Range<usize>> {
+        match &self.searcher {
+            Searcher::One(finder) => finder
+                .find(&haystack[start..])
+                .map(|i| start + i..start + i + finder.needle().len()),
+            Searcher::Many(ac) => ac
+                .find(&haystack[start..])
+                .map(|m| start + m.start()..start + m.end()),
+        }
+    }
+
+    /// Find the leftmost occurrence of one of the strings at or after `start`. Of the strings that
+    /// occur at the leftmost position, the shortest is returned.
+    #[inline]
+    pub(crate) fn find_shortest_at(&self, haystack: &[u8], start: usize) -> Option<Range<usize>> {
+        let m = self.find_at(haystack, start)?;
+        self.find_shortest_anchored_at(haystack, m.start)
+    }
+
+    /// Find an occurrence of one of the strings at `start`, preferred by the match kind.
+    #[inline]
+    pub(crate) fn find_anchored_at(&self, haystack: &[u8], start: usize) -> Option<Range<usize>> {
+        // Only the occurrences that start at `start` can fit in the truncated haystack.
+        let longest = self.patterns.iter().map(String::len).max().unwrap_or(0);
+        let end = haystack.len().min(start + longest);
+        self.find_at(&haystack[..end], start)
+            .filter(|m| m.start == start)
+    }
+
+    /// Find the shortest occurrence of one of the strings at `start`.
+    #[inline]
+    pub(crate) fn find_shortest_anchored_at(
+        &self,
+        haystack: &[u8],
+        start: usize,
+    ) -> Option<Range<usize>> {
+        self.patterns
+            .iter()
+            .map(String::as_bytes)
+            .filter(|pattern| haystack[start..].starts_with(pattern))
+            .map(<[u8]>::len)
+            .min()
+            .map(|len| start..start + len)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<Patterns> for Literals {
+    type Error = &'static str;
+
+    #[inline]
+    fn try_from(patterns: Patterns) -> Result<Self, Self::Error> {
+        Literals::new(patterns.patterns, patterns.match_kind).ok_or("no literal strings")
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Literals> for Patterns {
+    #[inline]
+    fn from(literals: Literals) -> Self {
+        // The match kind makes no difference to the search for a single string.
+        let match_kind = match &literals.searcher {
+            Searcher::Many(ac) if *ac.match_kind() == aho_corasick::MatchKind::LeftmostFirst => {
+                MatchKind::LeftmostFirst
+            }
+            _ => MatchKind::LeftmostLongest,
+        };
+        Patterns {
+            patterns: literals.patterns,
+            match_kind,
+        }
+    }
+}
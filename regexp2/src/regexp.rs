@@ -1,134 +1,1095 @@
+use crate::ast::{CompileError, Expr};
 use crate::class::CharClass;
-use crate::parser::{self, nfa::NFAParser};
+use crate::meta::{Meta, Strategy};
+use crate::parser::{self, ast::ASTParser, nfa::NFAParser, validate::Validator};
+use crate::prefilter::{self, Literals};
 
+use std::fmt;
+use std::mem;
 use std::ops::Range;
+use std::str::FromStr;
 
-use automata::{self, nfa::Transition, DFA, NFA};
+use automata::backtrack::BoundedBacktracker;
+use automata::convert::DFAFromNFA;
+use automata::onepass::OnePass;
+use automata::pikevm::PikeVM;
+use automata::step::StepResult;
+use automata::{self, DenseDFA, LazyDFA, StateSet, DFA, NFA};
 
-pub use parser::ParseResult;
+pub use automata::MatchKind;
+pub use parser::{ParseError, ParseResult};
 
-#[derive(Debug)]
-pub struct Match {
+/// Error returned when a regular expression fails to compile.
+#[derive(Debug, thiserror::Error)]
+pub enum Error<'r> {
+    /// The regular expression could not be parsed.
+    #[error("{0}")]
+    Parse(ParseError<'r>),
+    /// The compiled NFA would have more states than the configured limit.
+    #[error("compiled NFA exceeds the limit of {limit} states")]
+    NFASizeLimitExceeded { limit: usize },
+    /// The compiled DFA would have more states than the configured limit.
+    #[error("compiled DFA exceeds the limit of {limit} states")]
+    DFASizeLimitExceeded { limit: usize },
+}
+
+impl<'r> Error<'r> {
+    /// Convert the error into one that doesn't borrow the regular expression.
+    #[inline]
+    pub fn into_owned(self) -> Error<'static> {
+        match self {
+            Error::Parse(err) => Error::Parse(err.into_owned()),
+            Error::NFASizeLimitExceeded { limit } => Error::NFASizeLimitExceeded { limit },
+            Error::DFASizeLimitExceeded { limit } => Error::DFASizeLimitExceeded { limit },
+        }
+    }
+}
+
+impl<'r> From<ParseError<'r>> for Error<'r> {
+    #[inline]
+    fn from(err: ParseError<'r>) -> Self {
+        Error::Parse(err)
+    }
+}
+
+/// A single match of a regular expression in an input string, described by byte offsets into the
+/// input.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Match<'t> {
+    text: &'t str,
     start: usize,
     end: usize,
-
-    pub span: String,
 }
 
-impl Match {
+impl<'t> Match<'t> {
+    #[inline]
+    pub(crate) const fn new(text: &'t str, start: usize, end: usize) -> Self {
+        Self { text, start, end }
+    }
+
+    /// Convert a match over the characters of `text[offset..]` to a match of byte offsets into
+    /// `text`.
     #[inline]
-    pub const fn new(start: usize, end: usize, span: String) -> Self {
-        Self { start, end, span }
+    fn from_chars(text: &'t str, offset: usize, m: automata::Match<char>) -> Self {
+        let start = offset
+            + text[offset..]
+                .chars()
+                .take(m.start())
+                .map(char::len_utf8)
+                .sum::<usize>();
+        let end = start + m.span.iter().copied().map(char::len_utf8).sum::<usize>();
+        Self::new(text, start, end)
     }
 
+    /// The byte offset of the start of the match.
     #[inline]
     pub const fn start(&self) -> usize {
         self.start
     }
 
+    /// The byte offset of the end of the match.
     #[inline]
     pub const fn end(&self) -> usize {
         self.end
     }
 
+    /// The byte range of the match.
     #[inline]
     pub const fn range(&self) -> Range<usize> {
         self.start..self.end
     }
+
+    /// The matched text.
+    #[inline]
+    pub fn as_str(&self) -> &'t str {
+        &self.text[self.range()]
+    }
 }
 
-impl From<automata::Match<char>> for Match {
+/// The capture groups of a match. Group 0 is the whole match, and the other groups are numbered
+/// by their opening parenthesis, starting from 1. See [`RegExp::captures`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Captures<'t> {
+    text: &'t str,
+    /// The byte offsets of the start and end of each group.
+    slots: Vec<Option<usize>>,
+}
+
+impl<'t> Captures<'t> {
+    /// The match of the group with the given index, or `None` if the group didn't participate in
+    /// the match or doesn't exist.
+    #[inline]
+    pub fn get(&self, i: usize) -> Option<Match<'t>> {
+        match (self.slots.get(2 * i)?, self.slots.get(2 * i + 1)?) {
+            (&Some(start), &Some(end)) => Some(Match::new(self.text, start, end)),
+            _ => None,
+        }
+    }
+
+    /// The number of groups, including group 0.
     #[inline]
-    fn from(m: automata::Match<char>) -> Self {
-        Self::new(m.start(), m.end(), m.span.into_iter().collect())
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.slots.len() / 2
+    }
+
+    /// Iterate over the matches of the groups in order, starting with group 0.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = Option<Match<'t>>> + '_ {
+        (0..self.len()).map(move |i| self.get(i))
+    }
+
+    /// Write a template to `dst`, replacing each reference to a group with the text it matched.
+    ///
+    /// A group is referred to by `$` followed by its index, as in `$1`, or by its index in braces,
+    /// as in `${1}`, which may be followed by more digits. A group that didn't participate in the
+    /// match or doesn't exist is replaced by nothing, as is a name in braces, since groups have no
+    /// names. `$$` is replaced by a single `$`, and any other `$` is written as is.
+    ///
+    /// ```
+    /// use regexp2::RegExp;
+    ///
+    /// let re = RegExp::new(r"(\w+)@(\w+)").unwrap();
+    /// let caps = re.captures("mail bob@host").unwrap();
+    /// let mut dst = String::new();
+    /// caps.expand("$2: ${1}0 ($$5)", &mut dst).unwrap();
+    /// assert_eq!("host: bob0 ($5)", dst);
+    /// ```
+    #[inline]
+    pub fn expand<W>(&self, template: &str, dst: &mut W) -> fmt::Result
+    where
+        W: fmt::Write + ?Sized,
+    {
+        let mut rest = template;
+        while let Some(i) = rest.find('$') {
+            dst.write_str(&rest[..i])?;
+            rest = &rest[i + 1..];
+            if let Some(escaped) = rest.strip_prefix('$') {
+                dst.write_str("$")?;
+                rest = escaped;
+                continue;
+            }
+
+            match group_reference(rest) {
+                Some((name, len)) => {
+                    let m = name.parse().ok().and_then(|i| self.get(i));
+                    if let Some(m) = m {
+                        dst.write_str(m.as_str())?;
+                    }
+                    rest = &rest[len..];
+                }
+                None => dst.write_str("$")?,
+            }
+        }
+        dst.write_str(rest)
     }
 }
 
 /// A compiled regular expression for matching strings. It may be used to determine if given
 /// strings are within the language described by the regular expression.
+///
+/// With the `serde` feature, a regular expression whose engine supports serde can be serialized,
+/// so that it can be cached and loaded later without being compiled again.
 #[derive(Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde_crate::Serialize, serde_crate::Deserialize),
+    serde(crate = "serde_crate")
+)]
 pub struct RegExp<E: Engine> {
     /// The regular expression represented by this structure.
     expr: String,
     /// The compiled backend of the regular expression used to evaluate input strings.
     engine: E,
+    /// The semantics used to choose between matches that start at the same position.
+    match_kind: MatchKind,
+    /// A backend used instead of `engine` when searching, if `engine` can't search with
+    /// `match_kind` itself.
+    searcher: Option<E>,
+    /// A literal string that every match starts with, used to skip to the positions where a match
+    /// may start before running the backend.
+    prefix: String,
+    /// A few literal strings that every match starts with one of, used like `prefix` if there is
+    /// no common prefix.
+    #[cfg_attr(feature = "serde", serde(default))]
+    prefixes: Option<Literals>,
+    /// The strings matched by the regular expression, if it matches only a few literal strings,
+    /// which are then searched for directly instead of running the backend.
+    #[cfg_attr(feature = "serde", serde(default))]
+    literals: Option<Literals>,
+    /// A backend for the reversed regular expression with a leading `.*`, used to find the start
+    /// of the leftmost match by scanning the input backwards. See [`Engine::rfind_start`].
+    reverse: Option<E>,
+    /// A Pike VM over the NFA of the regular expression, used to find the positions of its
+    /// capture groups, or `None` if it has none.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pikevm: Option<PikeVM<CharClass>>,
+    /// A one-pass DFA compiled from the NFA of the Pike VM, used instead of it if the regular
+    /// expression is one-pass.
+    #[cfg_attr(feature = "serde", serde(default))]
+    onepass: Option<OnePass<CharClass>>,
+}
+
+/// The compiled parts of a regular expression, so that the `regexp2!` macro of `regexp2-macros`
+/// can emit a regular expression compiled at build time. Not part of the public API.
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct RegExpParts<E> {
+    pub expr: String,
+    pub engine: E,
+    pub match_kind: MatchKind,
+    pub searcher: Option<E>,
+    pub prefix: String,
+    pub prefixes: Vec<String>,
+    pub literals: Vec<String>,
+    pub reverse: Option<E>,
+    pub pikevm: Option<PikeVM<CharClass>>,
+    pub onepass: Option<OnePass<CharClass>>,
 }
 
 impl<E: Engine> RegExp<E> {
+    #[doc(hidden)]
+    #[inline]
+    pub fn from_parts(parts: RegExpParts<E>) -> Self {
+        RegExp {
+            expr: parts.expr,
+            engine: parts.engine,
+            match_kind: parts.match_kind,
+            searcher: parts.searcher,
+            prefix: parts.prefix,
+            prefixes: Literals::new(parts.prefixes, MatchKind::LeftmostFirst),
+            literals: Literals::new(parts.literals, parts.match_kind),
+            reverse: parts.reverse,
+            pikevm: parts.pikevm,
+            onepass: parts.onepass,
+        }
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn into_parts(self) -> RegExpParts<E> {
+        RegExpParts {
+            expr: self.expr,
+            engine: self.engine,
+            match_kind: self.match_kind,
+            searcher: self.searcher,
+            prefix: self.prefix,
+            prefixes: self
+                .prefixes
+                .map(|prefixes| prefixes.patterns().to_vec())
+                .unwrap_or_default(),
+            literals: self
+                .literals
+                .map(|literals| literals.patterns().to_vec())
+                .unwrap_or_default(),
+            reverse: self.reverse,
+            pikevm: self.pikevm,
+            onepass: self.onepass,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    pub(crate) fn from_engine(
+        expr: &str,
+        engine: E,
+        match_kind: MatchKind,
+        searcher: Option<E>,
+        prefix: String,
+        prefixes: Option<Vec<String>>,
+        literals: Option<Vec<String>>,
+        reverse: Option<E>,
+        pikevm: Option<PikeVM<CharClass>>,
+    ) -> Self {
+        RegExp {
+            expr: expr.to_owned(),
+            engine,
+            match_kind,
+            searcher,
+            // None of the prefixes is a prefix of another, so which is preferred makes no
+            // difference.
+            prefixes: prefixes
+                .filter(|_| prefix.is_empty())
+                .and_then(|prefixes| Literals::new(prefixes, MatchKind::LeftmostFirst)),
+            prefix,
+            literals: literals.and_then(|literals| Literals::new(literals, match_kind)),
+            reverse,
+            onepass: pikevm
+                .as_ref()
+                .and_then(|pikevm| OnePass::new(pikevm.nfa())),
+            pikevm,
+        }
+    }
+
+    /// The semantics used to choose between matches that start at the same position.
+    #[inline]
+    pub fn match_kind(&self) -> MatchKind {
+        self.match_kind
+    }
+
+    #[inline]
+    pub(crate) fn searcher(&self) -> &E {
+        self.searcher.as_ref().unwrap_or(&self.engine)
+    }
+
+    /// The pattern the regular expression was compiled from.
     #[inline]
     pub fn as_str(&self) -> &str {
         &self.expr
     }
 
+    /// The compiled backend used to evaluate input strings.
+    #[inline]
+    pub fn engine(&self) -> &E {
+        &self.engine
+    }
+
+    /// Parse the pattern the regular expression was compiled from into an AST. The pattern is
+    /// parsed with the default configuration, so options set with a
+    /// [`RegExpBuilder`](crate::RegExpBuilder) that aren't written in the pattern itself aren't
+    /// reflected in the AST.
+    ///
+    /// ```
+    /// use regexp2::ast::Expr;
+    /// use regexp2::RegExp;
+    ///
+    /// let re = RegExp::new("ab|c").unwrap();
+    /// let expr = re.ast().unwrap().strip_spans();
+    /// assert_eq!(Expr::literal("ab").or(Expr::literal("c")), expr);
+    /// ```
+    #[inline]
+    pub fn ast(&self) -> ParseResult<'_, Expr> {
+        ASTParser::<CharClass>::new().parse(&self.expr)
+    }
+
     /// Determine if the given input string is within the language described by the regular
     /// expression.
     #[inline]
     pub fn is_match(&self, input: &str) -> bool {
-        self.engine.is_match(input)
+        match &self.literals {
+            Some(literals) => literals.is_match(input.as_bytes()),
+            None => self.engine.is_match(input),
+        }
+    }
+
+    /// Determine if the rest of the input string from the byte offset `start` is within the
+    /// language described by the regular expression. This is the same as matching
+    /// `&input[start..]`, since matches don't depend on the input before them.
+    ///
+    /// ```
+    /// use regexp2::RegExp;
+    ///
+    /// let re = RegExp::new("b+").unwrap();
+    /// assert!(re.is_match_at("aabb", 2));
+    /// assert!(!re.is_match_at("aabb", 1));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start` is greater than the length of the input string or is not on a character
+    /// boundary.
+    #[inline]
+    pub fn is_match_at(&self, input: &str, start: usize) -> bool {
+        self.is_match(&input[start..])
     }
 
+    /// Find the leftmost match in the input string. Of the matches that start at the leftmost
+    /// position, the one preferred by the [`MatchKind`] is returned.
     #[inline]
-    pub fn find(&self, input: &str) -> Option<Match> {
+    pub fn find<'t>(&self, input: &'t str) -> Option<Match<'t>> {
         self.find_at(input, 0)
     }
 
+    /// Find the leftmost match that starts at or after the byte offset `start` of the input
+    /// string. The offsets of the match are into the whole input string, not the part after
+    /// `start`, and a match may be empty if it starts at the end of the input.
+    ///
+    /// ```
+    /// use regexp2::RegExp;
+    ///
+    /// let re = RegExp::new("é+").unwrap();
+    /// let m = re.find_at("éaéé", 2).unwrap();
+    /// assert_eq!((3, 7), (m.start(), m.end()));
+    /// assert_eq!(None, re.find_at("éaéé", 7));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start` is greater than the length of the input string or is not on a character
+    /// boundary.
     #[inline]
-    pub fn find_at(&self, input: &str, start: usize) -> Option<Match> {
-        self.engine.find_at(input, start)
+    pub fn find_at<'t>(&self, input: &'t str, start: usize) -> Option<Match<'t>> {
+        check_start(input, start);
+        let searcher = self.searcher();
+        if let Some(literals) = &self.literals {
+            let m = literals.find_at(input.as_bytes(), start)?;
+            Some(Match::new(input, m.start, m.end))
+        } else if let Some(prefixes) = &self.prefixes {
+            prefilter::find_any(prefixes, input.as_bytes(), start, |pos| {
+                searcher.find_anchored_at(input, pos, self.match_kind)
+            })
+        } else if !self.prefix.is_empty() {
+            prefilter::find(&self.prefix, input.as_bytes(), start, |pos| {
+                searcher.find_anchored_at(input, pos, self.match_kind)
+            })
+        } else if let Some(reverse) = &self.reverse {
+            let start = reverse.rfind_start(&self.engine, input, start)?;
+            searcher.find_anchored_at(input, start, self.match_kind)
+        } else {
+            searcher.find_at(input, start, self.match_kind)
+        }
     }
 
+    /// Find the leftmost match in the input string. Of the matches that start at the leftmost
+    /// position, the shortest is returned.
     #[inline]
-    pub fn find_shortest(&self, input: &str) -> Option<Match> {
+    pub fn find_shortest<'t>(&self, input: &'t str) -> Option<Match<'t>> {
         self.find_shortest_at(input, 0)
     }
 
+    /// Find the shortest of the leftmost matches that start at or after the byte offset `start` of
+    /// the input string, with offsets into the whole input string as for
+    /// [`find_at`](Self::find_at).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start` is greater than the length of the input string or is not on a character
+    /// boundary.
+    #[inline]
+    pub fn find_shortest_at<'t>(&self, input: &'t str, start: usize) -> Option<Match<'t>> {
+        check_start(input, start);
+        let searcher = self.searcher();
+        if let Some(literals) = &self.literals {
+            let m = literals.find_shortest_at(input.as_bytes(), start)?;
+            Some(Match::new(input, m.start, m.end))
+        } else if let Some(prefixes) = &self.prefixes {
+            prefilter::find_any(prefixes, input.as_bytes(), start, |pos| {
+                searcher.find_shortest_anchored_at(input, pos)
+            })
+        } else if !self.prefix.is_empty() {
+            prefilter::find(&self.prefix, input.as_bytes(), start, |pos| {
+                searcher.find_shortest_anchored_at(input, pos)
+            })
+        } else if let Some(reverse) = &self.reverse {
+            let start = reverse.rfind_start(&self.engine, input, start)?;
+            searcher.find_shortest_anchored_at(input, start)
+        } else {
+            searcher.find_shortest_at(input, start)
+        }
+    }
+
+    /// Find the leftmost match in the input string, as with [`find`](Self::find), along with the
+    /// positions of its capture groups.
+    ///
+    /// Where the groups could be placed in several ways within the match, they are placed as a
+    /// backtracking engine would place them, as for [`MatchKind::LeftmostFirst`]. A group that is
+    /// repeated holds its last repetition. The groups are found in a single pass if the regular
+    /// expression is one-pass, where each character of a match determines the group it belongs to;
+    /// otherwise, the groups of short matches are found by a bounded backtracker, and those of
+    /// longer matches by a Pike VM.
+    ///
+    /// ```
+    /// use regexp2::RegExp;
+    ///
+    /// let re = RegExp::new("(a+)(b|(c))").unwrap();
+    /// let caps = re.captures("xaab").unwrap();
+    /// assert_eq!("aab", caps.get(0).unwrap().as_str());
+    /// assert_eq!("aa", caps.get(1).unwrap().as_str());
+    /// assert_eq!("b", caps.get(2).unwrap().as_str());
+    /// assert_eq!(None, caps.get(3));
+    /// ```
     #[inline]
-    pub fn find_shortest_at(&self, input: &str, start: usize) -> Option<Match> {
-        self.engine.find_shortest_at(input, start)
+    pub fn captures<'t>(&self, input: &'t str) -> Option<Captures<'t>> {
+        self.captures_at(input, 0)
+    }
+
+    /// Find the leftmost match that starts at or after the byte offset `start` of the input
+    /// string, along with the positions of its capture groups. See [`captures`](Self::captures)
+    /// and [`find_at`](Self::find_at).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start` is greater than the length of the input string or is not on a character
+    /// boundary.
+    #[inline]
+    pub fn captures_at<'t>(&self, input: &'t str, start: usize) -> Option<Captures<'t>> {
+        let m = self.find_at(input, start)?;
+        Some(self.captures_of(m))
+    }
+
+    /// Iterate over the successive non-overlapping matches in the input string, along with the
+    /// positions of their capture groups. Each match is found as with [`captures`](Self::captures)
+    /// in the input after the previous match. An empty match that ends where the previous match
+    /// ended is skipped, so the iterator always advances.
+    ///
+    /// ```
+    /// use regexp2::RegExp;
+    ///
+    /// let re = RegExp::new(r"(\w+)=(\w*)").unwrap();
+    /// let pairs: Vec<_> = re
+    ///     .captures_iter("a=1 b= c=3")
+    ///     .map(|caps| (caps.get(1).unwrap().as_str(), caps.get(2).unwrap().as_str()))
+    ///     .collect();
+    /// assert_eq!(vec![("a", "1"), ("b", ""), ("c", "3")], pairs);
+    /// ```
+    #[inline]
+    pub fn captures_iter<'r, 't>(&'r self, input: &'t str) -> CaptureMatches<'r, 't, E> {
+        CaptureMatches {
+            re: self,
+            input,
+            pos: Some(0),
+            last_end: None,
+        }
+    }
+
+    /// Find the positions of the capture groups of a match.
+    #[inline]
+    fn captures_of<'t>(&self, m: Match<'t>) -> Captures<'t> {
+        let input = m.text;
+        let pikevm = match &self.pikevm {
+            Some(pikevm) => pikevm,
+            None => {
+                return Captures {
+                    text: input,
+                    slots: vec![Some(m.start()), Some(m.end())],
+                }
+            }
+        };
+
+        // The one-pass DFA is the fastest, if the regular expression is one-pass. Otherwise the
+        // backtracker is usually faster than the Pike VM, but only searches inputs short enough for
+        // its visited set.
+        let slots = if let Some(onepass) = &self.onepass {
+            onepass.captures_whole(m.as_str().chars())
+        } else {
+            let chars: Vec<_> = m.as_str().chars().collect();
+            let backtracker = BoundedBacktracker::new(pikevm.nfa());
+            if chars.len() <= backtracker.max_input_len() {
+                backtracker.captures_whole(&chars)
+            } else {
+                pikevm.captures_whole(chars.iter().copied())
+            }
+        }
+        .expect("the match should be accepted by the NFA of the regular expression");
+
+        // Convert the character positions of the slots to byte offsets.
+        let mut offsets: Vec<_> = m.as_str().char_indices().map(|(i, _)| i).collect();
+        offsets.push(m.as_str().len());
+        let slots = slots
+            .into_iter()
+            .map(|slot| slot.map(|pos| m.start() + offsets[pos]))
+            .collect();
+        Captures { text: input, slots }
+    }
+
+    /// The number of capture groups, including group 0 for the whole match.
+    #[inline]
+    pub fn captures_len(&self) -> usize {
+        self.pikevm.as_ref().map_or(1, |pikevm| pikevm.slots() / 2)
+    }
+
+    /// The literal string that every match starts with. It is empty if matches may start with
+    /// different characters.
+    #[inline]
+    pub fn literal_prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// A few literal strings that every match starts with one of, if there is no
+    /// [`literal_prefix`](Self::literal_prefix), used to skip to the positions where a match may
+    /// start. It is empty if some match may start with any of many characters.
+    ///
+    /// ```
+    /// use regexp2::RegExp;
+    ///
+    /// let re = RegExp::new("(error|warn|fatal).*timeout").unwrap();
+    /// assert_eq!("", re.literal_prefix());
+    /// assert_eq!(["error", "fatal", "warn"], re.literal_prefixes());
+    /// ```
+    #[inline]
+    pub fn literal_prefixes(&self) -> &[String] {
+        self.prefixes
+            .as_ref()
+            .map_or(&[], |prefixes| prefixes.patterns())
+    }
+
+    /// Iterate over the matches in the input string that start at each position, including
+    /// matches that overlap. Of the matches that start at the same position, only the one
+    /// preferred by the [`MatchKind`] is reported.
+    #[inline]
+    pub fn find_overlapping_iter<'r, 't>(&'r self, input: &'t str) -> FindOverlapping<'r, 't, E> {
+        FindOverlapping {
+            re: self,
+            input,
+            pos: Some(0),
+        }
+    }
+}
+
+/// An iterator over the overlapping matches in an input string. See
+/// [`RegExp::find_overlapping_iter`].
+#[derive(Debug)]
+pub struct FindOverlapping<'r, 't, E: Engine> {
+    re: &'r RegExp<E>,
+    input: &'t str,
+    /// The byte offset to search from next, or `None` if the search is done.
+    pos: Option<usize>,
+}
+
+impl<'r, 't, E: Engine> Iterator for FindOverlapping<'r, 't, E> {
+    type Item = Match<'t>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let m = self.re.find_at(self.input, self.pos?);
+
+        // Continue from the character after the start of the match.
+        self.pos = m.and_then(|m| {
+            self.input[m.start()..]
+                .chars()
+                .next()
+                .map(|c| m.start() + c.len_utf8())
+        });
+        m
+    }
+}
+
+/// Parse the group reference at the start of the template text after a `$`, returning the name or
+/// index of the group and the length of the reference.
+#[inline]
+fn group_reference(template: &str) -> Option<(&str, usize)> {
+    if let Some(braced) = template.strip_prefix('{') {
+        let end = braced.find('}')?;
+        return if end == 0 {
+            None
+        } else {
+            Some((&braced[..end], end + 2))
+        };
+    }
+
+    let end = template
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(template.len());
+    if end == 0 {
+        None
+    } else {
+        Some((&template[..end], end))
+    }
+}
+
+/// An iterator over the non-overlapping matches in an input string and their capture groups. See
+/// [`RegExp::captures_iter`].
+#[derive(Debug)]
+pub struct CaptureMatches<'r, 't, E: Engine> {
+    re: &'r RegExp<E>,
+    input: &'t str,
+    /// The byte offset to search from next, or `None` if the search is done.
+    pos: Option<usize>,
+    /// The byte offset of the end of the previous match.
+    last_end: Option<usize>,
+}
+
+impl<'r, 't, E: Engine> Iterator for CaptureMatches<'r, 't, E> {
+    type Item = Captures<'t>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let m = match self.re.find_at(self.input, self.pos?) {
+                Some(m) => m,
+                None => {
+                    self.pos = None;
+                    return None;
+                }
+            };
+
+            if m.start() == m.end() && Some(m.end()) == self.last_end {
+                // Retry from the next character, so that the same empty match isn't found again.
+                self.pos = self.input[m.end()..]
+                    .chars()
+                    .next()
+                    .map(|c| m.end() + c.len_utf8());
+                continue;
+            }
+
+            self.pos = Some(m.end());
+            self.last_end = Some(m.end());
+            return Some(self.re.captures_of(m));
+        }
     }
 }
 
+/// The maximum number of states of the DFA used to find the start of the leftmost match. The DFA
+/// can be exponentially larger than the one for the regular expression itself, in which case
+/// searches fall back to trying each starting position in turn.
+pub(crate) const REVERSE_SIZE_LIMIT: usize = 1 << 10;
+
+/// Create the Pike VM used to find the capture groups of a regular expression compiled to the NFA,
+/// or `None` if it has no capture groups.
+#[inline]
+pub(crate) fn pikevm(nfa: &NFA<CharClass>) -> Option<PikeVM<CharClass>> {
+    if nfa.tags.is_empty() {
+        None
+    } else {
+        Some(PikeVM::new(nfa.clone()))
+    }
+}
+
+/// Check that a search can start at the byte offset `start` of the input string, so that every
+/// backend panics on the same offsets.
+#[inline]
+fn check_start(input: &str, start: usize) {
+    assert!(
+        input.is_char_boundary(start),
+        "search starts at byte offset {}, which is not a character boundary of the input of length {}",
+        start,
+        input.len()
+    );
+}
+
+/// Construct an NFA for the reversal of an NFA with a leading `.*`. Scanning the input backwards
+/// from the end, it is in an accepting state exactly at the positions where a match of the original
+/// NFA starts.
+#[inline]
+pub(crate) fn unanchored_reverse(nfa: &NFA<CharClass>) -> NFA<CharClass> {
+    let mut reverse = nfa.reverse();
+    let start_state = reverse.start_state;
+    reverse.add_labeled_transition(start_state, start_state, CharClass::all());
+    reverse
+}
+
 impl RegExp<NFA<CharClass>> {
     /// Create a compiled regular expression that uses an NFA to evaluate input strings.
     #[inline]
     pub fn new_nfa(expr: &'_ str) -> ParseResult<'_, Self> {
         let parser = NFAParser::new();
-        let nfa: NFA<CharClass> = parser.parse(expr)?;
+        let nfa = parser.parse(expr)?;
+        Ok(Self::from_nfa(expr, nfa))
+    }
 
-        Ok(RegExp {
-            expr: expr.to_owned(),
-            engine: nfa,
-        })
+    /// Compile the NFA of a regular expression.
+    #[inline]
+    fn from_nfa(expr: &str, mut nfa: NFA<CharClass>) -> Self {
+        nfa.cache_epsilon_closures();
+        let prefix = prefilter::literal_prefix(&nfa);
+        let prefixes = prefilter::literal_prefixes(&nfa);
+        let literals = prefilter::literals(&nfa);
+        let pikevm = pikevm(&nfa);
+
+        RegExp::from_engine(
+            expr,
+            nfa,
+            MatchKind::default(),
+            None,
+            prefix,
+            prefixes,
+            literals,
+            None,
+            pikevm,
+        )
+    }
+
+    /// The NFA used to evaluate input strings, for analyses of the automaton.
+    #[inline]
+    pub fn nfa(&self) -> &NFA<CharClass> {
+        &self.engine
     }
 
     #[inline]
-    pub fn with_dfa(self) -> RegExp<DFA<CharClass>> {
+    pub fn with_dfa(self) -> RegExp<DenseDFA<CharClass>> {
+        let searcher = match self.match_kind {
+            MatchKind::LeftmostLongest => None,
+            kind => match DFAFromNFA::with_match_kind(&self.engine, kind, None) {
+                Ok(dfa_from_nfa) => Some(DFA::from(dfa_from_nfa).minimize().into()),
+                Err(_) => unreachable!("subset construction without a limit failed"),
+            },
+        };
+
+        let reverse =
+            DFAFromNFA::with_state_limit(&unanchored_reverse(&self.engine), REVERSE_SIZE_LIMIT)
+                .ok()
+                .map(|dfa_from_nfa| DFA::from(dfa_from_nfa).minimize().into());
+
         RegExp {
             expr: self.expr,
-            engine: self.engine.into(),
+            engine: DFA::from(self.engine).minimize().into(),
+            match_kind: self.match_kind,
+            searcher,
+            prefix: self.prefix,
+            prefixes: self.prefixes,
+            literals: self.literals,
+            reverse,
+            pikevm: self.pikevm,
+            onepass: self.onepass,
         }
     }
 }
 
-impl RegExp<DFA<CharClass>> {
+impl RegExp<DenseDFA<CharClass>> {
     /// Create a compiled regular expression that uses a DFA to evaluate input strings.
     #[inline]
     pub fn new(expr: &'_ str) -> ParseResult<'_, Self> {
         Ok(RegExp::new_nfa(expr)?.with_dfa())
     }
+
+    /// Compile a regular expression from an AST, such as one constructed or rewritten by a
+    /// program, without printing it to a pattern and parsing it again. The pattern of the regular
+    /// expression is the AST printed with its `Display` implementation.
+    ///
+    /// ```
+    /// use regexp2::ast::Expr;
+    /// use regexp2::RegExp;
+    ///
+    /// let expr = Expr::literal("ab").or(Expr::from('c').plus());
+    /// let re = RegExp::from_ast(&expr).unwrap();
+    /// assert_eq!("ab|c+", re.as_str());
+    /// assert!(re.is_match("ccc"));
+    /// ```
+    #[inline]
+    pub fn from_ast(expr: &Expr) -> Result<Self, CompileError> {
+        let nfa = expr.to_nfa()?;
+        Ok(RegExp::from_nfa(&expr.to_string(), nfa).with_dfa())
+    }
+
+    /// The minimized DFA that the backend was compiled from, with only the states of anchored
+    /// searches, for analyses of the automaton such as exporting it with [`DFA::to_dot`].
+    ///
+    /// ```
+    /// use regexp2::RegExp;
+    ///
+    /// let dfa = RegExp::new("a(b|c)*").unwrap().dfa();
+    /// assert_eq!(2, dfa.total_states);
+    /// assert!(dfa.is_match("abcb".chars()));
+    /// ```
+    #[inline]
+    pub fn dfa(&self) -> DFA<CharClass> {
+        DFA::from(&self.engine)
+    }
+
+    /// Determine if the input can be extended to one that matches, including by nothing if it
+    /// already matches. This is the case until the DFA reaches a state from which no input is
+    /// accepted, so inputs can be checked as they are typed.
+    ///
+    /// ```
+    /// use regexp2::RegExp;
+    ///
+    /// let re = RegExp::new("[0-9]+-[0-9]+").unwrap();
+    /// assert!(re.is_valid_prefix(""));
+    /// assert!(re.is_valid_prefix("555-"));
+    /// assert!(!re.is_valid_prefix("555-1x"));
+    /// assert!(!re.is_valid_prefix("-1"));
+    /// ```
+    #[inline]
+    pub fn is_valid_prefix(&self, input: &str) -> bool {
+        let mut state = self.engine.start();
+        for c in input.chars() {
+            if self.engine.step(&mut state, &c) == StepResult::Dead {
+                return false;
+            }
+        }
+        matches!(state.state(), Some(s) if self.engine.is_live(s))
+    }
+
+    /// Check that a regular expression is well-formed without compiling it. This accepts exactly
+    /// the regular expressions accepted by [`RegExp::new`], and fails with the same error.
+    ///
+    /// ```
+    /// use regexp2::RegExp;
+    ///
+    /// assert!(RegExp::validate("(a|b)*abb").is_ok());
+    /// let err = RegExp::validate("a(b").unwrap_err();
+    /// assert_eq!(RegExp::new("a(b").unwrap_err().to_string(), err.to_string());
+    /// ```
+    #[inline]
+    pub fn validate(expr: &'_ str) -> ParseResult<'_, ()> {
+        Validator::new().parse(expr)
+    }
+
+    /// Check that a regular expression is well-formed without compiling it, reporting every error
+    /// in it rather than only the first. See
+    /// [`ParserState::parse_with_recovery`](parser::ParserState::parse_with_recovery).
+    #[inline]
+    pub fn validate_all(expr: &'_ str) -> Result<(), Vec<ParseError<'_>>> {
+        Validator::new().parse_with_recovery(expr)
+    }
+
+    /// Escape every metacharacter in a literal string, so that it can be embedded in a pattern
+    /// that matches it exactly.
+    ///
+    /// ```
+    /// use regexp2::RegExp;
+    ///
+    /// let re = RegExp::new(&format!("{}|x", RegExp::escape("a.b*"))).unwrap();
+    /// assert!(re.is_match("a.b*"));
+    /// assert!(!re.is_match("aab"));
+    /// ```
+    #[inline]
+    pub fn escape(literal: &str) -> String {
+        let mut escaped = String::with_capacity(literal.len());
+        for c in literal.chars() {
+            if parser::is_meta_character(c) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    }
 }
 
-impl PartialEq<char> for CharClass {
+/// Operations on the languages of regular expressions, which are computed on their DFAs. The
+/// result has no capture groups and uses the default [`MatchKind`], and its pattern is converted
+/// back from its DFA with [`Expr::from_dfa`], so it may be much longer than the operands.
+///
+/// ```
+/// use regexp2::RegExp;
+///
+/// let word = RegExp::new("[a-z]+").unwrap();
+/// let keyword = RegExp::new("if|else").unwrap();
+/// let identifier = word.and(&keyword.not());
+/// assert!(identifier.is_match("iff"));
+/// assert!(!identifier.is_match("if"));
+/// assert!(!identifier.is_match("_"));
+/// ```
+impl RegExp<DenseDFA<CharClass>> {
+    /// Produce a regular expression that matches exactly the inputs matched by both this regular
+    /// expression and `other`.
     #[inline]
-    fn eq(&self, other: &char) -> bool {
-        self.contains(*other)
+    pub fn and(&self, other: &Self) -> Self {
+        Self::from_dfa(self.dfa().intersection(&other.dfa()))
+    }
+
+    /// Produce a regular expression that matches exactly the inputs matched by this regular
+    /// expression, `other`, or both.
+    #[inline]
+    pub fn or(&self, other: &Self) -> Self {
+        Self::from_dfa(self.dfa().union(&other.dfa()))
+    }
+
+    /// Produce a regular expression that matches exactly the inputs this regular expression
+    /// doesn't match.
+    #[inline]
+    pub fn not(&self) -> Self {
+        Self::from_dfa(self.dfa().complement())
+    }
+
+    /// Produce a regular expression that matches exactly the inputs matched by one of this
+    /// regular expression and `other`, but not both.
+    #[inline]
+    pub fn xor(&self, other: &Self) -> Self {
+        Self::from_dfa(self.dfa().symmetric_difference(&other.dfa()))
+    }
+
+    /// Determine if this regular expression and `other` match exactly the same inputs.
+    ///
+    /// ```
+    /// use regexp2::RegExp;
+    ///
+    /// let re = RegExp::new("(a|b)*").unwrap();
+    /// assert!(re.is_equivalent(&RegExp::new("(a*b*)*").unwrap()));
+    /// assert!(!re.is_equivalent(&RegExp::new("(ab)*").unwrap()));
+    /// ```
+    #[inline]
+    pub fn is_equivalent(&self, other: &Self) -> bool {
+        self.dfa().is_equivalent(&other.dfa())
+    }
+
+    /// Determine if every input matched by this regular expression is also matched by `other`,
+    /// such as when checking that a tightened pattern doesn't match any new inputs.
+    ///
+    /// ```
+    /// use regexp2::RegExp;
+    ///
+    /// let old = RegExp::new("[a-z]+").unwrap();
+    /// let new = RegExp::new("[a-f]+").unwrap();
+    /// assert!(new.matches_subset_of(&old));
+    /// assert!(!old.matches_subset_of(&new));
+    /// ```
+    #[inline]
+    pub fn matches_subset_of(&self, other: &Self) -> bool {
+        self.dfa().is_subset_of(&other.dfa())
+    }
+
+    /// Compile a regular expression from a DFA, with a pattern converted from it.
+    #[inline]
+    fn from_dfa(dfa: DFA<CharClass>) -> Self {
+        let dfa = dfa.minimize();
+        let expr = Expr::from_dfa(&dfa).to_string();
+        RegExp::from_nfa(&expr, NFA::from(&dfa)).with_dfa()
+    }
+}
+
+impl FromStr for RegExp<DenseDFA<CharClass>> {
+    type Err = ParseError<'static>;
+
+    /// Compile a regular expression that uses a DFA, as with [`RegExp::new`].
+    #[inline]
+    fn from_str(expr: &str) -> Result<Self, Self::Err> {
+        RegExp::new(expr).map_err(ParseError::into_owned)
+    }
+}
+
+/// Displays the pattern the regular expression was compiled from.
+impl<E: Engine> fmt::Display for RegExp<E> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expr)
     }
 }
 
-impl From<CharClass> for Transition<CharClass> {
+impl RegExp<DenseDFA<u8>> {
+    /// Determine if the given bytes are the UTF-8 encoding of a string within the language
+    /// described by the regular expression.
     #[inline]
-    fn from(c: CharClass) -> Self {
-        Transition::Some(c)
+    pub fn is_match_bytes(&self, input: &[u8]) -> bool {
+        match &self.literals {
+            Some(literals) => literals.is_match(input),
+            None => DenseDFA::is_match(&self.engine, input.iter().copied()),
+        }
+    }
+
+    /// Find the leftmost match in the given bytes, which need not be valid UTF-8. The match is
+    /// returned as a range of byte offsets.
+    #[inline]
+    pub fn find_bytes(&self, input: &[u8]) -> Option<Range<usize>> {
+        self.find_bytes_at(input, 0)
+    }
+
+    /// Find the leftmost match that starts at or after the byte offset `start` of the given bytes.
+    #[inline]
+    pub fn find_bytes_at(&self, input: &[u8], start: usize) -> Option<Range<usize>> {
+        if let Some(literals) = &self.literals {
+            return literals.find_at(input, start);
+        }
+
+        let searcher = self.searcher();
+        let m = if let Some(prefixes) = &self.prefixes {
+            prefilter::find_any(prefixes, input, start, |pos| {
+                DenseDFA::find_at(searcher, input.iter().copied(), pos)
+            })
+        } else if self.prefix.is_empty() {
+            DenseDFA::find_unanchored_at(searcher, input.iter().copied(), start)
+        } else {
+            prefilter::find(&self.prefix, input, start, |pos| {
+                DenseDFA::find_at(searcher, input.iter().copied(), pos)
+            })
+        };
+        m.map(|m| m.range())
+    }
+}
+
+impl RegExp<Meta> {
+    /// The strategy used to search for matches in an input string of `len` bytes. See
+    /// [`RegExpBuilder::build_meta`](crate::RegExpBuilder::build_meta).
+    #[inline]
+    pub fn strategy(&self, len: usize) -> Strategy {
+        self.searcher().strategy(len)
+    }
+}
+
+impl PartialEq<char> for CharClass {
+    #[inline]
+    fn eq(&self, other: &char) -> bool {
+        self.contains(*other)
     }
 }
 
@@ -136,9 +1097,40 @@ impl From<CharClass> for Transition<CharClass> {
 pub trait Engine {
     fn is_match(&self, input: &str) -> bool;
 
-    fn find_at(&self, input: &str, start: usize) -> Option<Match>;
+    /// Find the leftmost match that starts at or after the byte offset `start`. Backends whose
+    /// match semantics are fixed when they are constructed may ignore `kind`.
+    fn find_at<'t>(&self, input: &'t str, start: usize, kind: MatchKind) -> Option<Match<'t>>;
+
+    fn find_shortest_at<'t>(&self, input: &'t str, start: usize) -> Option<Match<'t>>;
+
+    /// Find a match that starts at the byte offset `start`.
+    fn find_anchored_at<'t>(
+        &self,
+        input: &'t str,
+        start: usize,
+        kind: MatchKind,
+    ) -> Option<Match<'t>>;
+
+    /// Find the shortest match that starts at the byte offset `start`.
+    fn find_shortest_anchored_at<'t>(&self, input: &'t str, start: usize) -> Option<Match<'t>>;
+
+    /// Treating the backend as one for a reversed regular expression with a leading `.*`, find
+    /// the smallest byte offset at or after `start` where a match of the original regular
+    /// expression starts. `forward` is the backend for the original regular expression, which
+    /// finds where the match ends, so that only the input up to there is scanned backwards.
+    ///
+    /// Only DFAs are compiled as reverse backends. The other backends find the match with
+    /// `forward` instead.
+    fn rfind_start(&self, forward: &Self, input: &str, start: usize) -> Option<usize>
+    where
+        Self: Sized;
+}
 
-    fn find_shortest_at(&self, input: &str, start: usize) -> Option<Match>;
+/// Find the start of the leftmost match with the forward backend, for backends that aren't
+/// compiled as reverse backends.
+#[inline]
+pub(crate) fn forward_start<E: Engine>(forward: &E, input: &str, start: usize) -> Option<usize> {
+    forward.find_shortest_at(input, start).map(|m| m.start())
 }
 
 impl Engine for NFA<CharClass> {
@@ -148,13 +1140,45 @@ impl Engine for NFA<CharClass> {
     }
 
     #[inline]
-    fn find_shortest_at(&self, input: &str, start: usize) -> Option<Match> {
-        NFA::find_shortest_at(self, input.chars(), start).map(From::from)
+    fn find_shortest_at<'t>(&self, input: &'t str, start: usize) -> Option<Match<'t>> {
+        NFA::find_shortest_unanchored_at(self, input[start..].chars(), 0)
+            .map(|m| Match::from_chars(input, start, m))
     }
 
     #[inline]
-    fn find_at(&self, input: &str, start: usize) -> Option<Match> {
-        NFA::find_at(self, input.chars(), start).map(From::from)
+    fn find_at<'t>(&self, input: &'t str, start: usize, kind: MatchKind) -> Option<Match<'t>> {
+        let chars = input[start..].chars();
+        match kind {
+            MatchKind::LeftmostLongest => NFA::find_unanchored(self, chars),
+            MatchKind::LeftmostFirst => NFA::find_leftmost_first(self, chars),
+        }
+        .map(|m| Match::from_chars(input, start, m))
+    }
+
+    #[inline]
+    fn find_anchored_at<'t>(
+        &self,
+        input: &'t str,
+        start: usize,
+        kind: MatchKind,
+    ) -> Option<Match<'t>> {
+        let chars = input[start..].chars();
+        match kind {
+            MatchKind::LeftmostLongest => NFA::find_at(self, chars, 0),
+            MatchKind::LeftmostFirst => NFA::find_leftmost_first_anchored_at(self, chars, 0),
+        }
+        .map(|m| Match::from_chars(input, start, m))
+    }
+
+    #[inline]
+    fn find_shortest_anchored_at<'t>(&self, input: &'t str, start: usize) -> Option<Match<'t>> {
+        NFA::find_shortest_at(self, input[start..].chars(), 0)
+            .map(|m| Match::from_chars(input, start, m))
+    }
+
+    #[inline]
+    fn rfind_start(&self, forward: &Self, input: &str, start: usize) -> Option<usize> {
+        forward_start(forward, input, start)
     }
 }
 
@@ -165,12 +1189,219 @@ impl Engine for DFA<CharClass> {
     }
 
     #[inline]
-    fn find_shortest_at(&self, input: &str, start: usize) -> Option<Match> {
-        DFA::find_shortest_at(self, input.chars(), start).map(From::from)
+    fn find_shortest_at<'t>(&self, input: &'t str, start: usize) -> Option<Match<'t>> {
+        DFA::find_shortest_unanchored_at(self, input[start..].chars(), 0)
+            .map(|m| Match::from_chars(input, start, m))
+    }
+
+    #[inline]
+    fn find_at<'t>(&self, input: &'t str, start: usize, _kind: MatchKind) -> Option<Match<'t>> {
+        DFA::find_unanchored(self, input[start..].chars())
+            .map(|m| Match::from_chars(input, start, m))
+    }
+
+    #[inline]
+    fn find_anchored_at<'t>(
+        &self,
+        input: &'t str,
+        start: usize,
+        _kind: MatchKind,
+    ) -> Option<Match<'t>> {
+        DFA::find_at(self, input[start..].chars(), 0).map(|m| Match::from_chars(input, start, m))
+    }
+
+    #[inline]
+    fn find_shortest_anchored_at<'t>(&self, input: &'t str, start: usize) -> Option<Match<'t>> {
+        DFA::find_shortest_at(self, input[start..].chars(), 0)
+            .map(|m| Match::from_chars(input, start, m))
+    }
+
+    #[inline]
+    fn rfind_start(&self, forward: &Self, input: &str, start: usize) -> Option<usize> {
+        forward_start(forward, input, start)
+    }
+}
+
+impl Engine for DenseDFA<CharClass> {
+    #[inline]
+    fn is_match(&self, input: &str) -> bool {
+        DenseDFA::is_match(self, input.chars())
+    }
+
+    #[inline]
+    fn find_shortest_at<'t>(&self, input: &'t str, start: usize) -> Option<Match<'t>> {
+        DenseDFA::find_shortest_unanchored_at(self, input[start..].chars(), 0)
+            .map(|m| Match::from_chars(input, start, m))
+    }
+
+    #[inline]
+    fn find_at<'t>(&self, input: &'t str, start: usize, _kind: MatchKind) -> Option<Match<'t>> {
+        DenseDFA::find_unanchored(self, input[start..].chars())
+            .map(|m| Match::from_chars(input, start, m))
+    }
+
+    #[inline]
+    fn find_anchored_at<'t>(
+        &self,
+        input: &'t str,
+        start: usize,
+        _kind: MatchKind,
+    ) -> Option<Match<'t>> {
+        DenseDFA::find_at(self, input[start..].chars(), 0)
+            .map(|m| Match::from_chars(input, start, m))
+    }
+
+    #[inline]
+    fn find_shortest_anchored_at<'t>(&self, input: &'t str, start: usize) -> Option<Match<'t>> {
+        DenseDFA::find_shortest_at(self, input[start..].chars(), 0)
+            .map(|m| Match::from_chars(input, start, m))
+    }
+
+    #[inline]
+    fn rfind_start(&self, forward: &Self, input: &str, start: usize) -> Option<usize> {
+        let end = leftmost_end(forward, input, start)?;
+        let mut state = self.start_state;
+        let mut found = if self.is_accepting_state(state) {
+            Some(end)
+        } else {
+            None
+        };
+        for (i, c) in input[start..end].char_indices().rev() {
+            state = match self.next_state(state, &c) {
+                Some(next) => next,
+                None => break,
+            };
+            if self.is_accepting_state(state) {
+                found = Some(start + i);
+            }
+        }
+        found
+    }
+}
+
+/// Find where a match that starts at the leftmost position at or after the byte offset `start`
+/// ends, by running the anchored search from every position at once.
+///
+/// The searches are kept in the order they were started, and those in the same state are merged
+/// into the earliest, so there are at most as many as there are states. Once a search matches,
+/// no more are started and the later ones are dropped; the earliest match is known once no earlier
+/// search is left.
+#[inline]
+fn leftmost_end(dfa: &DenseDFA<CharClass>, input: &str, start: usize) -> Option<usize> {
+    let mut searches = Vec::new();
+    let mut next = Vec::new();
+    let mut seen = StateSet::new();
+    let mut end = None;
+    let mut chars = input[start..].char_indices();
+    let mut pos = start;
+    loop {
+        if end.is_none() && !searches.contains(&dfa.start_state) {
+            searches.push(dfa.start_state);
+        }
+        if let Some(i) = searches.iter().position(|&s| dfa.is_accepting_state(s)) {
+            end = Some(pos);
+            searches.truncate(i);
+        }
+        if end.is_some() && searches.is_empty() {
+            return end;
+        }
+
+        let (i, c) = match chars.next() {
+            Some(next) => next,
+            None => return end,
+        };
+        pos = start + i + c.len_utf8();
+        seen.clear();
+        next.clear();
+        for &state in &searches {
+            if let Some(state) = dfa.next_state(state, &c) {
+                if seen.insert(state.as_usize()) {
+                    next.push(state);
+                }
+            }
+        }
+        mem::swap(&mut searches, &mut next);
+    }
+}
+
+impl Engine for LazyDFA<CharClass> {
+    #[inline]
+    fn is_match(&self, input: &str) -> bool {
+        LazyDFA::is_match(self, input.chars())
+    }
+
+    #[inline]
+    fn find_shortest_at<'t>(&self, input: &'t str, start: usize) -> Option<Match<'t>> {
+        LazyDFA::find_shortest_unanchored_at(self, input[start..].chars(), 0)
+            .map(|m| Match::from_chars(input, start, m))
+    }
+
+    #[inline]
+    fn find_at<'t>(&self, input: &'t str, start: usize, _kind: MatchKind) -> Option<Match<'t>> {
+        LazyDFA::find_unanchored(self, input[start..].chars())
+            .map(|m| Match::from_chars(input, start, m))
+    }
+
+    #[inline]
+    fn find_anchored_at<'t>(
+        &self,
+        input: &'t str,
+        start: usize,
+        _kind: MatchKind,
+    ) -> Option<Match<'t>> {
+        LazyDFA::find_at(self, input[start..].chars(), 0)
+            .map(|m| Match::from_chars(input, start, m))
+    }
+
+    #[inline]
+    fn find_shortest_anchored_at<'t>(&self, input: &'t str, start: usize) -> Option<Match<'t>> {
+        LazyDFA::find_shortest_at(self, input[start..].chars(), 0)
+            .map(|m| Match::from_chars(input, start, m))
+    }
+
+    #[inline]
+    fn rfind_start(&self, forward: &Self, input: &str, start: usize) -> Option<usize> {
+        forward_start(forward, input, start)
+    }
+}
+
+impl Engine for DenseDFA<u8> {
+    #[inline]
+    fn is_match(&self, input: &str) -> bool {
+        DenseDFA::is_match(self, input.bytes())
+    }
+
+    #[inline]
+    fn find_shortest_at<'t>(&self, input: &'t str, start: usize) -> Option<Match<'t>> {
+        DenseDFA::find_shortest_unanchored_at(self, input[start..].bytes(), 0)
+            .map(|m| Match::new(input, start + m.start(), start + m.end()))
+    }
+
+    #[inline]
+    fn find_at<'t>(&self, input: &'t str, start: usize, _kind: MatchKind) -> Option<Match<'t>> {
+        DenseDFA::find_unanchored(self, input[start..].bytes())
+            .map(|m| Match::new(input, start + m.start(), start + m.end()))
+    }
+
+    #[inline]
+    fn find_anchored_at<'t>(
+        &self,
+        input: &'t str,
+        start: usize,
+        _kind: MatchKind,
+    ) -> Option<Match<'t>> {
+        DenseDFA::find_at(self, input[start..].bytes(), 0)
+            .map(|m| Match::new(input, start + m.start(), start + m.end()))
+    }
+
+    #[inline]
+    fn find_shortest_anchored_at<'t>(&self, input: &'t str, start: usize) -> Option<Match<'t>> {
+        DenseDFA::find_shortest_at(self, input[start..].bytes(), 0)
+            .map(|m| Match::new(input, start + m.start(), start + m.end()))
     }
 
     #[inline]
-    fn find_at(&self, input: &str, start: usize) -> Option<Match> {
-        DFA::find_at(self, input.chars(), start).map(From::from)
+    fn rfind_start(&self, forward: &Self, input: &str, start: usize) -> Option<usize> {
+        forward_start(forward, input, start)
     }
 }
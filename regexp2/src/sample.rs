@@ -0,0 +1,243 @@
+//! Generation of sample strings accepted by a regular expression, for testing patterns and
+//! producing examples.
+
+use crate::class::CharClass;
+
+use std::collections::VecDeque;
+
+use automata::DFA;
+
+/// A generator of strings accepted by a DFA, up to a maximum length in characters.
+///
+/// ```
+/// use regexp2::automata::DFA;
+/// use regexp2::class::CharClass;
+/// use regexp2::parser::nfa::NFAParser;
+/// use regexp2::sample::Generator;
+///
+/// let dfa: DFA<CharClass> = NFAParser::new().parse("(a|b)*c").unwrap().into();
+/// let mut generator = Generator::new(&dfa, 2);
+///
+/// let all: Vec<_> = generator.iter().collect();
+/// assert_eq!(vec!["ac", "bc", "c"], all);
+///
+/// let sample = generator.sample().unwrap();
+/// assert!(dfa.is_match(sample.chars()));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Generator {
+    start: usize,
+    /// The transitions out of each state, ordered by their smallest character.
+    edges: Vec<Vec<(CharClass, usize)>>,
+    accepting: Vec<bool>,
+    /// The fewest characters needed to reach an accepting state from each state, if one can be
+    /// reached at all.
+    distances: Vec<Option<usize>>,
+    max_len: usize,
+    /// The state of the xorshift random number generator, which is never zero.
+    rng: u64,
+}
+
+/// The seed used by a new [`Generator`].
+const DEFAULT_SEED: u64 = 0x853c_49e6_748f_ea9b;
+
+impl Generator {
+    /// Create a generator of strings accepted by the DFA that are at most `max_len` characters
+    /// long.
+    #[inline]
+    pub fn new(dfa: &DFA<CharClass>, max_len: usize) -> Self {
+        let n = dfa.total_states;
+        let mut edges = vec![Vec::new(); n];
+        let mut incoming = vec![Vec::new(); n];
+        for (state, out) in edges.iter_mut().enumerate() {
+            for (t, &next) in dfa.transitions_on(&state) {
+                if !t.0.is_empty() {
+                    out.push((t.0.clone(), next));
+                    incoming[next].push(state);
+                }
+            }
+            out.sort_by_key(|(class, _)| class.iter().next().map(|range| range.start));
+        }
+        let accepting: Vec<_> = (0..n).map(|s| dfa.is_accepting_state(&s)).collect();
+
+        // Breadth-first search backwards from the accepting states.
+        let mut distances = vec![None; n];
+        let mut queue = VecDeque::new();
+        for state in (0..n).filter(|&s| accepting[s]) {
+            distances[state] = Some(0);
+            queue.push_back(state);
+        }
+        while let Some(state) = queue.pop_front() {
+            let distance = distances[state].map(|d| d + 1);
+            for &prev in &incoming[state] {
+                if distances[prev].is_none() {
+                    distances[prev] = distance;
+                    queue.push_back(prev);
+                }
+            }
+        }
+
+        Self {
+            start: dfa.start_state,
+            edges,
+            accepting,
+            distances,
+            max_len,
+            rng: DEFAULT_SEED,
+        }
+    }
+
+    /// Seed the random number generator used by [`sample`](Self::sample), so that the same
+    /// sequence of samples is produced for the same seed.
+    #[inline]
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = if seed == 0 { DEFAULT_SEED } else { seed };
+    }
+
+    /// Generate a random accepted string, or `None` if no string within the maximum length is
+    /// accepted. Each step of the walk through the DFA chooses uniformly between stopping, if the
+    /// current state is accepting, and following each transition that can still reach an accepting
+    /// state in time, and then chooses uniformly among the characters of that transition.
+    #[inline]
+    pub fn sample(&mut self) -> Option<String> {
+        if !self.can_finish(self.start, self.max_len) {
+            return None;
+        }
+
+        let mut sample = String::new();
+        let mut state = self.start;
+        for remaining in (0..self.max_len).rev() {
+            let choices: Vec<_> = self.edges[state]
+                .iter()
+                .filter(|(_, next)| self.can_finish(*next, remaining))
+                .cloned()
+                .collect();
+            let stop = usize::from(self.accepting[state]);
+            let choice = self.below((choices.len() + stop) as u64) as usize;
+            if choice < stop {
+                break;
+            }
+
+            let (class, next) = &choices[choice - stop];
+            sample.push(self.char_in(class));
+            state = *next;
+        }
+        Some(sample)
+    }
+
+    /// Iterate over every accepted string within the maximum length. Strings that are prefixes of
+    /// others come first, and the transitions out of each state are taken in order of their
+    /// smallest character, so strings are produced in lexicographic order when the transitions
+    /// don't interleave.
+    #[inline]
+    pub fn iter(&self) -> Samples<'_> {
+        Samples {
+            generator: self,
+            stack: Vec::new(),
+            sample: String::new(),
+            started: false,
+        }
+    }
+
+    /// Determine if an accepting state can be reached from the state with at most `remaining`
+    /// more characters.
+    #[inline]
+    fn can_finish(&self, state: usize, remaining: usize) -> bool {
+        matches!(self.distances[state], Some(distance) if distance <= remaining)
+    }
+
+    /// The characters and next states of the transitions out of a state that can still reach an
+    /// accepting state, with `remaining` characters to spare.
+    #[inline]
+    fn successors(&self, state: usize, remaining: usize) -> Successors<'_> {
+        let iter = self.edges[state]
+            .iter()
+            .filter(move |(_, next)| remaining > 0 && self.can_finish(*next, remaining - 1))
+            .flat_map(|(class, next)| class.chars().map(move |c| (c, *next)));
+        Box::new(iter)
+    }
+
+    /// Generate a random number less than `n`.
+    #[inline]
+    fn below(&mut self, n: u64) -> u64 {
+        // xorshift64*
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        self.rng.wrapping_mul(0x2545_f491_4f6c_dd1d) % n
+    }
+
+    /// Choose a random character in a non-empty class.
+    #[inline]
+    fn char_in(&mut self, class: &CharClass) -> char {
+        let mut n = self.below(class.len() as u64);
+        for range in class {
+            let len = range.len() as u64;
+            if n < len {
+                let mut c = range.start as u32 + n as u32;
+                if (range.start as u32) < SURROGATES_START && c >= SURROGATES_START {
+                    c += SURROGATES_LEN;
+                }
+                return std::char::from_u32(c).unwrap();
+            }
+            n -= len;
+        }
+        unreachable!()
+    }
+}
+
+/// The first surrogate code point.
+const SURROGATES_START: u32 = 0xd800;
+/// The number of surrogate code points, which are not characters.
+const SURROGATES_LEN: u32 = 0x800;
+
+type Successors<'g> = Box<dyn Iterator<Item = (char, usize)> + 'g>;
+
+/// An iterator over the strings accepted by a [`Generator`], created by
+/// [`Generator::iter`].
+pub struct Samples<'g> {
+    generator: &'g Generator,
+    /// The remaining successors of each state on the path to the current sample.
+    stack: Vec<Successors<'g>>,
+    sample: String,
+    started: bool,
+}
+
+impl<'g> Iterator for Samples<'g> {
+    type Item = String;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let generator = self.generator;
+        if !self.started {
+            self.started = true;
+            if !generator.can_finish(generator.start, generator.max_len) {
+                return None;
+            }
+            self.stack
+                .push(generator.successors(generator.start, generator.max_len));
+            if generator.accepting[generator.start] {
+                return Some(String::new());
+            }
+        }
+
+        // Every state on the path can reach an accepting state in time, so this search never
+        // backtracks without having produced a sample.
+        loop {
+            match self.stack.last_mut()?.next() {
+                Some((c, next)) => {
+                    self.sample.push(c);
+                    let remaining = generator.max_len - self.stack.len();
+                    self.stack.push(generator.successors(next, remaining));
+                    if generator.accepting[next] {
+                        return Some(self.sample.clone());
+                    }
+                }
+                None => {
+                    self.stack.pop();
+                    self.sample.pop();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::class::CharClass;
+use crate::regexp::RegExp;
+
+use std::io::{self, Read};
+use std::ops::Range;
+use std::str;
+
+use automata::stream::StreamState;
+use automata::DenseDFA;
+
+/// The size of the buffer used to read from an [`io::Read`].
+const BUFFER_SIZE: usize = 1 << 13;
+
+/// A search for the non-overlapping matches of a regular expression in input that is given in
+/// chunks, so that it doesn't need to be held in memory all at once. Matches that span chunk
+/// boundaries are found, and are reported as byte ranges of the whole input.
+#[derive(Debug, Clone)]
+pub struct StreamMatcher<'r> {
+    dfa: &'r DenseDFA<CharClass>,
+    state: StreamState,
+}
+
+impl RegExp<DenseDFA<CharClass>> {
+    /// Start a search of input that is given in chunks.
+    #[inline]
+    pub fn stream_matcher(&self) -> StreamMatcher<'_> {
+        StreamMatcher {
+            dfa: self.searcher(),
+            state: StreamState::new(),
+        }
+    }
+}
+
+impl<'r> StreamMatcher<'r> {
+    /// The number of bytes of input given so far.
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.state.position()
+    }
+
+    /// Give the next chunk of input, returning the matches that can no longer change with later
+    /// input.
+    #[inline]
+    pub fn feed(&mut self, chunk: &str) -> Vec<Range<usize>> {
+        self.dfa
+            .feed(&mut self.state, chunk.chars().map(|c| (c, c.len_utf8())))
+    }
+
+    /// Give all of the input read from `reader` until it is exhausted, returning the matches that
+    /// can no longer change with later input.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading fails, or if the input isn't valid UTF-8.
+    #[inline]
+    pub fn feed_reader<R: Read>(&mut self, mut reader: R) -> io::Result<Vec<Range<usize>>> {
+        let mut matches = Vec::new();
+        let mut buf = vec![0; BUFFER_SIZE];
+        // The number of bytes at the front of the buffer that haven't been fed, because they are
+        // the start of a character split between reads.
+        let mut len = 0;
+        loop {
+            let n = match reader.read(&mut buf[len..]) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            len += n;
+
+            let valid = match str::from_utf8(&buf[..len]) {
+                Ok(s) => s.len(),
+                Err(err) if err.error_len().is_none() => err.valid_up_to(),
+                Err(err) => return Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+            };
+            // The prefix was just validated.
+            let chunk = str::from_utf8(&buf[..valid]).unwrap();
+            matches.extend(self.feed(chunk));
+
+            buf.copy_within(valid..len, 0);
+            len -= valid;
+        }
+
+        if len > 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "input ended in the middle of a character",
+            ));
+        }
+        Ok(matches)
+    }
+
+    /// End the input, returning the remaining matches.
+    #[inline]
+    pub fn finish(mut self) -> Vec<Range<usize>> {
+        self.dfa.finish(&mut self.state)
+    }
+}
@@ -0,0 +1,120 @@
+//! Compilation of character automata to automata over the bytes of UTF-8 encoded input.
+
+use crate::bytes;
+use crate::class::{ByteClass, CharClass, CharRange};
+
+pub use crate::class::ByteRange;
+
+use automata::NFA;
+
+/// The largest scalar value encoded with 1, 2 and 3 bytes.
+const ENCODED_MAX: [u32; 3] = [0x7F, 0x7FF, 0xFFFF];
+
+/// Split a range of characters into sequences of byte ranges, so that the UTF-8 encoding of a
+/// character is matched by one of the sequences if and only if the character is in the range. The
+/// encoding of a character is matched by a sequence if each byte is within the corresponding byte
+/// range.
+#[inline]
+pub fn sequences(range: &CharRange) -> Vec<Vec<ByteRange>> {
+    let mut sequences = Vec::new();
+    let mut stack = vec![(range.start as u32, range.end as u32)];
+
+    'ranges: while let Some((start, mut end)) = stack.pop() {
+        'split: loop {
+            // Surrogates can't be encoded.
+            if start < 0xD800 && end > 0xDFFF {
+                stack.push((0xE000, end));
+                end = 0xD7FF;
+                continue 'split;
+            }
+
+            // Split the range so that every character in it is encoded with the same length.
+            for &max in &ENCODED_MAX {
+                if start <= max && max < end {
+                    stack.push((max + 1, end));
+                    end = max;
+                    continue 'split;
+                }
+            }
+
+            // Split the range so that each byte ranges independently of the others.
+            for i in 1..4 {
+                let mask = (1 << (6 * i)) - 1;
+                if start & !mask != end & !mask {
+                    if start & mask != 0 {
+                        stack.push(((start | mask) + 1, end));
+                        end = start | mask;
+                        continue 'split;
+                    }
+                    if end & mask != mask {
+                        stack.push((end & !mask, end));
+                        end = (end & !mask) - 1;
+                        continue 'split;
+                    }
+                }
+            }
+
+            let (mut s, mut e) = ([0; 4], [0; 4]);
+            let s = encode(start, &mut s);
+            let e = encode(end, &mut e);
+            sequences.push(
+                s.iter()
+                    .zip(e.iter())
+                    .map(|(&s, &e)| ByteRange::new(s, e))
+                    .collect(),
+            );
+            continue 'ranges;
+        }
+    }
+
+    sequences
+}
+
+#[inline]
+fn encode(c: u32, buf: &mut [u8; 4]) -> &[u8] {
+    match std::char::from_u32(c) {
+        Some(c) => c.encode_utf8(buf).as_bytes(),
+        None => unreachable!("surrogates were split off"),
+    }
+}
+
+/// Compile an NFA over characters to an equivalent NFA over the bytes of their UTF-8 encodings.
+/// The states of the original NFA keep their labels, and each labeled transition is replaced by
+/// paths through new states that read the encodings of the characters in its class.
+#[inline]
+pub fn compile(nfa: &NFA<CharClass>) -> NFA<u8> {
+    bytes::expand(&compile_classes(nfa))
+}
+
+/// Compile an NFA over characters to an equivalent NFA over byte classes, as in [`compile`], but
+/// with each range of a UTF-8 sequence read by a single transition.
+#[inline]
+pub fn compile_classes(nfa: &NFA<CharClass>) -> NFA<ByteClass> {
+    bytes::map_classes(nfa, |bytes, from, class, to| {
+        for range in class {
+            for sequence in sequences(range) {
+                add_sequence(bytes, from, &sequence, to);
+            }
+        }
+    })
+}
+
+/// Add a path from `from` that reads a sequence of byte ranges and ends in each of `to`.
+#[inline]
+fn add_sequence(nfa: &mut NFA<ByteClass>, from: usize, sequence: &[ByteRange], to: &[usize]) {
+    let (last, init) = match sequence.split_last() {
+        Some(split) => split,
+        None => return,
+    };
+
+    let mut state = from;
+    for &range in init {
+        let next = nfa.add_state(false);
+        nfa.add_labeled_transition(state, next, range.into());
+        state = next;
+    }
+
+    for &to in to {
+        nfa.add_labeled_transition(state, to, ByteClass::from(*last));
+    }
+}
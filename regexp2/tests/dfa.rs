@@ -0,0 +1,8 @@
+/// Compile an expression to a DFA through the NFA parser.
+#[allow(dead_code)]
+fn dfa(expr: &str) -> regexp2::automata::DFA<regexp2::class::CharClass> {
+    regexp2::parser::nfa::NFAParser::new()
+        .parse(expr)
+        .unwrap()
+        .into()
+}
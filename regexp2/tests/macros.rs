@@ -1,5 +1,4 @@
 #[allow(unused_macros)]
-
 macro_rules! run_tests {
     ($exprs:expr, $valids:expr, $invalids:expr) => {{
         $exprs.iter().for_each(|&expr| {
@@ -0,0 +1,103 @@
+use regexp2::automata::convert::DFAFromNFA;
+use regexp2::automata::{DenseDFA, DFA};
+use regexp2::class::{CharClass, CharRange, Partition};
+use regexp2::parser::nfa::NFAParser;
+
+#[test]
+fn test_alphabet_is_disjoint() {
+    let parser = NFAParser::new();
+    let nfa = parser.parse("[a-z]+|[m-p]x|q").unwrap();
+    let dfa_from_nfa: DFAFromNFA<CharClass> = nfa.into();
+
+    let alphabet = &dfa_from_nfa.alphabet;
+    // [a-l], [m-p], q, [r-w], x and [y-z].
+    assert_eq!(6, alphabet.len());
+    for (i, a) in alphabet.iter().enumerate() {
+        for b in alphabet.iter().skip(i + 1) {
+            assert!(a.intersection(b).is_empty());
+        }
+    }
+
+    // Every transition of the DFA is labeled with a class of the alphabet.
+    for (_, t, _) in &dfa_from_nfa.dfa.transition {
+        assert!(alphabet.contains(&t.0));
+    }
+}
+
+#[test]
+fn test_alphabet_around_surrogates() {
+    // The class of `.` spans the surrogate code points, which must be left out when it is split
+    // at them.
+    let nfa = NFAParser::new().parse("(.|\u{d7ff})b").unwrap();
+    let dfa_from_nfa: DFAFromNFA<CharClass> = nfa.into();
+    let alphabet = &dfa_from_nfa.alphabet;
+    assert!(alphabet.contains(&CharClass::from('\u{d7ff}')));
+    assert!(alphabet.contains(&CharClass::from(CharRange::new('\u{e000}', '\u{10ffff}'))));
+
+    let re = regexp2::RegExp::new("(.|\u{d7ff})b").unwrap();
+    assert!(re.is_match("\u{d7ff}b"));
+    assert!(re.is_match("\u{e000}b"));
+}
+
+#[test]
+fn test_partition() {
+    let classes = vec![
+        CharClass::from(CharRange::new('a', 'z')),
+        CharClass::from(CharRange::new('m', 'p')),
+        CharClass::from(vec!['q', 'c', 'x']),
+        CharClass::from(CharRange::new('0', '9')),
+        CharClass::new(),
+    ];
+    let partition = Partition::new(&classes);
+
+    // [abd-lr-wyz], [m-p], [cqx] and [0-9]. The letters outside [m-p] and [cqx] form one block
+    // even though their ranges aren't contiguous.
+    assert_eq!(4, partition.len());
+    for (i, a) in partition.blocks().iter().enumerate() {
+        for b in partition.blocks().iter().skip(i + 1) {
+            assert!(a.intersection(b).is_empty());
+        }
+    }
+
+    for (i, class) in classes.iter().enumerate() {
+        let union =
+            partition
+                .blocks_of_class(i)
+                .iter()
+                .fold(CharClass::new(), |mut union, &block| {
+                    union.add_other(partition.blocks()[block].clone());
+                    union
+                });
+        assert_eq!(class, &union);
+    }
+
+    for c in "abcdlmpqrxyz09".chars() {
+        let block = partition.block_of(c).unwrap();
+        assert!(partition.blocks()[block].contains(c));
+    }
+    assert_eq!(partition.block_of('a'), partition.block_of('z'));
+    assert_eq!(partition.block_of('c'), partition.block_of('x'));
+    assert_ne!(partition.block_of('a'), partition.block_of('c'));
+    for c in "A/:{\u{10ffff}".chars() {
+        assert_eq!(None, partition.block_of(c));
+    }
+
+    assert!(Partition::new(&[]).is_empty());
+}
+
+#[test]
+fn test_dense_class_lookup() {
+    let nfa = NFAParser::new().parse("[a-z]+|[m-p]x|\u{3b1}[^a]").unwrap();
+    let dfa: DFA<CharClass> = DFAFromNFA::from(nfa).into();
+    let dense: DenseDFA<CharClass> = dfa.minimize().into();
+
+    // Each character is looked up in the column of the class of the alphabet containing it.
+    for c in "afmpxz\u{3b1}\u{3b2}\u{10ffff}\0-".chars() {
+        let column = dense.alphabet.iter().position(|class| class.contains(c));
+        assert_eq!(column, dense.classes.get(c as u32));
+    }
+
+    assert!(dense.is_match("mx".chars()));
+    assert!(dense.is_match("\u{3b1}\u{10ffff}".chars()));
+    assert!(!dense.is_match("\u{3b1}a".chars()));
+}
@@ -0,0 +1,84 @@
+use regexp2::ast::{CompileError, Expr};
+use regexp2::automata::{DFA, NFA};
+use regexp2::class::CharClass;
+use regexp2::parser::ast::ASTParser;
+use regexp2::parser::nfa::NFAParser;
+use regexp2::parser::Anchor;
+use regexp2::{RegExp, RegExpBuilder};
+
+const EXPRS: [&str; 4] = ["(a|b)*abb", "x(yz)*|xy?", r"\d+(\.\d*)?", "é*ø+"];
+
+#[test]
+fn test_artifacts() {
+    for expr in &EXPRS {
+        let parsed: NFA<CharClass> = NFAParser::new().parse(expr).unwrap();
+        let expected: DFA<CharClass> = parsed.clone().into();
+
+        let re = RegExp::new_nfa(expr).unwrap();
+        assert_eq!(parsed.total_states, re.nfa().total_states, "{}", expr);
+        assert!(
+            parsed.final_states().eq(re.nfa().final_states()),
+            "{}",
+            expr
+        );
+        assert!(
+            DFA::from(re.nfa().clone()).is_equivalent(&expected),
+            "{}",
+            expr
+        );
+
+        let re = RegExp::new(expr).unwrap();
+        let dfa = re.dfa();
+        assert!(dfa.is_equivalent(&expected), "{}", expr);
+        assert_eq!(
+            expected.minimize().total_states,
+            dfa.total_states,
+            "{}",
+            expr
+        );
+        assert_eq!(re.engine().start_state.as_usize(), dfa.start_state);
+
+        let ast: Expr = ASTParser::<CharClass>::new().parse(expr).unwrap();
+        assert_eq!(ast, re.ast().unwrap(), "{}", expr);
+    }
+}
+
+#[test]
+fn test_artifacts_builder() {
+    // The DFA reflects the options of the builder, but the AST doesn't.
+    let re = RegExpBuilder::new("ab")
+        .case_insensitive(true)
+        .build()
+        .unwrap();
+    assert!(re.dfa().is_match("aB".chars()));
+    let nfa = re.ast().unwrap().to_nfa::<CharClass>().unwrap();
+    assert!(!nfa.is_match("aB".chars()));
+
+    assert!(re.dfa().to_dot().starts_with("digraph"));
+}
+
+#[test]
+fn test_from_ast() {
+    // A constructed AST matches the same strings as its pattern.
+    let expr = Expr::literal("ab")
+        .or(Expr::from('c').plus())
+        .then(Expr::from('d').optional());
+    let re = RegExp::from_ast(&expr).unwrap();
+    let parsed = RegExp::new(re.as_str()).unwrap();
+    for input in &["ab", "abd", "ccc", "cd", "", "abcd", "d"] {
+        assert_eq!(parsed.is_match(input), re.is_match(input), "{:?}", input);
+    }
+
+    // A parsed AST can be rewritten and compiled again.
+    let expr = ASTParser::<char>::new().parse("(a|b)c*").unwrap();
+    let re = RegExp::from_ast(&expr.clone().then(Expr::from('d'))).unwrap();
+    assert!(re.is_match("bccd"));
+    assert!(!re.is_match("bcc"));
+    let caps = re.captures("acd").unwrap();
+    assert_eq!(Some("a"), caps.get(1).map(|m| m.as_str()));
+
+    assert!(matches!(
+        RegExp::from_ast(&Expr::Anchor(Anchor::End)),
+        Err(CompileError::UnsupportedAnchor(..))
+    ));
+}
@@ -0,0 +1,531 @@
+use regexp2::ast::{self, BinaryOp, CompileError, Expr, Fold, UnaryOp, Visit};
+use regexp2::automata::convert::Disjoin;
+use regexp2::automata::{DFA, NFA};
+use regexp2::class::{CharClass, CharRange};
+use regexp2::parser::ast::{ASTParser, SpannedASTParser};
+use regexp2::parser::nfa::NFAParser;
+use regexp2::parser::Anchor;
+use regexp2::RegExp;
+
+fn parse(expr: &str) -> Expr {
+    ASTParser::<char>::new().parse(expr).unwrap()
+}
+
+/// Records the kinds of nodes in the order they are visited.
+#[derive(Default)]
+struct Trace(Vec<String>);
+
+impl Visit for Trace {
+    fn visit_unary(&mut self, op: &UnaryOp, operand: &Expr) {
+        self.0.push(format!("{:?}", op));
+        ast::walk_unary(self, op, operand);
+    }
+
+    fn visit_binary(&mut self, op: &BinaryOp, lhs: &Expr, rhs: &Expr) {
+        self.0.push(format!("{:?}", op));
+        ast::walk_binary(self, op, lhs, rhs);
+    }
+
+    fn visit_atom(&mut self, class: &CharClass) {
+        self.0.push(class.to_string());
+    }
+
+    fn visit_group(&mut self, inner: &Expr, index: usize) {
+        self.0.push(format!("Group({})", index));
+        ast::walk_group(self, inner, index);
+    }
+
+    fn visit_empty(&mut self) {
+        self.0.push("Empty".to_string());
+    }
+
+    fn visit_anchor(&mut self, anchor: Anchor) {
+        self.0.push(format!("{:?}", anchor));
+    }
+}
+
+#[test]
+fn test_visit() {
+    let mut trace = Trace::default();
+    trace.visit_expr(&parse("^(a|b?)()$"));
+    assert_eq!(
+        vec![
+            "Concat",
+            "Concat",
+            "Concat",
+            "Start",
+            "Group(1)",
+            "Alternate",
+            "a",
+            "Optional",
+            "b",
+            "Group(2)",
+            "Empty",
+            "End",
+        ],
+        trace.0
+    );
+}
+
+#[test]
+fn test_visit_default() {
+    /// Counts atoms, relying on the default traversal for everything else.
+    struct Atoms(usize);
+
+    impl Visit for Atoms {
+        fn visit_atom(&mut self, _class: &CharClass) {
+            self.0 += 1;
+        }
+    }
+
+    let mut atoms = Atoms(0);
+    atoms.visit_expr(&parse("(a(bc)*)|[de]?.x"));
+    assert_eq!(6, atoms.0);
+}
+
+#[test]
+fn test_fold() {
+    /// Removes groups and replaces every atom with its complement.
+    struct Rewrite;
+
+    impl Fold for Rewrite {
+        fn fold_atom(&mut self, class: CharClass) -> Expr {
+            Expr::Atom(class.complement())
+        }
+
+        fn fold_group(&mut self, inner: Expr, _index: usize) -> Expr {
+            self.fold_expr(inner)
+        }
+    }
+
+    assert_eq!(parse("[^a][^b]*"), Rewrite.fold_expr(parse("(a)(b)*")));
+    assert_eq!(parse("[^a]|^"), Rewrite.fold_expr(parse("((a))|^")));
+}
+
+#[test]
+fn test_fold_default() {
+    struct Identity;
+    impl Fold for Identity {}
+
+    let expr = parse("(a|b?)*c()$");
+    assert_eq!(expr.clone(), Identity.fold_expr(expr));
+}
+
+#[test]
+fn test_display_round_trip() {
+    let exprs = [
+        "abc",
+        "a|b|c",
+        "(a|b)*abb",
+        "a(b|c)?d",
+        r"\.\*\+\?\(\)\|\[\]\{\}\^\$\\",
+        "[a-c][^x]*",
+        r"[\-\]x]",
+        r"[\^\\z]",
+        r"[\[y]",
+        "a\tb\r\nc",
+        r"[\t\n]",
+        "^ab$|$",
+        "a**",
+        "[^\0-\u{10ffff}]",
+        "(?s).",
+        "[\0-\u{10ffff}]",
+        "[^a]|a",
+    ];
+    for expr in &exprs {
+        let parsed = parse(expr);
+        let displayed = parsed.to_string();
+        assert_eq!(
+            parsed,
+            parse(&displayed),
+            r#""{}" was displayed as "{}""#,
+            expr,
+            displayed
+        );
+    }
+}
+
+#[test]
+fn test_display_full_class() {
+    // The class of all characters is written so that it parses back, including when it is only
+    // found by simplifying.
+    for expr in &["(?:((?:é|[^éé]))|a)", "(?s).|a", "[^a]|a"] {
+        let simplified = parse(expr).simplify();
+        let displayed = simplified.to_string();
+        assert_eq!("[\0-\u{10ffff}]", displayed);
+        assert_eq!(simplified, parse(&displayed).simplify());
+    }
+}
+
+#[test]
+fn test_display_empty() {
+    // Empty expressions are rejected by default, so they are written in other forms that parse to
+    // the same expression once simplified.
+    let cases = [
+        ("((a))|()", "((a))|([^\0-\u{10ffff}]*)"),
+        ("()", "([^\0-\u{10ffff}]*)"),
+        ("a()*|()b", "a([^\0-\u{10ffff}]*)*|([^\0-\u{10ffff}]*)b"),
+    ];
+    for (expr, expected) in &cases {
+        let parsed = parse(expr);
+        let displayed = parsed.to_string();
+        assert_eq!(*expected, displayed);
+        assert_eq!(parsed.simplify(), parse(&displayed).simplify());
+        assert!(
+            RegExp::new(&displayed).is_ok(),
+            "{:?} doesn't parse",
+            displayed
+        );
+    }
+
+    let optional = Expr::Binary(
+        BinaryOp::Alternate,
+        Box::new(Expr::literal("ab")),
+        Box::new(Expr::Empty),
+    );
+    assert_eq!("(ab)?", optional.to_string());
+    let re = RegExp::from_ast(&optional).unwrap();
+    assert!(re.is_equivalent(&RegExp::new(re.as_str()).unwrap()));
+
+    let re = RegExp::from_ast(&Expr::Empty).unwrap();
+    let reparsed = RegExp::new(re.as_str()).unwrap();
+    assert!(reparsed.is_match(""));
+    assert!(!reparsed.is_match("a"));
+
+    let both = RegExp::new("a*").unwrap().and(&RegExp::new("b*").unwrap());
+    let reparsed: RegExp<_> = both.as_str().parse().unwrap();
+    assert!(reparsed.is_equivalent(&both));
+}
+
+#[test]
+fn test_display_precedence() {
+    let atom = |c: char| Box::new(Expr::Atom(c.into()));
+    let alternate = Box::new(Expr::Binary(BinaryOp::Alternate, atom('a'), atom('b')));
+    let concat = Box::new(Expr::Binary(BinaryOp::Concat, atom('a'), atom('b')));
+
+    let cases = [
+        (
+            Expr::Binary(BinaryOp::Concat, alternate.clone(), atom('c')),
+            "(a|b)c",
+        ),
+        (
+            Expr::Binary(BinaryOp::Alternate, atom('c'), alternate.clone()),
+            "c|a|b",
+        ),
+        (
+            Expr::Binary(BinaryOp::Concat, atom('c'), concat.clone()),
+            "cab",
+        ),
+        (Expr::Unary(UnaryOp::Plus, concat), "(ab)+"),
+        (Expr::Unary(UnaryOp::Star, alternate), "(a|b)*"),
+        (
+            Expr::Unary(
+                UnaryOp::Optional,
+                Box::new(Expr::Unary(UnaryOp::Star, atom('a'))),
+            ),
+            "a*?",
+        ),
+        (Expr::Group(Box::new(Expr::Empty), 1), "([^\0-\u{10ffff}]*)"),
+        (Expr::Empty, "[^\0-\u{10ffff}]*"),
+        (
+            Expr::Binary(
+                BinaryOp::Alternate,
+                Box::new(Expr::Binary(BinaryOp::Alternate, atom('a'), atom('b'))),
+                Box::new(Expr::Empty),
+            ),
+            "(a|b)?",
+        ),
+        (
+            Expr::Binary(BinaryOp::Alternate, Box::new(Expr::Empty), atom('a')),
+            "[^\0-\u{10ffff}]*|a",
+        ),
+        (Expr::Atom(CharClass::new()), "[^\0-\u{10ffff}]"),
+        (Expr::any(), "[\0-\u{10ffff}]"),
+        (Expr::Anchor(Anchor::End), "$"),
+        (Expr::Anchor(Anchor::Start), "^"),
+    ];
+    for (expr, expected) in &cases {
+        assert_eq!(*expected, expr.to_string());
+    }
+}
+
+#[test]
+fn test_simplify() {
+    let cases = [
+        ("(a*)*", "a*"),
+        ("((a+)?)", "a*"),
+        ("(a+)+", "a+"),
+        ("a??", "a?"),
+        ("a|a", "a"),
+        ("a|b|c", "[abc]"),
+        ("[a-c]|[b-e]", "[a-e]"),
+        ("a|bc|b", "a|bc|b"),
+        ("x|(x)|y", "[xy]"),
+        ("(ab)(cd)", "abcd"),
+        ("a(b|c)d", "a[bc]d"),
+        ("a()b", "ab"),
+        ("(a|(b|c)d)|e", "a|[bc]d|e"),
+    ];
+    for (expr, expected) in &cases {
+        assert_eq!(
+            parse(expected),
+            parse(expr).simplify(),
+            r#""{}" wasn't simplified to "{}""#,
+            expr,
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_simplify_empty() {
+    let nothing = || Box::new(Expr::Atom(CharClass::new()));
+    let a = || Box::new(Expr::Atom('a'.into()));
+
+    assert_eq!(Expr::Empty, parse("()*").simplify());
+    assert_eq!(Expr::Empty, parse("(()())").simplify());
+    assert_eq!(
+        *nothing(),
+        Expr::Binary(BinaryOp::Concat, a(), nothing()).simplify()
+    );
+    assert_eq!(
+        *a(),
+        Expr::Binary(BinaryOp::Alternate, nothing(), a()).simplify()
+    );
+    assert_eq!(
+        Expr::Empty,
+        Expr::Unary(UnaryOp::Star, nothing()).simplify()
+    );
+    assert_eq!(*nothing(), Expr::Unary(UnaryOp::Plus, nothing()).simplify());
+}
+
+/// Records the text of each spanned node in the order they are visited.
+struct Spans<'r>(&'r str, Vec<&'r str>);
+
+impl Visit for Spans<'_> {
+    fn visit_spanned(&mut self, inner: &Expr, span: &std::ops::Range<usize>) {
+        self.1.push(&self.0[span.clone()]);
+        ast::walk_spanned(self, inner, span);
+    }
+}
+
+fn spans(expr: &str) -> Vec<&str> {
+    let parsed = SpannedASTParser::<char>::new().parse(expr).unwrap();
+    let mut spans = Spans(expr, Vec::new());
+    spans.visit_expr(&parsed);
+    spans.1
+}
+
+#[test]
+fn test_spans() {
+    assert_eq!(
+        vec!["ab*|(c)", "ab*", "a", "b*", "b", "(c)", "c"],
+        spans("ab*|(c)")
+    );
+    assert_eq!(
+        vec!["é[x-z]\\.", "é[x-z]", "é", "[x-z]", "\\."],
+        spans("é[x-z]\\.")
+    );
+    assert_eq!(vec!["(())?", "(())", "()", ""], spans("(())?"));
+}
+
+#[test]
+fn test_strip_spans() {
+    let exprs = ["ab*|(c)", "((a|b)?)+c", "^x()$"];
+    for expr in &exprs {
+        let spanned = SpannedASTParser::<char>::new().parse(expr).unwrap();
+        assert!(spanned.span().is_some());
+        assert_eq!(spanned.to_string(), parse(expr).to_string());
+        assert_eq!(parse(expr).simplify(), spanned.clone().simplify());
+        assert_eq!(parse(expr), spanned.strip_spans());
+    }
+}
+
+#[test]
+fn test_to_nfa() {
+    let exprs = [
+        "(a|b)*abb",
+        "a+b?",
+        "[^a-c]x()y",
+        "((ab)+|c)*d",
+        r"\d+\.\d*",
+    ];
+    for expr in &exprs {
+        let expected: DFA<CharClass> = NFAParser::new().parse(expr).unwrap().into();
+        for parsed in &[
+            parse(expr),
+            parse(expr).simplify(),
+            SpannedASTParser::<char>::new().parse(expr).unwrap(),
+        ] {
+            let nfa: NFA<CharClass> = parsed.to_nfa().unwrap();
+            assert!(
+                expected.is_equivalent(&nfa.into()),
+                r#"AST of "{}" compiled to the wrong NFA"#,
+                expr
+            );
+        }
+    }
+
+    let nfa: NFA<CharClass> = Expr::<CharClass>::Empty.to_nfa().unwrap();
+    assert!(nfa.is_match("".chars()));
+    assert!(!nfa.is_match("a".chars()));
+
+    assert_eq!(
+        Err(CompileError::UnsupportedAnchor(Anchor::End)),
+        parse("a|b$").to_nfa::<CharClass>().map(|_| ())
+    );
+}
+
+#[test]
+fn test_deep_expr() {
+    // Long chains nested either way compile, format, and simplify without recursing per operand.
+    let branches = || (0..20000).map(|i| Expr::literal(&format!("x{}", i)));
+    let left = branches().reduce(Expr::or).unwrap();
+    let right = branches().rev().reduce(|rhs, lhs| lhs.or(rhs)).unwrap();
+    for expr in [left, right] {
+        let nfa: NFA<CharClass> = expr.to_nfa().unwrap();
+        assert!(nfa.is_match("x19999".chars()));
+        assert!(!nfa.is_match("x20000".chars()));
+
+        let formatted = expr.to_string();
+        assert!(formatted.starts_with("x0|x1|x2|"));
+        assert_eq!(formatted, expr.simplify().to_string());
+    }
+
+    let concat = Expr::literal(&"ab".repeat(10000));
+    let nfa: NFA<CharClass> = concat.to_nfa().unwrap();
+    assert!(nfa.is_match("ab".repeat(10000).chars()));
+    assert!(!nfa.is_match("ab".repeat(9999).chars()));
+    assert_eq!("ab".repeat(10000), concat.simplify().to_string());
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Token {
+    Ident,
+    Number,
+    Plus,
+    Open,
+    Close,
+}
+
+impl Disjoin for Token {
+    fn disjoin(vec: Vec<&Self>) -> Vec<Self> {
+        let mut tokens: Vec<_> = vec.into_iter().copied().collect();
+        tokens.sort_unstable();
+        tokens.dedup();
+        tokens
+    }
+
+    fn contains(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+#[test]
+fn test_token_expr() {
+    use Token::*;
+
+    // A sum of operands, each of which may be parenthesized.
+    let operand = Expr::from(Ident).or(Number);
+    let operand = operand
+        .clone()
+        .or(Expr::sequence(vec![Open]).then(operand).then(Close));
+    let sum = operand.clone().then(Expr::from(Plus).then(operand).star());
+
+    let nfa: NFA<Token> = sum.to_nfa().unwrap();
+    let dfa: DFA<Token> = nfa.clone().into();
+    let cases: &[(&[Token], bool)] = &[
+        (&[Ident], true),
+        (&[Ident, Plus, Number], true),
+        (
+            &[Open, Number, Close, Plus, Ident, Plus, Open, Ident, Close],
+            true,
+        ),
+        (&[], false),
+        (&[Ident, Plus], false),
+        (&[Open, Ident], false),
+        (&[Ident, Number], false),
+    ];
+    for &(input, expected) in cases {
+        assert_eq!(expected, nfa.is_match(input.iter().copied()), "{:?}", input);
+        assert_eq!(expected, dfa.is_match(input.iter().copied()), "{:?}", input);
+    }
+
+    let m = nfa
+        .find_unanchored(vec![Plus, Ident, Plus, Number, Close])
+        .unwrap();
+    assert_eq!(1..4, m.range());
+
+    assert_eq!(Expr::<Token>::Empty, Expr::sequence(Vec::<Token>::new()));
+}
+
+#[test]
+fn test_builder() {
+    let digits = CharClass::from(CharRange::new('0', '9'));
+    let cases = vec![
+        (Expr::literal("abc"), "abc"),
+        (Expr::literal("a").or('b').star().then("abb"), "(a|b)*abb"),
+        (Expr::literal("a.c").plus(), r"(a\.c)+"),
+        (
+            Expr::class(digits.clone())
+                .plus()
+                .then(Expr::literal("x").optional()),
+            r"[0-9]+x?",
+        ),
+        (Expr::literal("x").then(digits.complement()), r"x[^0-9]"),
+        (Expr::literal("ab").group(1).star(), "(ab)*"),
+    ];
+    for (built, expr) in cases {
+        assert_eq!(
+            parse(expr).simplify(),
+            built.clone().simplify(),
+            r#"builder didn't produce "{}""#,
+            expr
+        );
+        assert_eq!(parse(&built.to_string()).simplify(), built.simplify());
+    }
+
+    assert_eq!(Expr::Empty, Expr::literal(""));
+}
+
+#[test]
+fn test_from_automaton() {
+    let exprs = [
+        "a",
+        "(a|b)*abb",
+        "x(yz)*|xy?",
+        "[0-9]+(\\.[0-9]*)?",
+        "(ab|ba)*a?",
+        "é*ø+",
+        "a*",
+    ];
+
+    for expr in &exprs {
+        let nfa: NFA<CharClass> = NFAParser::new().parse(expr).unwrap();
+        let dfa: DFA<CharClass> = nfa.clone().into();
+        for converted in &[
+            Expr::from_dfa(&dfa),
+            Expr::from_dfa(&dfa.minimize()),
+            Expr::from_nfa(&nfa),
+        ] {
+            let reparsed: DFA<CharClass> = NFAParser::new()
+                .parse(&converted.to_string())
+                .unwrap()
+                .into();
+            assert!(
+                reparsed.is_equivalent(&dfa),
+                r#""{}" was converted to "{}""#,
+                expr,
+                converted
+            );
+        }
+    }
+
+    // Nothing is accepted, so the expression is an empty class.
+    let dfa: DFA<CharClass> = NFA::new().into();
+    assert_eq!(Expr::Atom(CharClass::new()), Expr::from_dfa(&dfa));
+
+    // Only the empty string is accepted.
+    let dfa: DFA<CharClass> = NFA::new_epsilon().into();
+    assert_eq!(Expr::Empty, Expr::from_dfa(&dfa));
+}
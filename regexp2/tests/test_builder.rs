@@ -0,0 +1,225 @@
+use regexp2::parser::{LineTerminator, ParseError};
+use regexp2::{Error, RegExpBuilder};
+
+#[test]
+fn test_case_insensitive() {
+    let re = RegExpBuilder::new("ab[c-e]")
+        .case_insensitive(true)
+        .build()
+        .unwrap();
+    assert!(re.is_match("abc"));
+    assert!(re.is_match("ABD"));
+    assert!(re.is_match("aBe"));
+    assert!(!re.is_match("abf"));
+
+    let re = RegExpBuilder::new("[^a]")
+        .case_insensitive(true)
+        .build()
+        .unwrap();
+    assert!(re.is_match("b"));
+    assert!(!re.is_match("a"));
+    assert!(!re.is_match("A"));
+
+    let re = RegExpBuilder::new("ab").build().unwrap();
+    assert!(!re.is_match("AB"));
+}
+
+#[test]
+fn test_dot_matches_new_line() {
+    let re = RegExpBuilder::new("a.b").build().unwrap();
+    assert!(!re.is_match("a\nb"));
+
+    let re = RegExpBuilder::new("a.b")
+        .dot_matches_new_line(true)
+        .build_nfa()
+        .unwrap();
+    assert!(re.is_match("a\nb"));
+    assert!(re.is_match("a-b"));
+}
+
+#[test]
+fn test_line_terminator() {
+    let cases = [
+        (LineTerminator::Newline, vec!["\n"], vec!["\r", "\u{85}"]),
+        (
+            LineTerminator::CRLF,
+            vec!["\n", "\r"],
+            vec!["\u{85}", "\u{2028}"],
+        ),
+        (
+            LineTerminator::Unicode,
+            vec![
+                "\n", "\r", "\u{b}", "\u{c}", "\u{85}", "\u{2028}", "\u{2029}",
+            ],
+            vec!["\t", " "],
+        ),
+    ];
+    for (terminator, ends, others) in &cases {
+        let re = RegExpBuilder::new("a.b")
+            .line_terminator(*terminator)
+            .build()
+            .unwrap();
+        let dotall = RegExpBuilder::new("a.b")
+            .line_terminator(*terminator)
+            .dot_matches_new_line(true)
+            .build_nfa()
+            .unwrap();
+        for c in ends {
+            let input = format!("a{}b", c);
+            assert!(!re.is_match(&input), "{:?} {:?}", terminator, c);
+            assert!(dotall.is_match(&input), "{:?} {:?}", terminator, c);
+        }
+        for c in others {
+            assert!(re.is_match(&format!("a{}b", c)), "{:?} {:?}", terminator, c);
+        }
+    }
+}
+
+#[test]
+fn test_unicode() {
+    let re = RegExpBuilder::new(r"\d\s").build().unwrap();
+    assert!(re.is_match("４\u{3000}"));
+
+    let re = RegExpBuilder::new(r"\d\s").unicode(false).build().unwrap();
+    assert!(re.is_match("4 "));
+    assert!(!re.is_match("４\u{3000}"));
+
+    let re = RegExpBuilder::new(r"\D").unicode(false).build().unwrap();
+    assert!(re.is_match("４"));
+    assert!(!re.is_match("4"));
+}
+
+#[test]
+fn test_dfa_size_limit() {
+    let err = RegExpBuilder::new("(a|b)*abb")
+        .dfa_size_limit(2)
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, Error::DFASizeLimitExceeded { limit: 2 }));
+
+    let re = RegExpBuilder::new("(a|b)*abb")
+        .dfa_size_limit(100)
+        .build()
+        .unwrap();
+    assert!(re.is_match("aababb"));
+}
+
+#[test]
+fn test_nest_limit() {
+    let err = RegExpBuilder::new("((a))")
+        .nest_limit(1)
+        .build()
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Parse(ParseError::NestLimitExceeded { limit: 1, .. })
+    ));
+
+    let re = RegExpBuilder::new("((a))").nest_limit(2).build().unwrap();
+    assert!(re.is_match("a"));
+}
+
+#[test]
+fn test_size_limit() {
+    // Three characters and two concatenations.
+    let re = RegExpBuilder::new("abc").size_limit(5).build().unwrap();
+    assert!(re.is_match("abc"));
+
+    let err = RegExpBuilder::new("abc").size_limit(4).build().unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Parse(ParseError::SizeLimitExceeded { limit: 4, .. })
+    ));
+
+    // Nested repetitions are rejected before they are expanded.
+    let expr = format!("{}a{}", "(".repeat(40), ")+".repeat(40));
+    let err = RegExpBuilder::new(&expr)
+        .size_limit(10_000)
+        .build_nfa()
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Parse(ParseError::SizeLimitExceeded { limit: 10_000, .. })
+    ));
+}
+
+#[test]
+fn test_nfa_size_limit() {
+    let err = RegExpBuilder::new("(a|b)*abb")
+        .nfa_size_limit(4)
+        .build_nfa()
+        .unwrap_err();
+    assert!(matches!(err, Error::NFASizeLimitExceeded { limit: 4 }));
+
+    let re = RegExpBuilder::new("(a|b)*abb")
+        .nfa_size_limit(100)
+        .build()
+        .unwrap();
+    assert!(re.is_match("aababb"));
+
+    // The NFA over bytes is larger than the one over characters.
+    let err = RegExpBuilder::new("[a-é]")
+        .nfa_size_limit(3)
+        .build_bytes()
+        .unwrap_err();
+    assert!(matches!(err, Error::NFASizeLimitExceeded { limit: 3 }));
+    assert!(RegExpBuilder::new("[a-é]")
+        .nfa_size_limit(3)
+        .build()
+        .is_ok());
+}
+
+#[test]
+fn test_empty_matches() {
+    for expr in &["", "()", "a|", "|a", "(|a)b*"] {
+        assert!(
+            RegExpBuilder::new(expr)
+                .empty_matches(true)
+                .build()
+                .unwrap()
+                .is_match(""),
+            "{:?}",
+            expr
+        );
+    }
+
+    let re = RegExpBuilder::new("a(|b)c")
+        .empty_matches(true)
+        .build()
+        .unwrap();
+    assert!(re.is_match("ac"));
+    assert!(re.is_match("abc"));
+    assert!(!re.is_match("a"));
+
+    let re = RegExpBuilder::new("a()b")
+        .empty_matches(true)
+        .build()
+        .unwrap();
+    assert!(re.is_match("ab"));
+
+    let re = RegExpBuilder::new("")
+        .empty_matches(true)
+        .build_nfa()
+        .unwrap();
+    assert_eq!(Some(0..0), re.find("abc").map(|m| m.range()));
+
+    let re = RegExpBuilder::new("()*")
+        .empty_matches(true)
+        .build()
+        .unwrap();
+    assert!(re.is_match(""));
+    assert!(!re.is_match("a"));
+
+    assert!(matches!(
+        RegExpBuilder::new("").build(),
+        Err(Error::Parse(ParseError::EmptyExpression { .. }))
+    ));
+    assert!(matches!(
+        RegExpBuilder::new("a|").build(),
+        Err(Error::Parse(ParseError::EmptyExpression { .. }))
+    ));
+    assert!(RegExpBuilder::new("|")
+        .empty_matches(true)
+        .validate()
+        .is_ok());
+}
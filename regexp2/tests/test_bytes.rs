@@ -0,0 +1,238 @@
+use regexp2::automata::convert::Disjoin;
+use regexp2::automata::{DenseDFA, DFA};
+use regexp2::bytes;
+use regexp2::class::{ByteClass, CharRange};
+use regexp2::utf8::{self, ByteRange};
+use regexp2::{MatchKind, RegExp, RegExpBuilder};
+
+fn matches_sequence(sequence: &[ByteRange], bytes: &[u8]) -> bool {
+    sequence.len() == bytes.len()
+        && sequence
+            .iter()
+            .zip(bytes.iter())
+            .all(|(r, &b)| r.contains(b))
+}
+
+#[test]
+fn test_sequences() {
+    let ranges = [
+        CharRange::new('\0', '\u{10FFFF}'),
+        CharRange::new('a', 'z'),
+        CharRange::new('\u{7F}', '\u{80}'),
+        CharRange::new('\u{3A9}', '\u{2603}'),
+        CharRange::new('\u{D7FF}', '\u{E000}'),
+        CharRange::new('\u{FFFF}', '\u{10001}'),
+        CharRange::new('\u{1F600}', '\u{1F64F}'),
+    ];
+    let samples: Vec<char> = (0..0x11_0000)
+        .step_by(97)
+        .chain(vec![
+            0x7F, 0x80, 0x7FF, 0x800, 0xD7FF, 0xE000, 0xFFFF, 0x1_0000, 0x10_FFFF,
+        ])
+        .filter_map(std::char::from_u32)
+        .collect();
+
+    for range in &ranges {
+        let sequences = utf8::sequences(range);
+        for &c in &samples {
+            let mut buf = [0; 4];
+            let bytes = c.encode_utf8(&mut buf).as_bytes();
+            let count = sequences
+                .iter()
+                .filter(|s| matches_sequence(s, bytes))
+                .count();
+            let expected = if range.contains(c) { 1 } else { 0 };
+            assert_eq!(
+                expected, count,
+                "{:?} matched {} sequences of {:?}",
+                c, count, range
+            );
+        }
+    }
+}
+
+#[test]
+fn test_byte_class() {
+    let class: ByteClass = vec![
+        ByteRange::new(b'a', b'c'),
+        ByteRange::new(b'd', b'f'),
+        ByteRange::new(0x80, 0xFF),
+        ByteRange::new(b'z', b'x'),
+    ]
+    .into();
+    assert_eq!(2, class.iter().count());
+    assert_eq!(134, class.len());
+    assert!(class.contains(b'e') && class.contains(0xFF));
+    assert!(!class.contains(b'g') && !class.contains(b'y'));
+    assert_eq!(r"[a-f\x80-\xFF]", class.to_string());
+    assert_eq!("ByteClass(a)", format!("{:?}", ByteClass::from(b'a')));
+
+    let complement = class.complement();
+    assert_eq!(256 - 134, complement.len());
+    assert!(complement.intersection(&class).is_empty());
+    assert_eq!(ByteClass::all(), ByteClass::new().complement());
+    assert_eq!(class, complement.complement());
+
+    let other: ByteClass = ByteRange::new(b'c', b'k').into();
+    let pieces = ByteClass::disjoin(vec![&class, &other]);
+    let pieces: Vec<_> = pieces.iter().map(ToString::to_string).collect();
+    assert_eq!(vec!["[a-b]", "[c-f]", "[g-k]", r"[\x80-\xFF]"], pieces);
+}
+
+#[test]
+fn test_compile_classes() {
+    let exprs = ["(a|b)*abb", "[^a-z]+", "é+|ü", "😀|[\u{10000}-\u{10FFFF}]+"];
+    let inputs = [
+        "abb",
+        "aababb",
+        "AGAQR",
+        "ééé",
+        "über",
+        "a😀b",
+        "\u{10FFFF}",
+    ];
+
+    for expr in &exprs {
+        let re = RegExpBuilder::new(expr).build_nfa().unwrap();
+        let classes = utf8::compile_classes(re.nfa());
+        let nfa = bytes::expand(&classes);
+        let dfa: DenseDFA<ByteClass> = DFA::from(classes).minimize().into();
+        for input in &inputs {
+            assert_eq!(
+                re.is_match(input),
+                dfa.is_match(input.bytes()),
+                r#""{}" disagreed on matching "{}""#,
+                expr,
+                input
+            );
+            assert_eq!(re.is_match(input), nfa.is_match(input.bytes()));
+        }
+    }
+}
+
+#[test]
+fn test_bytes() {
+    let exprs = [
+        "(a|b)*abb",
+        r"\d+\w?",
+        "[^a-z]+",
+        "é+|ü",
+        ".+",
+        "[α-ω]*x",
+        "😀|[\u{10000}-\u{10FFFF}]+",
+    ];
+    let inputs = [
+        "",
+        "abb",
+        "aababb",
+        "x 42 y",
+        "AGAQR",
+        "ééé",
+        "über",
+        "αβγx",
+        "a😀b",
+        "\u{10FFFF}",
+        "٣",
+        "\n",
+    ];
+
+    for expr in &exprs {
+        let re = RegExp::new(expr).unwrap();
+        let bytes_re = RegExpBuilder::new(expr).build_bytes().unwrap();
+        for input in &inputs {
+            assert_eq!(
+                re.is_match(input),
+                bytes_re.is_match(input),
+                r#""{}" disagreed on matching "{}""#,
+                expr,
+                input
+            );
+            assert_eq!(
+                re.find(input).map(|m| m.range()),
+                bytes_re.find(input).map(|m| m.range()),
+                r#""{}" disagreed on finding in "{}""#,
+                expr,
+                input
+            );
+            assert_eq!(
+                re.is_match(input),
+                bytes_re.is_match_bytes(input.as_bytes())
+            );
+        }
+    }
+}
+
+#[test]
+fn test_bytes_invalid_utf8() {
+    let re = RegExpBuilder::new("é+").build_bytes().unwrap();
+    assert_eq!(Some(2..6), re.find_bytes(b"\xff\xc3\xc3\xa9\xc3\xa9\xa9"));
+    assert_eq!(None, re.find_bytes(b"\xa9\xc3"));
+    assert!(!re.is_match_bytes(b"\xc3\xa9\xc3"));
+    assert_eq!(Some(4..6), re.find_bytes_at(b"\xc3\xa9x\xff\xc3\xa9", 2));
+}
+
+#[test]
+fn test_bytes_leftmost_first() {
+    let re = RegExpBuilder::new("é|éa")
+        .match_kind(MatchKind::LeftmostFirst)
+        .build_bytes()
+        .unwrap();
+    assert_eq!(Some(1..3), re.find("xéa").map(|m| m.range()));
+    assert_eq!(Some(1..3), re.find_bytes("xéa".as_bytes()));
+}
+
+#[test]
+fn test_byte_regexp_unicode() {
+    let re = bytes::RegExp::new("é.").unwrap();
+    assert!(re.is_match("éa".as_bytes()));
+    assert!(re.is_match("éé".as_bytes()));
+    assert!(!re.is_match(b"\xe9a"));
+    assert!(!re.is_match(b"\xc3\xa9\xff"));
+
+    let m = re.find(b"\xff\xc3\xa9\xce\xa9").unwrap();
+    assert_eq!(1..5, m.range());
+    assert_eq!("éΩ".as_bytes(), m.as_bytes());
+}
+
+#[test]
+fn test_byte_regexp_no_unicode() {
+    let re = RegExpBuilder::new("é.[^a]")
+        .unicode(false)
+        .build_byte_regexp()
+        .unwrap();
+    assert!(re.is_match(b"\xe9\xff\x80"));
+    assert!(re.is_match(b"\xe9a\x00"));
+    assert!(!re.is_match(b"\xe9\n\x00"));
+    assert!(!re.is_match(b"\xe9\x00a"));
+    assert!(!re.is_match("éab".as_bytes()));
+
+    let m = re.find(b"xx\xe9\xe9\xe9").unwrap();
+    assert_eq!(2..5, m.range());
+    assert_eq!(None, re.find_at(b"xx\xe9\xe9\xe9", 3));
+
+    // Characters beyond a byte never match.
+    let re = RegExpBuilder::new("Ω|a")
+        .unicode(false)
+        .build_byte_regexp()
+        .unwrap();
+    assert!(!re.is_match("Ω".as_bytes()));
+    assert!(re.is_match(b"a"));
+}
+
+#[test]
+fn test_byte_regexp_prefix() {
+    let re = RegExpBuilder::new("ab\u{FF}c")
+        .unicode(false)
+        .build_byte_regexp()
+        .unwrap();
+    assert_eq!(Some(3..7), re.find(b"ab\xc3ab\xffc").map(|m| m.range()));
+    assert_eq!(None, re.find("abÿc".as_bytes()));
+}
+
+#[test]
+fn test_byte_regexp_from_str() {
+    let re: bytes::RegExp = "a+b".parse().unwrap();
+    assert_eq!("a+b", re.as_str());
+    assert_eq!("a+b", re.to_string());
+    assert!("a(".parse::<bytes::RegExp>().is_err());
+}
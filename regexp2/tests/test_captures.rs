@@ -0,0 +1,211 @@
+use regexp2::{MatchKind, RegExp, RegExpBuilder};
+
+fn groups<'t>(re: &RegExp<impl regexp2::Engine>, input: &'t str) -> Option<Vec<Option<&'t str>>> {
+    let caps = re.captures(input)?;
+    Some(caps.iter().map(|m| m.map(|m| m.as_str())).collect())
+}
+
+#[test]
+fn test_captures() {
+    let re = RegExp::new(r"(\d+)-(\d+)").unwrap();
+    assert_eq!(3, re.captures_len());
+    assert_eq!(
+        Some(vec![Some("12-345"), Some("12"), Some("345")]),
+        groups(&re, "tel 12-345")
+    );
+    assert_eq!(None, groups(&re, "12-"));
+
+    let caps = re.captures("é12-3").unwrap();
+    assert_eq!(Some(2..6), caps.get(0).map(|m| m.range()));
+    assert_eq!(Some(2..4), caps.get(1).map(|m| m.range()));
+    assert_eq!(Some(5..6), caps.get(2).map(|m| m.range()));
+    assert_eq!(None, caps.get(3));
+}
+
+#[test]
+fn test_captures_optional_groups() {
+    let re = RegExp::new("(a)|(b)").unwrap();
+    assert_eq!(Some(vec![Some("b"), None, Some("b")]), groups(&re, "b"));
+
+    // A repeated group holds its last repetition.
+    let re = RegExp::new("(?:(a)|(b))+").unwrap();
+    assert_eq!(
+        Some(vec![Some("aba"), Some("a"), Some("b")]),
+        groups(&re, "aba")
+    );
+
+    let re = RegExp::new("x(y*)").unwrap();
+    assert_eq!(Some(vec![Some("x"), Some("")]), groups(&re, "xz"));
+}
+
+#[test]
+fn test_captures_without_groups() {
+    let re = RegExp::new("a+").unwrap();
+    assert_eq!(1, re.captures_len());
+    let caps = re.captures("baab").unwrap();
+    assert_eq!(1, caps.len());
+    assert_eq!(Some("aa"), caps.get(0).map(|m| m.as_str()));
+
+    let re = RegExp::new("(?:ab)+").unwrap();
+    assert_eq!(1, re.captures_len());
+}
+
+#[test]
+fn test_captures_match_kind() {
+    // The whole match follows the match kind, and the groups are placed within it.
+    let re = RegExp::new("(a|ab)(c|bcd)").unwrap();
+    assert_eq!(
+        Some(vec![Some("abcd"), Some("a"), Some("bcd")]),
+        groups(&re, "abcd")
+    );
+    let re = RegExp::new("(a*)(a*)").unwrap();
+    assert_eq!(
+        Some(vec![Some("aa"), Some("aa"), Some("")]),
+        groups(&re, "aa")
+    );
+
+    let re = RegExpBuilder::new("(a|ab)(c|bcd)")
+        .match_kind(MatchKind::LeftmostFirst)
+        .build()
+        .unwrap();
+    assert_eq!(
+        Some(vec![Some("abcd"), Some("a"), Some("bcd")]),
+        groups(&re, "abcd")
+    );
+    assert_eq!(
+        Some(vec![Some("abc"), Some("ab"), Some("c")]),
+        groups(&re, "abcx")
+    );
+}
+
+#[test]
+fn test_captures_engines() {
+    let expr = "([a-z]+)@([a-z]+)";
+    let input = "mail bob@host.";
+    let expected = Some(vec![Some("bob@host"), Some("bob"), Some("host")]);
+
+    let builder = RegExpBuilder::new(expr);
+    assert_eq!(expected, groups(&builder.build_nfa().unwrap(), input));
+    assert_eq!(expected, groups(&builder.build_lazy().unwrap(), input));
+    assert_eq!(expected, groups(&builder.build_bytes().unwrap(), input));
+    assert_eq!(expected, groups(&RegExp::new_nfa(expr).unwrap(), input));
+
+    let re = RegExp::new(expr).unwrap();
+    let caps = re.captures_at(input, 6).unwrap();
+    assert_eq!(Some("ob"), caps.get(1).map(|m| m.as_str()));
+}
+
+#[test]
+fn test_captures_long_match() {
+    // Matches too long for the bounded backtracker fall back to the Pike VM.
+    let re = RegExp::new("(a+)(b)").unwrap();
+    let n = 1 << 18;
+    let input = format!("x{}b", "a".repeat(n));
+    let caps = re.captures(&input).unwrap();
+    assert_eq!(Some(1..n + 1), caps.get(1).map(|m| m.range()));
+    assert_eq!(Some(n + 1..n + 2), caps.get(2).map(|m| m.range()));
+}
+
+#[test]
+fn test_captures_onepass() {
+    // Each character of a match determines its group, so the groups are found in one pass.
+    let re = RegExp::new(r"(\w+)@(\w+)\.(com|org)?").unwrap();
+    assert_eq!(
+        Some(vec![
+            Some("bob@host.org"),
+            Some("bob"),
+            Some("host"),
+            Some("org")
+        ]),
+        groups(&re, "to bob@host.org.")
+    );
+    assert_eq!(
+        Some(vec![Some("bob@host."), Some("bob"), Some("host"), None]),
+        groups(&re, "to bob@host.net")
+    );
+}
+
+#[test]
+fn test_captures_iter() {
+    let re = RegExp::new(r"(\d+)-(\d+)").unwrap();
+    let groups: Vec<_> = re
+        .captures_iter("1-2, 34-5,6-78-9")
+        .map(|caps| {
+            caps.iter()
+                .map(|m| m.map(|m| m.as_str()))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            vec![Some("1-2"), Some("1"), Some("2")],
+            vec![Some("34-5"), Some("34"), Some("5")],
+            vec![Some("6-78"), Some("6"), Some("78")],
+        ],
+        groups
+    );
+    assert_eq!(0, re.captures_iter("12-").count());
+
+    // Offsets are into the whole input.
+    let ranges: Vec<_> = re
+        .captures_iter("é1-2é3-4")
+        .map(|caps| caps.get(2).unwrap().range())
+        .collect();
+    assert_eq!(vec![4..5, 9..10], ranges);
+}
+
+#[test]
+fn test_captures_iter_empty_matches() {
+    let ranges = |expr: &str, input: &str| -> Vec<_> {
+        let re = RegExp::new(expr).unwrap();
+        re.captures_iter(input)
+            .map(|caps| caps.get(0).unwrap().range())
+            .collect()
+    };
+
+    // An empty match right after the previous match is skipped.
+    assert_eq!(vec![0..0, 1..2], ranges("a*", "ba"));
+    assert_eq!(vec![0..2, 3..3, 4..5], ranges("a*", "aabca"));
+    assert_eq!(vec![0..0, 1..1, 3..3], ranges("x*", "aé"));
+    assert_eq!(vec![0..0], ranges("(x)?", ""));
+
+    let re = RegExp::new("(a)?").unwrap();
+    let groups: Vec<_> = re
+        .captures_iter("ab")
+        .map(|caps| caps.get(1).map(|m| m.range()))
+        .collect();
+    assert_eq!(vec![Some(0..1), None], groups);
+}
+
+#[test]
+fn test_captures_expand() {
+    let re = RegExp::new("(a+)(b)?(c)").unwrap();
+    let caps = re.captures("xaac").unwrap();
+    let expand = |template: &str| {
+        let mut dst = String::new();
+        caps.expand(template, &mut dst).unwrap();
+        dst
+    };
+
+    assert_eq!("aa-c", expand("$1-$3"));
+    assert_eq!("aa0", expand("${1}0"));
+    assert_eq!("", expand("$10"));
+    assert_eq!("aac!", expand("$0!"));
+    assert_eq!("$1 costs $", expand("$$1 costs $$"));
+    assert_eq!("$", expand("$$"));
+
+    // Groups that didn't participate or don't exist, and names, are replaced by nothing.
+    assert_eq!("<>", expand("<$2>"));
+    assert_eq!("<><>", expand("<$4><${99999999999999999999}>"));
+    assert_eq!("<>", expand("<${name}>"));
+
+    // A `$` that doesn't start a reference is written as is.
+    assert_eq!("$x ${} ${1 $", expand("$x ${} ${1 $"));
+    assert_eq!("é$é", expand("é$é"));
+
+    // Any `fmt::Write` can be written to.
+    let mut dst = String::from("> ");
+    let writer: &mut dyn std::fmt::Write = &mut dst;
+    caps.expand("$3$1", writer).unwrap();
+    assert_eq!("> caa", dst);
+}
@@ -1,4 +1,6 @@
-use regexp2::RegExp;
+use regexp2::class::{CharClass, CharRange};
+use regexp2::parser::ParseError;
+use regexp2::{RegExp, RegExpBuilder};
 
 include!("macros.rs");
 
@@ -58,6 +60,57 @@ fn test_negation_symbol() {
     run_tests!(&exprs, &valids, &invalids);
 }
 
+#[test]
+fn test_class_escapes() {
+    let exprs = [r"[\]]", r"[x\]]"];
+    let valids = ["]"];
+    let invalids = ["", "]]", "[]", r"\"];
+    run_tests!(&exprs, &valids, &invalids);
+
+    let exprs = [r"[\^]", r"[x^]"];
+    let valids = ["^"];
+    let invalids = ["", "a", r"\"];
+    run_tests!(&exprs, &valids, &invalids);
+
+    let exprs = [r"[\\]"];
+    let valids = [r"\"];
+    let invalids = ["", "]", r"\\"];
+    run_tests!(&exprs, &valids, &invalids);
+
+    let exprs = [r"[a\-z]", r"[az\-]", r"[\-az]"];
+    let valids = ["a", "-", "z"];
+    let invalids = ["", "b", "y", r"\"];
+    run_tests!(&exprs, &valids, &invalids);
+
+    let exprs = [r"[\--/]"];
+    let valids = ["-", ".", "/"];
+    let invalids = ["", r"\", ",", "0"];
+    run_tests!(&exprs, &valids, &invalids);
+
+    let exprs = [r"[X-\]]", r"[\[-\]]"];
+    let valids = ["[", r"\", "]"];
+    let invalids = ["", "-", "^", "Z]"];
+    run_tests!(&exprs, &valids, &invalids);
+}
+
+#[test]
+fn test_class_literal_dash() {
+    let exprs = ["[-a]", "[a-]", "[-]"];
+    let valids = ["-"];
+    let invalids = ["", "b", "]"];
+    run_tests!(&exprs, &valids, &invalids);
+
+    let exprs = ["[^-]"];
+    let valids = ["a", "]"];
+    let invalids = ["", "-"];
+    run_tests!(&exprs, &valids, &invalids);
+
+    let exprs = [r"[\w-]", "[a-c-]"];
+    let valids = ["-", "a"];
+    let invalids = ["", "!", "]"];
+    run_tests!(&exprs, &valids, &invalids);
+}
+
 #[test]
 fn test_wildcard() {
     let exprs = ["."];
@@ -116,3 +169,289 @@ fn test_special_classes() {
     let invalids = ["", "a", "A", "5", "_"];
     run_tests!(&exprs, &valids, &invalids);
 }
+
+#[test]
+fn test_line_whitespace_classes() {
+    let exprs = [r"\h", r"[\h]"];
+    let valids = [" ", "\t", "\u{00a0}", "\u{2003}", "\u{3000}"];
+    let invalids = ["", "\n", "\r", "\u{000b}", "a"];
+    run_tests!(&exprs, &valids, &invalids);
+
+    let exprs = [r"\H"];
+    let valids = ["a", "\n"];
+    let invalids = ["", " ", "\t", "\u{2003}"];
+    run_tests!(&exprs, &valids, &invalids);
+
+    let exprs = [r"\v", r"[\v]"];
+    let valids = [
+        "\n", "\u{000b}", "\u{000c}", "\r", "\u{0085}", "\u{2028}", "\u{2029}",
+    ];
+    let invalids = ["", " ", "\t", "v", "\r\n"];
+    run_tests!(&exprs, &valids, &invalids);
+
+    let exprs = [r"\V"];
+    let valids = [" ", "v"];
+    let invalids = ["", "\n", "\r"];
+    run_tests!(&exprs, &valids, &invalids);
+
+    let exprs = [r"a\Rb"];
+    let valids = ["a\nb", "a\rb", "a\r\nb", "a\u{2028}b"];
+    let invalids = ["ab", "a b", "a\n\nb", "a\n\rb"];
+    run_tests!(&exprs, &valids, &invalids);
+
+    let re = RegExp::new(r"\R").unwrap();
+    assert_eq!(Some(1..3), re.find("a\r\nb").map(|m| m.range()));
+
+    let re = RegExpBuilder::new(r"\h\v").unicode(false).build().unwrap();
+    assert!(re.is_match(" \n"));
+    assert!(!re.is_match("\u{00a0}\n"));
+    assert!(!re.is_match(" \u{2028}"));
+
+    assert!(matches!(
+        RegExp::new(r"[a\R]"),
+        Err(ParseError::UnexpectedToken { token: 'R', .. })
+    ));
+}
+
+fn class(ranges: &[(char, char)]) -> CharClass {
+    ranges.iter().map(|&r| CharRange::from(r)).collect()
+}
+
+fn ranges(class: &CharClass) -> Vec<(char, char)> {
+    class.iter().map(|r| (r.start, r.end)).collect()
+}
+
+#[test]
+fn test_class_algebra() {
+    let lhs = class(&[('a', 'm'), ('x', 'z')]);
+    let rhs = class(&[('c', 'e'), ('k', 'y')]);
+
+    assert_eq!(
+        vec![('c', 'e'), ('k', 'm'), ('x', 'y')],
+        ranges(&lhs.intersection(&rhs))
+    );
+    assert_eq!(
+        vec![('a', 'b'), ('f', 'j'), ('z', 'z')],
+        ranges(&lhs.difference(&rhs))
+    );
+    assert_eq!(vec![('n', 'w')], ranges(&rhs.difference(&lhs)));
+    assert_eq!(
+        vec![('a', 'b'), ('f', 'j'), ('n', 'w'), ('z', 'z')],
+        ranges(&lhs.symmetric_difference(&rhs))
+    );
+
+    assert_eq!(ranges(&lhs), ranges(&lhs.difference(&CharClass::new())));
+    assert!(lhs.difference(&lhs).is_empty());
+    assert!(lhs.difference(&CharClass::all()).is_empty());
+    assert!(lhs.symmetric_difference(&lhs).is_empty());
+}
+
+#[test]
+fn test_class_difference_surrogates() {
+    // Removing the characters at either side of the surrogate code points must not produce ranges
+    // that start or end inside them.
+    let all = CharClass::all();
+    let edges = class(&[('\u{d7ff}', '\u{d7ff}'), ('\u{e000}', '\u{e000}')]);
+    assert_eq!(
+        vec![('\u{0}', '\u{d7fe}'), ('\u{e001}', '\u{10ffff}')],
+        ranges(&all.difference(&edges))
+    );
+    assert_eq!(
+        vec![('\u{0}', '\u{d7ff}')],
+        ranges(&all.difference(&class(&[('\u{e000}', '\u{10ffff}')])))
+    );
+    assert_eq!(
+        vec![('\u{10fffe}', '\u{10ffff}')],
+        ranges(&all.difference(&class(&[('\u{0}', '\u{10fffd}')])))
+    );
+}
+
+#[test]
+fn test_class_normalization() {
+    // Adjacent and overlapping ranges are merged, including a range spanning several others.
+    let merged = class(&[
+        ('b', 'b'),
+        ('d', 'd'),
+        ('f', 'f'),
+        ('a', 'z'),
+        ('0', '8'),
+        ('9', '9'),
+    ]);
+    assert_eq!(vec![('0', '9'), ('a', 'z')], ranges(&merged));
+
+    let abc: CharClass = vec!['c', 'a', 'b'].into();
+    assert_eq!(vec![('a', 'c')], ranges(&abc));
+    assert_eq!(class(&[('a', 'c')]), abc);
+
+    let around = class(&[('\u{e000}', '\u{10ffff}'), ('\u{0}', '\u{d7ff}')]);
+    assert_eq!(vec![('\u{0}', '\u{10ffff}')], ranges(&around));
+    assert_eq!(CharClass::all(), around);
+
+    // Inverted ranges contain no characters and are dropped.
+    let inverted = class(&[('a', 'c'), ('q', 'p'), ('z', 'x')]);
+    assert_eq!(vec![('a', 'c')], ranges(&inverted));
+    assert_eq!(class(&[('a', 'c')]), inverted);
+    assert!(!inverted.contains('y'));
+    assert!(CharClass::from(CharRange::new('q', 'p')).is_empty());
+
+    // Ranges inserted directly are only removed by normalizing.
+    let mut inserted = CharClass::from('a');
+    inserted.ranges.insert(CharRange::new('z', 'x'));
+    assert_eq!(CharClass::from('a'), inserted);
+    inserted.normalize();
+    assert_eq!(vec![('a', 'a')], ranges(&inserted));
+}
+
+#[test]
+fn test_class_contains() {
+    let classes = [
+        CharClass::letter(),
+        CharClass::decimal_number(),
+        CharClass::whitespace(),
+        CharClass::all(),
+        CharClass::new(),
+        class(&[('a', 'm'), ('c', 'b'), ('x', 'z')]),
+    ];
+    let chars = (0..0x11000u32)
+        .step_by(7)
+        .chain(0x10f000..0x110000)
+        .filter_map(std::char::from_u32)
+        .chain("abcdlmnwxyzé٣ \u{2005}".chars());
+
+    for c in chars {
+        for class in &classes {
+            let expected = class.iter().any(|r| r.contains(c));
+            assert_eq!(expected, class.contains(c), "{:?} in {:?}", c, class);
+        }
+    }
+}
+
+#[test]
+fn test_class_iteration() {
+    let class = class(&[('x', 'z'), ('a', 'c'), ('q', 'p')]);
+    assert_eq!(vec![('a', 'c'), ('x', 'z')], ranges(&class));
+    assert_eq!(
+        vec![('a', 'c'), ('x', 'z')],
+        class
+            .iter_ranges()
+            .map(|r| (r.start, r.end))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!("abcxyz", class.chars().collect::<String>());
+    assert_eq!(6, class.len());
+
+    assert_eq!(0, CharClass::new().len());
+    assert_eq!(0x10ffff + 1 - 0x800, CharClass::all().len());
+    assert_eq!(CharClass::all().len(), CharClass::all().chars().count());
+    assert_eq!(10, CharClass::from(CharRange::new('0', '9')).len());
+    assert_eq!(2, CharRange::new('\u{d7ff}', '\u{e000}').len());
+    assert_eq!(
+        vec!['\u{d7ff}', '\u{e000}'],
+        CharRange::new('\u{d7ff}', '\u{e000}')
+            .chars()
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_case_fold() {
+    let folded = CharClass::from('k').case_fold();
+    assert!(folded.contains('k'));
+    assert!(folded.contains('K'));
+
+    let folded = CharClass::from(vec!['a', 'B']).case_fold();
+    assert_eq!(CharClass::from(vec!['a', 'A', 'b', 'B']), folded);
+
+    // Folding is closed: folding again adds nothing.
+    let class: CharClass = CharRange::new('A', 'z').into();
+    let folded = class.case_fold();
+    assert_eq!(folded, folded.case_fold());
+
+    let class = CharClass::decimal_number();
+    assert_eq!(class, class.case_fold());
+}
+
+#[test]
+fn test_case_fold_large_class() {
+    let all = CharClass::all();
+    assert_eq!(all, all.case_fold());
+
+    // Only the parts of the class with case mappings are folded.
+    let class: CharClass = vec![
+        CharRange::new('\u{80}', '\u{d7ff}'),
+        CharRange::new('\u{e000}', '\u{10ffff}'),
+    ]
+    .into();
+    let folded = class.case_fold();
+    assert!(folded.contains('k'));
+    assert!(folded.contains('S'));
+    assert!(!folded.contains('a'));
+    assert!(CharClass::from('\u{24b6}').case_fold().contains('\u{24d0}'));
+    assert!(CharClass::from('\u{1e900}')
+        .case_fold()
+        .contains('\u{1e922}'));
+}
+
+#[cfg(feature = "unicode-case")]
+#[test]
+fn test_case_fold_tables() {
+    // The Kelvin sign and long s fold to k and s, which aren't their simple case mappings.
+    assert_eq!(
+        CharClass::from(vec!['k', 'K', '\u{212a}']),
+        CharClass::from('k').case_fold()
+    );
+    assert_eq!(
+        CharClass::from(vec!['s', 'S', 'ſ']),
+        CharClass::from('ſ').case_fold()
+    );
+    assert_eq!(
+        CharClass::from(vec!['Σ', 'σ', 'ς']),
+        CharClass::from('ς').case_fold()
+    );
+
+    let re = RegExpBuilder::new("[a-z]+")
+        .case_insensitive(true)
+        .build()
+        .unwrap();
+    assert!(re.is_match("\u{212a}ISS"));
+    assert!(re.is_match("ſ"));
+}
+
+#[cfg(feature = "unicode-properties")]
+#[test]
+fn test_unicode_property() {
+    use regexp2::class::UnknownPropertyError;
+
+    let greek = CharClass::from_unicode_property("Greek").unwrap();
+    assert!(greek.contains('α'));
+    assert!(greek.contains('Ω'));
+    assert!(!greek.contains('a'));
+    for alias in &["Grek", "greek", "GREEK", "g-r-e-e-k"] {
+        assert_eq!(Ok(&greek), CharClass::from_unicode_property(alias).as_ref());
+    }
+
+    let letter = CharClass::from_unicode_property("L").unwrap();
+    assert_eq!(letter, CharClass::from_unicode_property("Letter").unwrap());
+    assert!(letter.contains('a'));
+    assert!(letter.contains('α'));
+    assert!(!letter.contains('1'));
+
+    let digits = CharClass::from_unicode_property("Nd").unwrap();
+    assert_eq!(digits, CharClass::from_unicode_property("digit").unwrap());
+    assert!(digits.contains('٣'));
+
+    let common = CharClass::from_unicode_property("Zyyy").unwrap();
+    assert!(common.contains(' '));
+    assert!(!common.contains('a'));
+
+    assert_eq!(
+        Err(UnknownPropertyError("Klingon".to_owned())),
+        CharClass::from_unicode_property("Klingon")
+    );
+    assert_eq!(
+        "unknown Unicode property `Klingon`",
+        CharClass::from_unicode_property("Klingon")
+            .unwrap_err()
+            .to_string()
+    );
+}
@@ -0,0 +1,93 @@
+use regexp2::class::CharClass;
+
+include!("dfa.rs");
+
+#[test]
+fn test_complement() {
+    let exprs = ["(a|b)*abb", "[a-c]+", r"\d*", "x?"];
+    let inputs = [
+        "", "a", "abb", "aababb", "abc", "cab", "0", "123", "x", "xx", "é",
+    ];
+
+    for expr in &exprs {
+        let d = dfa(expr);
+        let complement = d.complement();
+        for input in &inputs {
+            assert_ne!(
+                d.is_match(input.chars()),
+                complement.is_match(input.chars()),
+                r#""{}" and its complement agreed on "{}""#,
+                expr,
+                input
+            );
+        }
+    }
+}
+
+#[test]
+fn test_complement_is_complete() {
+    let complement = dfa("ab").complement();
+    for state in 0..complement.total_states {
+        for c in &['a', 'b', 'z', '\n', '\u{10FFFF}'] {
+            let transitions = complement.transitions_on(&state);
+            assert_eq!(1, transitions.filter(|(t, _)| t.0 == *c).count());
+        }
+    }
+}
+
+#[test]
+fn test_double_complement() {
+    let d = dfa("(a|b)*abb");
+    let double = d.complement().complement();
+    for input in &["", "abb", "babb", "ab", "abba"] {
+        assert_eq!(d.is_match(input.chars()), double.is_match(input.chars()));
+    }
+}
+
+#[test]
+fn test_complement_surrogate_boundaries() {
+    use regexp2::class::CharRange;
+
+    let r = CharRange::new('\u{E000}', '\u{10FFFF}');
+    assert_eq!(vec![CharRange::new('\0', '\u{D7FF}')], r.complement());
+
+    let r = CharRange::new('\0', '\u{D7FF}');
+    assert_eq!(
+        vec![CharRange::new('\u{E000}', '\u{10FFFF}')],
+        r.complement()
+    );
+}
+
+#[test]
+fn test_make_total() {
+    let d = dfa("ab");
+    assert!(!d.is_total());
+
+    let mut total = d.clone();
+    let dead = total.make_total().unwrap();
+    assert!(total.is_total());
+    assert_eq!(d.total_states + 1, total.total_states);
+    assert!(!total.live_states()[dead]);
+    assert!(total.is_equivalent(&d));
+
+    // The dead state loops on every symbol.
+    let transitions: Vec<_> = total.transitions_on(&dead).collect();
+    assert_eq!(1, transitions.len());
+    assert_eq!(dead, *transitions[0].1);
+    assert_eq!(CharClass::all(), transitions[0].0 .0);
+
+    // Nothing is added to a DFA that is already total.
+    assert_eq!(None, total.make_total());
+    assert_eq!(d.total_states + 1, total.total_states);
+    assert!(d.complement().is_total());
+}
+
+#[test]
+fn test_count_complement() {
+    // Every character but the surrogates.
+    let chars = 0x110000 - 0x800;
+    let complement = dfa("ab").complement();
+    assert_eq!(Some(1), complement.count_accepted(0));
+    assert_eq!(Some(chars), complement.count_accepted(1));
+    assert_eq!(Some(chars * chars - 1), complement.count_accepted(2));
+}
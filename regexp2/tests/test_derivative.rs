@@ -0,0 +1,73 @@
+use regexp2::ast::{CompileError, Expr};
+use regexp2::automata::DFA;
+use regexp2::class::CharClass;
+use regexp2::derivative::DerivativeMatcher;
+use regexp2::parser::ast::{ASTParser, SpannedASTParser};
+use regexp2::parser::nfa::NFAParser;
+use regexp2::parser::Anchor;
+
+fn matcher(expr: &str) -> DerivativeMatcher {
+    DerivativeMatcher::new(&ASTParser::<char>::new().parse(expr).unwrap()).unwrap()
+}
+
+#[test]
+fn test_derivative_matches_dfa() {
+    let exprs = [
+        "(a|b)*abb",
+        "a+b?",
+        "[^a-c]x()y",
+        "((ab)+|c)*d",
+        r"\d+\.\d*",
+        "(a*)*b",
+        "(a|ab)(c|bcd)",
+        "é+x?",
+    ];
+    let inputs = [
+        "", "a", "b", "abb", "aababb", "abba", "ab", "aab", "dxy", "axy", "ababcd", "d", "3.",
+        "3.14", ".5", "b", "aaab", "abcd", "abc", "éé", "éx", "x",
+    ];
+
+    for expr in &exprs {
+        let dfa: DFA<CharClass> = NFAParser::new().parse(expr).unwrap().into();
+        let matcher = matcher(expr);
+        for input in &inputs {
+            assert_eq!(
+                dfa.is_match(input.chars()),
+                matcher.is_match(input.chars()),
+                r#"derivatives of "{}" disagree with the DFA on "{}""#,
+                expr,
+                input
+            );
+        }
+    }
+}
+
+#[test]
+fn test_derivative_memoization() {
+    let matcher = matcher("(a|b)*abb");
+    assert!(matcher.is_match("abababb".chars()));
+    let total = matcher.total_terms();
+
+    // The simplified derivatives of `(a|b)*abb` are finite, so further matching reuses them.
+    assert!(matcher.is_match("bbbabbabb".chars()));
+    assert!(!matcher.is_match("abbab".chars()));
+    assert_eq!(total, matcher.total_terms());
+}
+
+#[test]
+fn test_derivative_expr() {
+    let matcher = DerivativeMatcher::new(&Expr::Empty).unwrap();
+    assert!(matcher.is_match("".chars()));
+    assert!(!matcher.is_match("a".chars()));
+
+    let expr = SpannedASTParser::<char>::new().parse("(ab)+").unwrap();
+    let matcher = DerivativeMatcher::new(&expr).unwrap();
+    assert!(matcher.is_match("abab".chars()));
+    assert!(!matcher.is_match("aba".chars()));
+
+    let expr = ASTParser::<char>::new().parse("a$").unwrap();
+    assert_eq!(
+        CompileError::UnsupportedAnchor(Anchor::End),
+        DerivativeMatcher::new(&expr).unwrap_err()
+    );
+}
@@ -0,0 +1,49 @@
+use regexp2::parser::{Config, Parser};
+use regexp2::{parser::nfa::NFAParser, RegExp};
+
+fn render(expr: &str) -> String {
+    RegExp::new(expr).unwrap_err().diagnostic(expr).to_string()
+}
+
+#[test]
+fn test_diagnostic_unexpected_token() {
+    assert_eq!(
+        "error: unexpected token\n  |\n1 | a|*\n  |   ^ found '*', expected an expression\n",
+        render("a|*")
+    );
+    assert_eq!(
+        "error: unexpected token\n  |\n1 | é)\n  |  ^ found ')', expected an expression\n",
+        render("é)")
+    );
+}
+
+#[test]
+fn test_diagnostic_unexpected_eof() {
+    assert_eq!(
+        "error: unexpected end-of-file\n  |\n1 | (ab\n  |    ^ expression ends here, expected ')'\n",
+        render("(ab")
+    );
+}
+
+#[test]
+fn test_diagnostic_without_label() {
+    let expr = "((a))";
+    let config = Config {
+        nest_limit: Some(1),
+        ..Config::default()
+    };
+    let parser: NFAParser<regexp2::class::CharClass> = Parser::with_config(config);
+    let err = parser.parse(expr).unwrap_err();
+    assert_eq!(
+        "error: exceeded the nesting limit of 1\n  |\n1 | ((a))\n  |  ^\n",
+        err.diagnostic(expr).to_string()
+    );
+}
+
+#[test]
+fn test_diagnostic_multi_line() {
+    assert_eq!(
+        "error: unexpected token\n  |\n2 | b|*\n  |   ^ found '*', expected an expression\n",
+        render("a\nb|*")
+    );
+}
@@ -0,0 +1,81 @@
+use regexp2::automata::nfa::Transition;
+use regexp2::automata::DFA;
+use regexp2::class::{CharClass, CharRange};
+use regexp2::parser::nfa::NFAParser;
+
+#[test]
+fn test_char_class_display() {
+    let cases: Vec<(CharClass, &str)> = vec![
+        ('a'.into(), "a"),
+        ('.'.into(), r"\."),
+        ('\n'.into(), r"\n"),
+        ('\t'.into(), r"\t"),
+        ('['.into(), r"\["),
+        ('\\'.into(), r"\\"),
+        (CharRange::new('a', 'c').into(), "[a-c]"),
+        (vec!['a', 'x', '-'].into(), r"[\-ax]"),
+        (CharClass::all_but_newline(), r"[^\n]"),
+        (CharClass::new(), "[]"),
+    ];
+    for (class, expected) in cases {
+        assert_eq!(expected, class.to_string());
+    }
+}
+
+#[test]
+fn test_char_class_debug() {
+    let class: CharClass = vec![
+        CharRange::new('a', 'z'),
+        CharRange::new('A', 'Z'),
+        CharRange::new('0', '9'),
+        '_'.into(),
+    ]
+    .into();
+    assert_eq!("CharClass([0-9A-Z_a-z])", format!("{:?}", class));
+    assert_eq!("CharClass(a)", format!("{:?}", CharClass::from('a')));
+    assert_eq!(
+        "CharClass([^\\n])",
+        format!("{:?}", CharClass::all_but_newline())
+    );
+
+    assert_eq!("a-z", CharRange::new('a', 'z').to_string());
+    assert_eq!("\\-", CharRange::from('-').to_string());
+    assert_eq!("CharRange(\\n)", format!("{:?}", CharRange::from('\n')));
+
+    let some: Transition<CharClass> = Transition::Some(class);
+    assert_eq!("[0-9A-Z_a-z]", some.to_string());
+    assert_eq!("Some(CharClass([0-9A-Z_a-z]))", format!("{:?}", some));
+    assert_eq!("ε", Transition::<CharClass>::Epsilon.to_string());
+}
+
+#[test]
+fn test_nfa_to_dot() {
+    let nfa = NFAParser::new().parse("a|b").unwrap();
+    let dot = nfa.to_dot();
+    assert!(dot.starts_with("digraph {\n"));
+    assert!(dot.ends_with("}\n"));
+    assert!(dot.contains(&format!("start -> {};", nfa.start_state)));
+    assert!(dot.contains("[label=\"a\"]"));
+    assert!(dot.contains("[label=\"b\"]"));
+    assert!(dot.contains("[label=\"ε\"]"));
+    assert_eq!(1, dot.matches("doublecircle").count());
+}
+
+#[test]
+fn test_dfa_to_dot() {
+    let dfa: DFA<CharClass> = NFAParser::new().parse(r#"[a-c]"+"#).unwrap().into();
+    let dot = dfa.minimize().to_dot();
+    let expected = r#"digraph {
+    rankdir=LR;
+    start [shape=point];
+    0 [shape=circle];
+    1 [shape=circle];
+    2 [shape=doublecircle];
+    start -> 0;
+    0 -> 1 [label="[a-c]"];
+    1 -> 2 [label="\""];
+    2 -> 2 [label="\""];
+}
+"#;
+    assert_eq!(expected, dot);
+}
@@ -0,0 +1,82 @@
+use regexp2::automata::DFA;
+use regexp2::class::CharClass;
+
+include!("dfa.rs");
+
+#[test]
+fn test_is_equivalent() {
+    let equivalent = [
+        ("(a|b)*", "(a*b*)*"),
+        ("a+", "aa*"),
+        ("a?b", "ab|b"),
+        ("[a-c]", "a|b|c"),
+        ("[0-9][0-9]*", "[0-9]+"),
+        ("(ab)*a", "a(ba)*"),
+    ];
+    for (e1, e2) in &equivalent {
+        assert!(
+            dfa(e1).is_equivalent(&dfa(e2)),
+            r#""{}" and "{}" should be equivalent"#,
+            e1,
+            e2
+        );
+    }
+
+    let different = [
+        ("(a|b)*", "a*b*"),
+        ("a+", "a*"),
+        ("[a-c]", "a|b"),
+        (r"\d", "[0-9]"),
+        ("(ab)*", "(ba)*"),
+    ];
+    for (e1, e2) in &different {
+        assert!(
+            !dfa(e1).is_equivalent(&dfa(e2)),
+            r#""{}" and "{}" should not be equivalent"#,
+            e1,
+            e2
+        );
+    }
+}
+
+#[test]
+fn test_is_subset_of() {
+    assert!(dfa("a+").is_subset_of(&dfa("a*")));
+    assert!(!dfa("a*").is_subset_of(&dfa("a+")));
+    assert!(dfa("(a|b)*abb").is_subset_of(&dfa("(a|b)*")));
+    assert!(dfa("[a-c]+").is_subset_of(&dfa(r"\w+")));
+    assert!(!dfa(r"\w+").is_subset_of(&dfa("[a-c]+")));
+}
+
+#[test]
+fn test_is_empty() {
+    assert!(!dfa("a").is_empty());
+    assert!(dfa("a").intersection(&dfa("b")).is_empty());
+    assert!(DFA::<CharClass>::new().is_empty());
+}
+
+#[test]
+fn test_shortest_accepted() {
+    let cases = [
+        ("abc", Some("abc")),
+        ("a*", Some("")),
+        ("(a|b)*abb", Some("abb")),
+        ("xyz|q+", Some("q")),
+    ];
+    for (expr, expected) in &cases {
+        let witness = dfa(expr).shortest_accepted().map(|path| {
+            path.iter()
+                .map(|cc| cc.iter().next().unwrap().start)
+                .collect::<String>()
+        });
+        assert_eq!(expected.map(String::from), witness, r#""{}""#, expr);
+    }
+
+    assert_eq!(None, dfa("a").intersection(&dfa("b")).shortest_accepted());
+
+    // A witness that "(a|b)*" is not a subset of "a*b*".
+    let difference = dfa("(a|b)*").intersection(&dfa("a*b*").complement());
+    let witness = difference.shortest_accepted().unwrap();
+    assert_eq!(2, witness.len());
+    assert!(witness[0].contains('b') && witness[1].contains('a'));
+}
@@ -0,0 +1,46 @@
+use regexp2::parser::is_meta_character;
+use regexp2::RegExp;
+
+#[test]
+fn test_escape() {
+    assert_eq!(r"a\.b\*", RegExp::escape("a.b*"));
+    assert_eq!("plain text", RegExp::escape("plain text"));
+    assert_eq!(r"\\\(\)", RegExp::escape(r"\()"));
+    assert_eq!("é", RegExp::escape("é"));
+}
+
+#[test]
+fn test_escape_matches_literal() {
+    let meta: String = (0..128u8)
+        .map(char::from)
+        .filter(|&c| is_meta_character(c))
+        .collect();
+    let literals = [
+        meta.as_str(),
+        "1+1=2",
+        "[a-z]{3}",
+        "^(foo|bar)$",
+        "#&~-",
+        "price: $5.00?",
+    ];
+
+    for literal in &literals {
+        let re = RegExp::new(&RegExp::escape(literal)).unwrap();
+        assert!(re.is_match(literal), "{:?} did not match itself", literal);
+
+        // The escaped literal can be embedded in a larger pattern.
+        let expr = format!("x|{}|y", RegExp::escape(literal));
+        let re = RegExp::new(&expr).unwrap();
+        assert!(re.is_match(literal));
+        assert!(re.is_match("x"));
+        assert!(!re.is_match(&format!("{}z", literal)));
+    }
+}
+
+#[test]
+fn test_escape_in_class() {
+    let expr = format!("[{}]+", RegExp::escape("]-^\\"));
+    let re = RegExp::new(&expr).unwrap();
+    assert!(re.is_match("]^-\\"));
+    assert!(!re.is_match("a"));
+}
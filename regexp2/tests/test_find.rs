@@ -0,0 +1,79 @@
+use regexp2::RegExp;
+
+macro_rules! run_find_tests {
+    ($expr:expr, $cases:expr) => {{
+        let nfa_re = RegExp::new_nfa($expr).unwrap();
+        let dfa_re = RegExp::new($expr).unwrap();
+        $cases
+            .iter()
+            .for_each(|&(input, expected): &(&str, Option<(usize, usize)>)| {
+                let nfa_m = nfa_re.find(input).map(|m| (m.start(), m.end()));
+                assert_eq!(
+                    expected, nfa_m,
+                    r#""{}" found wrong match in "{}" using nfa"#,
+                    $expr, input
+                );
+
+                let dfa_m = dfa_re.find(input).map(|m| (m.start(), m.end()));
+                assert_eq!(
+                    expected, dfa_m,
+                    r#""{}" found wrong match in "{}" using dfa"#,
+                    $expr, input
+                );
+            });
+    }};
+}
+
+#[test]
+fn test_find_leftmost() {
+    let cases = [
+        ("ab", Some((0, 2))),
+        ("xxab", Some((2, 4))),
+        ("xaxab", Some((3, 5))),
+        ("abab", Some((0, 2))),
+        ("", None),
+        ("ba", None),
+    ];
+    run_find_tests!("ab", &cases);
+
+    let cases = [("aaa", Some((0, 3))), ("baa", Some((1, 3))), ("bbb", None)];
+    run_find_tests!("a+", &cases);
+}
+
+#[test]
+fn test_find_empty() {
+    let cases = [
+        ("", Some((0, 0))),
+        ("b", Some((0, 0))),
+        ("aab", Some((0, 2))),
+    ];
+    run_find_tests!("a*", &cases);
+}
+
+#[test]
+fn test_find_at() {
+    let re = RegExp::new("ab").unwrap();
+    let m = re.find_at("abab", 1).unwrap();
+    assert_eq!((2, 4), (m.start(), m.end()));
+    assert!(re.find_at("abab", 3).is_none());
+}
+
+#[test]
+fn test_find_shortest() {
+    let re = RegExp::new("xa+").unwrap();
+    let m = re.find_shortest("yxaaa").unwrap();
+    assert_eq!((1, 3), (m.start(), m.end()));
+    assert_eq!("xa", m.as_str());
+}
+
+#[test]
+fn test_find_byte_offsets() {
+    let re = RegExp::new("é+").unwrap();
+    let m = re.find("aéé!").unwrap();
+    assert_eq!((1, 5), (m.start(), m.end()));
+    assert_eq!("éé", m.as_str());
+
+    let m = re.find_at("éaé", 2).unwrap();
+    assert_eq!((3, 5), (m.start(), m.end()));
+    assert_eq!("é", m.as_str());
+}
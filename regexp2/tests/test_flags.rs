@@ -0,0 +1,119 @@
+use regexp2::parser::{Expected, ParseError};
+use regexp2::{RegExp, RegExpBuilder};
+
+#[test]
+fn test_unicode_flag() {
+    let re = RegExp::new(r"(?-u)\d\s").unwrap();
+    assert!(re.is_match("4 "));
+    assert!(!re.is_match("４\u{3000}"));
+
+    let re = RegExp::new(r"(?-u:\d)\d").unwrap();
+    assert!(re.is_match("4４"));
+    assert!(!re.is_match("４4"));
+
+    let re = RegExp::new(r"(?-u:\W)").unwrap();
+    assert!(re.is_match("é"));
+    assert!(!re.is_match("e"));
+
+    let re = RegExpBuilder::new(r"\d(?u)\d")
+        .unicode(false)
+        .build()
+        .unwrap();
+    assert!(re.is_match("4４"));
+    assert!(!re.is_match("４4"));
+}
+
+#[test]
+fn test_case_insensitive_flag() {
+    let re = RegExp::new("a(?i)b").unwrap();
+    assert!(re.is_match("aB"));
+    assert!(!re.is_match("AB"));
+
+    let re = RegExp::new("(?i:a)b").unwrap();
+    assert!(re.is_match("Ab"));
+    assert!(!re.is_match("AB"));
+
+    let re = RegExpBuilder::new("a(?-i)b")
+        .case_insensitive(true)
+        .build()
+        .unwrap();
+    assert!(re.is_match("Ab"));
+    assert!(!re.is_match("AB"));
+}
+
+#[test]
+fn test_dot_matches_new_line_flag() {
+    let re = RegExp::new("a(?s).b").unwrap();
+    assert!(re.is_match("a\nb"));
+    assert!(re.is_match("a-b"));
+
+    let re = RegExp::new("(?s:.).").unwrap();
+    assert!(re.is_match("\na"));
+    assert!(!re.is_match("a\n"));
+
+    let re = RegExpBuilder::new("(?-s).")
+        .dot_matches_new_line(true)
+        .build_nfa()
+        .unwrap();
+    assert!(re.is_match("a"));
+    assert!(!re.is_match("\n"));
+
+    let re = RegExpBuilder::new("(?is)A.").build_lazy().unwrap();
+    assert!(re.is_match("a\n"));
+}
+
+#[test]
+fn test_flag_scope() {
+    // Flags set without a colon apply until the end of the enclosing group.
+    let re = RegExp::new("(a(?i)b)c").unwrap();
+    assert!(re.is_match("aBc"));
+    assert!(!re.is_match("aBC"));
+
+    let re = RegExp::new("(?i)a|b").unwrap();
+    assert!(re.is_match("A"));
+    assert!(re.is_match("B"));
+
+    // Groups with flags don't capture.
+    let re = RegExp::new("(?:ab)+").unwrap();
+    assert!(re.is_match("abab"));
+    assert!(!re.is_match("aba"));
+}
+
+#[test]
+fn test_malformed_flags() {
+    let expected = vec![
+        Expected::Char('i'),
+        Expected::Char('s'),
+        Expected::Char('u'),
+        Expected::Char('-'),
+        Expected::Char(':'),
+        Expected::Char(')'),
+    ];
+
+    match RegExp::new("(?x)").unwrap_err() {
+        ParseError::UnexpectedToken {
+            token, expected: e, ..
+        } => {
+            assert_eq!('x', token);
+            assert_eq!(expected, e);
+        }
+        err => panic!("unexpected error {:?}", err),
+    }
+    // There is no multi-line flag, since anchors aren't supported.
+    assert!(matches!(
+        RegExp::new("(?m)a"),
+        Err(ParseError::UnexpectedToken { token: 'm', .. })
+    ));
+    assert!(matches!(
+        RegExp::new("(?i--u)"),
+        Err(ParseError::UnexpectedToken { token: '-', .. })
+    ));
+    assert!(matches!(
+        RegExp::new("(?i"),
+        Err(ParseError::UnexpectedEof { .. })
+    ));
+    assert!(matches!(
+        RegExp::new("(?i:a"),
+        Err(ParseError::UnexpectedEof { .. })
+    ));
+}
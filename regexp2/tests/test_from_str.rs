@@ -0,0 +1,31 @@
+use regexp2::automata::DenseDFA;
+use regexp2::class::CharClass;
+use regexp2::{ParseError, RegExp, RegExpBuilder};
+
+#[test]
+fn test_from_str() {
+    let re = "a(b|c)*".parse::<RegExp<_>>().unwrap();
+    assert!(re.is_match("abcb"));
+    assert!(!re.is_match("ad"));
+    assert_eq!("a(b|c)*", re.as_str());
+
+    // The error doesn't borrow the parsed string.
+    let err: ParseError<'static> = {
+        let expr = String::from("a(b");
+        expr.parse::<RegExp<DenseDFA<CharClass>>>().unwrap_err()
+    };
+    assert_eq!(3, err.span().start());
+}
+
+#[test]
+fn test_display() {
+    let re = RegExp::new(r"[a-z]+\d?").unwrap();
+    assert_eq!(r"[a-z]+\d?", re.to_string());
+    assert_eq!(
+        "pattern (a|b)*",
+        format!("pattern {}", RegExp::new_nfa("(a|b)*").unwrap())
+    );
+
+    let lazy = RegExpBuilder::new("x+").build_lazy().unwrap();
+    assert_eq!(lazy.as_str(), lazy.to_string());
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "grapheme")]
+
+use regexp2::grapheme::{self, Grapheme, RegExp};
+use regexp2::{MatchKind, RegExpBuilder};
+
+#[test]
+fn test_graphemes() {
+    let clusters: Vec<_> = grapheme::graphemes("e\u{301}👍🏽\r\nx").collect();
+    assert_eq!(
+        vec![
+            Grapheme("e\u{301}"),
+            Grapheme("👍🏽"),
+            Grapheme("\r\n"),
+            Grapheme("x")
+        ],
+        clusters
+    );
+}
+
+#[test]
+fn test_grapheme_is_match() {
+    let re = RegExp::new("a.+b").unwrap();
+    assert!(re.is_match("a👍🏽👨‍👩‍👧b"));
+    assert!(re.is_match("ae\u{301}b"));
+    assert!(!re.is_match("ab"));
+
+    // Quantifiers count clusters.
+    let re = RegExp::new("..").unwrap();
+    assert!(re.is_match("e\u{301}👍🏽"));
+    assert!(!re.is_match("e\u{301}"));
+    assert!(!re.is_match("👨‍👩‍👧"));
+
+    // A class matches a cluster only if it contains all of its characters.
+    let re = RegExp::new("[a-z]+").unwrap();
+    assert!(re.is_match("abc"));
+    assert!(!re.is_match("ae\u{301}"));
+    let re = RegExp::new("[a-z\u{300}-\u{36f}]+").unwrap();
+    assert!(re.is_match("ae\u{301}"));
+
+    // `.` doesn't match a cluster with a line terminator.
+    let re = RegExp::new("a.b").unwrap();
+    assert!(!re.is_match("a\r\nb"));
+    let re = RegExpBuilder::new("a.b")
+        .dot_matches_new_line(true)
+        .build_grapheme_regexp()
+        .unwrap();
+    assert!(re.is_match("a\r\nb"));
+}
+
+#[test]
+fn test_grapheme_find() {
+    let re = RegExp::new(".x").unwrap();
+    let input = "ae\u{301}x";
+    let m = re.find(input).unwrap();
+    assert_eq!("e\u{301}x", m.as_str());
+    assert_eq!(1..5, m.range());
+    assert_eq!(None, re.find("e\u{301}"));
+
+    // A match doesn't end in the middle of a cluster.
+    let re = RegExp::new("e").unwrap();
+    assert_eq!(None, re.find("e\u{301}"));
+    assert_eq!(Some(3), re.find("e\u{301}e").map(|m| m.start()));
+
+    let m = RegExp::new("b*").unwrap().find_at("abb", 1).unwrap();
+    assert_eq!(1..3, m.range());
+
+    let re = RegExpBuilder::new("a|ab")
+        .match_kind(MatchKind::LeftmostFirst)
+        .build_grapheme_regexp()
+        .unwrap();
+    assert_eq!("a", re.find("xab").unwrap().as_str());
+    assert_eq!(
+        "ab",
+        RegExp::new("a|ab").unwrap().find("xab").unwrap().as_str()
+    );
+}
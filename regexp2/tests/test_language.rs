@@ -0,0 +1,134 @@
+use regexp2::{RegExp, RegExpBuilder};
+
+const INPUTS: [&str; 14] = [
+    "", "a", "b", "ab", "ba", "abb", "aabb", "babb", "abab", "aaaa", "0", "a0", "ab0", "é",
+];
+
+#[test]
+fn test_language_operations() {
+    let pairs = [
+        ("(a|b)*abb", "a+b*"),
+        ("(a|b)*", "[^b]*"),
+        (r"\w+", "[ab]+0"),
+        ("a", "b"),
+        ("a*", "a?b"),
+    ];
+
+    for (e1, e2) in &pairs {
+        let (r1, r2) = (RegExp::new(e1).unwrap(), RegExp::new(e2).unwrap());
+        let (and, or, xor, not) = (r1.and(&r2), r1.or(&r2), r1.xor(&r2), r1.not());
+        for input in &INPUTS {
+            let (m1, m2) = (r1.is_match(input), r2.is_match(input));
+            let context = format!(r#""{}" and "{}" on "{}""#, e1, e2, input);
+            assert_eq!(m1 && m2, and.is_match(input), "and of {}", context);
+            assert_eq!(m1 || m2, or.is_match(input), "or of {}", context);
+            assert_eq!(m1 != m2, xor.is_match(input), "xor of {}", context);
+            assert_eq!(!m1, not.is_match(input), "not of {}", context);
+        }
+    }
+}
+
+#[test]
+fn test_language_operations_reparse() {
+    // The pattern of each result parses back to the same language, including results with the
+    // class of all characters or the empty string.
+    let exprs = ["a", ".", "a*", "b*", "(a|b)*abb", "é+x?", "[0-9]+", "ab|cd"];
+    for e1 in &exprs {
+        let r1 = RegExp::new(e1).unwrap();
+        let not = r1.not();
+        let mut results = vec![("not", e1, e1, not)];
+        for e2 in &exprs {
+            let r2 = RegExp::new(e2).unwrap();
+            results.push(("and", e1, e2, r1.and(&r2)));
+            results.push(("or", e1, e2, r1.or(&r2)));
+            results.push(("xor", e1, e2, r1.xor(&r2)));
+        }
+
+        for (op, e1, e2, result) in &results {
+            let reparsed = RegExp::new(result.as_str()).unwrap_or_else(|e| {
+                panic!(
+                    r#"{} of "{}" and "{}" gave "{}": {}"#,
+                    op, e1, e2, result, e
+                )
+            });
+            assert!(
+                reparsed.is_equivalent(result),
+                r#"{} of "{}" and "{}" gave "{}""#,
+                op,
+                e1,
+                e2,
+                result
+            );
+        }
+    }
+}
+
+#[test]
+fn test_language_operations_search() {
+    // Words of a's and b's other than "ab".
+    let re = RegExp::new("[ab]+")
+        .unwrap()
+        .and(&RegExp::new("ab").unwrap().not());
+    let matches: Vec<_> = ["x ab y", "x aab y", "ab ba"]
+        .iter()
+        .map(|input| re.find(input).map(|m| m.as_str()))
+        .collect();
+    assert_eq!(vec![Some("a"), Some("aab"), Some("a")], matches);
+
+    // The pattern matches the same inputs.
+    let reparsed = RegExp::new(re.as_str()).unwrap();
+    for input in &INPUTS {
+        assert_eq!(re.is_match(input), reparsed.is_match(input));
+    }
+}
+
+#[test]
+fn test_language_operations_flags() {
+    let re = RegExpBuilder::new("abc")
+        .case_insensitive(true)
+        .build()
+        .unwrap();
+    let lower = RegExp::new("[a-z]*").unwrap();
+    let mixed = re.and(&lower.not());
+    assert!(mixed.is_match("aBc"));
+    assert!(!mixed.is_match("abc"));
+    assert!(!mixed.is_match("xyz"));
+
+    // Nothing is matched by both an expression and its complement.
+    let nothing = re.and(&re.not());
+    assert!(["", "abc", "ABC"]
+        .iter()
+        .all(|input| !nothing.is_match(input)));
+    assert!(re.or(&re.not()).is_match("anything"));
+}
+
+#[test]
+fn test_equivalence_and_inclusion() {
+    let re = |expr| RegExp::new(expr).unwrap();
+
+    let cases = [
+        ("(a|b)*abb", "(a|b)*abb", true, true),
+        ("a+", "aa*", true, true),
+        ("[a-c]", "a|b|c", true, true),
+        ("a+", "a*", true, false),
+        ("abb", "(a|b)*abb", true, false),
+        ("ab", "ba", false, false),
+        (r"\d+", "[0-9]+", false, true),
+    ];
+    for &(e1, e2, subset, superset) in &cases {
+        let (r1, r2) = (re(e1), re(e2));
+        let context = format!(r#""{}" and "{}""#, e1, e2);
+        assert_eq!(subset, r1.matches_subset_of(&r2), "{}", context);
+        assert_eq!(superset, r2.matches_subset_of(&r1), "{}", context);
+        assert_eq!(subset && superset, r1.is_equivalent(&r2), "{}", context);
+    }
+
+    // The results of the language operations are related to their operands.
+    let (r1, r2) = (re("[a-z]+"), re("x*"));
+    assert!(r1.and(&r2).matches_subset_of(&r1));
+    assert!(r1.matches_subset_of(&r1.or(&r2)));
+    assert!(r1.not().not().is_equivalent(&r1));
+    assert!(r1
+        .xor(&r2)
+        .is_equivalent(&r1.or(&r2).and(&r1.and(&r2).not())));
+}
@@ -0,0 +1,67 @@
+use regexp2::{MatchKind, RegExp, RegExpBuilder};
+
+macro_rules! run_lazy_tests {
+    ($expr:expr, $capacity:expr, $inputs:expr) => {{
+        let dfa_re = RegExp::new($expr).unwrap();
+        let lazy_re = RegExpBuilder::new($expr)
+            .cache_capacity($capacity)
+            .build_lazy()
+            .unwrap();
+        $inputs.iter().for_each(|input: &&str| {
+            assert_eq!(
+                dfa_re.is_match(input),
+                lazy_re.is_match(input),
+                r#""{}" disagreed on matching "{}" with capacity {}"#,
+                $expr,
+                input,
+                $capacity
+            );
+            assert_eq!(
+                dfa_re.find(input).map(|m| m.range()),
+                lazy_re.find(input).map(|m| m.range()),
+                r#""{}" disagreed on finding in "{}" with capacity {}"#,
+                $expr,
+                input,
+                $capacity
+            );
+        });
+    }};
+}
+
+#[test]
+fn test_lazy() {
+    let inputs = ["", "abb", "aababb", "ab", "xxabbx", "babba"];
+    for &capacity in &[1, 2, 3, 1024] {
+        run_lazy_tests!("(a|b)*abb", capacity, &inputs);
+    }
+
+    let inputs = ["", "3a", "08m", "999_", "a3", "x 42 y"];
+    for &capacity in &[1, 4, 1024] {
+        run_lazy_tests!(r"\d+\w?", capacity, &inputs);
+    }
+}
+
+#[test]
+fn test_lazy_pathological() {
+    // The full DFA for this pattern has 2^9 states.
+    let expr = "(a|b)*a(a|b)(a|b)(a|b)(a|b)(a|b)(a|b)(a|b)(a|b)";
+    let re = RegExpBuilder::new(expr)
+        .cache_capacity(16)
+        .build_lazy()
+        .unwrap();
+    assert!(re.is_match("bbaaaaaaaaa"));
+    assert!(re.is_match("babbbbbbbb"));
+    assert!(!re.is_match("bbbbbbbbbbbbb"));
+    assert!(!re.is_match("baaaaaaaa"));
+    assert_eq!(Some(0..9), re.find("abbbbbbbbc").map(|m| m.range()));
+}
+
+#[test]
+fn test_lazy_leftmost_first() {
+    let re = RegExpBuilder::new("a|ab")
+        .match_kind(MatchKind::LeftmostFirst)
+        .build_lazy()
+        .unwrap();
+    assert_eq!(Some(1..2), re.find("xab").map(|m| m.range()));
+    assert!(re.is_match("ab"));
+}
@@ -1,4 +1,5 @@
-use regexp2::RegExp;
+use regexp2::parser::Expected;
+use regexp2::{ParseError, RegExp};
 
 macro_rules! run_invalid_tests {
     ($exprs:expr) => {{
@@ -15,3 +16,74 @@ fn test_malformed() {
     ];
     run_invalid_tests!(&exprs);
 }
+
+#[test]
+fn test_malformed_expected() {
+    let cases = [
+        ("*", vec![Expected::Expr]),
+        ("a|*", vec![Expected::Expr]),
+        ("(a", vec![Expected::Char(')')]),
+        ("a\\", vec![Expected::AnyChar]),
+        (
+            "[",
+            vec![Expected::Char('^'), Expected::ClassItem, Expected::ClassEnd],
+        ),
+        (
+            "[a",
+            vec![Expected::Char('-'), Expected::ClassItem, Expected::ClassEnd],
+        ),
+        ("[a-", vec![Expected::AnyChar]),
+        ("[^", vec![Expected::ClassItem, Expected::ClassEnd]),
+        ("[\\d", vec![Expected::ClassItem, Expected::ClassEnd]),
+    ];
+
+    for (expr, expected) in &cases {
+        match RegExp::new_nfa(expr) {
+            Err(ParseError::UnexpectedToken {
+                expected: found, ..
+            })
+            | Err(ParseError::UnexpectedEof {
+                expected: found, ..
+            }) => assert_eq!(expected, &found, r#""{}" expected the wrong tokens"#, expr),
+            other => panic!(r#""{}" gave the wrong error: {:?}"#, expr, other.err()),
+        }
+    }
+}
+
+#[test]
+fn test_empty_class() {
+    let cases = [
+        ("[]", 0, 1, "[]"),
+        ("a[^]b", 1, 3, "[^]"),
+        ("é|[]", 2, 3, "[]"),
+    ];
+
+    for &(expr, start, end, text) in &cases {
+        match RegExp::new_nfa(expr) {
+            Err(ParseError::EmptyCharacterClass { span }) => {
+                assert_eq!((start, end, text), (span.start(), span.end(), span.text()))
+            }
+            other => panic!(r#""{}" gave the wrong error: {:?}"#, expr, other.err()),
+        }
+    }
+
+    let errors = RegExp::validate_all("[]a[^]").unwrap_err();
+    assert_eq!(2, errors.len());
+    assert!(errors
+        .iter()
+        .all(|err| matches!(err, ParseError::EmptyCharacterClass { .. })));
+}
+
+#[test]
+fn test_invalid_class_range() {
+    let cases = [("[z-a]", 'z', 'a', "z-a"), (r"x[a-\]]", 'a', ']', r"a-\]")];
+
+    for &(expr, s, e, text) in &cases {
+        match RegExp::new_nfa(expr) {
+            Err(ParseError::InvalidClassRange { span, start, end }) => {
+                assert_eq!((s, e, text), (start, end, span.text()))
+            }
+            other => panic!(r#""{}" gave the wrong error: {:?}"#, expr, other.err()),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use regexp2::{MatchKind, RegExpBuilder};
+
+macro_rules! run_match_kind_tests {
+    ($expr:expr, $kind:expr, $cases:expr) => {{
+        let nfa_re = RegExpBuilder::new($expr)
+            .match_kind($kind)
+            .build_nfa()
+            .unwrap();
+        let dfa_re = RegExpBuilder::new($expr).match_kind($kind).build().unwrap();
+        $cases
+            .iter()
+            .for_each(|&(input, expected): &(&str, Option<(usize, usize)>)| {
+                let nfa_m = nfa_re.find(input).map(|m| (m.start(), m.end()));
+                assert_eq!(
+                    expected, nfa_m,
+                    r#""{}" found wrong match in "{}" using nfa"#,
+                    $expr, input
+                );
+
+                let dfa_m = dfa_re.find(input).map(|m| (m.start(), m.end()));
+                assert_eq!(
+                    expected, dfa_m,
+                    r#""{}" found wrong match in "{}" using dfa"#,
+                    $expr, input
+                );
+            });
+    }};
+}
+
+#[test]
+fn test_leftmost_longest() {
+    let cases = [
+        ("ab", Some((0, 2))),
+        ("xab", Some((1, 3))),
+        ("ac", Some((0, 1))),
+    ];
+    run_match_kind_tests!("a|ab", MatchKind::LeftmostLongest, &cases);
+
+    let cases = [("samwise", Some((0, 7))), ("sam", Some((0, 3)))];
+    run_match_kind_tests!("sam|samwise", MatchKind::LeftmostLongest, &cases);
+}
+
+#[test]
+fn test_leftmost_first() {
+    let cases = [
+        ("ab", Some((0, 1))),
+        ("xab", Some((1, 2))),
+        ("ac", Some((0, 1))),
+    ];
+    run_match_kind_tests!("a|ab", MatchKind::LeftmostFirst, &cases);
+
+    let cases = [("ab", Some((0, 2))), ("a", Some((0, 1)))];
+    run_match_kind_tests!("ab|a", MatchKind::LeftmostFirst, &cases);
+
+    let cases = [("samwise", Some((0, 3))), ("sam", Some((0, 3)))];
+    run_match_kind_tests!("sam|samwise", MatchKind::LeftmostFirst, &cases);
+}
+
+#[test]
+fn test_leftmost_first_greedy() {
+    let cases = [("aaab", Some((0, 3))), ("b", Some((0, 0)))];
+    run_match_kind_tests!("a*", MatchKind::LeftmostFirst, &cases);
+
+    let cases = [("aaab", Some((0, 3))), ("b", None)];
+    run_match_kind_tests!("a+", MatchKind::LeftmostFirst, &cases);
+
+    let cases = [("ab", Some((0, 1))), ("b", Some((0, 0)))];
+    run_match_kind_tests!("a?", MatchKind::LeftmostFirst, &cases);
+
+    let cases = [("aab", Some((0, 2))), ("b", Some((0, 0)))];
+    run_match_kind_tests!("(a*)*", MatchKind::LeftmostFirst, &cases);
+}
+
+#[test]
+fn test_leftmost_first_is_match() {
+    let re = RegExpBuilder::new("a|ab")
+        .match_kind(MatchKind::LeftmostFirst)
+        .build()
+        .unwrap();
+    assert_eq!(MatchKind::LeftmostFirst, re.match_kind());
+    assert!(re.is_match("a"));
+    assert!(re.is_match("ab"));
+    assert!(!re.is_match("abb"));
+}
+
+#[test]
+fn test_leftmost_first_star_empty_body() {
+    // An iteration of a star that matches the empty string ends the repetition, whichever branch
+    // of the body matched it, so the other branches are tried the same way on every input.
+    let cases = [
+        ("(?:b?|a)*", "a", "a"),
+        ("(?:b?|a)*", "ba", "ba"),
+        ("(?:|a)*", "aa", "aa"),
+        ("(?:a|)*", "aa", "aa"),
+        ("(?:c*|[^c])*", "é", "é"),
+        ("(?:a?b?)*", "abbac", "abba"),
+        ("(?:a*)+", "aab", "aa"),
+    ];
+    for &(expr, input, expected) in &cases {
+        let mut builder = RegExpBuilder::new(expr);
+        builder
+            .match_kind(MatchKind::LeftmostFirst)
+            .empty_matches(true);
+        let engines = [
+            builder.build().unwrap().find(input).map(|m| m.as_str()),
+            builder.build_nfa().unwrap().find(input).map(|m| m.as_str()),
+            builder
+                .build_lazy()
+                .unwrap()
+                .find(input)
+                .map(|m| m.as_str()),
+        ];
+        for m in &engines {
+            assert_eq!(Some(expected), *m, "{} on {:?}", expr, input);
+        }
+    }
+
+    // The groups hold the last iteration that matched something.
+    let re = RegExpBuilder::new("(b?|a)*")
+        .match_kind(MatchKind::LeftmostFirst)
+        .build()
+        .unwrap();
+    for &(input, last) in &[("a", "a"), ("ba", "a"), ("bb", "b")] {
+        let caps = re.captures(input).unwrap();
+        assert_eq!(Some(input), caps.get(0).map(|m| m.as_str()));
+        assert_eq!(Some(last), caps.get(1).map(|m| m.as_str()));
+    }
+}
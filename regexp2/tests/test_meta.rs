@@ -0,0 +1,155 @@
+use regexp2::meta::Strategy;
+use regexp2::{MatchKind, RegExpBuilder};
+
+const STRATEGIES: [Strategy; 6] = [
+    Strategy::Literal,
+    Strategy::DFA,
+    Strategy::ShiftAnd,
+    Strategy::LazyDFA,
+    Strategy::PikeVM,
+    Strategy::Backtrack,
+];
+
+macro_rules! run_meta_tests {
+    ($expr:expr, $kind:expr, $inputs:expr) => {{
+        let dfa_re = RegExpBuilder::new($expr).match_kind($kind).build().unwrap();
+        for &strategy in &STRATEGIES {
+            let meta_re = RegExpBuilder::new($expr)
+                .match_kind($kind)
+                .strategy(strategy)
+                .build_meta()
+                .unwrap();
+            $inputs.iter().for_each(|input: &&str| {
+                assert_eq!(
+                    dfa_re.is_match(input),
+                    meta_re.is_match(input),
+                    r#""{}" disagreed on matching "{}" with {:?}"#,
+                    $expr,
+                    input,
+                    strategy
+                );
+                assert_eq!(
+                    dfa_re.find(input).map(|m| m.range()),
+                    meta_re.find(input).map(|m| m.range()),
+                    r#""{}" disagreed on finding in "{}" with {:?}"#,
+                    $expr,
+                    input,
+                    strategy
+                );
+                assert_eq!(
+                    dfa_re.find_shortest(input).map(|m| m.range()),
+                    meta_re.find_shortest(input).map(|m| m.range()),
+                    r#""{}" disagreed on finding the shortest in "{}" with {:?}"#,
+                    $expr,
+                    input,
+                    strategy
+                );
+            });
+        }
+    }};
+}
+
+#[test]
+fn test_meta() {
+    for &kind in &[MatchKind::LeftmostLongest, MatchKind::LeftmostFirst] {
+        let inputs = ["", "abb", "aababb", "ab", "xxabbx", "babba"];
+        run_meta_tests!("(a|b)*abb", kind, &inputs);
+
+        let inputs = ["", "3a", "08m", "999_", "a3", "x 42 yé"];
+        run_meta_tests!(r"\d+\w?", kind, &inputs);
+
+        let inputs = ["", "abcd", "xabcx", "ab", "éabé"];
+        run_meta_tests!("a|ab|abc", kind, &inputs);
+
+        let inputs = ["", "hello", "say hello", "hell", "hellohello"];
+        run_meta_tests!("hello", kind, &inputs);
+    }
+}
+
+#[test]
+fn test_meta_strategy() {
+    let re = RegExpBuilder::new("hello").build_meta().unwrap();
+    assert_eq!(Strategy::Literal, re.strategy(1000));
+
+    let re = RegExpBuilder::new("(a|b)*abb").build_meta().unwrap();
+    assert_eq!(Strategy::DFA, re.strategy(1000));
+
+    // The DFA has more than a thousand states, so it isn't compiled ahead of time, but the
+    // regular expression is short enough for the bit-parallel matcher.
+    let expr = "(a|b)*a(a|b)(a|b)(a|b)(a|b)(a|b)(a|b)(a|b)(a|b)(a|b)(a|b)";
+    let re = RegExpBuilder::new(expr).build_meta().unwrap();
+    assert_eq!(Strategy::ShiftAnd, re.strategy(10));
+    assert!(re.is_match("bbabbbbbbbbbb"));
+    assert!(!re.is_match("bbbbbbbbbbbbb"));
+    assert_eq!(Some(0..13), re.find("bbabbbbbbbbbbb").map(|m| m.range()));
+
+    // With more than 128 characters and classes, neither is used.
+    let long = format!("(a|b)*a{}", "(a|b)".repeat(70));
+    let re = RegExpBuilder::new(&long).build_meta().unwrap();
+    assert_eq!(Strategy::LazyDFA, re.strategy(10));
+
+    // The backtracker is used for short inputs, if the match kind is leftmost-first.
+    let re = RegExpBuilder::new(expr)
+        .match_kind(MatchKind::LeftmostFirst)
+        .build_meta()
+        .unwrap();
+    assert_eq!(Strategy::Backtrack, re.strategy(10));
+    assert_eq!(Strategy::ShiftAnd, re.strategy(1 << 20));
+    assert_eq!(Some(0..12), re.find("babbbbbbbbbbb").map(|m| m.range()));
+}
+
+#[test]
+fn test_meta_override() {
+    let re = RegExpBuilder::new("(a|b)*abb")
+        .match_kind(MatchKind::LeftmostFirst)
+        .strategy(Strategy::PikeVM)
+        .build_meta()
+        .unwrap();
+    assert_eq!(Strategy::PikeVM, re.strategy(1000));
+
+    // The Pike VM doesn't find leftmost-longest matches, and the regular expression isn't a
+    // literal.
+    let re = RegExpBuilder::new("(a|b)*abb")
+        .strategy(Strategy::PikeVM)
+        .build_meta()
+        .unwrap();
+    assert_eq!(Strategy::DFA, re.strategy(1000));
+    let re = RegExpBuilder::new("(a|b)*abb")
+        .strategy(Strategy::Literal)
+        .build_meta()
+        .unwrap();
+    assert_eq!(Strategy::DFA, re.strategy(1000));
+
+    // The DFA is compiled regardless of its size if it is asked for.
+    let expr = "(a|b)*a(a|b)(a|b)(a|b)(a|b)(a|b)(a|b)(a|b)(a|b)(a|b)(a|b)";
+    let re = RegExpBuilder::new(expr)
+        .strategy(Strategy::DFA)
+        .build_meta()
+        .unwrap();
+    assert_eq!(Strategy::DFA, re.strategy(10));
+    assert!(RegExpBuilder::new(expr)
+        .strategy(Strategy::DFA)
+        .dfa_size_limit(100)
+        .build_meta()
+        .is_err());
+}
+
+#[test]
+fn test_meta_shift_and() {
+    for &kind in &[MatchKind::LeftmostLongest, MatchKind::LeftmostFirst] {
+        let inputs = ["", "ab", "abcd", "xbcx", "abcab", "éaabcé", "bbcbc"];
+        run_meta_tests!("abcd|bc|a?", kind, &inputs);
+
+        let inputs = ["", "x1", "x12y", "yx", "éx9é", "xx123"];
+        run_meta_tests!(r"x\d+y?|y", kind, &inputs);
+    }
+
+    let re = RegExpBuilder::new("(a|b)*c")
+        .strategy(Strategy::ShiftAnd)
+        .build_meta()
+        .unwrap();
+    assert_eq!(Strategy::ShiftAnd, re.strategy(10));
+    let haystack = format!("{}abc", "ab".repeat(1000));
+    assert_eq!(Some(0..2003), re.find(&haystack).map(|m| m.range()));
+    assert!(re.find("ababab").is_none());
+}
@@ -0,0 +1,41 @@
+use regexp2::class::CharClass;
+use regexp2::parser::nfa::NFAParser;
+use regexp2::parser::ParseError;
+use regexp2::RegExp;
+
+#[test]
+fn test_deeply_nested_groups() {
+    let depth = 100_000;
+    let expr = format!("{}a{}*", "(".repeat(depth), ")".repeat(depth));
+    let nfa = NFAParser::<CharClass>::new().parse(&expr).unwrap();
+    assert!(nfa.is_match("aaa".chars()));
+    assert!(!nfa.is_match("b".chars()));
+
+    let expr = format!("{}a|b{}", "(".repeat(depth), ")".repeat(depth));
+    let nfa = NFAParser::<CharClass>::new().parse(&expr).unwrap();
+    assert!(nfa.is_match("b".chars()));
+}
+
+#[test]
+fn test_deeply_nested_unbalanced() {
+    let depth = 100_000;
+    let expr = format!("{}a{}", "(".repeat(depth), ")".repeat(depth - 1));
+    assert!(matches!(
+        RegExp::new_nfa(&expr),
+        Err(ParseError::UnexpectedEof { .. })
+    ));
+
+    let expr = format!("{}{}", "(".repeat(depth), ")".repeat(depth + 1));
+    assert!(matches!(
+        RegExp::new_nfa(&expr),
+        Err(ParseError::UnexpectedToken { token: ')', .. })
+    ));
+}
+
+#[test]
+fn test_long_expression() {
+    let expr = "ab".repeat(200);
+    let re = RegExp::new_nfa(&expr).unwrap();
+    assert!(re.is_match(&expr));
+    assert!(!re.is_match(&expr[1..]));
+}
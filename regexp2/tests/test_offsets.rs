@@ -0,0 +1,61 @@
+use regexp2::{Engine, RegExp, RegExpBuilder};
+
+const EXPRS: [&str; 4] = ["é+", "a*b", "(ab|b)c?", "x|yz"];
+const INPUT: &str = "aébyzabcéé";
+
+/// Check that searching from each offset finds the same match as searching the rest of the input,
+/// with its offsets moved by the start.
+fn check<E: Engine>(re: &RegExp<E>) {
+    let starts = (0..=INPUT.len()).filter(|&i| INPUT.is_char_boundary(i));
+    for start in starts {
+        let rest = &INPUT[start..];
+        let shift = |m: Option<regexp2::Match<'_>>| m.map(|m| (m.start() + start, m.end() + start));
+        let range = |m: Option<regexp2::Match<'_>>| m.map(|m| (m.start(), m.end()));
+        let context = format!(r#""{}" from {}"#, re.as_str(), start);
+
+        assert_eq!(
+            shift(re.find(rest)),
+            range(re.find_at(INPUT, start)),
+            "{}",
+            context
+        );
+        assert_eq!(
+            shift(re.find_shortest(rest)),
+            range(re.find_shortest_at(INPUT, start)),
+            "{}",
+            context
+        );
+        assert_eq!(
+            re.is_match(rest),
+            re.is_match_at(INPUT, start),
+            "{}",
+            context
+        );
+    }
+}
+
+#[test]
+fn test_offsets() {
+    for expr in &EXPRS {
+        check(&RegExp::new(expr).unwrap());
+        check(&RegExp::new_nfa(expr).unwrap());
+        check(&RegExpBuilder::new(expr).build_lazy().unwrap());
+        check(&RegExpBuilder::new(expr).build_meta().unwrap());
+    }
+}
+
+#[test]
+fn test_offsets_at_end() {
+    let re = RegExp::new("a*").unwrap();
+    let m = re.find_at("ba", 2).unwrap();
+    assert_eq!((2, 2), (m.start(), m.end()));
+    assert!(re.is_match_at("ba", 2));
+    assert!(re.is_match_at("ba", 1));
+    assert!(!re.is_match_at("ba", 0));
+}
+
+#[test]
+#[should_panic]
+fn test_offsets_not_on_boundary() {
+    RegExp::new("é").unwrap().find_at("éé", 1);
+}
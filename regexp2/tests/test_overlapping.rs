@@ -0,0 +1,69 @@
+use regexp2::automata::DenseDFA;
+use regexp2::class::CharClass;
+use regexp2::parser::nfa::NFAParser;
+use regexp2::{MatchKind, RegExp, RegExpBuilder};
+
+#[test]
+fn test_find_overlapping_iter() {
+    let cases = [
+        ("ATA", "ATATATA", vec![(0, 3), (2, 5), (4, 7)]),
+        ("aa", "aaaa", vec![(0, 2), (1, 3), (2, 4)]),
+        ("a+", "baab", vec![(1, 3), (2, 3)]),
+        ("(a|b)*abb", "abbabb", vec![(0, 6), (1, 6), (2, 6), (3, 6)]),
+        ("éé", "ééé", vec![(0, 4), (2, 6)]),
+        ("x", "abc", vec![]),
+    ];
+
+    for (expr, input, expected) in &cases {
+        let re = RegExp::new(expr).unwrap();
+        let found: Vec<_> = re
+            .find_overlapping_iter(input)
+            .map(|m| (m.start(), m.end()))
+            .collect();
+        assert_eq!(
+            *expected, found,
+            r#""{}" found wrong overlapping matches in "{}""#,
+            expr, input
+        );
+
+        let nfa_re = RegExp::new_nfa(expr).unwrap();
+        let nfa_found: Vec<_> = nfa_re
+            .find_overlapping_iter(input)
+            .map(|m| (m.start(), m.end()))
+            .collect();
+        assert_eq!(found, nfa_found);
+    }
+}
+
+#[test]
+fn test_find_overlapping_iter_empty() {
+    let re = RegExp::new("a*").unwrap();
+    let found: Vec<_> = re.find_overlapping_iter("aba").map(|m| m.range()).collect();
+    assert_eq!(vec![0..1, 1..1, 2..3, 3..3], found);
+}
+
+#[test]
+fn test_find_overlapping_iter_leftmost_first() {
+    let re = RegExpBuilder::new("a|ab")
+        .match_kind(MatchKind::LeftmostFirst)
+        .build()
+        .unwrap();
+    let found: Vec<_> = re
+        .find_overlapping_iter("abab")
+        .map(|m| m.as_str())
+        .collect();
+    assert_eq!(vec!["a", "a"], found);
+}
+
+#[test]
+fn test_dense_find_overlapping() {
+    let dfa: DenseDFA<CharClass> = DenseDFA::from(
+        regexp2::automata::DFA::from(NFAParser::new().parse("ab*").unwrap()).minimize(),
+    );
+    let found: Vec<_> = dfa
+        .find_overlapping("abbab".chars())
+        .iter()
+        .map(|m| m.range())
+        .collect();
+    assert_eq!(vec![0..3, 3..5], found);
+}
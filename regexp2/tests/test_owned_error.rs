@@ -0,0 +1,51 @@
+use regexp2::parser::Expected;
+use regexp2::{Error, ParseError, RegExp, RegExpBuilder};
+
+/// Compile a pattern that is dropped before the error is returned.
+fn compile(
+    expr: &str,
+) -> Result<RegExp<regexp2::automata::DenseDFA<regexp2::class::CharClass>>, ParseError<'static>> {
+    let expr = expr.to_string();
+    RegExp::new(&expr).map_err(ParseError::into_owned)
+}
+
+#[test]
+fn test_owned_parse_error() {
+    let err = compile("ab(c").unwrap_err();
+    match &err {
+        ParseError::UnexpectedEof { expected, .. } => {
+            assert_eq!(&vec![Expected::Char(')')], expected)
+        }
+        _ => panic!("wrong error: {:?}", err),
+    }
+    assert_eq!(4, err.span().start());
+    assert!(compile("ab(c)").is_ok());
+
+    let borrowed = RegExp::new("a|*").unwrap_err();
+    let text = borrowed.span().text().to_string();
+    let owned = borrowed.into_owned();
+    assert_eq!(text, owned.span().text());
+    assert_eq!("unexpected token", owned.to_string());
+}
+
+#[test]
+fn test_owned_error() {
+    let err: Error<'static> = {
+        let expr = String::from("[ab]*a[ab][ab][ab][ab][ab]");
+        RegExpBuilder::new(&expr)
+            .dfa_size_limit(16)
+            .build()
+            .unwrap_err()
+            .into_owned()
+    };
+    assert!(matches!(err, Error::DFASizeLimitExceeded { limit: 16 }));
+
+    let err: Error<'static> = {
+        let expr = String::from("(a");
+        RegExpBuilder::new(&expr).build().unwrap_err().into_owned()
+    };
+    assert!(matches!(
+        err,
+        Error::Parse(ParseError::UnexpectedEof { .. })
+    ));
+}
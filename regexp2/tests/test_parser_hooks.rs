@@ -0,0 +1,121 @@
+use regexp2::class::CharClass;
+use regexp2::parser::{Anchor, ParseError, Parser, ParserEngine};
+use regexp2::RegExp;
+
+/// A group, empty expression, or anchor observed by the parser.
+#[derive(Debug, PartialEq)]
+enum Event {
+    Group(usize),
+    Empty,
+    Anchor(Anchor),
+}
+
+/// An engine that lists the groups, empty expressions, and anchors of an expression in the order
+/// they are parsed.
+struct Events;
+
+impl ParserEngine for Events {
+    type Output = Vec<Event>;
+
+    fn new() -> Self {
+        Events
+    }
+
+    fn handle_char<C>(&mut self, _c: C) -> Self::Output
+    where
+        C: Into<CharClass>,
+    {
+        Vec::new()
+    }
+
+    fn handle_wildcard(&mut self, _class: CharClass) -> Self::Output {
+        Vec::new()
+    }
+
+    fn handle_star(&mut self, lhs: Self::Output) -> Self::Output {
+        lhs
+    }
+
+    fn handle_plus(&mut self, lhs: Self::Output) -> Self::Output {
+        lhs
+    }
+
+    fn handle_optional(&mut self, lhs: Self::Output) -> Self::Output {
+        lhs
+    }
+
+    fn handle_concat(&mut self, mut lhs: Self::Output, rhs: Self::Output) -> Self::Output {
+        lhs.extend(rhs);
+        lhs
+    }
+
+    fn handle_alternate(&mut self, lhs: Self::Output, rhs: Self::Output) -> Self::Output {
+        self.handle_concat(lhs, rhs)
+    }
+
+    fn handle_group(&mut self, mut inner: Self::Output, capture_index: usize) -> Self::Output {
+        inner.push(Event::Group(capture_index));
+        inner
+    }
+
+    fn handle_empty(&mut self) -> Option<Self::Output> {
+        Some(vec![Event::Empty])
+    }
+
+    fn handle_anchor(&mut self, kind: Anchor) -> Option<Self::Output> {
+        Some(vec![Event::Anchor(kind)])
+    }
+}
+
+fn events(expr: &str) -> Vec<Event> {
+    Parser::<Events>::new().parse(expr).unwrap()
+}
+
+#[test]
+fn test_group_hooks() {
+    // Groups are reported as they close, but numbered as they open.
+    assert_eq!(
+        vec![Event::Group(2), Event::Group(1), Event::Group(3)],
+        events("(a(b))|(c)*")
+    );
+    assert_eq!(
+        vec![
+            Event::Empty,
+            Event::Group(1),
+            Event::Empty,
+            Event::Group(3),
+            Event::Group(2)
+        ],
+        events("a()b(())")
+    );
+}
+
+#[test]
+fn test_anchor_hooks() {
+    assert_eq!(
+        vec![
+            Event::Anchor(Anchor::Start),
+            Event::Anchor(Anchor::End),
+            Event::Group(1),
+            Event::Anchor(Anchor::End)
+        ],
+        events("^(a|$)b$")
+    );
+
+    // Escaped anchors are literal characters.
+    assert!(events(r"\^a\$").is_empty());
+}
+
+#[test]
+fn test_unsupported_anchor() {
+    for expr in &["^a", "a$", "a|(b^)"] {
+        match RegExp::new_nfa(expr) {
+            Err(ParseError::UnsupportedAnchor { .. }) => {}
+            other => panic!(r#""{}" didn't fail on its anchor: {:?}"#, expr, other.err()),
+        }
+    }
+
+    let re = RegExp::new(r"\^a\$").unwrap();
+    assert!(re.is_match("^a$"));
+    assert!(!re.is_match("a"));
+}
@@ -0,0 +1,241 @@
+use regexp2::automata::DFA;
+use regexp2::class::CharClass;
+use regexp2::parser::nfa::NFAParser;
+use regexp2::{MatchKind, RegExp, RegExpBuilder};
+
+#[test]
+fn test_literal_prefix() {
+    let cases = [
+        ("abc", "abc"),
+        ("abc|abd", "ab"),
+        ("ab*c", "a"),
+        ("hello.world", "hello"),
+        ("(ab)+", "ab"),
+        ("é+x", "é"),
+        ("a|b", ""),
+        ("a?b", ""),
+        ("[a-c]x", ""),
+        ("x*", ""),
+    ];
+
+    for (expr, expected) in &cases {
+        let re = RegExp::new(expr).unwrap();
+        assert_eq!(
+            *expected,
+            re.literal_prefix(),
+            r#""{}" had the wrong literal prefix"#,
+            expr
+        );
+    }
+
+    let re = RegExpBuilder::new("abc")
+        .case_insensitive(true)
+        .build()
+        .unwrap();
+    assert_eq!("", re.literal_prefix());
+}
+
+/// The leftmost-longest match found by the DFA without a prefilter, as a byte range.
+fn find_unfiltered(expr: &str, input: &str) -> Option<(usize, usize)> {
+    let dfa: DFA<CharClass> = NFAParser::new().parse(expr).unwrap().into();
+    let m = dfa.find_unanchored(input.chars())?;
+    let offset = |n| input.chars().take(n).map(char::len_utf8).sum::<usize>();
+    Some((offset(m.start()), offset(m.end())))
+}
+
+#[test]
+fn test_prefilter() {
+    let exprs = ["abc|abd", "ab*c", "aab", "(ab)+", "éa+"];
+    let inputs = [
+        "", "abd", "xxabcx", "ababab", "aaab", "aabaab", "ac abbbc", "ééaa", "abéab",
+    ];
+
+    for expr in &exprs {
+        let dfa_re = RegExp::new(expr).unwrap();
+        let nfa_re = RegExp::new_nfa(expr).unwrap();
+        let lazy_re = RegExpBuilder::new(expr).build_lazy().unwrap();
+        let bytes_re = RegExpBuilder::new(expr).build_bytes().unwrap();
+        for input in &inputs {
+            let expected = find_unfiltered(expr, input);
+            for found in &[
+                dfa_re.find(input).map(|m| (m.start(), m.end())),
+                nfa_re.find(input).map(|m| (m.start(), m.end())),
+                lazy_re.find(input).map(|m| (m.start(), m.end())),
+                bytes_re.find(input).map(|m| (m.start(), m.end())),
+                bytes_re
+                    .find_bytes(input.as_bytes())
+                    .map(|r| (r.start, r.end)),
+            ] {
+                assert_eq!(
+                    expected, *found,
+                    r#""{}" found wrong match in "{}""#,
+                    expr, input
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_prefilter_find_at() {
+    let re = RegExp::new("aab").unwrap();
+    assert_eq!(Some(3..6), re.find_at("aabaab", 1).map(|m| m.range()));
+    assert_eq!(None, re.find_at("aabaab", 4));
+
+    let re = RegExp::new("ab+").unwrap();
+    assert_eq!(Some(1..3), re.find_shortest("xabbb").map(|m| m.range()));
+    assert_eq!(
+        Some(5..7),
+        re.find_shortest_at("xabbbab", 2).map(|m| m.range())
+    );
+}
+
+#[test]
+fn test_prefilter_leftmost_first() {
+    let re = RegExpBuilder::new("ab|abc")
+        .match_kind(MatchKind::LeftmostFirst)
+        .build()
+        .unwrap();
+    assert_eq!("ab", re.literal_prefix());
+    assert_eq!(Some(2..4), re.find("xxabc").map(|m| m.range()));
+
+    let re = RegExpBuilder::new("ab|abc")
+        .match_kind(MatchKind::LeftmostFirst)
+        .build_nfa()
+        .unwrap();
+    assert_eq!(Some(2..4), re.find("xxabc").map(|m| m.range()));
+}
+
+#[test]
+fn test_literals() {
+    // These match a few literal strings, which are searched for without running an automaton.
+    let exprs = ["hello", "abc|abd|x", "a|ab|abc", "(?:ab|cd)e", "é|éa"];
+    let inputs = [
+        "",
+        "hello",
+        "say hello",
+        "xabd",
+        "abcabc",
+        "cde",
+        "ééa",
+        "abab",
+        "a",
+    ];
+
+    for expr in &exprs {
+        let re = RegExp::new(expr).unwrap();
+        let bytes_re = RegExpBuilder::new(expr).build_bytes().unwrap();
+        let dfa: DFA<CharClass> = NFAParser::new().parse(expr).unwrap().into();
+        for input in &inputs {
+            let expected = find_unfiltered(expr, input);
+            assert_eq!(
+                expected,
+                re.find(input).map(|m| (m.start(), m.end())),
+                r#""{}" found wrong match in "{}""#,
+                expr,
+                input
+            );
+            assert_eq!(
+                expected,
+                bytes_re
+                    .find_bytes(input.as_bytes())
+                    .map(|r| (r.start, r.end)),
+                r#""{}" found wrong match in "{}""#,
+                expr,
+                input
+            );
+            assert_eq!(dfa.is_match(input.chars()), re.is_match(input));
+        }
+    }
+
+    let re = RegExp::new("a|ab|abc").unwrap();
+    assert_eq!(Some(1..2), re.find_shortest("xabc").map(|m| m.range()));
+    assert_eq!(
+        Some(4..5),
+        re.find_shortest_at("xabca", 2).map(|m| m.range())
+    );
+
+    let re = RegExpBuilder::new("a|ab|abc")
+        .match_kind(MatchKind::LeftmostFirst)
+        .build()
+        .unwrap();
+    assert_eq!(Some(1..2), re.find("xabc").map(|m| m.range()));
+    let re = RegExpBuilder::new("abc|ab|a")
+        .match_kind(MatchKind::LeftmostFirst)
+        .build()
+        .unwrap();
+    assert_eq!(Some(1..3), re.find("xabx").map(|m| m.range()));
+}
+
+#[test]
+fn test_literal_prefixes() {
+    let cases: &[(&str, &[&str])] = &[
+        ("(error|warn|fatal).*timeout", &["error", "fatal", "warn"]),
+        ("(ab|abc)d+", &[]),
+        ("(a|b)c*", &["a", "b"]),
+        ("(x|é)[0-9]", &["x", "é"]),
+        ("abc", &[]),
+        ("[a-z]b", &[]),
+        ("a?b", &["a", "b"]),
+        ("x*y", &["x", "y"]),
+    ];
+
+    for (expr, expected) in cases {
+        let re = RegExp::new(expr).unwrap();
+        assert_eq!(
+            *expected,
+            re.literal_prefixes(),
+            r#""{}" had the wrong literal prefixes"#,
+            expr
+        );
+    }
+}
+
+#[test]
+fn test_prefilter_prefixes() {
+    let exprs = [
+        "(error|warn|fatal).*timeout",
+        "(ab|cd)e*",
+        "(é|x)a+",
+        "a?b+",
+    ];
+    let inputs = [
+        "",
+        "warn: timeout",
+        "fatal error: timeout",
+        "errortimeout warn timeout",
+        "xcdeeab",
+        "ééaxa",
+        "aabbb",
+        "ab",
+    ];
+
+    for expr in &exprs {
+        let dfa_re = RegExp::new(expr).unwrap();
+        let nfa_re = RegExp::new_nfa(expr).unwrap();
+        let lazy_re = RegExpBuilder::new(expr).build_lazy().unwrap();
+        let bytes_re = RegExpBuilder::new(expr).build_bytes().unwrap();
+        for input in &inputs {
+            let expected = find_unfiltered(expr, input);
+            for found in &[
+                dfa_re.find(input).map(|m| (m.start(), m.end())),
+                nfa_re.find(input).map(|m| (m.start(), m.end())),
+                lazy_re.find(input).map(|m| (m.start(), m.end())),
+                bytes_re.find(input).map(|m| (m.start(), m.end())),
+                bytes_re
+                    .find_bytes(input.as_bytes())
+                    .map(|r| (r.start, r.end)),
+            ] {
+                assert_eq!(
+                    expected, *found,
+                    r#""{}" found wrong match in "{}""#,
+                    expr, input
+                );
+            }
+        }
+    }
+
+    let re = RegExp::new("(ab|cd)e*").unwrap();
+    assert_eq!(Some(3..7), re.find_at("abecdee", 1).map(|m| m.range()));
+    assert_eq!(Some(3..5), re.find_shortest("xxxcdee").map(|m| m.range()));
+}
@@ -0,0 +1,102 @@
+include!("dfa.rs");
+
+const INPUTS: [&str; 14] = [
+    "", "a", "b", "ab", "ba", "abb", "aabb", "babb", "abab", "aaaa", "0", "a0", "ab0", "é",
+];
+
+#[test]
+fn test_intersection() {
+    let pairs = [
+        ("(a|b)*abb", "a+b*"),
+        ("(a|b)*", "[^b]*"),
+        ("(aa)*", "a*"),
+        (r"\w+", "[ab]+0"),
+        ("a", "b"),
+    ];
+
+    for (e1, e2) in &pairs {
+        let (d1, d2) = (dfa(e1), dfa(e2));
+        let intersection = d1.intersection(&d2);
+        for input in &INPUTS {
+            let expected = d1.is_match(input.chars()) && d2.is_match(input.chars());
+            assert_eq!(
+                expected,
+                intersection.is_match(input.chars()),
+                r#"intersection of "{}" and "{}" was wrong on "{}""#,
+                e1,
+                e2,
+                input
+            );
+        }
+    }
+}
+
+#[test]
+fn test_union() {
+    let pairs = [
+        ("(a|b)*abb", "a+b*"),
+        ("(aa)*", "b"),
+        (r"\d", "[ab]+0"),
+        ("a", "a"),
+    ];
+
+    for (e1, e2) in &pairs {
+        let (d1, d2) = (dfa(e1), dfa(e2));
+        let union = d1.union(&d2);
+        for input in &INPUTS {
+            let expected = d1.is_match(input.chars()) || d2.is_match(input.chars());
+            assert_eq!(
+                expected,
+                union.is_match(input.chars()),
+                r#"union of "{}" and "{}" was wrong on "{}""#,
+                e1,
+                e2,
+                input
+            );
+        }
+    }
+}
+
+#[test]
+fn test_intersection_empty() {
+    let intersection = dfa("a+").intersection(&dfa("b+"));
+    assert_eq!(1, intersection.total_states);
+    assert!(intersection.accepting_states.is_empty());
+}
+
+#[test]
+fn test_union_patterns() {
+    let (mut d1, mut d2) = (dfa("ab+"), dfa("a|abb"));
+    for (d, id) in [(&mut d1, 0), (&mut d2, 1)] {
+        let accepting: Vec<_> = d.accepting_states.iter().collect();
+        for state in accepting {
+            d.add_pattern(state, id);
+        }
+    }
+
+    let union = d1.union(&d2);
+    for (input, expected) in &[
+        ("a", vec![1]),
+        ("ab", vec![0]),
+        ("abb", vec![0, 1]),
+        ("abbb", vec![0]),
+        ("b", vec![]),
+    ] {
+        let mut state = Some(union.start_state);
+        for c in input.chars() {
+            state = state.and_then(|s| {
+                union
+                    .transitions_on(&s)
+                    .find(|(t, _)| t.0 == c)
+                    .map(|(_, &next)| next)
+            });
+        }
+        let ids: Vec<_> = state
+            .into_iter()
+            .flat_map(|s| union.pattern_ids(&s))
+            .collect();
+        assert_eq!(expected, &ids, "patterns of {:?}", input);
+    }
+
+    assert!(union.complement().patterns.is_empty());
+}
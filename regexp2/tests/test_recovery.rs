@@ -0,0 +1,92 @@
+use regexp2::automata::NFA;
+use regexp2::class::CharClass;
+use regexp2::parser::nfa::NFAParser;
+use regexp2::parser::validate::Validator;
+use regexp2::parser::Config;
+use regexp2::{ParseError, RegExp};
+
+/// The unexpected token of each error, or `None` for other errors.
+fn tokens(errors: &[ParseError<'_>]) -> Vec<Option<char>> {
+    errors
+        .iter()
+        .map(|err| match err {
+            ParseError::UnexpectedToken { token, .. } => Some(*token),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_recovery_single_error() {
+    let exprs = [
+        "", "(", ")", "a(", "(()", "*", "*a", "a|", "a)*", "(ab", "[a-", "a\\", "(?z)",
+    ];
+    for expr in &exprs {
+        let first = RegExp::validate(expr).unwrap_err();
+        let all = RegExp::validate_all(expr).unwrap_err();
+        assert_eq!(
+            vec![format!("{:?}", first)],
+            all.iter().map(|e| format!("{:?}", e)).collect::<Vec<_>>(),
+            "{:?}",
+            expr
+        );
+    }
+}
+
+#[test]
+fn test_recovery_multiple_errors() {
+    let cases: &[(&str, &[Option<char>])] = &[
+        ("*a|b(c|)|[a-", &[Some('*'), Some(')'), None]),
+        ("a)b)c", &[Some(')'), Some(')')]),
+        ("(?z:a)*|+", &[Some('z'), Some('+')]),
+        ("+|+|x", &[Some('+'), Some('+')]),
+        ("|", &[Some('|'), None]),
+        ("a||b|", &[Some('|'), None]),
+        ("(?i:*)(a|*)", &[Some('*'), Some('*')]),
+        ("((?q)|*)b)", &[Some('q'), Some('*'), Some(')')]),
+    ];
+    for &(expr, expected) in cases {
+        let errors = RegExp::validate_all(expr).unwrap_err();
+        assert_eq!(expected, &tokens(&errors)[..], "{:?}: {:?}", expr, errors);
+    }
+}
+
+#[test]
+fn test_recovery_skips_malformed_groups() {
+    let config = Config {
+        nest_limit: Some(1),
+        ..Config::default()
+    };
+    let errors = Validator::with_config(config)
+        .parse_with_recovery("a((b|*))c((d))*?")
+        .unwrap_err();
+    assert_eq!(2, errors.len());
+    assert!(errors
+        .iter()
+        .all(|err| matches!(err, ParseError::NestLimitExceeded { limit: 1, .. })));
+}
+
+#[test]
+fn test_recovery_stops_at_size_limit() {
+    let config = Config {
+        size_limit: Some(3),
+        ..Config::default()
+    };
+    let errors = Validator::with_config(config)
+        .parse_with_recovery("*abcdef|+")
+        .unwrap_err();
+    assert_eq!(2, errors.len());
+    assert!(matches!(
+        errors[1],
+        ParseError::SizeLimitExceeded { limit: 3, .. }
+    ));
+}
+
+#[test]
+fn test_recovery_valid() {
+    assert!(RegExp::validate_all("(a|b)*abb").is_ok());
+
+    let nfa: NFA<CharClass> = NFAParser::new().parse_with_recovery("a(b|c)+").unwrap();
+    assert!(nfa.is_match("acbc".chars()));
+    assert!(!nfa.is_match("a".chars()));
+}
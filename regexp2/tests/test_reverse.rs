@@ -0,0 +1,133 @@
+use regexp2::automata::{DFA, NFA};
+use regexp2::class::CharClass;
+use regexp2::parser::nfa::NFAParser;
+use regexp2::{MatchKind, RegExp, RegExpBuilder};
+
+fn nfa(expr: &str) -> NFA<CharClass> {
+    NFAParser::new().parse(expr).unwrap()
+}
+
+#[test]
+fn test_nfa_reverse() {
+    let cases = [
+        ("abc", vec!["cba"], vec!["abc", "", "cb"]),
+        (
+            "(a|b)*abb",
+            vec!["bba", "bbaba", "bbaaaa"],
+            vec!["abb", "bb"],
+        ),
+        ("é+x?", vec!["é", "xéé"], vec!["éx", "x"]),
+        ("a*", vec!["", "aaa"], vec!["b"]),
+    ];
+
+    for (expr, accepted, rejected) in &cases {
+        let reverse = nfa(expr).reverse();
+        for input in accepted {
+            assert!(
+                reverse.is_match(input.chars()),
+                r#"reversal of "{}" didn't accept "{}""#,
+                expr,
+                input
+            );
+        }
+        for input in rejected {
+            assert!(
+                !reverse.is_match(input.chars()),
+                r#"reversal of "{}" accepted "{}""#,
+                expr,
+                input
+            );
+        }
+    }
+}
+
+/// The leftmost-longest match found by trying each starting position in turn, as a byte range.
+fn find_forward(expr: &str, input: &str) -> Option<(usize, usize)> {
+    let dfa: DFA<CharClass> = nfa(expr).into();
+    let m = dfa.find_unanchored(input.chars())?;
+    let offset = |n| input.chars().take(n).map(char::len_utf8).sum::<usize>();
+    Some((offset(m.start()), offset(m.end())))
+}
+
+#[test]
+fn test_reverse_search() {
+    let exprs = [
+        "(a|b)*abb",
+        "[ab]c|d+",
+        r"\d+\w?",
+        "b*c",
+        "x?y?",
+        "[éa]+b",
+        // The match that starts first ends after another has already matched.
+        "abcd|c",
+        "a.*x|b",
+        // The reverse DFA for this one is too large, so searches fall back to the forward DFA.
+        "[ab]a[ab][ab][ab][ab][ab][ab][ab][ab][ab][ab][ab][ab]",
+    ];
+    let inputs = [
+        "",
+        "abb",
+        "xxababbx",
+        "bc dd",
+        "x 42 y",
+        "aaaabbbc",
+        "yx",
+        "zéab",
+        "babbbbbbbbbbbbbbbb",
+        "abcd",
+        "aaabax",
+    ];
+
+    for expr in &exprs {
+        let re = RegExp::new(expr).unwrap();
+        for input in &inputs {
+            assert_eq!(
+                find_forward(expr, input),
+                re.find(input).map(|m| (m.start(), m.end())),
+                r#""{}" found wrong match in "{}""#,
+                expr,
+                input
+            );
+        }
+    }
+}
+
+#[test]
+fn test_reverse_search_at() {
+    let re = RegExp::new("[ab]+").unwrap();
+    assert_eq!("", re.literal_prefix());
+    assert_eq!(Some(1..2), re.find_at("ab ab", 1).map(|m| m.range()));
+    assert_eq!(Some(3..5), re.find_at("ab ab", 2).map(|m| m.range()));
+    assert_eq!(
+        Some(3..4),
+        re.find_shortest_at("ab ab", 2).map(|m| m.range())
+    );
+    assert_eq!(None, re.find_at("ab ab", 5));
+}
+
+#[test]
+fn test_reverse_search_leftmost_first() {
+    let re = RegExpBuilder::new("[ab]|[ab]c")
+        .match_kind(MatchKind::LeftmostFirst)
+        .build()
+        .unwrap();
+    assert_eq!(Some(1..2), re.find("xbc").map(|m| m.range()));
+
+    let re = RegExpBuilder::new("[ab]|[ab]c").build().unwrap();
+    assert_eq!(Some(1..3), re.find("xbc").map(|m| m.range()));
+}
+
+#[test]
+fn test_reverse_search_iter() {
+    // Each search scans backwards only from the end of its match, rather than from the end of the
+    // input, so finding every match takes time linear in the input.
+    let re = RegExp::new("[ab]+").unwrap();
+    let input = "ab ".repeat(20000);
+    let (mut count, mut start) = (0, 0);
+    while let Some(m) = re.find_at(&input, start) {
+        assert_eq!(3 * count..3 * count + 2, m.range());
+        count += 1;
+        start = m.end();
+    }
+    assert_eq!(20000, count);
+}
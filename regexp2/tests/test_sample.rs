@@ -0,0 +1,73 @@
+use regexp2::sample::Generator;
+
+include!("dfa.rs");
+
+#[test]
+fn test_sample_exhaustive() {
+    let cases = vec![
+        ("abc", 3, vec!["abc"]),
+        ("abc", 2, vec![]),
+        ("a*", 3, vec!["", "a", "aa", "aaa"]),
+        ("(a|b)c?", 2, vec!["a", "ac", "b", "bc"]),
+        (
+            "[0-2]+",
+            2,
+            vec![
+                "0", "00", "01", "02", "1", "10", "11", "12", "2", "20", "21", "22",
+            ],
+        ),
+        ("x*y", 0, vec![]),
+    ];
+
+    for (expr, max_len, expected) in cases {
+        let samples: Vec<_> = Generator::new(&dfa(expr), max_len).iter().collect();
+        assert_eq!(expected, samples, r#"wrong samples for "{}""#, expr);
+    }
+}
+
+#[test]
+fn test_sample_random() {
+    let exprs = ["(a|b)*abb", r"\d+\.\d*", "[^a-c]x()y", ".*", "é+x?"];
+    for expr in &exprs {
+        let dfa = dfa(expr);
+        let mut generator = Generator::new(&dfa, 10);
+        for _ in 0..100 {
+            let sample = generator.sample().unwrap();
+            assert!(sample.chars().count() <= 10);
+            assert!(
+                dfa.is_match(sample.chars()),
+                r#""{}" doesn't match sample "{}""#,
+                expr,
+                sample
+            );
+        }
+    }
+
+    assert_eq!(None, Generator::new(&dfa("abc"), 2).sample());
+}
+
+#[test]
+fn test_sample_seed() {
+    let dfa = dfa("[a-z]+");
+    let samples = |seed| {
+        let mut generator = Generator::new(&dfa, 8);
+        generator.set_seed(seed);
+        (0..10)
+            .map(|_| generator.sample().unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(samples(7), samples(7));
+    assert_ne!(samples(7), samples(8));
+}
+
+#[test]
+fn test_sample_surrogates() {
+    // The class spans the surrogate code points, which must never be produced.
+    let dfa = dfa("[\u{d7fe}-\u{e001}]");
+    let mut generator = Generator::new(&dfa, 1);
+    for _ in 0..100 {
+        assert!(dfa.is_match(generator.sample().unwrap().chars()));
+    }
+    let all: Vec<_> = generator.iter().collect();
+    assert_eq!(vec!["\u{d7fe}", "\u{d7ff}", "\u{e000}", "\u{e001}"], all);
+}
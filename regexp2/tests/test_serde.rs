@@ -0,0 +1,101 @@
+#![cfg(feature = "serde")]
+
+use regexp2::ast::Expr;
+use regexp2::automata::{DenseDFA, DFA};
+use regexp2::class::CharClass;
+use regexp2::parser::ast::{ASTParser, SpannedASTParser};
+use regexp2::parser::nfa::NFAParser;
+use regexp2::{MatchKind, RegExp, RegExpBuilder};
+
+#[test]
+fn test_serde_dfa() {
+    let dfa: DFA<CharClass> = NFAParser::new().parse("(a|b)*abb").unwrap().into();
+    let json = serde_json::to_string(&dfa).unwrap();
+    let loaded: DFA<CharClass> = serde_json::from_str(&json).unwrap();
+
+    assert!(dfa.is_equivalent(&loaded));
+    assert_eq!(dfa.total_states, loaded.total_states);
+}
+
+#[test]
+fn test_serde_regexp() {
+    let re = RegExpBuilder::new(r"\w+@[a-z]+\.com")
+        .match_kind(MatchKind::LeftmostFirst)
+        .build()
+        .unwrap();
+    let json = serde_json::to_string(&re).unwrap();
+    let loaded: RegExp<DenseDFA<CharClass>> = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(re.as_str(), loaded.as_str());
+    assert_eq!(MatchKind::LeftmostFirst, loaded.match_kind());
+    for input in &["me@example.com", "me@example.org", "@example.com"] {
+        assert_eq!(re.is_match(input), loaded.is_match(input));
+    }
+    assert_eq!(
+        Some("me@example.com"),
+        loaded.find("mail me@example.com!").map(|m| m.as_str())
+    );
+}
+
+#[test]
+fn test_serde_literals() {
+    let re = RegExpBuilder::new("ab|abc|x")
+        .match_kind(MatchKind::LeftmostFirst)
+        .build()
+        .unwrap();
+    let json = serde_json::to_string(&re).unwrap();
+    let loaded: RegExp<DenseDFA<CharClass>> = serde_json::from_str(&json).unwrap();
+
+    for input in &["abc", "xab", "ab", "a"] {
+        assert_eq!(re.is_match(input), loaded.is_match(input));
+        assert_eq!(re.find(input), loaded.find(input));
+    }
+    assert_eq!(Some("ab"), loaded.find("zabc").map(|m| m.as_str()));
+}
+
+#[test]
+fn test_serde_ast() {
+    for expr in &[r"(a|b)*abb", r"[^a-c]+\d?()", "^x$"] {
+        for ast in &[
+            ASTParser::<char>::new().parse(expr).unwrap(),
+            SpannedASTParser::<char>::new().parse(expr).unwrap(),
+        ] {
+            let json = serde_json::to_string(ast).unwrap();
+            let loaded: Expr = serde_json::from_str(&json).unwrap();
+            assert_eq!(ast, &loaded);
+        }
+    }
+}
+
+#[test]
+fn test_serde_dense_dfa_corrupt() {
+    let dfa: DFA<CharClass> = NFAParser::new().parse("(a|b)*abb").unwrap().into();
+    let dfa = DenseDFA::from(&dfa);
+    let json = serde_json::to_value(&dfa).unwrap();
+    let loaded: DenseDFA<CharClass> = serde_json::from_value(json.clone()).unwrap();
+    assert!(loaded.is_match("aabb".chars()));
+
+    let states = dfa.total_states as u64;
+    let corruptions: Vec<(&str, serde_json::Value)> = vec![
+        ("/table", serde_json::json!([])),
+        ("/accepting", serde_json::json!([true])),
+        ("/fallback", serde_json::json!([])),
+        ("/start_state", serde_json::json!(states)),
+        ("/table/0", serde_json::json!(states)),
+        ("/classes/low", serde_json::json!([0])),
+        ("/classes/low/97", serde_json::json!(dfa.alphabet.len())),
+        (
+            "/classes/high",
+            serde_json::json!([[300, 400, 0], [350, 500, 0]]),
+        ),
+    ];
+    for (pointer, value) in corruptions {
+        let mut corrupt = json.clone();
+        *corrupt.pointer_mut(pointer).unwrap() = value;
+        assert!(
+            serde_json::from_value::<DenseDFA<CharClass>>(corrupt).is_err(),
+            "DFA with corrupt {} was loaded",
+            pointer
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use std::io::{self, Read};
+use std::ops::Range;
+
+use regexp2::automata::DenseDFA;
+use regexp2::class::CharClass;
+use regexp2::{MatchKind, RegExp, RegExpBuilder};
+
+/// The non-overlapping matches found by iterating over the matches in the whole input.
+fn find_all(re: &RegExp<DenseDFA<CharClass>>, input: &str) -> Vec<Range<usize>> {
+    re.captures_iter(input)
+        .map(|caps| caps.get(0).unwrap().range())
+        .collect()
+}
+
+/// Split the input into chunks of `size` bytes, moved forward to character boundaries.
+fn chunks(input: &str, size: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < input.len() {
+        let mut end = (start + size).min(input.len());
+        while !input.is_char_boundary(end) {
+            end += 1;
+        }
+        chunks.push(&input[start..end]);
+        start = end;
+    }
+    chunks
+}
+
+#[test]
+fn test_stream() {
+    let cases = [
+        ("(a|b)*abb", "xxabbyyaababbzabb"),
+        (r"\d+", "12 345 6789 0"),
+        ("ab|abcd", "abcdabcabab"),
+        ("a*", "baaacaab"),
+        ("é+x?", "aéébéxé"),
+        ("x", ""),
+        ("b?", "b"),
+        ("b?", "abbca"),
+    ];
+
+    for (expr, input) in &cases {
+        let re = RegExp::new(expr).unwrap();
+        let expected = find_all(&re, input);
+        for size in 1..=input.len().max(1) {
+            let mut stream = re.stream_matcher();
+            let mut actual = Vec::new();
+            for chunk in chunks(input, size) {
+                actual.extend(stream.feed(chunk));
+            }
+            actual.extend(stream.finish());
+            assert_eq!(
+                expected, actual,
+                r#""{}" found wrong matches in "{}" with chunk size {}"#,
+                expr, input, size
+            );
+        }
+    }
+}
+
+#[test]
+fn test_stream_reports_early() {
+    let re = RegExp::new("ab+").unwrap();
+    let mut stream = re.stream_matcher();
+    assert_eq!(Vec::<Range<usize>>::new(), stream.feed("xab"));
+    assert_eq!(vec![1..4], stream.feed("bx"));
+    assert_eq!(5, stream.position());
+    assert_eq!(Vec::<Range<usize>>::new(), stream.feed("ab"));
+    assert_eq!(vec![5..7], stream.finish());
+}
+
+#[test]
+fn test_stream_empty_after_match() {
+    // An empty match where the previous match ended is skipped, as by `captures_iter`.
+    let re = RegExp::new("b?").unwrap();
+    let mut stream = re.stream_matcher();
+    let mut matches = stream.feed("b");
+    matches.extend(stream.finish());
+    assert_eq!(vec![0..1], matches);
+
+    let mut stream = re.stream_matcher();
+    let mut matches = stream.feed("ab");
+    matches.extend(stream.feed("c"));
+    matches.extend(stream.finish());
+    assert_eq!(vec![0..0, 1..2, 3..3], matches);
+}
+
+#[test]
+fn test_stream_leftmost_first() {
+    let re = RegExpBuilder::new("a|ab")
+        .match_kind(MatchKind::LeftmostFirst)
+        .build()
+        .unwrap();
+    let mut stream = re.stream_matcher();
+    let mut matches = stream.feed("abxa");
+    matches.extend(stream.finish());
+    assert_eq!(vec![0..1, 3..4], matches);
+}
+
+#[test]
+fn test_stream_long_candidate() {
+    // Every `a` starts a run that may still match, but the runs all reach the same state, so only
+    // the earliest is kept and feeding takes time linear in the input.
+    let re = RegExp::new("a.*x").unwrap();
+    let mut stream = re.stream_matcher();
+    let chunk = "a".repeat(1000);
+    for _ in 0..100 {
+        assert_eq!(Vec::<Range<usize>>::new(), stream.feed(&chunk));
+    }
+    assert_eq!(Vec::<Range<usize>>::new(), stream.feed("x"));
+    assert_eq!(vec![0..100001], stream.finish());
+
+    // A match found by a later run is still reported if the earlier one never matches.
+    let re = RegExp::new("a.*x|b").unwrap();
+    let mut stream = re.stream_matcher();
+    let mut matches = stream.feed(&"ab".repeat(1000));
+    matches.extend(stream.finish());
+    let expected: Vec<_> = (0..1000).map(|i| 2 * i + 1..2 * i + 2).collect();
+    assert_eq!(expected, matches);
+}
+
+/// A reader that returns at most one byte at a time.
+struct Trickle<'a>(&'a [u8]);
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.0.is_empty() || buf.is_empty() {
+            return Ok(0);
+        }
+        buf[0] = self.0[0];
+        self.0 = &self.0[1..];
+        Ok(1)
+    }
+}
+
+#[test]
+fn test_stream_reader() {
+    let re = RegExp::new("é+b").unwrap();
+    let input = "aééb ébéb";
+    let expected = find_all(&re, input);
+
+    let mut stream = re.stream_matcher();
+    let mut matches = stream.feed_reader(Trickle(input.as_bytes())).unwrap();
+    matches.extend(stream.finish());
+    assert_eq!(expected, matches);
+
+    let mut stream = re.stream_matcher();
+    let mut matches = stream.feed_reader(input.as_bytes()).unwrap();
+    matches.extend(stream.finish());
+    assert_eq!(expected, matches);
+}
+
+#[test]
+fn test_stream_reader_invalid_utf8() {
+    let re = RegExp::new("a").unwrap();
+
+    let mut stream = re.stream_matcher();
+    let err = stream.feed_reader(&b"a\xffa"[..]).unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidData, err.kind());
+
+    let mut stream = re.stream_matcher();
+    let err = stream.feed_reader(&b"a\xc3"[..]).unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidData, err.kind());
+}
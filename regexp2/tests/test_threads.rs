@@ -0,0 +1,75 @@
+use std::sync::Arc;
+use std::thread;
+
+use regexp2::automata::{DenseDFA, LazyDFA, DFA, NFA};
+use regexp2::class::CharClass;
+use regexp2::derivative::DerivativeMatcher;
+use regexp2::parser::ast::ASTParser;
+use regexp2::{RegExp, RegExpBuilder};
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_send_sync() {
+    assert_send_sync::<RegExp<DenseDFA<CharClass>>>();
+    assert_send_sync::<RegExp<DenseDFA<u8>>>();
+    assert_send_sync::<RegExp<LazyDFA<CharClass>>>();
+    assert_send_sync::<RegExp<NFA<CharClass>>>();
+    assert_send_sync::<RegExp<DFA<CharClass>>>();
+    assert_send_sync::<DerivativeMatcher>();
+}
+
+#[test]
+fn test_shared_between_threads() {
+    let dense = Arc::new(RegExp::new("(a|b)*abb").unwrap());
+    let lazy = Arc::new(
+        RegExpBuilder::new("(a|b)*abb")
+            .cache_capacity(2)
+            .build_lazy()
+            .unwrap(),
+    );
+    let expr = ASTParser::<char>::new().parse("(a|b)*abb").unwrap();
+    let derivative = Arc::new(DerivativeMatcher::new(&expr).unwrap());
+
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            let dense = Arc::clone(&dense);
+            let lazy = Arc::clone(&lazy);
+            let derivative = Arc::clone(&derivative);
+            thread::spawn(move || {
+                for j in 0..100 {
+                    let input = format!("{}abb", "ab".repeat(i + j));
+                    assert!(dense.is_match(&input));
+                    assert!(lazy.is_match(&input));
+                    assert!(derivative.is_match(input.chars()));
+                    assert!(!lazy.is_match(&input[1..input.len() - 1]));
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+#[test]
+fn test_search_within_search() {
+    // Searches don't hold a lock while reading their input, so the input may search the same
+    // matcher.
+    let expr = ASTParser::<char>::new().parse("(a|b)*abb").unwrap();
+    let lazy = LazyDFA::new(expr.to_nfa::<CharClass>().unwrap());
+    let derivative = DerivativeMatcher::new(&expr).unwrap();
+    let inputs = ["ab", "b", "ba", "abb"];
+
+    let input = inputs.iter().map(|s| {
+        assert_eq!(s.ends_with("abb"), lazy.is_match(s.chars()));
+        'b'
+    });
+    assert!(!lazy.is_match(input));
+
+    let input = inputs.iter().map(|s| {
+        assert_eq!(s.ends_with("abb"), derivative.is_match(s.chars()));
+        'b'
+    });
+    assert!(!derivative.is_match(input));
+}
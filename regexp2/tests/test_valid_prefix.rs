@@ -0,0 +1,35 @@
+use regexp2::{RegExp, RegExpBuilder};
+
+#[test]
+fn test_is_valid_prefix() {
+    let re = RegExp::new("(a|b)*abb|cd").unwrap();
+    for input in &["", "a", "ab", "abb", "abba", "bbbab", "c", "cd"] {
+        assert!(re.is_valid_prefix(input), "{}", input);
+    }
+    for input in &["x", "abbx", "cc", "cda", "é"] {
+        assert!(!re.is_valid_prefix(input), "{}", input);
+    }
+
+    // Every prefix of a match is valid.
+    let input = "babbabb";
+    assert!(re.is_match(input));
+    assert!((0..=input.len()).all(|i| re.is_valid_prefix(&input[..i])));
+}
+
+#[test]
+fn test_is_valid_prefix_empty_language() {
+    let re = RegExp::new("a").unwrap();
+    let nothing = re.and(&re.not());
+    assert!(!nothing.is_valid_prefix(""));
+    assert!(!nothing.is_valid_prefix("a"));
+}
+
+#[test]
+fn test_is_valid_prefix_flags() {
+    let re = RegExpBuilder::new("abc")
+        .case_insensitive(true)
+        .build()
+        .unwrap();
+    assert!(re.is_valid_prefix("AB"));
+    assert!(!re.is_valid_prefix("ABCD"));
+}
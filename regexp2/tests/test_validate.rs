@@ -0,0 +1,47 @@
+use regexp2::{ParseError, RegExp, RegExpBuilder};
+
+#[test]
+fn test_validate() {
+    let exprs = [
+        "(a|b)*abb",
+        r"\d+\w?",
+        "[^B-Fa-z]*",
+        "a()b",
+        "(?i:ab)c",
+        "",
+        "(",
+        ")",
+        "a(",
+        "(()",
+        "*",
+        "a|",
+        "a)*",
+        "[a-",
+        "a\\",
+        "^a",
+        "a$",
+    ];
+    for expr in &exprs {
+        assert_eq!(
+            format!("{:?}", RegExp::new(expr).map(|_| ())),
+            format!("{:?}", RegExp::validate(expr)),
+        );
+    }
+}
+
+#[test]
+fn test_validate_builder() {
+    let nested = "((((a))))";
+    assert!(RegExpBuilder::new(nested).validate().is_ok());
+    assert!(matches!(
+        RegExpBuilder::new(nested).nest_limit(2).validate(),
+        Err(ParseError::NestLimitExceeded { limit: 2, .. })
+    ));
+
+    let long = "abcdefgh";
+    assert!(matches!(
+        RegExpBuilder::new(long).size_limit(4).validate(),
+        Err(ParseError::SizeLimitExceeded { limit: 4, .. })
+    ));
+    assert!(RegExpBuilder::new(long).size_limit(100).validate().is_ok());
+}